@@ -1,7 +1,7 @@
 use criterion::{criterion_group, criterion_main, Criterion, SamplingMode};
 use std::time::Duration;
 use mini_games_server::games::SessionRng;
-use mini_games_server::games::tictactoe::{calculate_move, BotInput, Mark};
+use mini_games_server::games::tictactoe::{calculate_move, BotInput, BotStrength, Mark, Swap2Phase, WinRule};
 use mini_games_server::proto::tictactoe::TicTacToeBotType;
 
 fn create_empty_board(width: usize, height: usize) -> Vec<Vec<Mark>> {
@@ -19,9 +19,11 @@ fn bench_minimax_10_moves() {
             board: board.clone(),
             win_count,
             current_mark,
+            win_rule: WinRule::Freestyle,
+            swap2_phase: Swap2Phase::None,
         };
 
-        if let Some(pos) = calculate_move(TicTacToeBotType::TictactoeBotTypeMinimax, input, &mut session_rng) {
+        if let Some(pos) = calculate_move(TicTacToeBotType::TictactoeBotTypeMinimax, &BotStrength::default(), input, &mut session_rng) {
             board[pos.y][pos.x] = current_mark;
             current_mark = current_mark.opponent().unwrap();
         } else {
@@ -36,9 +38,11 @@ fn bench_minimax_single_move_empty_board() {
         board,
         win_count: 5,
         current_mark: Mark::X,
+        win_rule: WinRule::Freestyle,
+        swap2_phase: Swap2Phase::None,
     };
     let mut session_rng = SessionRng::from_random();
-    calculate_move(TicTacToeBotType::TictactoeBotTypeMinimax, input, &mut session_rng);
+    calculate_move(TicTacToeBotType::TictactoeBotTypeMinimax, &BotStrength::default(), input, &mut session_rng);
 }
 
 fn bench_minimax_single_move_mid_game() {
@@ -58,9 +62,11 @@ fn bench_minimax_single_move_mid_game() {
         board: board.clone(),
         win_count: 5,
         current_mark: Mark::X,
+        win_rule: WinRule::Freestyle,
+        swap2_phase: Swap2Phase::None,
     };
     let mut session_rng = SessionRng::from_random();
-    calculate_move(TicTacToeBotType::TictactoeBotTypeMinimax, input, &mut session_rng);
+    calculate_move(TicTacToeBotType::TictactoeBotTypeMinimax, &BotStrength::default(), input, &mut session_rng);
 }
 
 
@@ -0,0 +1,66 @@
+use criterion::{criterion_group, criterion_main, BatchSize, Criterion};
+use mini_games_server::games::SessionRng;
+use mini_games_server::games::snake::{BotController, DeadSnakeBehavior, Direction, FieldSize, Point, SnakeGameState, WallCollisionMode};
+use mini_games_server::{PlayerId, SnakeBotType};
+
+const FIELD_SIZE: usize = 100;
+const SNAKE_COUNT: usize = 10;
+
+fn snake_ids() -> Vec<PlayerId> {
+    (0..SNAKE_COUNT).map(|i| PlayerId::new(format!("bot{}", i))).collect()
+}
+
+/// Ten snakes spread over a 100×100 field, optionally played forward with
+/// space-aware bots so the bodies have grown and the field is crowded.
+fn create_state(ticks: u64) -> SnakeGameState {
+    let mut state = SnakeGameState::new(
+        FieldSize { width: FIELD_SIZE, height: FIELD_SIZE },
+        WallCollisionMode::Death,
+        DeadSnakeBehavior::StayOnField,
+        100,
+        1.0,
+    );
+    for (i, id) in snake_ids().into_iter().enumerate() {
+        let x = (i % 5) * FIELD_SIZE / 5 + FIELD_SIZE / 10;
+        let y = (i / 5) * FIELD_SIZE / 2 + FIELD_SIZE / 4;
+        state.add_snake(id, Point::new(x, y), Direction::Up);
+    }
+
+    let mut rng = SessionRng::new(41);
+    for _ in 0..ticks {
+        play_tick(&mut state, &mut rng);
+        state.update(&mut rng);
+        if state.is_game_over() {
+            break;
+        }
+    }
+    state
+}
+
+/// What one server tick spends on bots: a move for every snake on the field.
+fn play_tick(state: &mut SnakeGameState, rng: &mut SessionRng) {
+    for id in snake_ids() {
+        if let Some(direction) = BotController::calculate_move(SnakeBotType::SpaceAware, &id, state, rng) {
+            let _ = state.set_snake_direction(&id, direction);
+        }
+    }
+}
+
+fn snake_bot_bench(c: &mut Criterion) {
+    // Snake lobbies tick at least every 50 ms; a tick of bot moves has to fit
+    // well inside that.
+    let mut group = c.benchmark_group("snake_space_aware_100x100_10_snakes");
+
+    for (name, ticks) in [("tick_at_start", 0), ("tick_after_300_ticks", 300)] {
+        let state = create_state(ticks);
+        group.bench_function(name, |b| {
+            let mut rng = SessionRng::new(7);
+            b.iter_batched_ref(|| state.clone(), |state| play_tick(state, &mut rng), BatchSize::SmallInput)
+        });
+    }
+
+    group.finish();
+}
+
+criterion_group!(benches, snake_bot_bench);
+criterion_main!(benches);
@@ -0,0 +1,343 @@
+mod snake;
+mod tictactoe;
+
+use std::fmt;
+use std::ops::Range;
+use std::sync::Mutex;
+use std::sync::atomic::{AtomicU64, Ordering};
+
+use crate::games::tictactoe::BotStrength;
+use crate::proto::tictactoe::TicTacToeBotLevel;
+use crate::replay::ReplayRecorder;
+use crate::{
+    GameOverNotification, PlayerIdentity, ReplayGame, ReplayV1, ScoreEntry, SnakeBotType, SnakeLobbySettings,
+    TicTacToeBotType, TicTacToeLobbySettings, lobby_settings,
+};
+
+/// A bot-vs-bot matchup, played headless without ticking timers.
+#[derive(Clone)]
+pub enum ArenaGame {
+    Snake {
+        settings: SnakeLobbySettings,
+        bots: Vec<SnakeBotType>,
+        /// Games still undecided after this many ticks count as draws.
+        max_ticks: u64,
+    },
+    TicTacToe {
+        settings: TicTacToeLobbySettings,
+        bots: [TicTacToeBotType; 2],
+        /// Only minimax bots read their strength.
+        strengths: [BotStrength; 2],
+    },
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct MatchResult {
+    pub seed: u64,
+    /// Seat index of the winner, `None` for a draw.
+    pub winner: Option<usize>,
+    pub scores: Vec<u32>,
+    /// Ticks for Snake, moves for TicTacToe.
+    pub length: u64,
+}
+
+impl ArenaGame {
+    pub fn seat_names(&self) -> Vec<String> {
+        match self {
+            ArenaGame::Snake { bots, .. } => bots
+                .iter()
+                .enumerate()
+                .map(|(seat, bot)| format!("{}-{}", seat, snake_bot_name(*bot)))
+                .collect(),
+            ArenaGame::TicTacToe { bots, strengths, .. } => (0..2)
+                .map(|seat| tictactoe_seat_name(seat, bots[seat], &strengths[seat]))
+                .collect(),
+        }
+    }
+
+    pub fn play(&self, seed: u64) -> MatchResult {
+        self.play_match(seed, None)
+    }
+
+    /// Plays the same match as `play` and returns its replay.
+    pub fn record(&self, seed: u64) -> ReplayV1 {
+        let (game, settings) = match self {
+            ArenaGame::Snake { settings, .. } => (ReplayGame::Snake, lobby_settings::Settings::Snake(settings.clone())),
+            ArenaGame::TicTacToe { settings, .. } => {
+                (ReplayGame::Tictactoe, lobby_settings::Settings::Tictactoe(*settings))
+            }
+        };
+        let players: Vec<PlayerIdentity> = self
+            .seat_names()
+            .into_iter()
+            .map(|player_id| PlayerIdentity { player_id, is_bot: true })
+            .collect();
+
+        let mut recorder = ReplayRecorder::new(
+            crate::version::VERSION.to_string(),
+            game,
+            seed,
+            Some(settings),
+            players.clone(),
+        );
+        let result = self.play_match(seed, Some(&mut recorder));
+
+        recorder.finalize(Some(GameOverNotification {
+            scores: players
+                .iter()
+                .zip(&result.scores)
+                .map(|(identity, score)| ScoreEntry { identity: Some(identity.clone()), score: *score })
+                .collect(),
+            winner: result.winner.map(|seat| players[seat].clone()),
+            game_info: None,
+        }))
+    }
+
+    fn play_match(&self, seed: u64, recorder: Option<&mut ReplayRecorder>) -> MatchResult {
+        match self {
+            ArenaGame::Snake { settings, bots, max_ticks } => {
+                snake::play(settings, bots, *max_ticks, seed, recorder)
+            }
+            ArenaGame::TicTacToe { settings, bots, strengths } => {
+                tictactoe::play(settings, bots, strengths, seed, recorder)
+            }
+        }
+    }
+}
+
+/// Plays one match per seed on `threads` worker threads; results come back in seed order.
+pub fn run_arena(game: &ArenaGame, seeds: Range<u64>, threads: usize) -> Vec<MatchResult> {
+    let next_seed = AtomicU64::new(seeds.start);
+    let results = Mutex::new(Vec::with_capacity(seeds.end.saturating_sub(seeds.start) as usize));
+
+    std::thread::scope(|scope| {
+        for _ in 0..threads.max(1) {
+            scope.spawn(|| {
+                loop {
+                    let seed = next_seed.fetch_add(1, Ordering::Relaxed);
+                    if seed >= seeds.end {
+                        break;
+                    }
+                    let result = game.play(seed);
+                    results.lock().unwrap_or_else(|e| e.into_inner()).push(result);
+                }
+            });
+        }
+    });
+
+    let mut results = results.into_inner().unwrap_or_else(|e| e.into_inner());
+    results.sort_by_key(|r| r.seed);
+    results
+}
+
+pub struct SeatStats {
+    pub name: String,
+    pub wins: u64,
+    pub total_score: u64,
+}
+
+pub struct ArenaReport {
+    pub seats: Vec<SeatStats>,
+    pub games: u64,
+    pub draws: u64,
+    pub total_length: u64,
+}
+
+impl ArenaReport {
+    pub fn new(game: &ArenaGame, results: &[MatchResult]) -> Self {
+        let mut seats: Vec<SeatStats> = game
+            .seat_names()
+            .into_iter()
+            .map(|name| SeatStats { name, wins: 0, total_score: 0 })
+            .collect();
+        let mut draws = 0;
+
+        for result in results {
+            match result.winner {
+                Some(seat) => seats[seat].wins += 1,
+                None => draws += 1,
+            }
+            for (stats, score) in seats.iter_mut().zip(&result.scores) {
+                stats.total_score += *score as u64;
+            }
+        }
+
+        Self {
+            seats,
+            games: results.len() as u64,
+            draws,
+            total_length: results.iter().map(|r| r.length).sum(),
+        }
+    }
+
+    /// The seat with the most wins, if one seat has strictly more than every other.
+    pub fn favourite(&self) -> Option<usize> {
+        let best = self.seats.iter().map(|s| s.wins).max()?;
+        let mut leaders = self.seats.iter().enumerate().filter(|(_, s)| s.wins == best);
+        let (seat, _) = leaders.next()?;
+        leaders.next().is_none().then_some(seat)
+    }
+
+    /// A game won by anyone other than the favourite.
+    pub fn is_upset(&self, result: &MatchResult) -> bool {
+        match (result.winner, self.favourite()) {
+            (Some(winner), Some(favourite)) => winner != favourite,
+            _ => false,
+        }
+    }
+}
+
+impl fmt::Display for ArenaReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let games = self.games.max(1) as f64;
+        writeln!(f, "{} games", self.games)?;
+        for stats in &self.seats {
+            writeln!(
+                f,
+                "  {:<20} {:>6} wins ({:>5.1}%)  avg score {:.2}",
+                stats.name,
+                stats.wins,
+                stats.wins as f64 * 100.0 / games,
+                stats.total_score as f64 / games
+            )?;
+        }
+        writeln!(f, "  {:<20} {:>6}      ({:>5.1}%)", "draws", self.draws, self.draws as f64 * 100.0 / games)?;
+        write!(f, "  avg length {:.1}", self.total_length as f64 / games)
+    }
+}
+
+pub fn snake_bot_name(bot: SnakeBotType) -> &'static str {
+    match bot {
+        SnakeBotType::Efficient => "efficient",
+        SnakeBotType::Random => "random",
+        SnakeBotType::SpaceAware => "space-aware",
+        SnakeBotType::Unspecified => "unspecified",
+    }
+}
+
+pub fn tictactoe_bot_name(bot: TicTacToeBotType) -> &'static str {
+    match bot {
+        TicTacToeBotType::TictactoeBotTypeMinimax => "minimax",
+        TicTacToeBotType::TictactoeBotTypeRandom => "random",
+        TicTacToeBotType::TictactoeBotTypeUnspecified => "unspecified",
+    }
+}
+
+/// Hard minimax bots and random bots go by the bot name alone.
+fn tictactoe_seat_name(seat: usize, bot: TicTacToeBotType, strength: &BotStrength) -> String {
+    let level = match strength.level {
+        _ if bot != TicTacToeBotType::TictactoeBotTypeMinimax => None,
+        TicTacToeBotLevel::TictactoeBotLevelEasy => Some("easy"),
+        TicTacToeBotLevel::TictactoeBotLevelMedium => Some("medium"),
+        TicTacToeBotLevel::TictactoeBotLevelCustom => Some("custom"),
+        TicTacToeBotLevel::TictactoeBotLevelHard | TicTacToeBotLevel::TictactoeBotLevelUnspecified => None,
+    };
+    match level {
+        Some(level) => format!("{}-{}-{}", seat, tictactoe_bot_name(bot), level),
+        None => format!("{}-{}", seat, tictactoe_bot_name(bot)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::replay::verify::verify_replay;
+    use crate::{FirstPlayerMode, WallCollisionMode};
+
+    fn snake_game() -> ArenaGame {
+        ArenaGame::Snake {
+            settings: SnakeLobbySettings {
+                field_width: 12,
+                field_height: 12,
+                wall_collision_mode: WallCollisionMode::Death as i32,
+                tick_interval_ms: 100,
+                max_food_count: 3,
+                food_spawn_probability: 0.5,
+                dead_snake_behavior: 0,
+                ..Default::default()
+            },
+            bots: vec![SnakeBotType::Efficient, SnakeBotType::Random],
+            max_ticks: 500,
+        }
+    }
+
+    #[test]
+    fn test_run_arena_deterministic_across_thread_counts() {
+        let game = snake_game();
+        assert_eq!(run_arena(&game, 0..8, 1), run_arena(&game, 0..8, 4));
+    }
+
+    #[test]
+    fn test_recorded_snake_match_verifies_without_desync() {
+        let game = snake_game();
+        let replay = game.record(3);
+        let summary = verify_replay(replay).unwrap();
+        assert_eq!(summary.desync_position, None);
+        assert_eq!(summary.ticks, game.play(3).length);
+    }
+
+    #[test]
+    fn test_recorded_swap2_match_verifies_without_desync() {
+        let game = ArenaGame::TicTacToe {
+            settings: TicTacToeLobbySettings {
+                field_width: 9,
+                field_height: 9,
+                win_count: 5,
+                first_player: FirstPlayerMode::Random as i32,
+                win_rule: crate::proto::tictactoe::WinRule::Renju as i32,
+                opening: crate::proto::tictactoe::OpeningRule::Swap2 as i32,
+                ..Default::default()
+            },
+            bots: [TicTacToeBotType::TictactoeBotTypeRandom, TicTacToeBotType::TictactoeBotTypeRandom],
+            strengths: [BotStrength::default(); 2],
+        };
+
+        for seed in 0..10 {
+            let replay = game.record(seed);
+            let summary = verify_replay(replay).unwrap();
+            assert_eq!(summary.desync_position, None, "seed {}", seed);
+            assert_eq!(summary.ticks, game.play(seed).length, "seed {}", seed);
+        }
+    }
+
+    #[test]
+    fn test_minimax_never_loses_to_random_on_small_board() {
+        let game = ArenaGame::TicTacToe {
+            settings: TicTacToeLobbySettings {
+                field_width: 3,
+                field_height: 3,
+                win_count: 3,
+                first_player: FirstPlayerMode::Random as i32,
+                ..Default::default()
+            },
+            bots: [TicTacToeBotType::TictactoeBotTypeMinimax, TicTacToeBotType::TictactoeBotTypeRandom],
+            strengths: [BotStrength::default(); 2],
+        };
+
+        let report = ArenaReport::new(&game, &run_arena(&game, 0..20, 2));
+        assert_eq!(report.seats[1].wins, 0);
+        assert_eq!(report.favourite(), Some(0));
+    }
+
+    #[test]
+    fn test_easy_minimax_never_beats_hard_minimax() {
+        let game = ArenaGame::TicTacToe {
+            settings: TicTacToeLobbySettings {
+                field_width: 3,
+                field_height: 3,
+                win_count: 3,
+                first_player: FirstPlayerMode::Random as i32,
+                ..Default::default()
+            },
+            bots: [TicTacToeBotType::TictactoeBotTypeMinimax; 2],
+            strengths: [
+                BotStrength::preset(TicTacToeBotLevel::TictactoeBotLevelEasy),
+                BotStrength::preset(TicTacToeBotLevel::TictactoeBotLevelHard),
+            ],
+        };
+
+        assert_eq!(game.seat_names(), vec!["0-minimax-easy".to_string(), "1-minimax".to_string()]);
+        let report = ArenaReport::new(&game, &run_arena(&game, 0..20, 2));
+        assert_eq!(report.seats[0].wins, 0);
+    }
+}
@@ -0,0 +1,64 @@
+use crate::games::snake::{BotController, Side, SnakeSessionSettings, create_turn_command, initial_game_state};
+use crate::games::{RngStream, SessionRng};
+use crate::replay::{ReplayRecorder, STATE_HASH_INTERVAL};
+use crate::{PlayerId, SnakeBotType, SnakeLobbySettings};
+
+use super::{MatchResult, snake_bot_name};
+
+pub(super) fn play(
+    settings: &SnakeLobbySettings,
+    bots: &[SnakeBotType],
+    max_ticks: u64,
+    seed: u64,
+    mut recorder: Option<&mut ReplayRecorder>,
+) -> MatchResult {
+    let players: Vec<PlayerId> = bots
+        .iter()
+        .enumerate()
+        .map(|(seat, bot)| PlayerId::new(format!("{}-{}", seat, snake_bot_name(*bot))))
+        .collect();
+
+    let mut game_state = initial_game_state(&SnakeSessionSettings::from(settings), &players, seed);
+    let mut rng = SessionRng::new(seed);
+    let mut bot_rng = SessionRng::for_stream(seed, RngStream::Bots);
+    let mut tick = 0u64;
+
+    loop {
+        for (seat, (player_id, bot_type)) in players.iter().zip(bots).enumerate() {
+            let Some(direction) = BotController::calculate_move(*bot_type, player_id, &game_state, &mut bot_rng) else {
+                continue;
+            };
+            if game_state.set_snake_direction(player_id, direction).is_ok()
+                && let Some(recorder) = recorder.as_deref_mut()
+            {
+                recorder.record_command(tick as i64, seat as i32, create_turn_command(direction));
+            }
+        }
+
+        game_state.update(&mut rng);
+        tick += 1;
+
+        if let Some(recorder) = recorder.as_deref_mut()
+            && tick.is_multiple_of(STATE_HASH_INTERVAL)
+        {
+            recorder.record_state_hash(tick, game_state.state_hash());
+        }
+
+        let game_over = game_state.is_game_over();
+        if game_over || tick >= max_ticks {
+            let winner = game_state
+                .winner()
+                .filter(|_| game_over && players.len() > 1)
+                .and_then(|winner| match winner {
+                    Side::Player(winner) => players.iter().position(|id| *id == winner),
+                    Side::Team(_) => None,
+                });
+            return MatchResult {
+                seed,
+                winner,
+                scores: players.iter().map(|id| game_state.snakes.get(id).map_or(0, |s| s.score)).collect(),
+                length: tick,
+            };
+        }
+    }
+}
@@ -0,0 +1,74 @@
+use crate::games::tictactoe::{
+    BotInput, BotStrength, GameStatus, TicTacToeGameState, TicTacToeSessionSettings, calculate_move, calculate_swap2_choice,
+    create_place_command, create_swap2_choice_command,
+};
+use crate::games::{RngStream, SessionRng};
+use crate::replay::ReplayRecorder;
+use crate::{PlayerId, TicTacToeBotType, TicTacToeLobbySettings};
+
+use super::{MatchResult, tictactoe_seat_name};
+
+pub(super) fn play(
+    settings: &TicTacToeLobbySettings,
+    bots: &[TicTacToeBotType; 2],
+    strengths: &[BotStrength; 2],
+    seed: u64,
+    mut recorder: Option<&mut ReplayRecorder>,
+) -> MatchResult {
+    let players: Vec<PlayerId> = (0..2)
+        .map(|seat| PlayerId::new(tictactoe_seat_name(seat, bots[seat], &strengths[seat])))
+        .collect();
+
+    let settings = TicTacToeSessionSettings::from(settings);
+    let mut rng = SessionRng::new(seed);
+    let mut bot_rng = SessionRng::for_stream(seed, RngStream::Bots);
+    let mut game_state = TicTacToeGameState::new(
+        settings.field_width,
+        settings.field_height,
+        settings.win_count,
+        players.clone(),
+        settings.first_player_mode,
+        &mut rng,
+    );
+    game_state.set_rules(settings.win_rule, settings.opening);
+    let mut moves = 0u64;
+
+    while game_state.status == GameStatus::InProgress {
+        let current_player = game_state.current_player.clone();
+        let Some(seat) = players.iter().position(|p| *p == current_player) else {
+            break;
+        };
+        let command = if game_state.swap2_phase.awaits_choice() {
+            let choice = calculate_swap2_choice(bots[seat], &BotInput::from_game_state(&game_state), &mut bot_rng);
+            if game_state.choose_swap2(&current_player, choice).is_err() {
+                break;
+            }
+            create_swap2_choice_command(choice)
+        } else {
+            let Some(pos) = calculate_move(bots[seat], &strengths[seat], BotInput::from_game_state(&game_state), &mut bot_rng) else {
+                break;
+            };
+            if game_state.place_mark(&current_player, pos.x, pos.y).is_err() {
+                break;
+            }
+            create_place_command(pos.x as u32, pos.y as u32)
+        };
+
+        if let Some(recorder) = recorder.as_deref_mut() {
+            recorder.record_timed_command(moves as i64, 0, seat as i32, command);
+            recorder.record_state_hash(recorder.actions_count() as u64, game_state.state_hash());
+        }
+        moves += 1;
+    }
+
+    let winner = game_state
+        .get_winner()
+        .and_then(|winner| players.iter().position(|p| *p == winner));
+
+    MatchResult {
+        seed,
+        winner,
+        scores: (0..players.len()).map(|seat| u32::from(winner == Some(seat))).collect(),
+        length: moves,
+    }
+}
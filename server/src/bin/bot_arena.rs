@@ -0,0 +1,317 @@
+use std::path::{Path, PathBuf};
+use std::process::ExitCode;
+use std::time::Instant;
+
+use clap::{Args as ClapArgs, Parser, Subcommand, ValueEnum};
+use mini_games_server::arena::{ArenaGame, ArenaReport, MatchResult, run_arena};
+use mini_games_server::games::LobbySettings;
+use mini_games_server::games::snake::{DEFAULT_INPUT_BUFFER_SIZE, find_map, with_embedded_map};
+use mini_games_server::games::tictactoe::BotStrength;
+use mini_games_server::proto::snake::{MovementResolution, SnakeGameMode, SpawnPlacement};
+use mini_games_server::proto::tictactoe::{OpeningRule, TicTacToeBotLevel, WinRule};
+use mini_games_server::replay::{REPLAY_FILE_EXTENSION, save_replay};
+use mini_games_server::{
+    DeadSnakeBehavior, FirstPlayerMode, SnakeBotType, SnakeLobbySettings, TicTacToeBotType, TicTacToeLobbySettings,
+    WallCollisionMode,
+};
+
+#[derive(Parser)]
+#[command(name = "bot_arena")]
+struct Args {
+    #[command(subcommand)]
+    game: Game,
+    #[command(flatten)]
+    run: RunArgs,
+}
+
+#[derive(ClapArgs)]
+struct RunArgs {
+    /// Number of games, one per seed.
+    #[arg(long, global = true, default_value_t = 100)]
+    games: u64,
+    #[arg(long, global = true, default_value_t = 0)]
+    first_seed: u64,
+    /// Worker threads; defaults to the number of cores.
+    #[arg(long, global = true)]
+    threads: Option<usize>,
+    /// Directory to write replays of the games picked by `--save`.
+    #[arg(long, global = true)]
+    replay_dir: Option<PathBuf>,
+    #[arg(long, global = true, value_enum, default_value_t = SaveFilter::Upsets)]
+    save: SaveFilter,
+}
+
+#[derive(Subcommand)]
+enum Game {
+    /// Snake bots on one field, one snake per listed bot.
+    Snake {
+        #[arg(long, value_enum, value_delimiter = ',', default_values_t = [SnakeBot::Efficient, SnakeBot::Random])]
+        bots: Vec<SnakeBot>,
+        #[arg(long, default_value_t = 20)]
+        width: u32,
+        #[arg(long, default_value_t = 20)]
+        height: u32,
+        #[arg(long)]
+        wrap_around: bool,
+        #[arg(long, default_value_t = 3)]
+        max_food: u32,
+        #[arg(long, default_value_t = 0.5)]
+        food_probability: f32,
+        #[arg(long, default_value_t = 5_000)]
+        max_ticks: u64,
+        /// Map to play on; its size replaces `--width` and `--height`.
+        #[arg(long)]
+        map: Option<String>,
+        /// Move snakes one at a time in seat order, as games before simultaneous movement did.
+        #[arg(long)]
+        sequential_movement: bool,
+        /// Line snakes up on the middle row, as games before spread placement did.
+        #[arg(long)]
+        row_spawns: bool,
+        #[arg(long, default_value_t = DEFAULT_INPUT_BUFFER_SIZE)]
+        input_buffer_size: u32,
+        #[command(flatten)]
+        mode: SnakeModeArgs,
+        #[command(flatten)]
+        items: SnakeItemWeights,
+    },
+    /// Two TicTacToe bots; the first listed bot plays X when the first player is fixed.
+    Tictactoe {
+        #[arg(long, value_enum, value_delimiter = ',', num_args = 2, default_values_t = [TicTacToeBot::Minimax, TicTacToeBot::Random])]
+        bots: Vec<TicTacToeBot>,
+        /// Strength of each minimax bot, in the order of `--bots`.
+        #[arg(long, value_enum, value_delimiter = ',', num_args = 2, default_values_t = [TicTacToeLevel::Hard, TicTacToeLevel::Hard])]
+        levels: Vec<TicTacToeLevel>,
+        #[arg(long, default_value_t = 3)]
+        width: u32,
+        #[arg(long, default_value_t = 3)]
+        height: u32,
+        #[arg(long, default_value_t = 3)]
+        win_count: u32,
+        /// Randomise who moves first instead of always starting with the first bot.
+        #[arg(long)]
+        random_first: bool,
+        #[arg(long, value_enum, default_value_t = TicTacToeWinRule::Freestyle)]
+        win_rule: TicTacToeWinRule,
+        /// Open with Swap2; the first bot is the opener.
+        #[arg(long)]
+        swap2: bool,
+    },
+}
+
+/// How a Snake game is won; the mode's limit comes from the matching option.
+#[derive(ClapArgs)]
+struct SnakeModeArgs {
+    #[arg(long, value_enum, default_value_t = SnakeMode::LastStanding)]
+    mode: SnakeMode,
+    #[arg(long, default_value_t = 60)]
+    time_limit_seconds: u32,
+    #[arg(long, default_value_t = 20)]
+    score_target: u32,
+    #[arg(long, default_value_t = 10)]
+    shrink_interval_seconds: u32,
+}
+
+#[derive(Clone, Copy, ValueEnum)]
+enum SnakeMode {
+    LastStanding,
+    TimeLimit,
+    ScoreTarget,
+    BattleRoyale,
+}
+
+/// Spawn weights of the special items; normal food weighs 100.
+#[derive(ClapArgs)]
+struct SnakeItemWeights {
+    #[arg(long, default_value_t = 0)]
+    golden_food_weight: u32,
+    #[arg(long, default_value_t = 0)]
+    speed_boost_weight: u32,
+    #[arg(long, default_value_t = 0)]
+    ghost_weight: u32,
+    #[arg(long, default_value_t = 0)]
+    shrink_weight: u32,
+    #[arg(long, default_value_t = 0)]
+    reverse_controls_weight: u32,
+}
+
+#[derive(Clone, Copy, ValueEnum)]
+enum SnakeBot {
+    Efficient,
+    Random,
+    SpaceAware,
+}
+
+#[derive(Clone, Copy, ValueEnum)]
+enum TicTacToeBot {
+    Minimax,
+    Random,
+}
+
+#[derive(Clone, Copy, ValueEnum)]
+enum TicTacToeLevel {
+    Easy,
+    Medium,
+    Hard,
+}
+
+#[derive(Clone, Copy, ValueEnum)]
+enum TicTacToeWinRule {
+    Freestyle,
+    Exact,
+    Renju,
+}
+
+#[derive(Clone, Copy, ValueEnum)]
+enum SaveFilter {
+    /// Games won by a bot other than the overall favourite.
+    Upsets,
+    Draws,
+    All,
+}
+
+fn main() -> ExitCode {
+    let args = Args::parse();
+    mini_games_server::logger::init_silent_logger();
+    let game = match arena_game(args.game) {
+        Ok(game) => game,
+        Err(e) => {
+            eprintln!("{}", e);
+            return ExitCode::FAILURE;
+        }
+    };
+    let run = args.run;
+
+    let threads = run
+        .threads
+        .unwrap_or_else(|| std::thread::available_parallelism().map_or(1, |n| n.get()));
+    let seeds = run.first_seed..run.first_seed.saturating_add(run.games);
+
+    let started = Instant::now();
+    let results = run_arena(&game, seeds, threads);
+    let report = ArenaReport::new(&game, &results);
+    println!("{}", report);
+    println!("  {:.2}s on {} threads", started.elapsed().as_secs_f64(), threads);
+
+    if let Some(dir) = run.replay_dir {
+        let picked: Vec<&MatchResult> = results
+            .iter()
+            .filter(|r| match run.save {
+                SaveFilter::Upsets => report.is_upset(r),
+                SaveFilter::Draws => r.winner.is_none(),
+                SaveFilter::All => true,
+            })
+            .collect();
+
+        if let Err(e) = save_replays(&game, &picked, &dir) {
+            eprintln!("{}", e);
+            return ExitCode::FAILURE;
+        }
+        println!("  saved {} replays to {}", picked.len(), dir.display());
+    }
+
+    ExitCode::SUCCESS
+}
+
+fn arena_game(game: Game) -> Result<ArenaGame, String> {
+    let game = match game {
+        Game::Snake { bots, width, height, wrap_around, max_food, food_probability, max_ticks, map, sequential_movement, row_spawns, input_buffer_size, mode, items } => {
+            let (width, height) = match &map {
+                Some(name) => {
+                    let map = find_map(name).ok_or_else(|| format!("Unknown map '{}'", name))?;
+                    (map.field_size.width as u32, map.field_size.height as u32)
+                }
+                None => (width, height),
+            };
+            let settings = SnakeLobbySettings {
+                field_width: width,
+                field_height: height,
+                wall_collision_mode: if wrap_around { WallCollisionMode::WrapAround } else { WallCollisionMode::Death } as i32,
+                tick_interval_ms: 100,
+                max_food_count: max_food,
+                food_spawn_probability: food_probability,
+                dead_snake_behavior: DeadSnakeBehavior::Disappear as i32,
+                golden_food_weight: items.golden_food_weight,
+                speed_boost_weight: items.speed_boost_weight,
+                ghost_weight: items.ghost_weight,
+                shrink_weight: items.shrink_weight,
+                reverse_controls_weight: items.reverse_controls_weight,
+                map_name: map.unwrap_or_default(),
+                map: None,
+                movement_resolution: if sequential_movement {
+                    MovementResolution::Sequential
+                } else {
+                    MovementResolution::Simultaneous
+                } as i32,
+                input_buffer_size: Some(input_buffer_size),
+                game_mode: match mode.mode {
+                    SnakeMode::LastStanding => SnakeGameMode::LastStanding,
+                    SnakeMode::TimeLimit => SnakeGameMode::TimeLimit,
+                    SnakeMode::ScoreTarget => SnakeGameMode::ScoreTarget,
+                    SnakeMode::BattleRoyale => SnakeGameMode::BattleRoyale,
+                } as i32,
+                time_limit_seconds: mode.time_limit_seconds,
+                score_target: mode.score_target,
+                shrink_interval_seconds: mode.shrink_interval_seconds,
+                teammates_pass_through: false,
+                team_members: Vec::new(),
+                spawn_placement: if row_spawns { SpawnPlacement::Row } else { SpawnPlacement::Spread } as i32,
+            };
+            settings.validate(bots.len() as u32)?;
+            ArenaGame::Snake {
+                settings: with_embedded_map(&settings)?,
+                bots: bots
+                    .into_iter()
+                    .map(|bot| match bot {
+                        SnakeBot::Efficient => SnakeBotType::Efficient,
+                        SnakeBot::Random => SnakeBotType::Random,
+                        SnakeBot::SpaceAware => SnakeBotType::SpaceAware,
+                    })
+                    .collect(),
+                max_ticks,
+            }
+        }
+        Game::Tictactoe { bots, levels, width, height, win_count, random_first, win_rule, swap2 } => {
+            let bot = |bot: TicTacToeBot| match bot {
+                TicTacToeBot::Minimax => TicTacToeBotType::TictactoeBotTypeMinimax,
+                TicTacToeBot::Random => TicTacToeBotType::TictactoeBotTypeRandom,
+            };
+            let strength = |level: TicTacToeLevel| {
+                BotStrength::preset(match level {
+                    TicTacToeLevel::Easy => TicTacToeBotLevel::TictactoeBotLevelEasy,
+                    TicTacToeLevel::Medium => TicTacToeBotLevel::TictactoeBotLevelMedium,
+                    TicTacToeLevel::Hard => TicTacToeBotLevel::TictactoeBotLevelHard,
+                })
+            };
+            let settings = TicTacToeLobbySettings {
+                field_width: width,
+                field_height: height,
+                win_count,
+                first_player: if random_first { FirstPlayerMode::Random } else { FirstPlayerMode::Host } as i32,
+                win_rule: match win_rule {
+                    TicTacToeWinRule::Freestyle => WinRule::Freestyle,
+                    TicTacToeWinRule::Exact => WinRule::Exact,
+                    TicTacToeWinRule::Renju => WinRule::Renju,
+                } as i32,
+                opening: if swap2 { OpeningRule::Swap2 } else { OpeningRule::Standard } as i32,
+                ..Default::default()
+            };
+            settings.validate(2)?;
+            ArenaGame::TicTacToe {
+                settings,
+                bots: [bot(bots[0]), bot(bots[1])],
+                strengths: [strength(levels[0]), strength(levels[1])],
+            }
+        }
+    };
+    Ok(game)
+}
+
+fn save_replays(game: &ArenaGame, results: &[&MatchResult], dir: &Path) -> Result<(), String> {
+    std::fs::create_dir_all(dir).map_err(|e| format!("{}: {}", dir.display(), e))?;
+    for result in results {
+        let path = dir.join(format!("seed_{}.{}", result.seed, REPLAY_FILE_EXTENSION));
+        save_replay(&path, &game.record(result.seed)).map_err(|e| format!("{}: {}", path.display(), e))?;
+    }
+    Ok(())
+}
@@ -0,0 +1,182 @@
+use std::path::{Path, PathBuf};
+use std::process::ExitCode;
+
+use clap::{Parser, Subcommand, ValueEnum};
+use mini_games_server::replay::gomoku_notation::{
+    gomoku_game_from_replay, parse_pgn, parse_psq, replay_from_gomoku_game, to_pgn, to_psq,
+};
+use mini_games_server::replay::json_export::replay_to_json;
+use mini_games_server::replay::listing::describe_result;
+use mini_games_server::replay::session::replay_game_type_name;
+use mini_games_server::replay::verify::verify_replay;
+use mini_games_server::replay::{REPLAY_FILE_EXTENSION, load_replay, load_replay_metadata, save_replay};
+use mini_games_server::ReplayGame;
+
+#[derive(Parser)]
+#[command(name = "replay_tool")]
+struct Args {
+    #[command(subcommand)]
+    command: Command,
+}
+
+#[derive(Subcommand)]
+enum Command {
+    /// Prints each replay's game and result without simulating it.
+    List {
+        /// Replay files, or directories searched recursively for replay files.
+        #[arg(required = true)]
+        paths: Vec<PathBuf>,
+    },
+    /// Re-simulates recorded replays and reports any that no longer reproduce.
+    Verify {
+        /// Replay files, or directories searched recursively for replay files.
+        #[arg(required = true)]
+        paths: Vec<PathBuf>,
+    },
+    /// Writes a replay in a readable format to stdout.
+    Export {
+        replay: PathBuf,
+        #[arg(long, value_enum, default_value_t = ExportFormat::Json)]
+        format: ExportFormat,
+    },
+    /// Creates a 15x15 five-in-a-row TicTacToe replay from a Gomoku record.
+    Import {
+        input: PathBuf,
+        #[arg(long, value_enum)]
+        format: GomokuFormat,
+        #[arg(long, short)]
+        output: PathBuf,
+    },
+}
+
+#[derive(Clone, Copy, ValueEnum)]
+enum ExportFormat {
+    Json,
+    Psq,
+    Pgn,
+}
+
+#[derive(Clone, Copy, ValueEnum)]
+enum GomokuFormat {
+    Psq,
+    Pgn,
+}
+
+fn main() -> ExitCode {
+    let args = Args::parse();
+    mini_games_server::logger::init_silent_logger();
+
+    let result = match args.command {
+        Command::List { paths } => list(&paths),
+        Command::Verify { paths } => return verify(&paths),
+        Command::Export { replay, format } => export(&replay, format),
+        Command::Import { input, format, output } => import(&input, format, &output),
+    };
+
+    match result {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(e) => {
+            eprintln!("{}", e);
+            ExitCode::FAILURE
+        }
+    }
+}
+
+fn list(paths: &[PathBuf]) -> Result<(), String> {
+    for file in collect_all_replay_files(paths)? {
+        match load_replay_metadata(&file) {
+            Ok(metadata) => {
+                let game = ReplayGame::try_from(metadata.game).unwrap_or(ReplayGame::Unspecified);
+                let result = describe_result(&metadata).unwrap_or_else(|| "unfinished".to_string());
+                println!("{}: {}, {}", file.display(), replay_game_type_name(game), result);
+            }
+            Err(e) => println!("{}: MALFORMED: {}", file.display(), e),
+        }
+    }
+    Ok(())
+}
+
+fn verify(paths: &[PathBuf]) -> ExitCode {
+    let files = match collect_all_replay_files(paths) {
+        Ok(files) => files,
+        Err(e) => {
+            eprintln!("{}", e);
+            return ExitCode::FAILURE;
+        }
+    };
+
+    let mut failures = 0;
+    for file in &files {
+        let result = load_replay(file)
+            .map_err(|e| e.to_string())
+            .and_then(verify_replay);
+
+        match result {
+            Ok(summary) => {
+                if summary.desync_position.is_some() {
+                    failures += 1;
+                }
+                println!("{}: {}", file.display(), summary);
+            }
+            Err(e) => {
+                failures += 1;
+                println!("{}: MALFORMED: {}", file.display(), e);
+            }
+        }
+    }
+
+    println!("{} replays checked, {} failed", files.len(), failures);
+    if failures > 0 { ExitCode::FAILURE } else { ExitCode::SUCCESS }
+}
+
+fn export(path: &Path, format: ExportFormat) -> Result<(), String> {
+    let replay = load_replay(path).map_err(|e| format!("{}: {}", path.display(), e))?;
+    let output = match format {
+        ExportFormat::Json => replay_to_json(&replay)?,
+        ExportFormat::Psq => to_psq(&gomoku_game_from_replay(&replay)?),
+        ExportFormat::Pgn => to_pgn(&gomoku_game_from_replay(&replay)?),
+    };
+    print!("{}", output);
+    Ok(())
+}
+
+fn import(input: &Path, format: GomokuFormat, output: &Path) -> Result<(), String> {
+    let text = std::fs::read_to_string(input).map_err(|e| format!("{}: {}", input.display(), e))?;
+    let game = match format {
+        GomokuFormat::Psq => parse_psq(&text)?,
+        GomokuFormat::Pgn => parse_pgn(&text)?,
+    };
+    let replay = replay_from_gomoku_game(&game)?;
+    save_replay(output, &replay).map_err(|e| format!("{}: {}", output.display(), e))?;
+    println!("{}: {} moves, {} vs {}, {}", output.display(), game.moves.len(), game.black, game.white, game.result);
+    Ok(())
+}
+
+fn collect_all_replay_files(paths: &[PathBuf]) -> Result<Vec<PathBuf>, String> {
+    let mut files = Vec::new();
+    for path in paths {
+        collect_replay_files(path, &mut files).map_err(|e| format!("{}: {}", path.display(), e))?;
+    }
+    Ok(files)
+}
+
+fn collect_replay_files(path: &Path, files: &mut Vec<PathBuf>) -> std::io::Result<()> {
+    if !path.is_dir() {
+        files.push(path.to_path_buf());
+        return Ok(());
+    }
+
+    let mut entries: Vec<PathBuf> = std::fs::read_dir(path)?
+        .map(|entry| entry.map(|e| e.path()))
+        .collect::<Result<_, _>>()?;
+    entries.sort();
+
+    for entry in entries {
+        if entry.is_dir() {
+            collect_replay_files(&entry, files)?;
+        } else if entry.extension().is_some_and(|ext| ext == REPLAY_FILE_EXTENSION) {
+            files.push(entry);
+        }
+    }
+    Ok(())
+}
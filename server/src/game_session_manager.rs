@@ -1,22 +1,33 @@
 use std::collections::HashMap;
+use std::future::Future;
+use std::pin::Pin;
 use std::sync::Arc;
 use tokio::sync::Mutex;
 
-use crate::{ClientId, LobbyId, PlayerId, log, ServerMessage, server_message, InGameCommand, InReplayCommand, ReplayFileReadyNotification};
+use crate::{ClientId, LobbyId, PlayerId, TournamentId, log, ServerMessage, server_message, InGameCommand, InReplayCommand, ReplayFileReadyNotification};
 use crate::games::{GameResolver, GameSession, GameSessionConfig, ReplayMode};
 use crate::replay::{generate_replay_filename, save_replay_to_bytes, REPLAY_VERSION};
 use crate::broadcaster::Broadcaster;
-use crate::lobby::{LobbyManager, LobbySettings, PlayAgainStatus};
+use crate::lobby::{LobbyManager, LobbySettings, LobbyStateAfterLeave, PlayAgainStatus};
+use crate::replay::branch;
 use crate::replay::session::{self as replay_session, ReplaySessionHandle, ReplaySessionCommand};
+use crate::tournament::TournamentManager;
 
 pub type SessionId = String;
 
+#[derive(Clone)]
+struct RunningSession {
+    session: GameSession,
+    config: GameSessionConfig,
+}
+
 pub struct GameSessionManager {
-    sessions: Arc<Mutex<HashMap<SessionId, GameSession>>>,
+    sessions: Arc<Mutex<HashMap<SessionId, RunningSession>>>,
     client_to_session: Arc<Mutex<HashMap<ClientId, SessionId>>>,
     replay_sessions: Arc<Mutex<HashMap<SessionId, ReplaySessionHandle>>>,
     broadcaster: Broadcaster,
     lobby_manager: LobbyManager,
+    tournament_manager: TournamentManager,
 }
 
 impl std::fmt::Debug for GameSessionManager {
@@ -26,13 +37,14 @@ impl std::fmt::Debug for GameSessionManager {
 }
 
 impl GameSessionManager {
-    pub fn new(broadcaster: Broadcaster, lobby_manager: LobbyManager) -> Self {
+    pub fn new(broadcaster: Broadcaster, lobby_manager: LobbyManager, tournament_manager: TournamentManager) -> Self {
         Self {
             sessions: Arc::new(Mutex::new(HashMap::new())),
             client_to_session: Arc::new(Mutex::new(HashMap::new())),
             replay_sessions: Arc::new(Mutex::new(HashMap::new())),
             broadcaster,
             lobby_manager,
+            tournament_manager,
         }
     }
 
@@ -72,12 +84,15 @@ impl GameSessionManager {
             all_participants.join(", ")
         );
 
-        let config = GameSessionConfig {
-            session_id: session_id.clone(),
-            human_players: human_players.clone(),
-            observers: lobby.observers.clone(),
-            bots: lobby.bots.clone(),
-        };
+        let mut config = GameSessionConfig::new(
+            session_id.clone(),
+            human_players.clone(),
+            lobby.observers.clone(),
+            lobby.bots.clone(),
+            lobby.spectator_delay,
+            lobby.bot_takeover,
+        );
+        config.teams = lobby.teams.clone();
 
         let seed: u64 = rand::random();
 
@@ -100,49 +115,354 @@ impl GameSessionManager {
         };
 
         match game_session {
-            Ok(session) => {
-                self.register_session(session_id.clone(), session.clone(), &human_players)
-                    .await;
+            Ok(session) => self.spawn_session(session_id, session, config).await,
+            Err(e) => {
+                log!("Failed to create game session: {}", e);
+            }
+        }
+    }
 
-                let manager = self.clone();
-                let broadcaster = self.broadcaster.clone();
+    async fn spawn_session(&self, session_id: SessionId, session: GameSession, config: GameSessionConfig) {
+        self.register_session(session_id, session.clone(), config.clone())
+            .await;
+
+        let manager = self.clone();
+        let broadcaster = self.broadcaster.clone();
 
-                tokio::spawn(async move {
-                    let notification = GameResolver::run(config.clone(), session, broadcaster).await;
-                    manager.handle_game_over(&config, notification).await;
-                });
+        tokio::spawn(async move {
+            let notification = GameResolver::run(config.clone(), session, broadcaster).await;
+            manager.handle_game_over(&config, notification).await;
+        });
+    }
+
+    pub async fn start_prepared_lobby(&self, lobby_id: &LobbyId) {
+        let Some(lobby_details) = self.lobby_manager.get_lobby_details(lobby_id).await else {
+            log!("Cannot start prepared lobby: lobby {} not found", lobby_id);
+            return;
+        };
+
+        self.broadcaster
+            .broadcast_to_lobby(
+                &lobby_details,
+                ServerMessage {
+                    message: Some(server_message::Message::LobbyUpdate(
+                        crate::LobbyUpdateNotification {
+                            details: Some(lobby_details.clone()),
+                        },
+                    )),
+                },
+            )
+            .await;
+
+        self.broadcaster
+            .broadcast_to_lobby(
+                &lobby_details,
+                ServerMessage {
+                    message: Some(server_message::Message::GameStarting(
+                        crate::GameStartingNotification {
+                            session_id: lobby_id.to_string(),
+                        },
+                    )),
+                },
+            )
+            .await;
+
+        self.create_session(lobby_id.to_string(), lobby_details).await;
+    }
+
+    pub async fn start_tournament_games(&self, tournament_id: &TournamentId) {
+        for game in self.tournament_manager.take_ready_games(tournament_id).await {
+            self.start_tournament_game(game).await;
+        }
+
+        self.broadcast_tournament_update(tournament_id).await;
+    }
+
+    async fn start_tournament_game(&self, game: crate::tournament::ScheduledGame) {
+        for player in &game.players {
+            self.close_finished_tournament_lobby(player).await;
+        }
+
+        let result = self
+            .lobby_manager
+            .create_started_lobby(
+                game.lobby_name.clone(),
+                game.settings.clone(),
+                2,
+                &game.players,
+                &[],
+                game.spectator_delay,
+            )
+            .await;
+
+        match result {
+            Ok(lobby_id) => {
+                log!("[tournament:{}] Starting {}", game.tournament_id, game.lobby_name);
+                self.tournament_manager.attach_lobby(&game, lobby_id.clone()).await;
+                self.start_prepared_lobby(&lobby_id).await;
             }
             Err(e) => {
-                log!("Failed to create game session: {}", e);
+                log!("[tournament:{}] Failed to start {}: {}", game.tournament_id, game.lobby_name, e);
+                self.tournament_manager.abort_game(&game).await;
             }
         }
     }
 
+    pub async fn broadcast_tournament_update(&self, tournament_id: &TournamentId) {
+        let Some(details) = self.tournament_manager.get_details(tournament_id).await else {
+            return;
+        };
+        let recipients = self.tournament_manager.get_recipients(tournament_id).await;
+
+        self.broadcaster
+            .broadcast_to_clients(
+                &recipients,
+                ServerMessage {
+                    message: Some(server_message::Message::TournamentUpdate(
+                        crate::TournamentUpdateNotification {
+                            details: Some(details),
+                        },
+                    )),
+                },
+            )
+            .await;
+    }
+
+    /// Closes the lobby of `client_id`'s last tournament game, if they are
+    /// still in it, so they can be seated for the next one.
+    async fn close_finished_tournament_lobby(&self, client_id: &ClientId) {
+        let Some(details) = self.lobby_manager.get_client_lobby(client_id).await else {
+            return;
+        };
+        let lobby_id = LobbyId::new(details.lobby_id);
+        if self.lobby_manager.get_lobby(&lobby_id).await.is_none_or(|lobby| lobby.in_game)
+            || !self.tournament_manager.release_finished_lobby(&lobby_id).await
+        {
+            return;
+        }
+
+        let members = self.lobby_manager.close_lobby(&lobby_id).await;
+        self.broadcaster
+            .broadcast_to_clients(
+                &members,
+                ServerMessage {
+                    message: Some(server_message::Message::LobbyClosed(
+                        crate::LobbyClosedNotification {
+                            message: "Tournament game finished".to_string(),
+                        },
+                    )),
+                },
+            )
+            .await;
+    }
+
+    // Boxed to break the async recursion through create_session -> handle_game_over.
+    fn handle_tournament_game_over<'a>(
+        &'a self,
+        lobby_id: &'a LobbyId,
+        winner: Option<crate::PlayerIdentity>,
+    ) -> Pin<Box<dyn Future<Output = ()> + Send + 'a>> {
+        Box::pin(async move {
+            let Some((tournament_id, next_game)) = self
+                .tournament_manager
+                .record_game_result(lobby_id, winner.as_ref())
+                .await
+            else {
+                return;
+            };
+
+            if let Some(game) = next_game {
+                self.start_tournament_game(game).await;
+            }
+
+            self.start_tournament_games(&tournament_id).await;
+        })
+    }
+
     async fn register_session(
         &self,
         session_id: SessionId,
         session: GameSession,
-        human_players: &[PlayerId],
+        config: GameSessionConfig,
     ) {
         let game_type = session.game_type();
-
-        let mut sessions = self.sessions.lock().await;
-        sessions.insert(session_id.clone(), session);
-        drop(sessions);
+        let player_count = config.human_players.len();
 
         let mut mapping = self.client_to_session.lock().await;
-        for player_id in human_players {
+        for player_id in &config.human_players {
             mapping.insert(ClientId::new(player_id.to_string()), session_id.clone());
         }
+        drop(mapping);
+
+        let mut sessions = self.sessions.lock().await;
+        sessions.insert(session_id.clone(), RunningSession { session, config });
+        drop(sessions);
 
         log!(
             "{:?} game session registered: {} with {} players",
             game_type,
             session_id,
-            human_players.len()
+            player_count
         );
     }
 
+    pub async fn add_observer(&self, lobby_id: &LobbyId, client_id: &ClientId) {
+        let sessions = self.sessions.lock().await;
+        let Some(running) = sessions.get(lobby_id.as_str()).cloned() else {
+            return;
+        };
+        drop(sessions);
+
+        self.broadcaster
+            .send_to_client(
+                client_id,
+                ServerMessage {
+                    message: Some(server_message::Message::GameStarting(
+                        crate::GameStartingNotification {
+                            session_id: lobby_id.to_string(),
+                        },
+                    )),
+                },
+            )
+            .await;
+
+        // Joined before the snapshot is taken so no update falls between the
+        // two; GameStarting has already gone out, so it still comes first.
+        if running.config.add_observer(PlayerId::new(client_id.to_string())).await {
+            log!("[session:{}] Observer {} joined running game", lobby_id, client_id);
+        }
+
+        let state_update = if running.config.spectator_delay == 0 {
+            Some(GameResolver::current_state(&running.session).await)
+        } else {
+            running.config.spectator_state().await
+        };
+        if let Some(state_update) = state_update {
+            self.broadcaster
+                .send_to_client(
+                    client_id,
+                    ServerMessage {
+                        message: Some(server_message::Message::GameState(state_update)),
+                    },
+                )
+                .await;
+        }
+    }
+
+    pub async fn remove_observer(&self, client_id: &ClientId) {
+        let configs: Vec<GameSessionConfig> = {
+            let sessions = self.sessions.lock().await;
+            sessions.values().map(|running| running.config.clone()).collect()
+        };
+
+        let player_id = PlayerId::new(client_id.to_string());
+        for config in configs {
+            if config.remove_observer(&player_id).await {
+                log!("[session:{}] Observer {} left running game", config.session_id, client_id);
+            }
+        }
+    }
+
+    pub async fn broadcast_leave_lobby_result(
+        &self,
+        client_id: &ClientId,
+        leave_state: LobbyStateAfterLeave,
+    ) {
+        match leave_state {
+            LobbyStateAfterLeave::HostLeft { kicked_players } => {
+                for kicked_id in &kicked_players {
+                    self.remove_observer(kicked_id).await;
+                }
+                self.broadcaster
+                    .broadcast_to_clients(
+                        &kicked_players,
+                        ServerMessage {
+                            message: Some(server_message::Message::LobbyClosed(
+                                crate::LobbyClosedNotification {
+                                    message: "Lobby closed".to_string(),
+                                },
+                            )),
+                        },
+                    )
+                    .await;
+                self.notify_lobby_list_update().await;
+            }
+            LobbyStateAfterLeave::LobbyStillActive { updated_details } => {
+                self.broadcaster
+                    .broadcast_to_lobby(
+                        &updated_details,
+                        ServerMessage {
+                            message: Some(server_message::Message::PlayerLeft(
+                                crate::PlayerLeftNotification {
+                                    player: Some(crate::PlayerIdentity {
+                                        player_id: client_id.to_string(),
+                                        is_bot: false,
+                                    }),
+                                },
+                            )),
+                        },
+                    )
+                    .await;
+
+                self.broadcaster
+                    .broadcast_to_lobby(
+                        &updated_details,
+                        ServerMessage {
+                            message: Some(server_message::Message::LobbyUpdate(
+                                crate::LobbyUpdateNotification {
+                                    details: Some(updated_details.clone()),
+                                },
+                            )),
+                        },
+                    )
+                    .await;
+
+                self.broadcaster
+                    .broadcast_to_lobby(
+                        &updated_details,
+                        ServerMessage {
+                            message: Some(server_message::Message::PlayAgainStatus(
+                                crate::PlayAgainStatusNotification {
+                                    ready_players: vec![],
+                                    pending_players: vec![],
+                                    available: false,
+                                },
+                            )),
+                        },
+                    )
+                    .await;
+
+                self.notify_lobby_list_update().await;
+            }
+        }
+    }
+
+    pub async fn notify_lobby_list_update(&self) {
+        let clients_not_in_lobbies = self.lobby_manager.get_clients_not_in_lobbies().await;
+        self.broadcaster
+            .broadcast_to_clients(
+                &clients_not_in_lobbies,
+                ServerMessage {
+                    message: Some(server_message::Message::LobbyListUpdate(
+                        crate::LobbyListUpdateNotification {},
+                    )),
+                },
+            )
+            .await;
+    }
+
+    /// Removes players who disconnected while a bot held their seat, now that
+    /// there is no game left to come back to.
+    async fn release_held_seats(&self, lobby_id: &LobbyId) {
+        for client_id in self.lobby_manager.release_held_seats(lobby_id).await {
+            log!("Releasing held seat of {} in lobby {}", client_id, lobby_id);
+            if let Ok(leave_state) = self.lobby_manager.leave_lobby(&client_id).await {
+                self.broadcast_leave_lobby_result(&client_id, leave_state).await;
+            }
+            self.lobby_manager.remove_client(&client_id).await;
+        }
+    }
+
     async fn handle_game_over(
         &self,
         config: &GameSessionConfig,
@@ -186,18 +506,11 @@ impl GameSessionManager {
             scores_str.join(", ")
         );
 
-        let client_ids: Vec<ClientId> = config
-            .human_players
-            .iter()
-            .map(|p| ClientId::new(p.to_string()))
-            .chain(
-                config
-                    .observers
-                    .iter()
-                    .map(|p| ClientId::new(p.to_string())),
-            )
-            .collect();
+        config.flush_spectator_feed(&self.broadcaster).await;
+        let client_ids = config.get_all_recipients().await;
 
+        let winner = notification.winner.clone();
+        let replay_result = notification.clone();
         let game_over_msg = ServerMessage {
             message: Some(server_message::Message::GameOver(notification)),
         };
@@ -206,7 +519,7 @@ impl GameSessionManager {
             .broadcast_to_clients(&client_ids, game_over_msg)
             .await;
 
-        if let Some(replay_notification) = self.finalize_replay(&config.session_id).await {
+        if let Some(replay_notification) = self.finalize_replay(&config.session_id, replay_result).await {
             let replay_msg = ServerMessage {
                 message: Some(server_message::Message::ReplayFile(replay_notification)),
             };
@@ -216,7 +529,19 @@ impl GameSessionManager {
         }
 
         let lobby_id = LobbyId::new(config.session_id.clone());
-        match self.lobby_manager.end_game(&lobby_id).await {
+        if self.tournament_manager.is_tournament_lobby(&lobby_id).await {
+            if let Err(e) = self.lobby_manager.end_game(&lobby_id).await {
+                log!("Failed to end game for lobby {}: {}", config.session_id, e);
+            }
+            self.remove_session(&config.session_id).await;
+            self.handle_tournament_game_over(&lobby_id, winner).await;
+            return;
+        }
+
+        let end_result = self.lobby_manager.end_game(&lobby_id).await;
+        self.release_held_seats(&lobby_id).await;
+
+        match end_result {
             Ok(_player_ids) => {
                 log!(
                     "Game ended for lobby {}, {} players in lobby",
@@ -282,9 +607,13 @@ impl GameSessionManager {
         self.remove_session(&config.session_id).await;
     }
 
-    async fn finalize_replay(&self, session_id: &SessionId) -> Option<ReplayFileReadyNotification> {
+    async fn finalize_replay(
+        &self,
+        session_id: &SessionId,
+        result: crate::GameOverNotification,
+    ) -> Option<ReplayFileReadyNotification> {
         let sessions = self.sessions.lock().await;
-        let session = sessions.get(session_id)?;
+        let session = &sessions.get(session_id)?.session;
 
         let game_type = session.game_type();
         let replay_recorder = session.replay_recorder()?;
@@ -292,7 +621,7 @@ impl GameSessionManager {
 
         let replay = {
             let mut recorder = replay_recorder.lock().await;
-            recorder.finalize()
+            recorder.finalize(Some(result))
         };
 
         let suggested_file_name = generate_replay_filename(game_type, crate::version::VERSION);
@@ -341,13 +670,14 @@ impl GameSessionManager {
         host_only_control: bool,
     ) -> Result<(), String> {
         let replay = replay_session::parse_replay(replay_bytes)?;
+        crate::replay::verify::check_rng_algorithm(&replay)?;
         let game_type = replay_session::replay_game_type(&replay)?;
         let game_name = replay_session::replay_game_type_name(game_type);
 
         let player = crate::replay::ReplayPlayer::new(replay.clone());
         let lobby_settings = player
             .lobby_settings()
-            .map(|s| LobbySettings::from_proto(Some(*s)))
+            .map(|s| LobbySettings::from_proto(Some(s.clone())))
             .transpose()?
             .unwrap_or(LobbySettings::Snake(crate::SnakeLobbySettings {
                 field_width: 15,
@@ -357,6 +687,7 @@ impl GameSessionManager {
                 max_food_count: 5,
                 food_spawn_probability: 0.5,
                 dead_snake_behavior: 0,
+                ..Default::default()
             }));
 
         let lobby_name = format!("Replay: {}", game_name);
@@ -395,6 +726,7 @@ impl GameSessionManager {
             command_tx,
             host_id: host_id.clone(),
             host_only_control,
+            replay: replay.clone(),
         };
 
         {
@@ -455,6 +787,135 @@ impl GameSessionManager {
         }
     }
 
+    /// Ends the host's replay and continues it as a live game from `position`,
+    /// seating the viewers and filling the remaining seats with bots.
+    pub async fn branch_replay(&self, client_id: &ClientId, position: u64) -> Result<(), String> {
+        let replay_session_id = self
+            .client_to_session
+            .lock()
+            .await
+            .get(client_id)
+            .cloned()
+            .ok_or("Not watching a replay")?;
+
+        let replay = {
+            let replay_sessions = self.replay_sessions.lock().await;
+            let handle = replay_sessions.get(&replay_session_id).ok_or("Not watching a replay")?;
+            if *client_id != handle.host_id {
+                return Err("Only the host can branch a replay".to_string());
+            }
+            handle.replay.clone()
+        };
+
+        let replay_lobby_id = LobbyId::new(replay_session_id.clone());
+        let replay_lobby = self
+            .lobby_manager
+            .get_lobby(&replay_lobby_id)
+            .await
+            .ok_or("Replay lobby not found")?;
+
+        let mut viewers: Vec<ClientId> = replay_lobby
+            .observers
+            .iter()
+            .map(|p| ClientId::new(p.to_string()))
+            .filter(|c| c != client_id)
+            .collect();
+        viewers.sort();
+        viewers.insert(0, client_id.clone());
+
+        let game = replay_session::replay_game_type(&replay)?;
+        let recorded = replay.metadata.as_ref().map(|m| m.players.clone()).unwrap_or_default();
+        let seats = branch::assign_seats(&recorded, &viewers);
+        let bot_type = crate::matchmaking::backfill_bot_type(&replay_lobby.settings);
+        let bot_count = seats.iter().filter(|s| s.is_none()).count();
+        if bot_count > 0 && bot_type.is_none() {
+            return Err(format!(
+                "{} has no bots to fill {} empty seats",
+                replay_session::replay_game_type_name(game),
+                bot_count
+            ));
+        }
+
+        // Check the position with the recorded players before anyone leaves the replay.
+        let dry_run_config = branch::branch_config(replay_session_id.clone(), &recorded, bot_type, Default::default());
+        branch::branch_session(replay.clone(), position, recorded.clone(), &dry_run_config)?;
+
+        self.replay_sessions.lock().await.remove(&replay_session_id);
+        self.lobby_manager.close_lobby(&replay_lobby_id).await;
+
+        let seated: Vec<ClientId> = seats.iter().flatten().cloned().collect();
+        let lobby_id = self
+            .lobby_manager
+            .create_started_lobby(
+                format!("Branch: {}", replay_session::replay_game_type_name(game)),
+                replay_lobby.settings.clone(),
+                seats.len() as u32,
+                &seated,
+                &bot_type.map(|t| vec![t; bot_count]).unwrap_or_default(),
+                0,
+            )
+            .await?;
+
+        for viewer in viewers.iter().filter(|v| !seated.contains(v)) {
+            if let Err(e) = self.lobby_manager.join_lobby(lobby_id.clone(), viewer.clone(), true).await {
+                log!("[lobby:{}] {} could not follow the branch: {}", lobby_id, viewer, e);
+            }
+        }
+
+        let lobby = self.lobby_manager.get_lobby(&lobby_id).await.ok_or("Branch lobby not found")?;
+        let mut bot_ids = lobby.bots.keys();
+        let players: Vec<crate::PlayerIdentity> = seats
+            .iter()
+            .map(|seat| match seat {
+                Some(client) => crate::PlayerIdentity { player_id: client.to_string(), is_bot: false },
+                None => crate::PlayerIdentity {
+                    player_id: bot_ids.next().map(|b| b.to_string()).unwrap_or_default(),
+                    is_bot: true,
+                },
+            })
+            .collect();
+
+        let config = branch::branch_config(lobby_id.to_string(), &players, bot_type, lobby.observers.clone());
+        let session = match branch::branch_session(replay, position, players, &config) {
+            Ok(session) => session,
+            Err(e) => {
+                self.lobby_manager.close_lobby(&lobby_id).await;
+                return Err(e);
+            }
+        };
+
+        log!("[session:{}] Branched from replay {} at {}", lobby_id, replay_session_id, position);
+
+        let lobby_details = lobby.to_details();
+        self.broadcaster
+            .broadcast_to_lobby(
+                &lobby_details,
+                ServerMessage {
+                    message: Some(server_message::Message::LobbyUpdate(
+                        crate::LobbyUpdateNotification {
+                            details: Some(lobby_details.clone()),
+                        },
+                    )),
+                },
+            )
+            .await;
+        self.broadcaster
+            .broadcast_to_lobby(
+                &lobby_details,
+                ServerMessage {
+                    message: Some(server_message::Message::GameStarting(
+                        crate::GameStartingNotification {
+                            session_id: lobby_id.to_string(),
+                        },
+                    )),
+                },
+            )
+            .await;
+
+        self.spawn_session(lobby_id.to_string(), session, config).await;
+        Ok(())
+    }
+
     pub async fn handle_command(&self, client_id: &ClientId, command: InGameCommand) {
         let mapping = self.client_to_session.lock().await;
         let session_id = match mapping.get(client_id) {
@@ -465,7 +926,7 @@ impl GameSessionManager {
 
         let sessions = self.sessions.lock().await;
         let session = match sessions.get(&session_id) {
-            Some(s) => s.clone(),
+            Some(running) => running.session.clone(),
             None => return,
         };
         drop(sessions);
@@ -473,20 +934,64 @@ impl GameSessionManager {
         GameResolver::handle_command(&session, client_id, command).await;
     }
 
-    pub async fn handle_player_disconnect(&self, client_id: &ClientId) {
+    /// Returns true if a bot now holds the player's seat until they reconnect.
+    pub async fn handle_player_disconnect(&self, client_id: &ClientId) -> bool {
         let mapping = self.client_to_session.lock().await;
         let session_id = match mapping.get(client_id) {
             Some(id) => id.clone(),
-            None => return,
+            None => return false,
+        };
+        drop(mapping);
+
+        let sessions = self.sessions.lock().await;
+        let Some(running) = sessions.get(&session_id) else {
+            return false;
+        };
+        let session = running.session.clone();
+        drop(sessions);
+        GameResolver::handle_player_disconnect(&session, client_id).await
+    }
+
+    /// Puts a reconnecting player back in control of a seat a bot was playing for.
+    pub async fn handle_player_reconnect(&self, client_id: &ClientId) {
+        let mapping = self.client_to_session.lock().await;
+        let Some(session_id) = mapping.get(client_id).cloned() else {
+            return;
         };
         drop(mapping);
 
         let sessions = self.sessions.lock().await;
-        if let Some(session) = sessions.get(&session_id) {
-            let session = session.clone();
-            drop(sessions);
-            GameResolver::handle_player_disconnect(&session, client_id).await;
+        let Some(running) = sessions.get(&session_id).cloned() else {
+            return;
+        };
+        drop(sessions);
+
+        if !GameResolver::return_control(&running.session, client_id).await {
+            return;
         }
+
+        log!("[session:{}] Player {} reconnected", session_id, client_id);
+
+        self.broadcaster
+            .send_to_client(
+                client_id,
+                ServerMessage {
+                    message: Some(server_message::Message::GameStarting(
+                        crate::GameStartingNotification { session_id },
+                    )),
+                },
+            )
+            .await;
+
+        let state_update = GameResolver::current_state(&running.session).await;
+        self.broadcaster
+            .send_to_client(
+                client_id,
+                ServerMessage {
+                    message: Some(server_message::Message::GameState(state_update)),
+                },
+            )
+            .await;
     }
 }
 
@@ -498,6 +1003,7 @@ impl Clone for GameSessionManager {
             replay_sessions: self.replay_sessions.clone(),
             broadcaster: self.broadcaster.clone(),
             lobby_manager: self.lobby_manager.clone(),
+            tournament_manager: self.tournament_manager.clone(),
         }
     }
 }
@@ -0,0 +1,71 @@
+use std::collections::{HashMap, HashSet};
+
+use crate::PlayerId;
+
+pub use crate::lobby::BotTakeover;
+
+#[derive(Debug, Default)]
+pub struct TakeoverTracker {
+    idle_counts: HashMap<PlayerId, u32>,
+    controlled: HashSet<PlayerId>,
+}
+
+impl TakeoverTracker {
+    pub fn is_controlled(&self, player_id: &PlayerId) -> bool {
+        self.controlled.contains(player_id)
+    }
+
+    /// Returns false if a bot was already playing for this player.
+    pub fn take_over(&mut self, player_id: &PlayerId) -> bool {
+        self.idle_counts.remove(player_id);
+        self.controlled.insert(player_id.clone())
+    }
+
+    /// Resets the idle count and hands control back; returns true if a bot was playing.
+    pub fn record_input(&mut self, player_id: &PlayerId) -> bool {
+        self.idle_counts.remove(player_id);
+        self.controlled.remove(player_id)
+    }
+
+    /// Counts one tick or turn without input; returns true once `idle_limit` is reached.
+    pub fn count_idle(&mut self, player_id: &PlayerId, idle_limit: u32) -> bool {
+        if idle_limit == 0 || self.controlled.contains(player_id) {
+            return false;
+        }
+        let idle = self.idle_counts.entry(player_id.clone()).or_insert(0);
+        *idle += 1;
+        *idle >= idle_limit
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_count_idle_limit_reached_until_input() {
+        let mut tracker = TakeoverTracker::default();
+        let player = PlayerId::new("player".to_string());
+
+        assert!(!tracker.count_idle(&player, 2));
+        assert!(tracker.count_idle(&player, 2));
+        assert!(tracker.take_over(&player));
+        assert!(!tracker.count_idle(&player, 2));
+        assert!(tracker.is_controlled(&player));
+
+        assert!(tracker.record_input(&player));
+        assert!(!tracker.is_controlled(&player));
+        assert!(!tracker.count_idle(&player, 2));
+        assert!(!tracker.record_input(&player));
+    }
+
+    #[test]
+    fn test_count_idle_zero_limit_never_triggers() {
+        let mut tracker = TakeoverTracker::default();
+        let player = PlayerId::new("player".to_string());
+
+        for _ in 0..100 {
+            assert!(!tracker.count_idle(&player, 0));
+        }
+    }
+}
@@ -1,3 +1,4 @@
+mod bot_takeover;
 mod bot_type;
 mod broadcaster;
 mod game_session;
@@ -6,6 +7,7 @@ mod replay_mode;
 mod resolver;
 mod session_config;
 mod session_rng;
+mod state_hash;
 
 pub mod numbers_match;
 pub mod puzzle2048;
@@ -13,6 +15,7 @@ pub mod snake;
 pub mod stack_attack;
 pub mod tictactoe;
 
+pub use bot_takeover::{BotTakeover, TakeoverTracker};
 pub use bot_type::BotType;
 pub use broadcaster::GameBroadcaster;
 pub use game_session::GameSession;
@@ -20,4 +23,5 @@ pub use lobby_settings::LobbySettings;
 pub use replay_mode::ReplayMode;
 pub use resolver::GameResolver;
 pub use session_config::GameSessionConfig;
-pub use session_rng::SessionRng;
+pub use session_rng::{LEGACY_RNG_ALGORITHM, RNG_ALGORITHM, RngStream, SessionRng};
+pub use state_hash::StateHasher;
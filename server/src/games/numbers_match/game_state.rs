@@ -3,9 +3,11 @@ use super::types::{
     GameEvent, GameStatus, HintMode, HintResult, Position,
     HINT_BONUS_PER_REFILL, INITIAL_HINTS_LIMITED, INITIAL_REFILLS,
 };
+use crate::games::StateHasher;
 use crate::games::session_rng::SessionRng;
 use crate::proto::numbers_match as proto;
 
+#[derive(Clone)]
 pub struct NumbersMatchGameState {
     board: Board,
     hint_mode: HintMode,
@@ -291,6 +293,15 @@ impl NumbersMatchGameState {
             hint: Some(hint_oneof),
         }
     }
+
+    /// Events are left out: live sessions drain them on every broadcast, replays do not.
+    pub fn state_hash(&self) -> u64 {
+        let mut state = self.to_proto();
+        state.events.clear();
+        let mut hasher = StateHasher::new();
+        hasher.write_message(&state);
+        hasher.finish()
+    }
 }
 
 pub fn position_from_index(index: u32) -> Position {
@@ -2,27 +2,53 @@ use std::time::Duration;
 
 use tokio::sync::mpsc;
 
-use crate::games::numbers_match::{self as nm, HintMode, NumbersMatchGameState, position_from_index};
-use crate::games::SessionRng;
+use crate::games::numbers_match::{self as nm, HintMode, NumbersMatchGameState, NumbersMatchSessionState, position_from_index};
+use crate::games::{GameSession, GameSessionConfig, SessionRng};
 use crate::replay::ReplayPlayer;
+use crate::replay::branch::advance_to;
 use crate::replay::session::{
     ReplayCommandResult, ReplaySessionCommand, broadcast_state_and_replay_info,
-    handle_replay_command, wait_for_restart_or_stop,
+    handle_replay_command,
 };
+use crate::replay::timeline::{ReplaySimulation, ReplayTimeline};
+use crate::replay::verify::{ReplaySummary, play_to_end};
 use crate::{
-    ClientId, GameStateUpdate, PlayerAction, game_state_update, in_game_command, lobby_settings,
+    ClientId, GameStateUpdate, PlayerAction, ReplayGame, game_state_update, in_game_command, lobby_settings,
     player_action_content,
 };
-pub(crate) async fn run_replay(
-    mut player: ReplayPlayer,
-    command_rx: &mut mpsc::UnboundedReceiver<ReplaySessionCommand>,
-    viewers: &[ClientId],
-    host_only_control: bool,
-    broadcaster: &crate::broadcaster::Broadcaster,
-) -> bool {
+
+#[derive(Clone)]
+struct NumbersMatchReplay {
+    game_state: NumbersMatchGameState,
+    rng: SessionRng,
+    moves: u64,
+}
+
+impl ReplaySimulation for NumbersMatchReplay {
+    fn position(&self) -> u64 {
+        self.moves
+    }
+
+    fn is_finished(&self, player: &ReplayPlayer) -> bool {
+        player.is_finished() || self.game_state.status() != nm::GameStatus::InProgress
+    }
+
+    fn step(&mut self, player: &mut ReplayPlayer) {
+        if let Some(action) = player.next_action() {
+            apply_numbers_match_action(&mut self.game_state, action);
+            self.moves += 1;
+        }
+    }
+
+    fn state_hash(&self) -> u64 {
+        self.game_state.state_hash()
+    }
+}
+
+fn new_replay(player: &ReplayPlayer) -> Result<NumbersMatchReplay, String> {
     let settings = match player.lobby_settings() {
         Some(lobby_settings::Settings::NumbersMatch(s)) => *s,
-        _ => return false,
+        _ => return Err("Replay has no NumbersMatch settings".to_string()),
     };
 
     let hint_mode = match crate::proto::numbers_match::HintMode::try_from(settings.hint_mode) {
@@ -32,20 +58,71 @@ pub(crate) async fn run_replay(
         _ => HintMode::Limited,
     };
 
-    let mut rng = SessionRng::new(player.seed());
-    let mut game_state = NumbersMatchGameState::new(&mut rng, hint_mode);
+    let mut rng = player.rng();
+    Ok(NumbersMatchReplay {
+        game_state: NumbersMatchGameState::new(&mut rng, hint_mode),
+        rng,
+        moves: 0,
+    })
+}
+
+pub(crate) fn branch(
+    mut player: ReplayPlayer,
+    position: u64,
+    config: &GameSessionConfig,
+) -> Result<GameSession, String> {
+    let mut replay = new_replay(&player)?;
+    advance_to(&mut replay, &mut player, position);
+    if replay.game_state.status() != nm::GameStatus::InProgress {
+        return Err(format!("The game is already over at move {}", replay.moves));
+    }
+
+    let state = NumbersMatchSessionState::from_state(config, replay.game_state, replay.rng, replay.moves, None)?;
+    Ok(GameSession::NumbersMatch(state))
+}
+
+pub(crate) fn simulate(mut player: ReplayPlayer) -> Result<ReplaySummary, String> {
+    let mut replay = new_replay(&player)?;
+    let desync_position = play_to_end(&mut replay, &mut player);
+
+    let outcome = match replay.game_state.status() {
+        nm::GameStatus::Won => "Won",
+        nm::GameStatus::Lost => "Lost",
+        nm::GameStatus::InProgress => "Unfinished",
+    };
+
+    Ok(ReplaySummary {
+        game: ReplayGame::NumbersMatch,
+        ticks: replay.moves,
+        outcome: outcome.to_string(),
+        scores: Vec::new(),
+        desync_position,
+    })
+}
+
+pub(crate) async fn run_replay(
+    mut player: ReplayPlayer,
+    command_rx: &mut mpsc::UnboundedReceiver<ReplaySessionCommand>,
+    viewers: &[ClientId],
+    host_only_control: bool,
+    broadcaster: &crate::broadcaster::Broadcaster,
+) -> bool {
+    let Ok(mut replay) = new_replay(&player) else {
+        return false;
+    };
+    let mut timeline = ReplayTimeline::new(&replay, &player);
 
     let total_actions = player.total_actions() as u64;
-    let mut current_action: u64 = 0;
     let mut is_paused = false;
+    let mut is_finished = false;
     let mut speed = 1.0_f32;
     let mut pending_highlight: Option<(u32, u32)> = None;
 
     let base_delay_ms = 400.0;
     let highlight_delay_ms = 300.0;
 
-    let state_update = build_numbers_match_state(&game_state);
-    broadcast_state_and_replay_info(broadcaster, viewers, state_update, is_paused, current_action, total_actions, speed, false, host_only_control).await;
+    let state_update = build_numbers_match_state(&replay.game_state);
+    broadcast_state_and_replay_info(broadcaster, viewers, state_update, is_paused, replay.moves, total_actions, speed, is_finished, host_only_control, timeline.desync_position()).await;
 
     loop {
         let current_delay = if pending_highlight.is_some() {
@@ -56,91 +133,61 @@ pub(crate) async fn run_replay(
 
         tokio::select! {
             _ = tokio::time::sleep(current_delay) => {
-                if is_paused {
+                if is_paused || is_finished {
                     continue;
                 }
 
-                if pending_highlight.is_some() {
-                    if let Some(action) = player.next_action() {
-                        apply_numbers_match_action(&mut game_state, action);
-                        current_action += 1;
-                    }
-                    pending_highlight = None;
-
-                    let is_finished = player.is_finished() || game_state.status() != nm::GameStatus::InProgress;
-                    let state_update = build_numbers_match_state(&game_state);
-                    broadcast_state_and_replay_info(broadcaster, viewers, state_update, is_paused, current_action.min(total_actions), total_actions, speed, is_finished, host_only_control).await;
-
-                    if is_finished {
-                        return wait_for_restart_or_stop(command_rx).await;
-                    }
-                } else if let Some(action) = player.peek_next_action() {
-                    let highlight = extract_remove_pair_indices(action);
-                    if highlight.is_some() {
-                        pending_highlight = highlight;
-                        let state_update = build_numbers_match_state(&game_state);
-                        broadcast_state_and_replay_info(broadcaster, viewers, state_update, is_paused, current_action, total_actions, speed, false, host_only_control).await;
-                    } else {
-                        let action = player.next_action().unwrap();
-                        apply_numbers_match_action(&mut game_state, action);
-                        current_action += 1;
-
-                        let is_finished = player.is_finished() || game_state.status() != nm::GameStatus::InProgress;
-                        let state_update = build_numbers_match_state(&game_state);
-                        broadcast_state_and_replay_info(broadcaster, viewers, state_update, is_paused, current_action.min(total_actions), total_actions, speed, is_finished, host_only_control).await;
-
+                advance_with_highlight(&mut replay, &mut timeline, &mut player, &mut pending_highlight);
+                is_finished = replay.is_finished(&player);
+            }
+            cmd = command_rx.recv() => {
+                let Some(ReplaySessionCommand::ReplayCommand(replay_cmd)) = cmd else {
+                    return false;
+                };
+                match handle_replay_command(&replay_cmd, &mut is_paused, &mut speed) {
+                    ReplayCommandResult::StateChanged => {}
+                    ReplayCommandResult::SpeedChanged => continue,
+                    ReplayCommandResult::StepForward => {
                         if is_finished {
-                            return wait_for_restart_or_stop(command_rx).await;
+                            continue;
                         }
+                        advance_with_highlight(&mut replay, &mut timeline, &mut player, &mut pending_highlight);
                     }
-                } else {
-                    let state_update = build_numbers_match_state(&game_state);
-                    broadcast_state_and_replay_info(broadcaster, viewers, state_update, is_paused, current_action.min(total_actions), total_actions, speed, true, host_only_control).await;
-                    return wait_for_restart_or_stop(command_rx).await;
-                }
-            }
-            Some(cmd) = command_rx.recv() => {
-                match cmd {
-                    ReplaySessionCommand::ReplayCommand(replay_cmd) => {
-                        match handle_replay_command(&replay_cmd, &mut is_paused, &mut speed) {
-                            ReplayCommandResult::StateChanged => {
-                                let state_update = build_numbers_match_state(&game_state);
-                                broadcast_state_and_replay_info(broadcaster, viewers, state_update, is_paused, current_action, total_actions, speed, false, host_only_control).await;
-                            }
-                            ReplayCommandResult::SpeedChanged => {}
-                            ReplayCommandResult::StepForward => {
-                                if pending_highlight.is_some() {
-                                    if let Some(action) = player.next_action() {
-                                        apply_numbers_match_action(&mut game_state, action);
-                                        current_action += 1;
-                                    }
-                                    pending_highlight = None;
-                                } else if let Some(action) = player.peek_next_action() {
-                                    let highlight = extract_remove_pair_indices(action);
-                                    if highlight.is_some() {
-                                        pending_highlight = highlight;
-                                    } else {
-                                        let action = player.next_action().unwrap();
-                                        apply_numbers_match_action(&mut game_state, action);
-                                        current_action += 1;
-                                    }
-                                }
-                                let is_finished = player.is_finished() || game_state.status() != nm::GameStatus::InProgress;
-                                let state_update = build_numbers_match_state(&game_state);
-                                broadcast_state_and_replay_info(broadcaster, viewers, state_update, is_paused, current_action.min(total_actions), total_actions, speed, is_finished, host_only_control).await;
-
-                                if is_finished {
-                                    return wait_for_restart_or_stop(command_rx).await;
-                                }
-                            }
-                            ReplayCommandResult::Restart => return true,
-                            ReplayCommandResult::None => {}
-                        }
+                    ReplayCommandResult::StepBackward => {
+                        pending_highlight = None;
+                        timeline.step_back(&mut replay, &mut player);
                     }
+                    ReplayCommandResult::Seek(target) => {
+                        pending_highlight = None;
+                        timeline.seek(&mut replay, &mut player, target);
+                    }
+                    ReplayCommandResult::Restart => return true,
+                    ReplayCommandResult::None => continue,
                 }
+                is_finished = replay.is_finished(&player);
             }
         }
+
+        let state_update = build_numbers_match_state(&replay.game_state);
+        broadcast_state_and_replay_info(broadcaster, viewers, state_update, is_paused, replay.moves.min(total_actions), total_actions, speed, is_finished, host_only_control, timeline.desync_position()).await;
+    }
+}
+/// Pairs are highlighted for one step before they are removed.
+fn advance_with_highlight(
+    replay: &mut NumbersMatchReplay,
+    timeline: &mut ReplayTimeline<NumbersMatchReplay>,
+    player: &mut ReplayPlayer,
+    pending_highlight: &mut Option<(u32, u32)>,
+) {
+    if pending_highlight.take().is_none()
+        && let Some(action) = player.peek_next_action()
+    {
+        *pending_highlight = extract_remove_pair_indices(action);
+        if pending_highlight.is_some() {
+            return;
+        }
     }
+    timeline.step(replay, player);
 }
 fn build_numbers_match_state(game_state: &NumbersMatchGameState) -> GameStateUpdate {
     let proto_state = game_state.to_proto();
@@ -32,14 +32,25 @@ impl NumbersMatchSessionState {
         hint_mode: HintMode,
         seed: u64,
         replay_recorder: Option<Arc<Mutex<ReplayRecorder>>>,
+    ) -> Result<Self, String> {
+        let mut rng = SessionRng::new(seed);
+        let game_state = NumbersMatchGameState::new(&mut rng, hint_mode);
+
+        Self::from_state(config, game_state, rng, 0, replay_recorder)
+    }
+
+    /// Continues a game `moves` actions in.
+    pub fn from_state(
+        config: &GameSessionConfig,
+        game_state: NumbersMatchGameState,
+        rng: SessionRng,
+        moves: u64,
+        replay_recorder: Option<Arc<Mutex<ReplayRecorder>>>,
     ) -> Result<Self, String> {
         if config.human_players.len() != 1 {
             return Err("NumbersMatch requires exactly 1 player".to_string());
         }
 
-        let mut rng = SessionRng::new(seed);
-        let game_state = NumbersMatchGameState::new(&mut rng, hint_mode);
-
         let player_id = ClientId::new(config.human_players[0].to_string());
 
         Ok(Self {
@@ -49,7 +60,7 @@ impl NumbersMatchSessionState {
             action_notify: Arc::new(Notify::new()),
             replay_recorder,
             player_id,
-            tick: Arc::new(Mutex::new(0)),
+            tick: Arc::new(Mutex::new(moves)),
         })
     }
 }
@@ -62,8 +73,6 @@ impl NumbersMatchSession {
         state: &NumbersMatchSessionState,
         broadcaster: &B,
     ) -> GameOverNotification {
-        let recipients = config.get_all_recipients();
-
         loop {
             let (proto_state, status) = {
                 let mut game_state = state.game_state.lock().await;
@@ -78,9 +87,7 @@ impl NumbersMatchSession {
                     proto_state,
                 )),
             };
-            broadcaster
-                .broadcast_state(state_update, recipients.clone())
-                .await;
+            config.broadcast_state(broadcaster, state_update).await;
 
             match status {
                 GameStatus::Won => {
@@ -109,10 +116,10 @@ impl NumbersMatchSession {
             return;
         };
 
-        let result = {
+        let (result, state_hash) = {
             let mut game_state = state.game_state.lock().await;
 
-            match cmd {
+            let result = match cmd {
                 proto::numbers_match_in_game_command::Command::RemovePair(remove) => {
                     let pos1 = position_from_index(remove.first_index);
                     let pos2 = position_from_index(remove.second_index);
@@ -122,7 +129,8 @@ impl NumbersMatchSession {
                 proto::numbers_match_in_game_command::Command::RequestHint(_) => {
                     game_state.request_hint().map(|_| ())
                 }
-            }
+            };
+            (result, game_state.state_hash())
         };
 
         if result.is_ok() {
@@ -134,6 +142,8 @@ impl NumbersMatchSession {
                         command: Some(in_game_command::Command::NumbersMatch(command)),
                     };
                     recorder.record_command(*tick as i64, player_index, in_game_command);
+                    let position = recorder.actions_count() as u64;
+                    recorder.record_state_hash(position, state_hash);
                 }
             }
             *tick += 1;
@@ -143,6 +153,15 @@ impl NumbersMatchSession {
         }
     }
 
+    pub async fn current_state(state: &NumbersMatchSessionState) -> GameStateUpdate {
+        let game_state = state.game_state.lock().await;
+        GameStateUpdate {
+            state: Some(crate::proto::game_service::game_state_update::State::NumbersMatch(
+                game_state.to_proto(),
+            )),
+        }
+    }
+
     pub async fn handle_player_disconnect(state: &NumbersMatchSessionState) {
         let game_state = state.game_state.lock().await;
         if game_state.status() == GameStatus::InProgress {
@@ -1,7 +1,9 @@
 use super::types::{Direction, GameStatus};
+use crate::games::StateHasher;
 use crate::games::session_rng::SessionRng;
 use crate::proto::puzzle2048 as proto;
 
+#[derive(Clone)]
 pub struct Puzzle2048GameState {
     cells: Vec<u32>,
     width: usize,
@@ -194,6 +196,12 @@ impl Puzzle2048GameState {
     fn cells(&self) -> &[u32] {
         &self.cells
     }
+
+    pub fn state_hash(&self) -> u64 {
+        let mut hasher = StateHasher::new();
+        hasher.write_message(&self.to_proto());
+        hasher.finish()
+    }
 }
 
 fn slide_and_merge_line(line: &[u32]) -> (Vec<u32>, u32) {
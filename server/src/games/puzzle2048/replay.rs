@@ -2,97 +2,166 @@ use std::time::Duration;
 
 use tokio::sync::mpsc;
 
-use crate::games::SessionRng;
-use crate::games::puzzle2048::{Direction as Puzzle2048Direction, Puzzle2048GameState};
+use crate::games::{GameSession, GameSessionConfig, SessionRng};
+use crate::games::puzzle2048::{Direction as Puzzle2048Direction, Puzzle2048GameState, Puzzle2048SessionState};
 use crate::replay::ReplayPlayer;
+use crate::replay::branch::advance_to;
 use crate::replay::session::{
     ReplayCommandResult, ReplaySessionCommand, broadcast_state_and_replay_info,
-    handle_replay_command, wait_for_restart_or_stop,
+    handle_replay_command,
 };
+use crate::replay::timeline::{ReplaySimulation, ReplayTimeline};
+use crate::replay::verify::{ReplaySummary, play_to_end};
 use crate::{
-    ClientId, GameStateUpdate, PlayerAction, game_state_update, in_game_command, lobby_settings,
+    ClientId, GameStateUpdate, PlayerAction, ReplayGame, game_state_update, in_game_command, lobby_settings,
     player_action_content,
 };
-pub(crate) async fn run_replay(
-    mut player: ReplayPlayer,
-    command_rx: &mut mpsc::UnboundedReceiver<ReplaySessionCommand>,
-    viewers: &[ClientId],
-    host_only_control: bool,
-    broadcaster: &crate::broadcaster::Broadcaster,
-) -> bool {
+
+#[derive(Clone)]
+struct Puzzle2048Replay {
+    game_state: Puzzle2048GameState,
+    rng: SessionRng,
+    moves: u64,
+}
+
+impl ReplaySimulation for Puzzle2048Replay {
+    fn position(&self) -> u64 {
+        self.moves
+    }
+
+    fn is_finished(&self, player: &ReplayPlayer) -> bool {
+        player.is_finished() || self.game_state.status() != crate::games::puzzle2048::GameStatus::InProgress
+    }
+
+    fn step(&mut self, player: &mut ReplayPlayer) {
+        if let Some(action) = player.next_action() {
+            apply_puzzle2048_action(&mut self.game_state, action, &mut self.rng);
+            self.moves += 1;
+        }
+    }
+
+    fn state_hash(&self) -> u64 {
+        self.game_state.state_hash()
+    }
+}
+
+fn new_replay(player: &ReplayPlayer) -> Result<Puzzle2048Replay, String> {
     let settings = match player.lobby_settings() {
         Some(lobby_settings::Settings::Puzzle2048(s)) => *s,
-        _ => return false,
+        _ => return Err("Replay has no Puzzle2048 settings".to_string()),
     };
 
-    let mut rng = SessionRng::new(player.seed());
-    let mut game_state = Puzzle2048GameState::new(
+    let mut rng = player.rng();
+    let game_state = Puzzle2048GameState::new(
         settings.field_width as usize,
         settings.field_height as usize,
         settings.target_value,
         &mut rng,
     );
 
+    Ok(Puzzle2048Replay { game_state, rng, moves: 0 })
+}
+
+pub(crate) fn branch(
+    mut player: ReplayPlayer,
+    position: u64,
+    config: &GameSessionConfig,
+) -> Result<GameSession, String> {
+    let mut replay = new_replay(&player)?;
+    advance_to(&mut replay, &mut player, position);
+    if replay.game_state.status() != crate::games::puzzle2048::GameStatus::InProgress {
+        return Err(format!("The game is already over at move {}", replay.moves));
+    }
+
+    let state = Puzzle2048SessionState::from_state(config, replay.game_state, replay.rng, replay.moves, None)?;
+    Ok(GameSession::Puzzle2048(state))
+}
+
+pub(crate) fn simulate(mut player: ReplayPlayer) -> Result<ReplaySummary, String> {
+    let mut replay = new_replay(&player)?;
+    let desync_position = play_to_end(&mut replay, &mut player);
+
+    let outcome = match replay.game_state.status() {
+        crate::games::puzzle2048::GameStatus::Won => "Won",
+        crate::games::puzzle2048::GameStatus::Lost => "Lost",
+        crate::games::puzzle2048::GameStatus::InProgress => "Unfinished",
+    };
+    let scores = player
+        .players()
+        .first()
+        .map(|p| (p.player_id.clone(), replay.game_state.score()))
+        .into_iter()
+        .collect();
+
+    Ok(ReplaySummary {
+        game: ReplayGame::Puzzle2048,
+        ticks: replay.moves,
+        outcome: outcome.to_string(),
+        scores,
+        desync_position,
+    })
+}
+
+pub(crate) async fn run_replay(
+    mut player: ReplayPlayer,
+    command_rx: &mut mpsc::UnboundedReceiver<ReplaySessionCommand>,
+    viewers: &[ClientId],
+    host_only_control: bool,
+    broadcaster: &crate::broadcaster::Broadcaster,
+) -> bool {
+    let Ok(mut replay) = new_replay(&player) else {
+        return false;
+    };
+    let mut timeline = ReplayTimeline::new(&replay, &player);
     let total_actions = player.total_actions() as u64;
-    let mut current_action: u64 = 0;
     let mut is_paused = false;
+    let mut is_finished = false;
     let mut speed = 1.0_f32;
     let base_delay_ms = 400.0;
 
-    let state_update = build_puzzle2048_state(&game_state);
-    broadcast_state_and_replay_info(broadcaster, viewers, state_update, is_paused, current_action, total_actions, speed, false, host_only_control).await;
+    let state_update = build_puzzle2048_state(&replay.game_state);
+    broadcast_state_and_replay_info(broadcaster, viewers, state_update, is_paused, replay.moves, total_actions, speed, is_finished, host_only_control, timeline.desync_position()).await;
 
     loop {
         let current_delay = Duration::from_millis((base_delay_ms / speed) as u64);
 
         tokio::select! {
             _ = tokio::time::sleep(current_delay) => {
-                if is_paused {
+                if is_paused || is_finished {
                     continue;
                 }
 
-                if let Some(action) = player.next_action() {
-                    apply_puzzle2048_action(&mut game_state, action, &mut rng);
-                    current_action += 1;
-                }
-
-                let is_finished = player.is_finished() || game_state.status() != crate::games::puzzle2048::GameStatus::InProgress;
-                let state_update = build_puzzle2048_state(&game_state);
-                broadcast_state_and_replay_info(broadcaster, viewers, state_update, is_paused, current_action.min(total_actions), total_actions, speed, is_finished, host_only_control).await;
-
-                if is_finished {
-                    return wait_for_restart_or_stop(command_rx).await;
-                }
+                timeline.step(&mut replay, &mut player);
+                is_finished = replay.is_finished(&player);
             }
-            Some(cmd) = command_rx.recv() => {
-                match cmd {
-                    ReplaySessionCommand::ReplayCommand(replay_cmd) => {
-                        match handle_replay_command(&replay_cmd, &mut is_paused, &mut speed) {
-                            ReplayCommandResult::StateChanged => {
-                                let state_update = build_puzzle2048_state(&game_state);
-                                broadcast_state_and_replay_info(broadcaster, viewers, state_update, is_paused, current_action, total_actions, speed, false, host_only_control).await;
-                            }
-                            ReplayCommandResult::SpeedChanged => {}
-                            ReplayCommandResult::StepForward => {
-                                if let Some(action) = player.next_action() {
-                                    apply_puzzle2048_action(&mut game_state, action, &mut rng);
-                                    current_action += 1;
-                                }
-                                let is_finished = player.is_finished() || game_state.status() != crate::games::puzzle2048::GameStatus::InProgress;
-                                let state_update = build_puzzle2048_state(&game_state);
-                                broadcast_state_and_replay_info(broadcaster, viewers, state_update, is_paused, current_action.min(total_actions), total_actions, speed, is_finished, host_only_control).await;
-
-                                if is_finished {
-                                    return wait_for_restart_or_stop(command_rx).await;
-                                }
-                            }
-                            ReplayCommandResult::Restart => return true,
-                            ReplayCommandResult::None => {}
+            cmd = command_rx.recv() => {
+                let Some(ReplaySessionCommand::ReplayCommand(replay_cmd)) = cmd else {
+                    return false;
+                };
+                match handle_replay_command(&replay_cmd, &mut is_paused, &mut speed) {
+                    ReplayCommandResult::StateChanged => {}
+                    ReplayCommandResult::SpeedChanged => continue,
+                    ReplayCommandResult::StepForward => {
+                        if is_finished {
+                            continue;
                         }
+                        timeline.step(&mut replay, &mut player);
                     }
+                    ReplayCommandResult::StepBackward => {
+                        timeline.step_back(&mut replay, &mut player);
+                    }
+                    ReplayCommandResult::Seek(target) => {
+                        timeline.seek(&mut replay, &mut player, target);
+                    }
+                    ReplayCommandResult::Restart => return true,
+                    ReplayCommandResult::None => continue,
                 }
+                is_finished = replay.is_finished(&player);
             }
         }
+
+        let state_update = build_puzzle2048_state(&replay.game_state);
+        broadcast_state_and_replay_info(broadcaster, viewers, state_update, is_paused, replay.moves.min(total_actions), total_actions, speed, is_finished, host_only_control, timeline.desync_position()).await;
     }
 }
 fn build_puzzle2048_state(game_state: &Puzzle2048GameState) -> GameStateUpdate {
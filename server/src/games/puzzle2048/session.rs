@@ -34,14 +34,25 @@ impl Puzzle2048SessionState {
         target_value: u32,
         seed: u64,
         replay_recorder: Option<Arc<Mutex<ReplayRecorder>>>,
+    ) -> Result<Self, String> {
+        let mut rng = SessionRng::new(seed);
+        let game_state = Puzzle2048GameState::new(width, height, target_value, &mut rng);
+
+        Self::from_state(config, game_state, rng, 0, replay_recorder)
+    }
+
+    /// Continues a game `moves` actions in.
+    pub fn from_state(
+        config: &GameSessionConfig,
+        game_state: Puzzle2048GameState,
+        rng: SessionRng,
+        moves: u64,
+        replay_recorder: Option<Arc<Mutex<ReplayRecorder>>>,
     ) -> Result<Self, String> {
         if config.human_players.len() != 1 {
             return Err("Puzzle 2048 requires exactly 1 player".to_string());
         }
 
-        let mut rng = SessionRng::new(seed);
-        let game_state = Puzzle2048GameState::new(width, height, target_value, &mut rng);
-
         let player_id = ClientId::new(config.human_players[0].to_string());
 
         Ok(Self {
@@ -51,7 +62,7 @@ impl Puzzle2048SessionState {
             action_notify: Arc::new(Notify::new()),
             replay_recorder,
             player_id,
-            tick: Arc::new(Mutex::new(0)),
+            tick: Arc::new(Mutex::new(moves)),
         })
     }
 }
@@ -64,8 +75,6 @@ impl Puzzle2048Session {
         state: &Puzzle2048SessionState,
         broadcaster: &B,
     ) -> GameOverNotification {
-        let recipients = config.get_all_recipients();
-
         loop {
             let (proto_state, status) = {
                 let game_state = state.game_state.lock().await;
@@ -79,9 +88,7 @@ impl Puzzle2048Session {
                     crate::proto::game_service::game_state_update::State::Puzzle2048(proto_state),
                 ),
             };
-            broadcaster
-                .broadcast_state(state_update, recipients.clone())
-                .await;
+            config.broadcast_state(broadcaster, state_update).await;
 
             match status {
                 GameStatus::Won => {
@@ -110,11 +117,11 @@ impl Puzzle2048Session {
             return;
         };
 
-        let result = {
+        let (result, state_hash) = {
             let mut game_state = state.game_state.lock().await;
             let mut rng = state.rng.lock().await;
 
-            match cmd {
+            let result = match cmd {
                 proto::puzzle2048_in_game_command::Command::Move(move_cmd) => {
                     let direction = match move_cmd.direction() {
                         proto::Puzzle2048Direction::Up => Direction::Up,
@@ -126,7 +133,8 @@ impl Puzzle2048Session {
                     let changed = game_state.apply_move(direction, &mut rng);
                     if changed { Ok(()) } else { Err("No change") }
                 }
-            }
+            };
+            (result, game_state.state_hash())
         };
 
         if result.is_ok() {
@@ -138,6 +146,8 @@ impl Puzzle2048Session {
                         command: Some(in_game_command::Command::Puzzle2048(command)),
                     };
                     recorder.record_command(*tick as i64, player_index, in_game_command);
+                    let position = recorder.actions_count() as u64;
+                    recorder.record_state_hash(position, state_hash);
                 }
             }
             *tick += 1;
@@ -147,6 +157,15 @@ impl Puzzle2048Session {
         }
     }
 
+    pub async fn current_state(state: &Puzzle2048SessionState) -> GameStateUpdate {
+        let game_state = state.game_state.lock().await;
+        GameStateUpdate {
+            state: Some(
+                crate::proto::game_service::game_state_update::State::Puzzle2048(game_state.to_proto()),
+            ),
+        }
+    }
+
     pub async fn handle_player_disconnect(state: &Puzzle2048SessionState) {
         let game_state = state.game_state.lock().await;
         if game_state.status() == GameStatus::InProgress {
@@ -1,4 +1,4 @@
-use crate::{ClientId, GameOverNotification, InGameCommand, in_game_command};
+use crate::{ClientId, GameOverNotification, GameStateUpdate, InGameCommand, in_game_command};
 use crate::games::{
     GameBroadcaster, GameSession, GameSessionConfig, LobbySettings, ReplayMode,
     numbers_match::NumbersMatchSession,
@@ -72,7 +72,39 @@ impl GameResolver {
         }
     }
 
-    pub async fn handle_player_disconnect(session: &GameSession, client_id: &ClientId) {
+    pub async fn current_state(session: &GameSession) -> GameStateUpdate {
+        match session {
+            GameSession::Snake(state) => SnakeSession::current_state(state).await,
+            GameSession::TicTacToe(state) => TicTacToeSession::current_state(state).await,
+            GameSession::NumbersMatch(state) => NumbersMatchSession::current_state(state).await,
+            GameSession::StackAttack(state) => StackAttackSession::current_state(state).await,
+            GameSession::Puzzle2048(state) => Puzzle2048Session::current_state(state).await,
+        }
+    }
+
+    /// Hands the player's seat to a bot if the lobby enabled bot takeover.
+    pub async fn take_over(session: &GameSession, client_id: &ClientId) -> bool {
+        match session {
+            GameSession::Snake(state) => SnakeSession::take_over(state, client_id).await,
+            GameSession::TicTacToe(state) => TicTacToeSession::take_over(state, client_id).await,
+            _ => false,
+        }
+    }
+
+    pub async fn return_control(session: &GameSession, client_id: &ClientId) -> bool {
+        match session {
+            GameSession::Snake(state) => SnakeSession::return_control(state, client_id).await,
+            GameSession::TicTacToe(state) => TicTacToeSession::return_control(state, client_id).await,
+            _ => false,
+        }
+    }
+
+    /// Returns true if a bot now holds the player's seat for them.
+    pub async fn handle_player_disconnect(session: &GameSession, client_id: &ClientId) -> bool {
+        if Self::take_over(session, client_id).await {
+            return true;
+        }
+
         session.record_disconnect(client_id).await;
 
         match session {
@@ -92,5 +124,6 @@ impl GameResolver {
                 Puzzle2048Session::handle_player_disconnect(state).await;
             }
         }
+        false
     }
 }
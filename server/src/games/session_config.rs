@@ -1,28 +1,226 @@
-use std::collections::{HashMap, HashSet};
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::sync::Arc;
+use tokio::sync::Mutex;
 
-use crate::{BotId, ClientId, PlayerId};
-use crate::games::BotType;
+use crate::{BotId, ClientId, GameStateUpdate, PlayerId};
+use crate::games::{BotTakeover, BotType, GameBroadcaster};
+
+#[derive(Debug, Default)]
+struct SpectatorFeed {
+    pending: VecDeque<GameStateUpdate>,
+    last_released: Option<GameStateUpdate>,
+}
 
 #[derive(Debug, Clone)]
 pub struct GameSessionConfig {
     pub session_id: String,
     pub human_players: Vec<PlayerId>,
-    pub observers: HashSet<PlayerId>,
+    pub observers: Arc<Mutex<HashSet<PlayerId>>>,
     pub bots: HashMap<BotId, BotType>,
+    pub spectator_delay: u32,
+    pub bot_takeover: Option<BotTakeover>,
+    /// Teams the lobby host assigned; games without team play ignore them.
+    pub teams: HashMap<PlayerId, u32>,
+    spectator_feed: Arc<Mutex<SpectatorFeed>>,
 }
 
 impl GameSessionConfig {
-    pub fn get_all_recipients(&self) -> Vec<ClientId> {
-        let mut recipients: Vec<ClientId> = self
-            .human_players
+    pub fn new(
+        session_id: String,
+        human_players: Vec<PlayerId>,
+        observers: HashSet<PlayerId>,
+        bots: HashMap<BotId, BotType>,
+        spectator_delay: u32,
+        bot_takeover: Option<BotTakeover>,
+    ) -> Self {
+        Self {
+            session_id,
+            human_players,
+            observers: Arc::new(Mutex::new(observers)),
+            bots,
+            spectator_delay,
+            bot_takeover,
+            teams: HashMap::new(),
+            spectator_feed: Arc::new(Mutex::new(SpectatorFeed::default())),
+        }
+    }
+
+    pub async fn add_observer(&self, player_id: PlayerId) -> bool {
+        if self.human_players.contains(&player_id) {
+            return false;
+        }
+        self.observers.lock().await.insert(player_id)
+    }
+
+    pub async fn remove_observer(&self, player_id: &PlayerId) -> bool {
+        self.observers.lock().await.remove(player_id)
+    }
+
+    pub async fn get_all_recipients(&self) -> Vec<ClientId> {
+        let mut recipients = self.player_recipients();
+        recipients.extend(self.observer_recipients().await);
+        recipients
+    }
+
+    fn player_recipients(&self) -> Vec<ClientId> {
+        self.human_players
             .iter()
             .map(|p| ClientId::new(p.to_string()))
-            .collect();
-        recipients.extend(
-            self.observers
-                .iter()
-                .map(|p| ClientId::new(p.to_string())),
+            .collect()
+    }
+
+    async fn observer_recipients(&self) -> Vec<ClientId> {
+        self.observers
+            .lock()
+            .await
+            .iter()
+            .map(|p| ClientId::new(p.to_string()))
+            .collect()
+    }
+
+    /// Players get the state live; observers get it `spectator_delay` updates later.
+    pub async fn broadcast_state(&self, broadcaster: &impl GameBroadcaster, state: GameStateUpdate) {
+        if self.spectator_delay == 0 {
+            broadcaster.broadcast_state(state, self.get_all_recipients().await).await;
+            return;
+        }
+
+        broadcaster.broadcast_state(state.clone(), self.player_recipients()).await;
+
+        let released = {
+            let mut feed = self.spectator_feed.lock().await;
+            feed.pending.push_back(state);
+            if feed.pending.len() > self.spectator_delay as usize {
+                let released = feed.pending.pop_front();
+                feed.last_released = released.clone();
+                released
+            } else {
+                None
+            }
+        };
+
+        if let Some(released) = released {
+            broadcaster.broadcast_state(released, self.observer_recipients().await).await;
+        }
+    }
+
+    pub async fn flush_spectator_feed(&self, broadcaster: &impl GameBroadcaster) {
+        let pending: Vec<GameStateUpdate> = {
+            let mut feed = self.spectator_feed.lock().await;
+            let pending: Vec<GameStateUpdate> = feed.pending.drain(..).collect();
+            if let Some(last) = pending.last() {
+                feed.last_released = Some(last.clone());
+            }
+            pending
+        };
+
+        for state in pending {
+            broadcaster.broadcast_state(state, self.observer_recipients().await).await;
+        }
+    }
+
+    pub async fn spectator_state(&self) -> Option<GameStateUpdate> {
+        self.spectator_feed.lock().await.last_released.clone()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    type SentStates = Vec<(u64, Vec<ClientId>)>;
+
+    #[derive(Clone, Default)]
+    struct RecordingBroadcaster {
+        sent: Arc<Mutex<SentStates>>,
+    }
+
+    impl GameBroadcaster for RecordingBroadcaster {
+        async fn broadcast_state(&self, state: GameStateUpdate, recipients: Vec<ClientId>) {
+            let tick = match state.state {
+                Some(crate::game_state_update::State::Snake(snake)) => snake.tick,
+                _ => 0,
+            };
+            self.sent.lock().await.push((tick, recipients));
+        }
+
+        async fn broadcast_game_over(&self, _notification: crate::GameOverNotification, _recipients: Vec<ClientId>) {}
+    }
+
+    fn test_config() -> GameSessionConfig {
+        GameSessionConfig::new(
+            "session".to_string(),
+            vec![PlayerId::new("player".to_string())],
+            HashSet::new(),
+            HashMap::new(),
+            0,
+            None,
+        )
+    }
+
+    fn snake_state(tick: u64) -> GameStateUpdate {
+        GameStateUpdate {
+            state: Some(crate::game_state_update::State::Snake(crate::SnakeGameState {
+                tick,
+                ..Default::default()
+            })),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_broadcast_state_spectator_delay_observers_lag_behind_players() {
+        let mut config = test_config();
+        config.spectator_delay = 2;
+        config.add_observer(PlayerId::new("observer".to_string())).await;
+        let broadcaster = RecordingBroadcaster::default();
+        let player = vec![ClientId::new("player".to_string())];
+        let observer = vec![ClientId::new("observer".to_string())];
+
+        for tick in 1..=3 {
+            config.broadcast_state(&broadcaster, snake_state(tick)).await;
+        }
+
+        assert_eq!(
+            *broadcaster.sent.lock().await,
+            vec![
+                (1, player.clone()),
+                (2, player.clone()),
+                (3, player),
+                (1, observer.clone()),
+            ]
         );
-        recipients
+        assert_eq!(config.spectator_state().await, Some(snake_state(1)));
+
+        broadcaster.sent.lock().await.clear();
+        config.flush_spectator_feed(&broadcaster).await;
+
+        assert_eq!(
+            *broadcaster.sent.lock().await,
+            vec![(2, observer.clone()), (3, observer)]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_get_all_recipients_observer_added_and_removed_recipients_updated() {
+        let config = test_config();
+        let shared = config.clone();
+        let observer = PlayerId::new("observer".to_string());
+
+        assert!(shared.add_observer(observer.clone()).await);
+        assert_eq!(config.get_all_recipients().await.len(), 2);
+
+        assert!(shared.remove_observer(&observer).await);
+        assert_eq!(
+            config.get_all_recipients().await,
+            vec![ClientId::new("player".to_string())]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_add_observer_human_player_rejected() {
+        let config = test_config();
+
+        assert!(!config.add_observer(PlayerId::new("player".to_string())).await);
+        assert_eq!(config.get_all_recipients().await.len(), 1);
     }
 }
@@ -1,19 +1,76 @@
+use std::ops::{Range, RangeInclusive};
+
+use rand::distr::uniform::SampleUniform;
+
 use rand::rngs::StdRng;
 use rand::{Rng, SeedableRng};
 
+/// Id of the algorithm below, stored in replay metadata. Any change to the
+/// output stream of `SessionRng`, including how ranges are sampled, needs a new id.
+pub const RNG_ALGORITHM: u32 = 1;
+/// `rand`'s `StdRng`, which every replay recorded before the algorithm was
+/// stored was played with. Only their world stream is ever re-simulated.
+pub const LEGACY_RNG_ALGORITHM: u32 = 0;
+
+/// Independent sequences drawn from one session seed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RngStream {
+    /// Everything replays re-simulate: food, crane drops, tiles, first player.
+    World,
+    /// Bot decisions, so bots never shift the world stream.
+    Bots,
+    /// Snake start positions, drawn once before the first tick.
+    Spawns,
+}
+
+/// xoshiro256** (Blackman and Vigna), seeded by expanding the session seed
+/// with SplitMix64. Implemented here so the stream can't change under a
+/// dependency bump; stored replays rely on every value it produces. Each
+/// `RngStream` starts one xoshiro jump (2^128 values) after the previous one.
+#[derive(Clone)]
 pub struct SessionRng {
-    rng: StdRng,
+    generator: Generator,
     seed: u64,
 }
 
+#[derive(Clone)]
+enum Generator {
+    Xoshiro([u64; 4]),
+    Legacy(StdRng),
+}
+
+const JUMP: [u64; 4] = [0x180ec6d33cfd0aba, 0xd5a61266f0c9392c, 0xa9582618e03fc9aa, 0x39abdc4529b1661c];
+
 impl SessionRng {
     pub fn new(seed: u64) -> Self {
+        Self::for_stream(seed, RngStream::World)
+    }
+
+    pub fn for_stream(seed: u64, stream: RngStream) -> Self {
+        let mut splitmix = seed;
+        let mut state = std::array::from_fn(|_| splitmix64(&mut splitmix));
+        for _ in 0..stream as usize {
+            jump(&mut state);
+        }
         Self {
-            rng: StdRng::seed_from_u64(seed),
+            generator: Generator::Xoshiro(state),
             seed,
         }
     }
 
+    /// The world stream of a replay recorded with `algorithm`, or `None` for
+    /// an algorithm this server doesn't know.
+    pub fn with_algorithm(seed: u64, algorithm: u32) -> Option<Self> {
+        match algorithm {
+            RNG_ALGORITHM => Some(Self::new(seed)),
+            LEGACY_RNG_ALGORITHM => Some(Self {
+                generator: Generator::Legacy(StdRng::seed_from_u64(seed)),
+                seed,
+            }),
+            _ => None,
+        }
+    }
+
     pub fn from_random() -> Self {
         let seed: u64 = rand::rng().random();
         Self::new(seed)
@@ -23,22 +80,231 @@ impl SessionRng {
         self.seed
     }
 
-    pub fn random<T>(&mut self) -> T
-    where
-        rand::distr::StandardUniform: rand::distr::Distribution<T>,
-    {
-        self.rng.random()
+    pub fn algorithm(&self) -> u32 {
+        match self.generator {
+            Generator::Xoshiro(_) => RNG_ALGORITHM,
+            Generator::Legacy(_) => LEGACY_RNG_ALGORITHM,
+        }
+    }
+
+    pub fn next_u64(&mut self) -> u64 {
+        match &mut self.generator {
+            Generator::Xoshiro(state) => xoshiro_next(state),
+            Generator::Legacy(rng) => rng.random(),
+        }
+    }
+
+    pub fn next_u32(&mut self) -> u32 {
+        match &mut self.generator {
+            Generator::Xoshiro(state) => (xoshiro_next(state) >> 32) as u32,
+            Generator::Legacy(rng) => rng.random(),
+        }
     }
 
-    pub fn random_range<T, R>(&mut self, range: R) -> T
-    where
-        T: rand::distr::uniform::SampleUniform,
-        R: rand::distr::uniform::SampleRange<T>,
-    {
-        self.rng.random_range(range)
+    /// Uniform in `[0, 1)` with 24 bits of precision.
+    pub fn random_f32(&mut self) -> f32 {
+        match &mut self.generator {
+            Generator::Xoshiro(state) => (xoshiro_next(state) >> 40) as f32 / (1u32 << 24) as f32,
+            Generator::Legacy(rng) => rng.random(),
+        }
     }
 
     pub fn random_bool(&mut self) -> bool {
-        self.rng.random()
+        match &mut self.generator {
+            Generator::Xoshiro(state) => xoshiro_next(state) >> 63 == 1,
+            Generator::Legacy(rng) => rng.random(),
+        }
+    }
+
+    /// Uniform over a non-empty `a..b` or `a..=b`; panics on an empty range.
+    pub fn random_range<T: UniformInt>(&mut self, range: impl IntoInclusive<T>) -> T {
+        if let Generator::Legacy(rng) = &mut self.generator {
+            return range.sample_legacy(rng);
+        }
+        let (low, high) = range.into_inclusive();
+        let (low, high) = (low.to_i128(), high.to_i128());
+        assert!(low <= high, "random_range called with an empty range");
+
+        let span = (high - low) as u128 + 1;
+        let offset = if span > u64::MAX as u128 {
+            self.next_u64()
+        } else {
+            self.below(span as u64)
+        };
+        T::from_i128(low + offset as i128)
+    }
+
+    /// Unbiased value in `[0, n)`: draws in the short top zone of the u64
+    /// range that would favour small results are rejected.
+    fn below(&mut self, n: u64) -> u64 {
+        let threshold = n.wrapping_neg() % n;
+        loop {
+            let value = self.next_u64();
+            if value >= threshold {
+                return value % n;
+            }
+        }
+    }
+}
+
+fn xoshiro_next(state: &mut [u64; 4]) -> u64 {
+    let [s0, s1, s2, s3] = state;
+    let result = s1.wrapping_mul(5).rotate_left(7).wrapping_mul(9);
+    let t = *s1 << 17;
+
+    *s2 ^= *s0;
+    *s3 ^= *s1;
+    *s1 ^= *s2;
+    *s0 ^= *s3;
+    *s2 ^= t;
+    *s3 = s3.rotate_left(45);
+
+    result
+}
+
+fn jump(state: &mut [u64; 4]) {
+    let mut jumped = [0u64; 4];
+    for word in JUMP {
+        for bit in 0..64 {
+            if word & (1 << bit) != 0 {
+                for (j, s) in jumped.iter_mut().zip(*state) {
+                    *j ^= s;
+                }
+            }
+            xoshiro_next(state);
+        }
+    }
+    *state = jumped;
+}
+
+fn splitmix64(state: &mut u64) -> u64 {
+    *state = state.wrapping_add(0x9e3779b97f4a7c15);
+    let mut z = *state;
+    z = (z ^ (z >> 30)).wrapping_mul(0xbf58476d1ce4e5b9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94d049bb133111eb);
+    z ^ (z >> 31)
+}
+
+pub trait UniformInt: Copy + PartialOrd + SampleUniform {
+    fn to_i128(self) -> i128;
+    fn from_i128(value: i128) -> Self;
+}
+
+macro_rules! impl_uniform_int {
+    ($($type:ty),+) => {
+        $(
+            impl UniformInt for $type {
+                fn to_i128(self) -> i128 {
+                    self as i128
+                }
+                fn from_i128(value: i128) -> Self {
+                    value as $type
+                }
+            }
+        )+
+    };
+}
+
+impl_uniform_int!(u8, u16, u32, u64, usize, i8, i16, i32, i64);
+
+pub trait IntoInclusive<T> {
+    fn into_inclusive(self) -> (T, T);
+    /// Samples the range the way `StdRng::random_range` did, which depends on
+    /// whether the upper bound is inclusive.
+    fn sample_legacy(self, rng: &mut StdRng) -> T;
+}
+
+impl<T: UniformInt> IntoInclusive<T> for Range<T> {
+    fn into_inclusive(self) -> (T, T) {
+        assert!(self.start.to_i128() < self.end.to_i128(), "random_range called with an empty range");
+        (self.start, T::from_i128(self.end.to_i128() - 1))
+    }
+
+    fn sample_legacy(self, rng: &mut StdRng) -> T {
+        rng.random_range(self)
+    }
+}
+
+impl<T: UniformInt> IntoInclusive<T> for RangeInclusive<T> {
+    fn into_inclusive(self) -> (T, T) {
+        self.into_inner()
+    }
+
+    fn sample_legacy(self, rng: &mut StdRng) -> T {
+        rng.random_range(self)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_splitmix64_reference_vector() {
+        let mut state = 0;
+        assert_eq!(splitmix64(&mut state), 0xe220a8397b1dcdaf);
+        assert_eq!(splitmix64(&mut state), 0x6e789e6aa1b965f4);
+    }
+
+    #[test]
+    fn test_world_stream_golden_values() {
+        let mut rng = SessionRng::new(0);
+        assert_eq!(rng.next_u64(), 0x99ec5f36cb75f2b4);
+        assert_eq!(rng.next_u64(), 0xbf6e1f784956452a);
+        assert_eq!(rng.next_u64(), 0x1a5f849d4933e6e0);
+
+        let mut rng = SessionRng::new(42);
+        assert_eq!(rng.next_u64(), 0x15780b2e0c2ec716);
+        assert_eq!(rng.next_u64(), 0x6104d9866d113a7e);
+        assert_eq!(rng.next_u64(), 0xae17533239e499a1);
+    }
+
+    #[test]
+    fn test_derived_values_golden() {
+        let mut rng = SessionRng::new(42);
+        let values: Vec<u32> = (0..8).map(|_| rng.random_range(0..10)).collect();
+        assert_eq!(values, vec![2, 2, 9, 3, 6, 4, 4, 7]);
+        assert_eq!(rng.random_range(1..=9), 5);
+        assert!(rng.random_bool());
+        assert_eq!(rng.random_f32(), 0.682_452_86);
+    }
+
+    #[test]
+    fn test_bot_stream_independent_of_world() {
+        let mut world = SessionRng::new(42);
+        let mut bots = SessionRng::for_stream(42, RngStream::Bots);
+        assert_eq!(bots.seed(), 42);
+        assert_eq!(bots.next_u64(), 0x50086ef83cbf4f4a);
+        assert_ne!(world.next_u64(), 0x50086ef83cbf4f4a);
+    }
+
+    #[test]
+    fn test_legacy_algorithm_matches_std_rng() {
+        let mut rng = SessionRng::with_algorithm(42, LEGACY_RNG_ALGORITHM).unwrap();
+        let mut std_rng = StdRng::seed_from_u64(42);
+        assert_eq!(rng.algorithm(), LEGACY_RNG_ALGORITHM);
+        assert_eq!(rng.next_u64(), std_rng.random::<u64>());
+        assert_eq!(rng.next_u32(), std_rng.random::<u32>());
+        assert_eq!(rng.random_f32(), std_rng.random::<f32>());
+        assert_eq!(rng.random_bool(), std_rng.random::<bool>());
+        assert_eq!(rng.random_range(0usize..10), std_rng.random_range(0usize..10));
+        assert_eq!(rng.random_range(1u32..=9), std_rng.random_range(1u32..=9));
+    }
+
+    #[test]
+    fn test_with_algorithm_rejects_unknown_ids() {
+        assert_eq!(SessionRng::with_algorithm(42, RNG_ALGORITHM).unwrap().next_u64(), SessionRng::new(42).next_u64());
+        assert!(SessionRng::with_algorithm(42, RNG_ALGORITHM + 1).is_none());
+    }
+
+    #[test]
+    fn test_random_range_stays_in_bounds() {
+        let mut rng = SessionRng::new(5);
+        for _ in 0..1_000 {
+            let value = rng.random_range(-3i32..4);
+            assert!((-3..4).contains(&value));
+        }
+        assert_eq!(rng.random_range(7usize..=7), 7);
+        let _ = rng.random_range(u64::MIN..=u64::MAX);
     }
 }
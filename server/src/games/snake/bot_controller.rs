@@ -1,10 +1,23 @@
+use std::collections::VecDeque;
+
 use crate::{PlayerId, SnakeBotType};
 use crate::games::SessionRng;
 use super::game_state::SnakeGameState;
-use super::types::{DeadSnakeBehavior, Direction, Point, WallCollisionMode};
+use super::types::{DeadSnakeBehavior, Direction, EffectKind, Point, WallCollisionMode};
+
+const ALL_DIRECTIONS: [Direction; 4] = [Direction::Up, Direction::Down, Direction::Left, Direction::Right];
 
 pub struct BotController;
 
+struct CandidateMove {
+    direction: Direction,
+    /// Free cells reachable from the new head position.
+    space: usize,
+    reaches_tail: bool,
+    /// An opponent head could move into the same cell this tick.
+    contested: bool,
+}
+
 impl BotController {
     pub fn calculate_move(
         bot_type: SnakeBotType,
@@ -12,11 +25,32 @@ impl BotController {
         state: &SnakeGameState,
         rng: &mut SessionRng,
     ) -> Option<Direction> {
-        match bot_type {
+        let direction = match bot_type {
             SnakeBotType::Efficient => Self::efficient_pathfinding(player_id, state, rng),
             SnakeBotType::Random => Self::random_valid_move(player_id, state, rng),
+            SnakeBotType::SpaceAware => Self::space_aware_move(player_id, state),
             SnakeBotType::Unspecified => None,
-        }
+        }?;
+
+        // Bots know their controls are mirrored and send the opposite turn.
+        let reversed = state
+            .snakes
+            .get(player_id)
+            .is_some_and(|snake| snake.has_effect(EffectKind::ReverseControls));
+        Some(if reversed { direction.opposite() } else { direction })
+    }
+
+    /// Whether turning off the current heading would land on a safe cell this
+    /// tick. A snake in a corridor only has the straight move.
+    pub fn can_turn(player_id: &PlayerId, state: &SnakeGameState) -> bool {
+        let Some(snake) = state.snakes.get(player_id).filter(|snake| snake.is_alive()) else {
+            return false;
+        };
+        let head = snake.head();
+        Self::get_valid_directions(snake.direction)
+            .into_iter()
+            .filter(|&dir| dir != snake.direction)
+            .any(|dir| Self::safe_destination(head, dir, player_id, state).is_some())
     }
 
     fn efficient_pathfinding(
@@ -32,7 +66,9 @@ impl BotController {
         let head = snake.head();
         let current_direction = snake.direction;
 
-        let nearest_food = Self::find_nearest_food(head, state)?;
+        let Some(nearest_food) = Self::find_nearest_food(head, state) else {
+            return Self::random_valid_move(player_id, state, rng);
+        };
 
         let valid_directions = Self::get_valid_directions(current_direction);
 
@@ -40,9 +76,7 @@ impl BotController {
         let mut best_distance = f32::MAX;
 
         for dir in valid_directions {
-            if let Some(next_pos) = Self::calculate_next_position(head, dir, state)
-                && Self::is_safe_position(next_pos, player_id, state)
-            {
+            if let Some(next_pos) = Self::safe_destination(head, dir, player_id, state) {
                 let distance = Self::manhattan_distance(next_pos, nearest_food, state);
                 if distance < best_distance {
                     best_distance = distance;
@@ -70,13 +104,7 @@ impl BotController {
         let head = snake.head();
         let safe_directions: Vec<Direction> = valid_directions
             .into_iter()
-            .filter(|&dir| {
-                if let Some(next_pos) = Self::calculate_next_position(head, dir, state) {
-                    Self::is_safe_position(next_pos, player_id, state)
-                } else {
-                    false
-                }
-            })
+            .filter(|&dir| Self::safe_destination(head, dir, player_id, state).is_some())
             .collect();
 
         if safe_directions.is_empty() {
@@ -87,23 +115,206 @@ impl BotController {
         }
     }
 
+    /// Takes the shortest path to food while the first step leaves room for the
+    /// whole body, falls back to following its own tail, and otherwise moves
+    /// into the largest open area. Cells an opponent head can reach this tick
+    /// are avoided whenever there is another option.
+    fn space_aware_move(player_id: &PlayerId, state: &SnakeGameState) -> Option<Direction> {
+        let snake = state.snakes.get(player_id)?;
+        if !snake.is_alive() {
+            return None;
+        }
+
+        let head = snake.head();
+        let tail = snake.tail();
+        let blocked = Self::blocked_cells(player_id, state);
+        let contested = Self::contested_cells(player_id, state);
+
+        let candidates: Vec<CandidateMove> = Self::get_valid_directions(snake.direction)
+            .into_iter()
+            .filter_map(|direction| {
+                let next = Self::calculate_next_position(head, direction, state)?;
+                let index = Self::cell_index(next, state);
+                if blocked[index] {
+                    return None;
+                }
+                let end = Self::safe_destination(head, direction, player_id, state)?;
+                let (space, reaches_tail) = Self::flood_fill(end, tail, &blocked, state);
+                Some(CandidateMove { direction, space, reaches_tail, contested: contested[index] })
+            })
+            .collect();
+
+        let Some(fallback) = candidates
+            .iter()
+            .max_by_key(|m| (!m.contested, m.reaches_tail, m.space))
+            .map(|m| m.direction)
+        else {
+            return Some(snake.direction);
+        };
+
+        let avoid: Vec<bool> = blocked.iter().zip(&contested).map(|(b, c)| *b || *c).collect();
+        let is_roomy = |direction: Direction| {
+            candidates
+                .iter()
+                .any(|m| m.direction == direction && (m.reaches_tail || m.space >= snake.body.len()))
+        };
+
+        if let Some(direction) = Self::first_step_to(head, &avoid, state, |p| Self::is_wanted_food(p, state))
+            && is_roomy(direction)
+        {
+            return Some(direction);
+        }
+
+        if snake.body.len() > 2
+            && let Some(direction) = Self::first_step_to(head, &avoid, state, |p| p == tail)
+        {
+            return Some(direction);
+        }
+
+        Some(fallback)
+    }
+
+    /// Cells a snake can't move into this tick. The bot's own tail stays free
+    /// because it moves away as the head advances.
+    fn blocked_cells(player_id: &PlayerId, state: &SnakeGameState) -> Vec<bool> {
+        let mut blocked = vec![false; state.field_size.width * state.field_size.height];
+        for obstacle in &state.obstacles {
+            blocked[Self::cell_index(*obstacle, state)] = true;
+        }
+        for y in 0..state.field_size.height {
+            for x in 0..state.field_size.width {
+                if !state.arena.contains(Point::new(x, y)) {
+                    blocked[Self::cell_index(Point::new(x, y), state)] = true;
+                }
+            }
+        }
+        let is_ghost = Self::is_ghost(player_id, state);
+        for (id, snake) in &state.snakes {
+            if (is_ghost && id != player_id) || state.passes_through(player_id, id) {
+                continue;
+            }
+            let should_check = match state.dead_snake_behavior {
+                DeadSnakeBehavior::Disappear => snake.is_alive(),
+                DeadSnakeBehavior::StayOnField => true,
+            };
+            if !should_check {
+                continue;
+            }
+
+            let skip_tail = id == player_id && snake.is_alive();
+            for (i, segment) in snake.body.iter().enumerate() {
+                if skip_tail && i == snake.body.len() - 1 {
+                    continue;
+                }
+                blocked[Self::cell_index(*segment, state)] = true;
+            }
+        }
+        blocked
+    }
+
+    fn contested_cells(player_id: &PlayerId, state: &SnakeGameState) -> Vec<bool> {
+        let mut contested = vec![false; state.field_size.width * state.field_size.height];
+        for (id, snake) in &state.snakes {
+            if id == player_id || !snake.is_alive() || state.passes_through(player_id, id) {
+                continue;
+            }
+            for direction in Self::get_valid_directions(snake.direction) {
+                if let Some(next) = Self::calculate_next_position(snake.head(), direction, state) {
+                    contested[Self::cell_index(next, state)] = true;
+                }
+            }
+        }
+        contested
+    }
+
+    /// Counts free cells reachable from `start` and whether `tail` is among them.
+    fn flood_fill(start: Point, tail: Point, blocked: &[bool], state: &SnakeGameState) -> (usize, bool) {
+        let mut visited = blocked.to_vec();
+        visited[Self::cell_index(start, state)] = true;
+        let mut queue = VecDeque::from([start]);
+        let mut count = 0;
+        let mut reaches_tail = false;
+
+        while let Some(pos) = queue.pop_front() {
+            count += 1;
+            reaches_tail |= pos == tail;
+            for direction in ALL_DIRECTIONS {
+                if let Some(next) = Self::calculate_next_position(pos, direction, state) {
+                    let index = Self::cell_index(next, state);
+                    if !visited[index] {
+                        visited[index] = true;
+                        queue.push_back(next);
+                    }
+                }
+            }
+        }
+
+        (count, reaches_tail)
+    }
+
+    /// First direction of a shortest path from `from` to the nearest cell
+    /// matching `is_target`, moving only through cells that aren't blocked.
+    fn first_step_to(
+        from: Point,
+        blocked: &[bool],
+        state: &SnakeGameState,
+        is_target: impl Fn(Point) -> bool,
+    ) -> Option<Direction> {
+        let mut visited = blocked.to_vec();
+        visited[Self::cell_index(from, state)] = true;
+        let mut queue = VecDeque::new();
+
+        for direction in ALL_DIRECTIONS {
+            if let Some(next) = Self::calculate_next_position(from, direction, state) {
+                let index = Self::cell_index(next, state);
+                if !visited[index] {
+                    visited[index] = true;
+                    queue.push_back((next, direction));
+                }
+            }
+        }
+
+        while let Some((pos, first_step)) = queue.pop_front() {
+            if is_target(pos) {
+                return Some(first_step);
+            }
+            for direction in ALL_DIRECTIONS {
+                if let Some(next) = Self::calculate_next_position(pos, direction, state) {
+                    let index = Self::cell_index(next, state);
+                    if !visited[index] {
+                        visited[index] = true;
+                        queue.push_back((next, first_step));
+                    }
+                }
+            }
+        }
+
+        None
+    }
+
+    fn cell_index(pos: Point, state: &SnakeGameState) -> usize {
+        pos.y * state.field_size.width + pos.x
+    }
+
     fn get_valid_directions(current: Direction) -> Vec<Direction> {
-        vec![
-            Direction::Up,
-            Direction::Down,
-            Direction::Left,
-            Direction::Right,
-        ]
-        .into_iter()
+        ALL_DIRECTIONS
+            .into_iter()
         .filter(|d| !d.is_opposite(&current))
         .collect()
     }
 
+    fn is_wanted_food(pos: Point, state: &SnakeGameState) -> bool {
+        state.food.get(&pos).is_some_and(|kind| !kind.is_trap())
+    }
+
     fn find_nearest_food(from: Point, state: &SnakeGameState) -> Option<Point> {
         state
-            .food_set
+            .food
             .iter()
-            .min_by_key(|food| Self::manhattan_distance(from, **food, state) as i32)
+            .filter(|(_, kind)| !kind.is_trap())
+            .map(|(food, _)| food)
+            // Ties go to the lowest point so the choice doesn't depend on hash order.
+            .min_by_key(|food| (Self::manhattan_distance(from, **food, state) as i32, food.y, food.x))
             .copied()
     }
 
@@ -166,8 +377,44 @@ impl BotController {
         }
     }
 
+    /// Where the head ends up this tick after turning to `direction`, if every
+    /// step on the way is safe. Speed-boosted snakes take two steps.
+    fn safe_destination(
+        from: Point,
+        direction: Direction,
+        player_id: &PlayerId,
+        state: &SnakeGameState,
+    ) -> Option<Point> {
+        let boosted = state
+            .snakes
+            .get(player_id)
+            .is_some_and(|snake| snake.has_effect(EffectKind::SpeedBoost));
+        let mut pos = from;
+        for _ in 0..if boosted { 2 } else { 1 } {
+            pos = Self::calculate_next_position(pos, direction, state)?;
+            if !Self::is_safe_position(pos, player_id, state) {
+                return None;
+            }
+        }
+        Some(pos)
+    }
+
+    fn is_ghost(player_id: &PlayerId, state: &SnakeGameState) -> bool {
+        state
+            .snakes
+            .get(player_id)
+            .is_some_and(|snake| snake.has_effect(EffectKind::Ghost))
+    }
+
     fn is_safe_position(pos: Point, player_id: &PlayerId, state: &SnakeGameState) -> bool {
+        if state.obstacles.contains(&pos) || !state.arena.contains(pos) {
+            return false;
+        }
+        let is_ghost = Self::is_ghost(player_id, state);
         for (id, snake) in &state.snakes {
+            if (is_ghost && id != player_id) || state.passes_through(player_id, id) {
+                continue;
+            }
             let should_check = match state.dead_snake_behavior {
                 DeadSnakeBehavior::Disappear => snake.is_alive(),
                 DeadSnakeBehavior::StayOnField => true,
@@ -188,3 +435,58 @@ impl BotController {
         true
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::games::snake::types::{DeathReason, FieldSize, FoodKind};
+
+    fn state(dead_snake_behavior: DeadSnakeBehavior) -> SnakeGameState {
+        SnakeGameState::new(FieldSize { width: 8, height: 8 }, WallCollisionMode::Death, dead_snake_behavior, 3, 0.0)
+    }
+
+    fn space_aware_move(state: &SnakeGameState) -> Option<Direction> {
+        let mut rng = SessionRng::new(0);
+        BotController::calculate_move(SnakeBotType::SpaceAware, &PlayerId::new("bot".to_string()), state, &mut rng)
+    }
+
+    #[test]
+    fn test_space_aware_skips_food_in_dead_end() {
+        let mut state = state(DeadSnakeBehavior::StayOnField);
+        state.add_snake(PlayerId::new("bot".to_string()), Point::new(3, 3), Direction::Right);
+        state.add_snake(PlayerId::new("wall".to_string()), Point::new(6, 6), Direction::Left);
+
+        let wall = state.snakes.get_mut(&PlayerId::new("wall".to_string())).unwrap();
+        wall.body = [Point::new(2, 2), Point::new(3, 1), Point::new(4, 2)].into();
+        wall.body_set = wall.body.iter().copied().collect();
+        wall.death_reason = Some(DeathReason::SelfCollision);
+        state.food.insert(Point::new(3, 2), FoodKind::Normal);
+
+        let direction = space_aware_move(&state).unwrap();
+        assert_ne!(direction, Direction::Up);
+    }
+
+    #[test]
+    fn test_space_aware_avoids_cell_opponent_head_can_reach() {
+        let mut state = state(DeadSnakeBehavior::Disappear);
+        state.add_snake(PlayerId::new("bot".to_string()), Point::new(2, 5), Direction::Right);
+        state.add_snake(PlayerId::new("rival".to_string()), Point::new(4, 5), Direction::Left);
+        state.food.insert(Point::new(3, 5), FoodKind::Normal);
+
+        let direction = space_aware_move(&state).unwrap();
+        assert!(matches!(direction, Direction::Up | Direction::Down));
+    }
+
+    #[test]
+    fn test_can_turn_false_in_corridor() {
+        let mut state = state(DeadSnakeBehavior::StayOnField);
+        let player = PlayerId::new("player".to_string());
+        state.add_snake(player.clone(), Point::new(3, 3), Direction::Right);
+        assert!(BotController::can_turn(&player, &state));
+
+        state.obstacles.insert(Point::new(3, 2));
+        assert!(BotController::can_turn(&player, &state));
+        state.obstacles.insert(Point::new(3, 4));
+        assert!(!BotController::can_turn(&player, &state));
+    }
+}
@@ -1,15 +1,17 @@
 use std::collections::{HashSet, VecDeque};
 
-use super::types::{DeathReason, Direction, FieldSize, Point};
+use super::types::{ActiveEffect, DeathReason, Direction, EffectKind, FieldSize, Point};
 
 #[derive(Clone, Debug)]
 pub struct Snake {
     pub body: VecDeque<Point>,
     pub body_set: HashSet<Point>,
     pub direction: Direction,
-    pub pending_direction: Option<Direction>,
+    /// Turns waiting to be applied, one per tick.
+    pub pending_directions: VecDeque<Direction>,
     pub death_reason: Option<DeathReason>,
     pub score: u32,
+    pub effects: Vec<ActiveEffect>,
 }
 
 impl Snake {
@@ -49,9 +51,10 @@ impl Snake {
             body,
             body_set,
             direction,
-            pending_direction: None,
+            pending_directions: VecDeque::new(),
             death_reason: None,
             score: 0,
+            effects: Vec::new(),
         }
     }
 
@@ -66,4 +69,42 @@ impl Snake {
     pub fn tail(&self) -> Point {
         *self.body.back().expect("Snake body should never be empty")
     }
+
+    pub fn has_effect(&self, kind: EffectKind) -> bool {
+        self.effects.iter().any(|e| e.kind == kind)
+    }
+
+    /// Starts the effect for the next `duration_ticks` ticks. Picking up an
+    /// effect that is already running restarts it.
+    pub fn add_effect(&mut self, kind: EffectKind, duration_ticks: u32) {
+        match self.effects.iter_mut().find(|e| e.kind == kind) {
+            Some(effect) => effect.remaining_ticks = duration_ticks,
+            None => self.effects.push(ActiveEffect { kind, remaining_ticks: duration_ticks }),
+        }
+    }
+
+    /// Called at the start of every tick; effects still apply to the tick
+    /// that uses up their last count.
+    pub fn consume_effect_tick(&mut self) {
+        for effect in &mut self.effects {
+            effect.remaining_ticks = effect.remaining_ticks.saturating_sub(1);
+        }
+    }
+
+    /// Called at the end of every tick, so between ticks `effects` holds
+    /// exactly what applies to the next one.
+    pub fn drop_expired_effects(&mut self) {
+        self.effects.retain(|e| e.remaining_ticks > 0);
+    }
+
+    pub fn shrink(&mut self, segments: usize, min_length: usize) {
+        for _ in 0..segments {
+            if self.body.len() <= min_length {
+                break;
+            }
+            if let Some(tail) = self.body.pop_back() {
+                self.body_set.remove(&tail);
+            }
+        }
+    }
 }
@@ -1,14 +1,69 @@
 use std::collections::{HashMap, HashSet};
 
 use crate::{log, PlayerId};
-use crate::games::SessionRng;
+use crate::games::{SessionRng, StateHasher};
 use super::entity::Snake;
-use super::types::{DeadSnakeBehavior, DeathReason, Direction, FieldSize, Point, WallCollisionMode};
+use super::types::{
+    ArenaBounds, DeadSnakeBehavior, DeathReason, Direction, EffectKind, FieldSize, FoodKind, FoodWeights,
+    GameMode, MovementResolution, Point, Side, WallCollisionMode,
+};
+
+pub const NORMAL_FOOD_WEIGHT: u32 = 100;
+pub const GOLDEN_FOOD_SCORE: u32 = 3;
+pub const SHRINK_SEGMENTS: usize = 3;
+const MIN_SHRUNK_LENGTH: usize = 3;
+pub const SPEED_BOOST_TICKS: u32 = 20;
+pub const GHOST_TICKS: u32 = 30;
+pub const REVERSE_CONTROLS_TICKS: u32 = 30;
+/// A battle royale arena stops shrinking before either side gets shorter than this.
+const MIN_ARENA_SIZE: usize = 5;
+
+impl FoodWeights {
+    /// Only draws from `rng` when a special item is enabled, so games without
+    /// them keep the random sequence of older replays.
+    pub fn pick(&self, rng: &mut SessionRng) -> FoodKind {
+        let choices = [
+            (FoodKind::Normal, NORMAL_FOOD_WEIGHT),
+            (FoodKind::Golden, self.golden),
+            (FoodKind::SpeedBoost, self.speed_boost),
+            (FoodKind::Ghost, self.ghost),
+            (FoodKind::Shrink, self.shrink),
+            (FoodKind::ReverseControls, self.reverse_controls),
+        ];
+        let total: u32 = choices.iter().map(|(_, weight)| weight).sum();
+        if total == NORMAL_FOOD_WEIGHT {
+            return FoodKind::Normal;
+        }
+
+        let mut roll = rng.random_range(0..total);
+        for (kind, weight) in choices {
+            if roll < weight {
+                return kind;
+            }
+            roll -= weight;
+        }
+        FoodKind::Normal
+    }
+}
 
 #[derive(Clone, Debug)]
 pub struct SnakeGameState {
     pub snakes: HashMap<PlayerId, Snake>,
-    pub food_set: HashSet<Point>,
+    pub food: HashMap<Point, FoodKind>,
+    pub food_weights: FoodWeights,
+    /// Map cells no snake can enter; they kill like walls.
+    pub obstacles: HashSet<Point>,
+    pub movement_resolution: MovementResolution,
+    /// Turns each snake can queue; 0 keeps a single slot that every turn overwrites.
+    pub input_buffer_size: usize,
+    pub game_mode: GameMode,
+    /// The whole field unless a battle royale has shrunk it.
+    pub arena: ArenaBounds,
+    /// Updates played so far.
+    pub tick: u64,
+    /// Team of each player in a team game; players missing from it play alone.
+    pub teams: HashMap<PlayerId, u32>,
+    pub teammates_pass_through: bool,
     pub field_size: FieldSize,
     pub wall_collision_mode: WallCollisionMode,
     pub dead_snake_behavior: DeadSnakeBehavior,
@@ -28,7 +83,19 @@ impl SnakeGameState {
     ) -> Self {
         Self {
             snakes: HashMap::new(),
-            food_set: HashSet::new(),
+            food: HashMap::new(),
+            food_weights: FoodWeights::default(),
+            obstacles: HashSet::new(),
+            movement_resolution: MovementResolution::Simultaneous,
+            input_buffer_size: 0,
+            game_mode: GameMode::LastStanding,
+            arena: ArenaBounds {
+                min: Point::new(0, 0),
+                max: Point::new(field_size.width - 1, field_size.height - 1),
+            },
+            tick: 0,
+            teams: HashMap::new(),
+            teammates_pass_through: false,
             field_size,
             wall_collision_mode,
             dead_snake_behavior,
@@ -62,6 +129,15 @@ impl SnakeGameState {
         self.player_order.sort();
     }
 
+    /// Hands each snake and team place to the player `renames` maps it to,
+    /// keeping the order the snakes move in.
+    pub fn rename_players(&mut self, renames: &HashMap<PlayerId, PlayerId>) {
+        let rename = |id: PlayerId| renames.get(&id).cloned().unwrap_or(id);
+        self.snakes = std::mem::take(&mut self.snakes).into_iter().map(|(id, snake)| (rename(id), snake)).collect();
+        self.teams = std::mem::take(&mut self.teams).into_iter().map(|(id, team)| (rename(id), team)).collect();
+        self.player_order = std::mem::take(&mut self.player_order).into_iter().map(rename).collect();
+    }
+
     pub fn kill_snake(&mut self, player_id: &PlayerId, reason: DeathReason) -> Result<(), String> {
         let snake = self.snakes.get_mut(player_id)
             .ok_or_else(|| format!("Player {} not found", player_id))?;
@@ -83,11 +159,35 @@ impl SnakeGameState {
             return Err(format!("Player {} is dead", player_id));
         }
 
-        if direction.is_opposite(&snake.direction) {
+        let direction = if snake.has_effect(EffectKind::ReverseControls) {
+            direction.opposite()
+        } else {
+            direction
+        };
+
+        if self.input_buffer_size == 0 {
+            if direction.is_opposite(&snake.direction) {
+                return Err("Cannot turn 180 degrees".to_string());
+            }
+            snake.pending_directions.clear();
+            snake.pending_directions.push_back(direction);
+            return Ok(());
+        }
+
+        // Each turn is checked against the one queued before it, so two quick
+        // turns like Up then Left both apply instead of the second one winning.
+        let last = snake.pending_directions.back().copied().unwrap_or(snake.direction);
+        if direction == last {
+            return Ok(());
+        }
+        if direction.is_opposite(&last) {
             return Err("Cannot turn 180 degrees".to_string());
         }
+        if snake.pending_directions.len() >= self.input_buffer_size {
+            return Err("Input buffer is full".to_string());
+        }
 
-        snake.pending_direction = Some(direction);
+        snake.pending_directions.push_back(direction);
         Ok(())
     }
 
@@ -95,16 +195,159 @@ impl SnakeGameState {
         self.try_spawn_food(rng);
 
         for snake in self.snakes.values_mut() {
+            snake.consume_effect_tick();
             if !snake.is_alive() {
                 continue;
             }
 
-            if let Some(new_direction) = snake.pending_direction {
+            if let Some(new_direction) = snake.pending_directions.pop_front() {
                 snake.direction = new_direction;
-                snake.pending_direction = None;
             }
         }
 
+        match self.movement_resolution {
+            MovementResolution::Sequential => self.move_sequentially(),
+            MovementResolution::Simultaneous => self.move_simultaneously(),
+        }
+
+        for snake in self.snakes.values_mut() {
+            snake.drop_expired_effects();
+        }
+
+        self.tick += 1;
+        self.shrink_arena_if_due();
+    }
+
+    /// Whether the match is decided, either by the game mode or because at
+    /// most one side has a snake left (none when everyone plays together).
+    pub fn is_game_over(&self) -> bool {
+        let sides = self.sides();
+        let alive_count = sides.iter().filter(|side| self.side_is_alive(side)).count();
+        let last_standing = if sides.len() == 1 { alive_count == 0 } else { alive_count <= 1 };
+        last_standing || self.time_is_up() || self.score_target_reached()
+    }
+
+    pub fn side_of(&self, player_id: &PlayerId) -> Side {
+        match self.teams.get(player_id) {
+            Some(team) => Side::Team(*team),
+            None => Side::Player(player_id.clone()),
+        }
+    }
+
+    /// Every team and teamless player, in player order.
+    pub fn sides(&self) -> Vec<Side> {
+        let mut sides: Vec<Side> = Vec::new();
+        for player_id in &self.player_order {
+            let side = self.side_of(player_id);
+            if !sides.contains(&side) {
+                sides.push(side);
+            }
+        }
+        sides
+    }
+
+    /// A team scores the sum of its members' scores.
+    pub fn side_score(&self, side: &Side) -> u32 {
+        self.members(side).map(|snake| snake.score).sum()
+    }
+
+    fn side_is_alive(&self, side: &Side) -> bool {
+        self.members(side).any(|snake| snake.is_alive())
+    }
+
+    fn members<'a>(&'a self, side: &'a Side) -> impl Iterator<Item = &'a Snake> {
+        self.player_order
+            .iter()
+            .filter(move |id| self.side_of(id) == *side)
+            .map(|id| &self.snakes[id])
+    }
+
+    /// Whether `other_id` is a different snake on `player_id`'s team.
+    pub fn are_teammates(&self, player_id: &PlayerId, other_id: &PlayerId) -> bool {
+        player_id != other_id
+            && self.teams.get(player_id).is_some_and(|team| self.teams.get(other_id) == Some(team))
+    }
+
+    /// Whether `player_id` moves through `other_id`'s body without colliding.
+    pub fn passes_through(&self, player_id: &PlayerId, other_id: &PlayerId) -> bool {
+        self.teammates_pass_through && self.are_teammates(player_id, other_id)
+    }
+
+    pub fn time_is_up(&self) -> bool {
+        matches!(self.game_mode, GameMode::TimeLimit { ticks } if self.tick >= ticks)
+    }
+
+    pub fn score_target_reached(&self) -> bool {
+        matches!(self.game_mode, GameMode::ScoreTarget { score }
+            if self.sides().iter().any(|side| self.side_score(side) >= score))
+    }
+
+    /// Ticks left in a time limit game.
+    pub fn remaining_ticks(&self) -> Option<u64> {
+        match self.game_mode {
+            GameMode::TimeLimit { ticks } => Some(ticks.saturating_sub(self.tick)),
+            _ => None,
+        }
+    }
+
+    /// The winner of a finished match: the top scoring side when time runs
+    /// out or the target is reached, otherwise the last side alive.
+    pub fn winner(&self) -> Option<Side> {
+        match self.game_mode {
+            GameMode::TimeLimit { .. } => self.top_scorer(|_| true),
+            GameMode::ScoreTarget { score } if self.score_target_reached() => self.top_scorer(|s| s >= score),
+            _ => self.sides().into_iter().find(|side| self.side_is_alive(side)),
+        }
+    }
+
+    /// The highest scoring side among those whose score matches `filter`; none on a tie.
+    fn top_scorer(&self, filter: impl Fn(u32) -> bool) -> Option<Side> {
+        let candidates: Vec<(Side, u32)> = self
+            .sides()
+            .into_iter()
+            .map(|side| {
+                let score = self.side_score(&side);
+                (side, score)
+            })
+            .filter(|(_, score)| filter(*score))
+            .collect();
+        let best = candidates.iter().map(|(_, score)| *score).max()?;
+        let mut leaders = candidates.into_iter().filter(|(_, score)| *score == best);
+        match (leaders.next(), leaders.next()) {
+            (Some((winner, _)), None) => Some(winner),
+            _ => None,
+        }
+    }
+
+    fn shrink_arena_if_due(&mut self) {
+        let GameMode::BattleRoyale { shrink_interval_ticks } = self.game_mode else {
+            return;
+        };
+        if shrink_interval_ticks == 0 || !self.tick.is_multiple_of(shrink_interval_ticks) {
+            return;
+        }
+
+        let ArenaBounds { min, max } = self.arena;
+        if max.x - min.x + 1 < MIN_ARENA_SIZE + 2 || max.y - min.y + 1 < MIN_ARENA_SIZE + 2 {
+            return;
+        }
+        self.arena = ArenaBounds {
+            min: Point::new(min.x + 1, min.y + 1),
+            max: Point::new(max.x - 1, max.y - 1),
+        };
+        log!("Arena shrank to ({}, {})-({}, {})", min.x + 1, min.y + 1, max.x - 1, max.y - 1);
+
+        let arena = self.arena;
+        self.food.retain(|p, _| arena.contains(*p));
+        for player_id in self.player_order.clone() {
+            let snake = &self.snakes[&player_id];
+            if snake.is_alive() && !arena.contains(snake.head()) {
+                self.set_death(&player_id, DeathReason::WallCollision);
+            }
+        }
+    }
+
+    fn move_sequentially(&mut self) {
         for player_id in self.player_order.clone() {
             let snake = self
                 .snakes
@@ -114,20 +357,135 @@ impl SnakeGameState {
                 continue;
             }
 
-            match self.try_move_snake_for_player(&player_id) {
-                Ok(_) => {}
-                Err(reason) => {
-                    let snake = self
-                        .snakes
-                        .get_mut(&player_id)
-                        .expect("Player ID should exist in snakes map");
-                    snake.death_reason = Some(reason);
-                    self.game_end_reason = Some(reason);
+            let moves = if snake.has_effect(EffectKind::SpeedBoost) { 2 } else { 1 };
+            for _ in 0..moves {
+                if let Err(reason) = self.try_move_snake_for_player(&player_id) {
+                    self.set_death(&player_id, reason);
+                    break;
                 }
             }
         }
     }
 
+    /// Speed-boosted snakes take their second move in a round of their own.
+    fn move_simultaneously(&mut self) {
+        let movers: Vec<PlayerId> = self
+            .player_order
+            .iter()
+            .filter(|id| self.snakes[*id].is_alive())
+            .cloned()
+            .collect();
+        let boosted: Vec<PlayerId> = movers
+            .iter()
+            .filter(|id| self.snakes[*id].has_effect(EffectKind::SpeedBoost))
+            .cloned()
+            .collect();
+
+        self.step_simultaneously(&movers);
+        let boosted: Vec<PlayerId> = boosted.into_iter().filter(|id| self.snakes[id].is_alive()).collect();
+        self.step_simultaneously(&boosted);
+    }
+
+    /// Moves every snake in `movers` one cell at once. Each snake is checked
+    /// against the others' bodies as they would be after the step, so a head-on
+    /// meeting, a swap through each other or two heads entering the same cell
+    /// kills everyone involved instead of favouring whoever moves first.
+    fn step_simultaneously(&mut self, movers: &[PlayerId]) {
+        let mut deaths: Vec<(PlayerId, DeathReason)> = Vec::new();
+        let mut targets: Vec<(PlayerId, Point)> = Vec::new();
+        for player_id in movers {
+            match self.next_head_position(&self.snakes[player_id]) {
+                Ok(target) => targets.push((player_id.clone(), target)),
+                Err(reason) => deaths.push((player_id.clone(), reason)),
+            }
+        }
+
+        // A dead snake left on the field keeps its tail where it is, and that
+        // can kill a snake that would have moved into it, so collisions are
+        // resolved again until no more snakes die.
+        let mut collisions = Vec::new();
+        loop {
+            let vacated_tails: HashMap<&PlayerId, Point> = targets
+                .iter()
+                .filter(|(_, target)| !matches!(self.food.get(target), Some(FoodKind::Normal | FoodKind::Golden)))
+                .filter(|(id, _)| {
+                    matches!(self.dead_snake_behavior, DeadSnakeBehavior::Disappear)
+                        || !collisions.iter().any(|(dead_id, _)| dead_id == id)
+                })
+                .map(|(id, _)| (id, self.snakes[id].tail()))
+                .collect();
+
+            let resolved = self.collision_deaths(&targets, &vacated_tails);
+            if resolved.len() == collisions.len() {
+                break;
+            }
+            collisions = resolved;
+        }
+        for (player_id, target) in &targets {
+            if collisions.contains(&(player_id.clone(), DeathReason::OtherSnakeCollision)) {
+                log!("{} collided with another snake at ({}, {})", player_id, target.x, target.y);
+            }
+        }
+        deaths.extend(collisions);
+
+        for (player_id, target) in targets {
+            if !deaths.iter().any(|(id, _)| *id == player_id) {
+                self.advance_snake(&player_id, target);
+            }
+        }
+        for (player_id, reason) in deaths {
+            self.set_death(&player_id, reason);
+        }
+    }
+
+    /// The snakes that run into themselves or another snake when every snake
+    /// in `targets` moves, with `vacated_tails` the tail cells freed this step.
+    fn collision_deaths(
+        &self,
+        targets: &[(PlayerId, Point)],
+        vacated_tails: &HashMap<&PlayerId, Point>,
+    ) -> Vec<(PlayerId, DeathReason)> {
+        let mut deaths = Vec::new();
+        for (player_id, target) in targets {
+            let snake = &self.snakes[player_id];
+            if snake.body_set.contains(target) && *target != snake.tail() {
+                deaths.push((player_id.clone(), DeathReason::SelfCollision));
+                continue;
+            }
+            if snake.has_effect(EffectKind::Ghost) {
+                continue;
+            }
+
+            let shares_target = targets
+                .iter()
+                .any(|(id, other)| id != player_id && other == target && !self.passes_through(player_id, id));
+            let hits_body = self.snakes.iter().any(|(other_id, other_snake)| {
+                let should_check_collision = match self.dead_snake_behavior {
+                    DeadSnakeBehavior::Disappear => other_snake.is_alive(),
+                    DeadSnakeBehavior::StayOnField => true,
+                };
+                other_id != player_id
+                    && should_check_collision
+                    && !self.passes_through(player_id, other_id)
+                    && other_snake.body_set.contains(target)
+                    && vacated_tails.get(other_id) != Some(target)
+            });
+            if shares_target || hits_body {
+                deaths.push((player_id.clone(), DeathReason::OtherSnakeCollision));
+            }
+        }
+        deaths
+    }
+
+    fn set_death(&mut self, player_id: &PlayerId, reason: DeathReason) {
+        let snake = self
+            .snakes
+            .get_mut(player_id)
+            .expect("Player ID should exist in snakes map");
+        snake.death_reason = Some(reason);
+        self.game_end_reason = Some(reason);
+    }
+
     fn try_move_snake_for_player(&mut self, player_id: &PlayerId) -> Result<(), DeathReason> {
         let next_head = {
             let snake = self
@@ -136,7 +494,13 @@ impl SnakeGameState {
                 .expect("Player ID should exist in snakes map");
             self.calculate_next_head_position_for_player(player_id, snake)?
         };
+        self.advance_snake(player_id, next_head);
+        Ok(())
+    }
 
+    /// Moves the head into `next_head`, which must already be known to be safe,
+    /// and applies whatever item lies there.
+    fn advance_snake(&mut self, player_id: &PlayerId, next_head: Point) {
         let snake = self
             .snakes
             .get_mut(player_id)
@@ -144,9 +508,9 @@ impl SnakeGameState {
         snake.body.push_front(next_head);
         snake.body_set.insert(next_head);
 
-        if self.food_set.contains(&next_head) {
-            self.food_set.remove(&next_head);
-            snake.score += 1;
+        let eaten = self.food.remove(&next_head);
+        if matches!(eaten, Some(FoodKind::Normal | FoodKind::Golden)) {
+            snake.score += if eaten == Some(FoodKind::Golden) { GOLDEN_FOOD_SCORE } else { 1 };
             log!(
                 "[{}] ate food at ({}, {}). Score: {}",
                 player_id,
@@ -162,7 +526,15 @@ impl SnakeGameState {
             snake.body_set.remove(&tail);
         }
 
-        Ok(())
+        match eaten {
+            Some(FoodKind::SpeedBoost) => snake.add_effect(EffectKind::SpeedBoost, SPEED_BOOST_TICKS),
+            Some(FoodKind::Ghost) => snake.add_effect(EffectKind::Ghost, GHOST_TICKS),
+            Some(FoodKind::ReverseControls) => {
+                snake.add_effect(EffectKind::ReverseControls, REVERSE_CONTROLS_TICKS)
+            }
+            Some(FoodKind::Shrink) => snake.shrink(SHRINK_SEGMENTS, MIN_SHRUNK_LENGTH),
+            Some(FoodKind::Normal | FoodKind::Golden) | None => {}
+        }
     }
 
     fn calculate_next_head_position_for_player(
@@ -170,6 +542,43 @@ impl SnakeGameState {
         player_id: &PlayerId,
         snake: &Snake,
     ) -> Result<Point, DeathReason> {
+        let next_head = self.next_head_position(snake)?;
+
+        if snake.body_set.contains(&next_head) && next_head != snake.tail() {
+            return Err(DeathReason::SelfCollision);
+        }
+
+        if snake.has_effect(EffectKind::Ghost) {
+            return Ok(next_head);
+        }
+
+        for (other_id, other_snake) in &self.snakes {
+            if other_id == player_id || self.passes_through(player_id, other_id) {
+                continue;
+            }
+
+            let should_check_collision = match self.dead_snake_behavior {
+                DeadSnakeBehavior::Disappear => other_snake.is_alive(),
+                DeadSnakeBehavior::StayOnField => true,
+            };
+
+            if should_check_collision && other_snake.body_set.contains(&next_head) {
+                log!(
+                    "{} collided with {} at ({}, {})",
+                    player_id,
+                    other_id,
+                    next_head.x,
+                    next_head.y
+                );
+                return Err(DeathReason::OtherSnakeCollision);
+            }
+        }
+
+        Ok(next_head)
+    }
+
+    /// The cell the snake's head moves into, checked against walls and obstacles only.
+    fn next_head_position(&self, snake: &Snake) -> Result<Point, DeathReason> {
         let head = snake.head();
         let direction = &snake.direction;
 
@@ -216,30 +625,8 @@ impl SnakeGameState {
             },
         };
 
-        if snake.body_set.contains(&next_head) && next_head != snake.tail() {
-            return Err(DeathReason::SelfCollision);
-        }
-
-        for (other_id, other_snake) in &self.snakes {
-            if other_id == player_id {
-                continue;
-            }
-
-            let should_check_collision = match self.dead_snake_behavior {
-                DeadSnakeBehavior::Disappear => other_snake.is_alive(),
-                DeadSnakeBehavior::StayOnField => true,
-            };
-
-            if should_check_collision && other_snake.body_set.contains(&next_head) {
-                log!(
-                    "{} collided with {} at ({}, {})",
-                    player_id,
-                    other_id,
-                    next_head.x,
-                    next_head.y
-                );
-                return Err(DeathReason::OtherSnakeCollision);
-            }
+        if self.obstacles.contains(&next_head) || !self.arena.contains(next_head) {
+            return Err(DeathReason::WallCollision);
         }
 
         Ok(next_head)
@@ -255,7 +642,7 @@ impl SnakeGameState {
             let y = rng.random_range(0..self.field_size.height);
             let pos = Point::new(x, y);
 
-            if self.food_set.contains(&pos) {
+            if self.food.contains_key(&pos) || self.obstacles.contains(&pos) || !self.arena.contains(pos) {
                 continue;
             }
 
@@ -273,17 +660,340 @@ impl SnakeGameState {
             }
 
             if !occupied {
-                self.food_set.insert(pos);
-                log!("Food spawned at ({}, {})", pos.x, pos.y);
+                let kind = self.food_weights.pick(rng);
+                self.food.insert(pos, kind);
+                log!("{:?} food spawned at ({}, {})", kind, pos.x, pos.y);
                 return;
             }
         }
     }
 
     fn should_spawn_food(&self, rng: &mut SessionRng) -> bool {
-        if self.food_set.len() >= self.max_food_count {
+        if self.food.len() >= self.max_food_count {
             return false;
         }
-        rng.random::<f32>() < self.food_spawn_probability
+        rng.random_f32() < self.food_spawn_probability
+    }
+
+    pub fn state_hash(&self) -> u64 {
+        let mut hasher = StateHasher::new();
+        for player_id in &self.player_order {
+            let Some(snake) = self.snakes.get(player_id) else {
+                continue;
+            };
+            hasher.write_str(&player_id.to_string());
+            hasher.write_bool(snake.is_alive());
+            hasher.write_u64(snake.direction as u64);
+            hasher.write_u64(snake.score as u64);
+            hasher.write_u64(snake.body.len() as u64);
+            for point in &snake.body {
+                hasher.write_u64(point.x as u64);
+                hasher.write_u64(point.y as u64);
+            }
+            // Items and effects only enter the hash when present, so games
+            // without them hash the same as replays recorded before they existed.
+            for effect in &snake.effects {
+                hasher.write_u64(effect.kind as u64);
+                hasher.write_u64(effect.remaining_ticks as u64);
+            }
+        }
+
+        let mut food: Vec<(&Point, &FoodKind)> = self.food.iter().collect();
+        food.sort_by_key(|(p, _)| (p.x, p.y));
+        for (point, kind) in food {
+            hasher.write_u64(point.x as u64);
+            hasher.write_u64(point.y as u64);
+            if *kind != FoodKind::Normal {
+                hasher.write_u64(*kind as u64);
+            }
+        }
+        hasher.finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn state_with_bot() -> (SnakeGameState, PlayerId) {
+        let mut state = SnakeGameState::new(
+            FieldSize { width: 10, height: 10 },
+            WallCollisionMode::Death,
+            DeadSnakeBehavior::Disappear,
+            3,
+            0.0,
+        );
+        let player_id = PlayerId::new("bot".to_string());
+        state.add_snake(player_id.clone(), Point::new(4, 5), Direction::Right);
+        (state, player_id)
+    }
+
+    #[test]
+    fn test_speed_boost_moves_twice_per_tick_and_expires() {
+        let (mut state, player_id) = state_with_bot();
+        let mut rng = SessionRng::new(0);
+        state.food.insert(Point::new(5, 5), FoodKind::SpeedBoost);
+
+        state.update(&mut rng);
+        assert_eq!(state.snakes[&player_id].head(), Point::new(5, 5));
+        assert!(state.snakes[&player_id].has_effect(EffectKind::SpeedBoost));
+
+        state.update(&mut rng);
+        assert_eq!(state.snakes[&player_id].head(), Point::new(7, 5));
+
+        state.snakes.get_mut(&player_id).unwrap().effects[0].remaining_ticks = 1;
+        state.update(&mut rng);
+        assert_eq!(state.snakes[&player_id].head(), Point::new(9, 5));
+        assert!(state.snakes[&player_id].effects.is_empty());
+    }
+
+    #[test]
+    fn test_reverse_controls_mirror_turns() {
+        let (mut state, player_id) = state_with_bot();
+        let mut rng = SessionRng::new(0);
+        state.snakes.get_mut(&player_id).unwrap().add_effect(EffectKind::ReverseControls, 5);
+
+        state.set_snake_direction(&player_id, Direction::Up).unwrap();
+        state.update(&mut rng);
+        assert_eq!(state.snakes[&player_id].head(), Point::new(4, 6));
+    }
+
+    #[test]
+    fn test_golden_food_and_shrink() {
+        let (mut state, player_id) = state_with_bot();
+        let mut rng = SessionRng::new(0);
+        state.food.insert(Point::new(5, 5), FoodKind::Golden);
+        state.food.insert(Point::new(6, 5), FoodKind::Shrink);
+
+        state.update(&mut rng);
+        assert_eq!(state.snakes[&player_id].score, GOLDEN_FOOD_SCORE);
+        assert_eq!(state.snakes[&player_id].body.len(), 4);
+
+        state.update(&mut rng);
+        assert_eq!(state.snakes[&player_id].body.len(), MIN_SHRUNK_LENGTH);
+    }
+
+    #[test]
+    fn test_obstacle_kills_like_a_wall() {
+        let (mut state, player_id) = state_with_bot();
+        let mut rng = SessionRng::new(0);
+        state.obstacles.insert(Point::new(5, 5));
+
+        state.update(&mut rng);
+        assert_eq!(state.snakes[&player_id].death_reason, Some(DeathReason::WallCollision));
+    }
+
+    fn head_on_state(movement_resolution: MovementResolution) -> SnakeGameState {
+        let (mut state, _) = state_with_bot();
+        state.movement_resolution = movement_resolution;
+        state.add_snake(PlayerId::new("rival".to_string()), Point::new(6, 5), Direction::Left);
+        state
+    }
+
+    fn set_body(state: &mut SnakeGameState, player: &str, body: [Point; 3]) {
+        let snake = state.snakes.get_mut(&PlayerId::new(player.to_string())).unwrap();
+        snake.body = body.into();
+        snake.body_set = snake.body.iter().copied().collect();
+    }
+
+    #[test]
+    fn test_simultaneous_same_cell_kills_both() {
+        let mut state = head_on_state(MovementResolution::Simultaneous);
+        state.update(&mut SessionRng::new(0));
+        assert!(state.snakes.values().all(|s| s.death_reason == Some(DeathReason::OtherSnakeCollision)));
+    }
+
+    #[test]
+    fn test_simultaneous_swap_through_kills_both() {
+        let mut state = head_on_state(MovementResolution::Simultaneous);
+        set_body(&mut state, "rival", [Point::new(5, 5), Point::new(6, 5), Point::new(7, 5)]);
+
+        state.update(&mut SessionRng::new(0));
+        assert!(state.snakes.values().all(|s| !s.is_alive()));
+    }
+
+    #[test]
+    fn test_simultaneous_allows_following_a_moving_tail() {
+        let mut state = head_on_state(MovementResolution::Simultaneous);
+        set_body(&mut state, "bot", [Point::new(5, 5), Point::new(4, 5), Point::new(3, 5)]);
+        set_body(&mut state, "rival", [Point::new(5, 4), Point::new(6, 4), Point::new(6, 5)]);
+        state.snakes.get_mut(&PlayerId::new("rival".to_string())).unwrap().direction = Direction::Up;
+
+        state.update(&mut SessionRng::new(0));
+        assert!(state.snakes.values().all(|s| s.is_alive()));
+    }
+
+    #[test]
+    fn test_simultaneous_tail_of_snake_dying_this_tick_only_moves_if_it_disappears() {
+        for (dead_snake_behavior, third_survives) in
+            [(DeadSnakeBehavior::StayOnField, false), (DeadSnakeBehavior::Disappear, true)]
+        {
+            let mut state = head_on_state(MovementResolution::Simultaneous);
+            state.dead_snake_behavior = dead_snake_behavior;
+            // Heads for the rival's tail while the rival dies head-on with the bot.
+            state.add_snake(PlayerId::new("third".to_string()), Point::new(8, 6), Direction::Up);
+
+            state.update(&mut SessionRng::new(0));
+            let third = &state.snakes[&PlayerId::new("third".to_string())];
+            assert_eq!(third.is_alive(), third_survives);
+            assert!(!state.snakes[&PlayerId::new("rival".to_string())].is_alive());
+        }
+    }
+
+    #[test]
+    fn test_sequential_same_cell_favours_first_player() {
+        let mut state = head_on_state(MovementResolution::Sequential);
+        state.update(&mut SessionRng::new(0));
+        assert!(state.snakes[&PlayerId::new("bot".to_string())].is_alive());
+        assert!(!state.snakes[&PlayerId::new("rival".to_string())].is_alive());
+    }
+
+    #[test]
+    fn test_input_buffer_applies_quick_turns_on_consecutive_ticks() {
+        let (mut state, player_id) = state_with_bot();
+        state.input_buffer_size = 3;
+        let mut rng = SessionRng::new(0);
+
+        state.set_snake_direction(&player_id, Direction::Up).unwrap();
+        state.set_snake_direction(&player_id, Direction::Left).unwrap();
+        assert!(state.set_snake_direction(&player_id, Direction::Right).is_err());
+
+        state.update(&mut rng);
+        assert_eq!(state.snakes[&player_id].head(), Point::new(4, 4));
+        state.update(&mut rng);
+        assert_eq!(state.snakes[&player_id].head(), Point::new(3, 4));
+    }
+
+    #[test]
+    fn test_input_buffer_rejects_turns_beyond_its_size() {
+        let (mut state, player_id) = state_with_bot();
+        state.input_buffer_size = 2;
+
+        state.set_snake_direction(&player_id, Direction::Up).unwrap();
+        state.set_snake_direction(&player_id, Direction::Left).unwrap();
+        assert!(state.set_snake_direction(&player_id, Direction::Down).is_err());
+    }
+
+    #[test]
+    fn test_without_input_buffer_last_turn_wins() {
+        let (mut state, player_id) = state_with_bot();
+        let mut rng = SessionRng::new(0);
+
+        state.set_snake_direction(&player_id, Direction::Up).unwrap();
+        state.set_snake_direction(&player_id, Direction::Down).unwrap();
+        state.update(&mut rng);
+        assert_eq!(state.snakes[&player_id].head(), Point::new(4, 6));
+        assert!(state.snakes[&player_id].pending_directions.is_empty());
+    }
+
+    fn two_snake_state(game_mode: GameMode) -> SnakeGameState {
+        let (mut state, _) = state_with_bot();
+        state.game_mode = game_mode;
+        state.add_snake(PlayerId::new("rival".to_string()), Point::new(7, 3), Direction::Right);
+        state
+    }
+
+    #[test]
+    fn test_time_limit_ends_with_top_scorer_winning() {
+        let mut state = two_snake_state(GameMode::TimeLimit { ticks: 2 });
+        let mut rng = SessionRng::new(0);
+        state.snakes.get_mut(&PlayerId::new("rival".to_string())).unwrap().score = 3;
+
+        state.update(&mut rng);
+        assert!(!state.is_game_over());
+        assert_eq!(state.remaining_ticks(), Some(1));
+        state.update(&mut rng);
+        assert!(state.is_game_over() && state.time_is_up());
+        assert_eq!(state.winner(), Some(Side::Player(PlayerId::new("rival".to_string()))));
+    }
+
+    #[test]
+    fn test_time_limit_tie_has_no_winner() {
+        let mut state = two_snake_state(GameMode::TimeLimit { ticks: 1 });
+        state.update(&mut SessionRng::new(0));
+        assert!(state.is_game_over());
+        assert_eq!(state.winner(), None);
+    }
+
+    #[test]
+    fn test_score_target_ends_when_reached() {
+        let mut state = two_snake_state(GameMode::ScoreTarget { score: 1 });
+        state.food.insert(Point::new(5, 5), FoodKind::Normal);
+
+        state.update(&mut SessionRng::new(0));
+        assert!(state.is_game_over() && state.score_target_reached());
+        assert_eq!(state.winner(), Some(Side::Player(PlayerId::new("bot".to_string()))));
+    }
+
+    #[test]
+    fn test_battle_royale_shrink_kills_snakes_outside_the_arena() {
+        let mut state = two_snake_state(GameMode::BattleRoyale { shrink_interval_ticks: 2 });
+        let mut rng = SessionRng::new(0);
+        state.food.insert(Point::new(0, 0), FoodKind::Normal);
+
+        state.update(&mut rng);
+        assert_eq!(state.arena.min, Point::new(0, 0));
+        state.update(&mut rng);
+        assert_eq!(state.arena, ArenaBounds { min: Point::new(1, 1), max: Point::new(8, 8) });
+        assert!(state.snakes[&PlayerId::new("bot".to_string())].is_alive());
+        let rival = &state.snakes[&PlayerId::new("rival".to_string())];
+        assert_eq!(rival.death_reason, Some(DeathReason::WallCollision));
+        assert!(!state.food.contains_key(&Point::new(0, 0)));
+        assert_eq!(state.winner(), Some(Side::Player(PlayerId::new("bot".to_string()))));
+    }
+
+    fn team_state(teammates_pass_through: bool) -> SnakeGameState {
+        let mut state = head_on_state(MovementResolution::Simultaneous);
+        state.add_snake(PlayerId::new("solo".to_string()), Point::new(1, 8), Direction::Right);
+        state.teams = HashMap::from([
+            (PlayerId::new("bot".to_string()), 1),
+            (PlayerId::new("rival".to_string()), 1),
+        ]);
+        state.teammates_pass_through = teammates_pass_through;
+        state
+    }
+
+    #[test]
+    fn test_teammates_pass_through_each_other_when_enabled() {
+        let mut state = team_state(true);
+        state.update(&mut SessionRng::new(0));
+        assert!(state.snakes.values().all(|s| s.is_alive()));
+
+        let mut state = team_state(false);
+        state.update(&mut SessionRng::new(0));
+        assert!(!state.snakes[&PlayerId::new("bot".to_string())].is_alive());
+        assert!(!state.snakes[&PlayerId::new("rival".to_string())].is_alive());
+    }
+
+    #[test]
+    fn test_last_team_standing_wins() {
+        let mut state = team_state(true);
+        state.kill_snake(&PlayerId::new("bot".to_string()), DeathReason::PlayerDisconnected).unwrap();
+        assert!(!state.is_game_over());
+
+        state.kill_snake(&PlayerId::new("solo".to_string()), DeathReason::PlayerDisconnected).unwrap();
+        assert!(state.is_game_over());
+        assert_eq!(state.winner(), Some(Side::Team(1)));
+    }
+
+    #[test]
+    fn test_team_score_is_sum_of_members() {
+        let mut state = team_state(true);
+        state.game_mode = GameMode::TimeLimit { ticks: 1 };
+        state.snakes.get_mut(&PlayerId::new("bot".to_string())).unwrap().score = 2;
+        state.snakes.get_mut(&PlayerId::new("rival".to_string())).unwrap().score = 2;
+        state.snakes.get_mut(&PlayerId::new("solo".to_string())).unwrap().score = 3;
+
+        assert_eq!(state.side_score(&Side::Team(1)), 4);
+        state.update(&mut SessionRng::new(0));
+        assert_eq!(state.winner(), Some(Side::Team(1)));
+    }
+
+    #[test]
+    fn test_default_weights_do_not_draw_from_rng() {
+        let mut rng = SessionRng::new(7);
+        let mut untouched = SessionRng::new(7);
+        assert_eq!(FoodWeights::default().pick(&mut rng), FoodKind::Normal);
+        assert_eq!(rng.next_u64(), untouched.next_u64());
     }
 }
@@ -0,0 +1,333 @@
+use std::collections::{HashMap, HashSet};
+use std::path::Path;
+use std::sync::OnceLock;
+
+use serde::Deserialize;
+
+use crate::log;
+use crate::proto::snake::{
+    Direction as ProtoDirection, Position, SnakeLobbySettings, SnakeMap as ProtoSnakeMap, SpawnPoint,
+    WallCollisionMode as ProtoWallCollisionMode,
+};
+use super::entity::Snake;
+use super::spawn::row_spawns;
+use super::types::{Direction, FieldSize, Point, WallCollisionMode};
+
+const BUNDLED_MAPS: [&str; 3] = [
+    include_str!("../../../maps/snake/box.yaml"),
+    include_str!("../../../maps/snake/cross.yaml"),
+    include_str!("../../../maps/snake/rooms.yaml"),
+];
+
+const MAP_FILE_EXTENSION: &str = "yaml";
+
+static MAPS: OnceLock<HashMap<String, SnakeMap>> = OnceLock::new();
+
+/// A field layout: obstacle cells, an optional wall rule and the spawn points.
+#[derive(Clone, Debug)]
+pub struct SnakeMap {
+    pub name: String,
+    pub field_size: FieldSize,
+    pub obstacles: HashSet<Point>,
+    /// Overrides the lobby's wall collision mode when set.
+    pub wall_collision_mode: Option<WallCollisionMode>,
+    /// One per player in order; empty means the default placement.
+    pub spawn_points: Vec<(Point, Direction)>,
+}
+
+/// Map file layout. The grid has one character per cell: `#` is an obstacle,
+/// `.` an empty cell and `^`, `v`, `<`, `>` a spawn point facing that way.
+/// Spawn points are handed out in reading order.
+#[derive(Deserialize)]
+struct MapFile {
+    name: String,
+    #[serde(default)]
+    wall_collision_mode: Option<MapWallCollisionMode>,
+    grid: String,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "snake_case")]
+enum MapWallCollisionMode {
+    Death,
+    WrapAround,
+}
+
+impl SnakeMap {
+    pub fn from_yaml(content: &str) -> Result<Self, String> {
+        let file: MapFile = serde_yaml_ng::from_str(content).map_err(|e| e.to_string())?;
+
+        let rows: Vec<&str> = file.grid.lines().map(str::trim).filter(|row| !row.is_empty()).collect();
+        let width = rows.first().map_or(0, |row| row.chars().count());
+        if rows.iter().any(|row| row.chars().count() != width) {
+            return Err(format!("Map '{}' has rows of different lengths", file.name));
+        }
+
+        let mut obstacles = HashSet::new();
+        let mut spawn_points = Vec::new();
+        for (y, row) in rows.iter().enumerate() {
+            for (x, cell) in row.chars().enumerate() {
+                let point = Point::new(x, y);
+                match cell {
+                    '.' => {}
+                    '#' => {
+                        obstacles.insert(point);
+                    }
+                    '^' => spawn_points.push((point, Direction::Up)),
+                    'v' => spawn_points.push((point, Direction::Down)),
+                    '<' => spawn_points.push((point, Direction::Left)),
+                    '>' => spawn_points.push((point, Direction::Right)),
+                    other => return Err(format!("Map '{}' has unknown cell '{}'", file.name, other)),
+                }
+            }
+        }
+
+        let map = Self {
+            name: file.name,
+            field_size: FieldSize { width, height: rows.len() },
+            obstacles,
+            wall_collision_mode: file.wall_collision_mode.map(|mode| match mode {
+                MapWallCollisionMode::Death => WallCollisionMode::Death,
+                MapWallCollisionMode::WrapAround => WallCollisionMode::WrapAround,
+            }),
+            spawn_points,
+        };
+        map.check_layout()?;
+        Ok(map)
+    }
+
+    pub fn from_proto(map: &ProtoSnakeMap) -> Result<Self, String> {
+        let point = |position: &Position| {
+            if position.x < 0 || position.y < 0 {
+                return Err(format!("Map '{}' has a cell outside the field", map.name));
+            }
+            Ok(Point::new(position.x as usize, position.y as usize))
+        };
+
+        let obstacles = map.obstacles.iter().map(point).collect::<Result<HashSet<_>, _>>()?;
+        let spawn_points = map
+            .spawn_points
+            .iter()
+            .map(|spawn| {
+                let position = spawn
+                    .position
+                    .as_ref()
+                    .ok_or_else(|| format!("Map '{}' has a spawn point without a position", map.name))?;
+                let direction = match ProtoDirection::try_from(spawn.direction) {
+                    Ok(ProtoDirection::Up) => Direction::Up,
+                    Ok(ProtoDirection::Down) => Direction::Down,
+                    Ok(ProtoDirection::Left) => Direction::Left,
+                    Ok(ProtoDirection::Right) => Direction::Right,
+                    _ => return Err(format!("Map '{}' has a spawn point without a direction", map.name)),
+                };
+                Ok((point(position)?, direction))
+            })
+            .collect::<Result<Vec<_>, String>>()?;
+
+        let wall_collision_mode = match ProtoWallCollisionMode::try_from(map.wall_collision_mode) {
+            Ok(ProtoWallCollisionMode::Death) => Some(WallCollisionMode::Death),
+            Ok(ProtoWallCollisionMode::WrapAround) => Some(WallCollisionMode::WrapAround),
+            _ => None,
+        };
+
+        let map = Self {
+            name: map.name.clone(),
+            field_size: FieldSize { width: map.width as usize, height: map.height as usize },
+            obstacles,
+            wall_collision_mode,
+            spawn_points,
+        };
+        map.check_layout()?;
+        Ok(map)
+    }
+
+    pub fn to_proto(&self) -> ProtoSnakeMap {
+        let position = |p: &Point| Position { x: p.x as i32, y: p.y as i32 };
+
+        let mut obstacles: Vec<&Point> = self.obstacles.iter().collect();
+        obstacles.sort_by_key(|p| (p.y, p.x));
+
+        ProtoSnakeMap {
+            name: self.name.clone(),
+            width: self.field_size.width as u32,
+            height: self.field_size.height as u32,
+            obstacles: obstacles.into_iter().map(position).collect(),
+            wall_collision_mode: match self.wall_collision_mode {
+                Some(WallCollisionMode::Death) => ProtoWallCollisionMode::Death,
+                Some(WallCollisionMode::WrapAround) => ProtoWallCollisionMode::WrapAround,
+                None => ProtoWallCollisionMode::Unspecified,
+            } as i32,
+            spawn_points: self
+                .spawn_points
+                .iter()
+                .map(|(point, direction)| SpawnPoint {
+                    position: Some(position(point)),
+                    direction: match direction {
+                        Direction::Up => ProtoDirection::Up,
+                        Direction::Down => ProtoDirection::Down,
+                        Direction::Left => ProtoDirection::Left,
+                        Direction::Right => ProtoDirection::Right,
+                    } as i32,
+                })
+                .collect(),
+        }
+    }
+
+    /// Checks that every player up to `max_players` gets a start position
+    /// clear of obstacles.
+    pub fn check_players(&self, max_players: usize) -> Result<(), String> {
+        if !self.spawn_points.is_empty() {
+            if max_players > self.spawn_points.len() {
+                return Err(format!(
+                    "Map '{}' has spawn points for {} players",
+                    self.name,
+                    self.spawn_points.len()
+                ));
+            }
+            return Ok(());
+        }
+
+        for total in 1..=max_players {
+            if self.blocks_any(&row_spawns(total, &self.field_size)) {
+                return Err(format!("Map '{}' blocks the start positions for {} players", self.name, total));
+            }
+        }
+        Ok(())
+    }
+
+    fn check_layout(&self) -> Result<(), String> {
+        if self.name.is_empty() {
+            return Err("Map name must not be empty".to_string());
+        }
+        let FieldSize { width, height } = self.field_size;
+        if !(10..=100).contains(&width) || !(10..=100).contains(&height) {
+            return Err(format!("Map '{}' must be between 10x10 and 100x100", self.name));
+        }
+
+        let in_field = |p: &Point| p.x < width && p.y < height;
+        if !self.obstacles.iter().all(in_field) || !self.spawn_points.iter().all(|(p, _)| in_field(p)) {
+            return Err(format!("Map '{}' has a cell outside the field", self.name));
+        }
+
+        if self.blocks_any(&self.spawn_points) {
+            return Err(format!("Map '{}' has a spawn point blocked by an obstacle or another snake", self.name));
+        }
+        Ok(())
+    }
+
+    /// Whether any snake starting at `starts` would overlap an obstacle or
+    /// another of the snakes.
+    fn blocks_any(&self, starts: &[(Point, Direction)]) -> bool {
+        let mut occupied = self.obstacles.clone();
+        starts.iter().any(|(point, direction)| {
+            let snake = Snake::new(*point, *direction, &self.field_size);
+            !snake.body.iter().all(|segment| occupied.insert(*segment))
+        })
+    }
+}
+
+/// Loads the bundled maps plus every map file in `dir`. A file replaces a
+/// bundled map of the same name; invalid files are logged and skipped.
+/// Call once at startup, before any lobby is validated.
+pub fn init_maps(dir: Option<&Path>) -> Result<usize, String> {
+    let mut maps = bundled_maps();
+
+    if let Some(dir) = dir {
+        let entries = std::fs::read_dir(dir).map_err(|e| format!("{}: {}", dir.display(), e))?;
+        for entry in entries {
+            let path = entry.map_err(|e| format!("{}: {}", dir.display(), e))?.path();
+            if path.extension().and_then(|e| e.to_str()) != Some(MAP_FILE_EXTENSION) {
+                continue;
+            }
+            match std::fs::read_to_string(&path).map_err(|e| e.to_string()).and_then(|c| SnakeMap::from_yaml(&c)) {
+                Ok(map) => {
+                    maps.insert(map.name.clone(), map);
+                }
+                Err(e) => log!("Skipping map {}: {}", path.display(), e),
+            }
+        }
+    }
+
+    let count = maps.len();
+    MAPS.set(maps).map_err(|_| "Snake maps are already loaded".to_string())?;
+    Ok(count)
+}
+
+pub fn find_map(name: &str) -> Option<SnakeMap> {
+    MAPS.get_or_init(bundled_maps).get(name).cloned()
+}
+
+fn bundled_maps() -> HashMap<String, SnakeMap> {
+    BUNDLED_MAPS
+        .iter()
+        .map(|content| {
+            let map = SnakeMap::from_yaml(content).expect("Bundled maps should be valid");
+            (map.name.clone(), map)
+        })
+        .collect()
+}
+
+/// The map a lobby plays on: the embedded one if the settings carry it,
+/// otherwise the loaded map named in `map_name`.
+pub fn resolve_map(settings: &SnakeLobbySettings) -> Result<Option<SnakeMap>, String> {
+    if let Some(map) = &settings.map {
+        return SnakeMap::from_proto(map).map(Some);
+    }
+    if settings.map_name.is_empty() {
+        return Ok(None);
+    }
+    find_map(&settings.map_name)
+        .map(Some)
+        .ok_or_else(|| format!("Unknown map '{}'", settings.map_name))
+}
+
+/// Copies `settings` with the resolved map embedded, so a replay recorded
+/// with them doesn't depend on the map files.
+pub fn with_embedded_map(settings: &SnakeLobbySettings) -> Result<SnakeLobbySettings, String> {
+    let mut settings = settings.clone();
+    settings.map = resolve_map(&settings)?.map(|map| map.to_proto());
+    Ok(settings)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_bundled_maps_round_trip_through_proto() {
+        for map in bundled_maps().values() {
+            let restored = SnakeMap::from_proto(&map.to_proto()).unwrap();
+            assert_eq!(restored.obstacles, map.obstacles);
+            assert_eq!(restored.spawn_points, map.spawn_points);
+            assert_eq!(restored.wall_collision_mode, map.wall_collision_mode);
+        }
+    }
+
+    #[test]
+    fn test_map_file_rejects_blocked_spawn_point() {
+        let mut grid = String::new();
+        for y in 0..10 {
+            grid.push_str(if y == 5 { "  ....^.....\n" } else if y == 6 { "  ....#.....\n" } else { "  ..........\n" });
+        }
+        let error = SnakeMap::from_yaml(&format!("name: tight\ngrid: |\n{}", grid)).unwrap_err();
+        assert!(error.contains("blocked by an obstacle"));
+    }
+
+    #[test]
+    fn test_check_players_limits_to_spawn_points() {
+        let map = find_map("cross").unwrap();
+        assert!(map.check_players(4).is_ok());
+        assert!(map.check_players(5).is_err());
+    }
+
+    #[test]
+    fn test_embedded_map_wins_over_map_name() {
+        let mut settings = SnakeLobbySettings { map_name: "box".to_string(), ..Default::default() };
+        let mut embedded = find_map("box").unwrap().to_proto();
+        embedded.obstacles.pop();
+        settings.map = Some(embedded);
+
+        let resolved = resolve_map(&settings).unwrap().unwrap();
+        assert_eq!(resolved.obstacles.len(), find_map("box").unwrap().obstacles.len() - 1);
+    }
+}
@@ -1,15 +1,22 @@
 mod bot_controller;
 mod entity;
 mod game_state;
+mod map;
 pub(crate) mod replay;
 mod session;
 mod settings;
+mod spawn;
 mod types;
 mod validate;
 
 pub use bot_controller::BotController;
 pub use entity::Snake;
 pub use game_state::SnakeGameState;
-pub use session::{SnakeSession, SnakeSessionState};
-pub use settings::SnakeSessionSettings;
-pub use types::{DeadSnakeBehavior, DeathReason, Direction, FieldSize, Point, WallCollisionMode};
+pub use map::{SnakeMap, find_map, init_maps, resolve_map, with_embedded_map};
+pub use session::{SnakeSession, SnakeSessionState, initial_game_state};
+pub use session::create_turn_command;
+pub use settings::{DEFAULT_INPUT_BUFFER_SIZE, SnakeSessionSettings};
+pub use types::{
+    ActiveEffect, ArenaBounds, DeadSnakeBehavior, DeathReason, Direction, EffectKind, FieldSize, FoodKind,
+    FoodWeights, GameMode, MovementResolution, Point, Side, SpawnPlacement, WallCollisionMode,
+};
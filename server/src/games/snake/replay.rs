@@ -1,154 +1,231 @@
 use std::collections::HashMap;
+use std::sync::Arc;
 use std::time::Duration;
 
 use tokio::sync::mpsc;
 
 use crate::games::snake::{
-    DeadSnakeBehavior, DeathReason, Direction, FieldSize, Point, SnakeGameState, WallCollisionMode,
+    DeadSnakeBehavior, DeathReason, Direction, Side, SnakeGameState, SnakeSessionSettings, SnakeSessionState,
+    WallCollisionMode, initial_game_state, resolve_map,
 };
-use crate::games::SessionRng;
+use crate::games::{GameSession, GameSessionConfig, SessionRng};
 use crate::replay::ReplayPlayer;
+use crate::replay::branch::advance_to;
 use crate::replay::session::{
     ReplayCommandResult, ReplaySessionCommand, broadcast_state_and_replay_info,
-    estimate_total_ticks, handle_replay_command, wait_for_restart_or_stop,
+    estimate_total_ticks, handle_replay_command,
 };
+use crate::replay::timeline::{ReplaySimulation, ReplayTimeline};
+use crate::replay::verify::{ReplaySummary, play_to_end};
 use crate::{
-    ClientId, GameStateUpdate, PlayerAction, PlayerId, SnakePosition, game_state_update,
+    ClientId, GameStateUpdate, PlayerAction, PlayerId, ReplayGame, SnakePosition, game_state_update,
     in_game_command, lobby_settings, player_action_content, log,
 };
-pub(crate) async fn run_replay(
-    mut player: ReplayPlayer,
-    command_rx: &mut mpsc::UnboundedReceiver<ReplaySessionCommand>,
-    viewers: &[ClientId],
-    host_only_control: bool,
-    broadcaster: &crate::broadcaster::Broadcaster,
-) -> bool {
-    let settings = match player.lobby_settings() {
-        Some(lobby_settings::Settings::Snake(s)) => *s,
-        _ => return false,
-    };
 
-    let wall_collision_mode = match crate::proto::snake::WallCollisionMode::try_from(settings.wall_collision_mode) {
-        Ok(crate::proto::snake::WallCollisionMode::WrapAround) => WallCollisionMode::WrapAround,
-        _ => WallCollisionMode::Death,
-    };
+#[derive(Clone)]
+struct SnakeReplay {
+    game_state: SnakeGameState,
+    rng: SessionRng,
+    tick: u64,
+    total_players: usize,
+    player_map: Arc<HashMap<i32, PlayerId>>,
+}
 
-    let dead_snake_behavior = match crate::proto::snake::DeadSnakeBehavior::try_from(settings.dead_snake_behavior) {
-        Ok(crate::proto::snake::DeadSnakeBehavior::StayOnField) => DeadSnakeBehavior::StayOnField,
-        _ => DeadSnakeBehavior::Disappear,
-    };
+impl SnakeReplay {
+    fn is_game_over(&self) -> bool {
+        self.game_state.is_game_over()
+    }
+}
 
-    let field_width = settings.field_width as usize;
-    let field_height = settings.field_height as usize;
-    let field_size = FieldSize {
-        width: field_width,
-        height: field_height,
-    };
+impl ReplaySimulation for SnakeReplay {
+    fn position(&self) -> u64 {
+        self.tick
+    }
 
-    let mut game_state = SnakeGameState::new(
-        field_size,
-        wall_collision_mode,
-        dead_snake_behavior,
-        settings.max_food_count.max(1) as usize,
-        settings.food_spawn_probability.clamp(0.001, 1.0),
-    );
+    fn is_finished(&self, player: &ReplayPlayer) -> bool {
+        self.is_game_over() || player.is_finished()
+    }
+
+    fn step(&mut self, player: &mut ReplayPlayer) {
+        let actions = player.actions_for_tick(self.tick as i64);
+        for action in &actions {
+            apply_snake_action(&mut self.game_state, action, &self.player_map);
+        }
+        self.game_state.update(&mut self.rng);
+        self.tick += 1;
+    }
+
+    fn state_hash(&self) -> u64 {
+        self.game_state.state_hash()
+    }
+}
+
+fn new_replay(player: &ReplayPlayer) -> Result<SnakeReplay, String> {
+    let settings = match player.lobby_settings() {
+        Some(lobby_settings::Settings::Snake(s)) => s,
+        _ => return Err("Replay has no Snake settings".to_string()),
+    };
+    resolve_map(settings)?;
 
     let players = player.players();
     let total_players = players.len();
-    let player_map: HashMap<i32, PlayerId> = players
+    let player_ids: Vec<PlayerId> = players.iter().map(|p| PlayerId::new(p.player_id.clone())).collect();
+    let player_map: HashMap<i32, PlayerId> = player_ids
         .iter()
         .enumerate()
-        .map(|(i, p)| (i as i32, PlayerId::new(p.player_id.clone())))
+        .map(|(i, id)| (i as i32, id.clone()))
         .collect();
 
-    for (idx, p) in players.iter().enumerate() {
-        let start_pos = calculate_snake_start_position(idx, total_players, field_width, field_height);
-        game_state.add_snake(PlayerId::new(p.player_id.clone()), start_pos, Direction::Up);
+    let game_state = initial_game_state(&SnakeSessionSettings::from(settings), &player_ids, player.seed());
+
+    Ok(SnakeReplay {
+        game_state,
+        rng: player.rng(),
+        tick: 0,
+        total_players,
+        player_map: Arc::new(player_map),
+    })
+}
+
+pub(crate) fn branch(
+    mut player: ReplayPlayer,
+    position: u64,
+    renames: &HashMap<PlayerId, PlayerId>,
+    config: &GameSessionConfig,
+) -> Result<GameSession, String> {
+    let settings = match player.lobby_settings() {
+        Some(lobby_settings::Settings::Snake(s)) => SnakeSessionSettings::from(s),
+        _ => return Err("Replay has no Snake settings".to_string()),
+    };
+
+    let mut replay = new_replay(&player)?;
+    advance_to(&mut replay, &mut player, position);
+    if replay.is_game_over() {
+        return Err(format!("The game is already over at tick {}", replay.tick));
     }
+    replay.game_state.rename_players(renames);
+
+    Ok(GameSession::Snake(SnakeSessionState::from_state(
+        config,
+        &settings,
+        replay.game_state,
+        replay.rng,
+        replay.tick,
+        None,
+    )))
+}
+
+pub(crate) fn simulate(mut player: ReplayPlayer) -> Result<ReplaySummary, String> {
+    let mut replay = new_replay(&player)?;
+    let desync_position = play_to_end(&mut replay, &mut player);
+
+    let state = &replay.game_state;
+    let outcome = match state.winner() {
+        _ if !state.is_game_over() => "Unfinished".to_string(),
+        Some(Side::Team(team)) => format!("Team {} won", team),
+        Some(Side::Player(winner)) if replay.total_players > 1 => format!("{} won", winner),
+        _ if state.snakes.values().all(|s| !s.is_alive()) => "No survivors".to_string(),
+        _ => "No winner".to_string(),
+    };
+    let scores = player
+        .players()
+        .iter()
+        .map(|p| {
+            let score = replay.game_state.snakes.get(&PlayerId::new(p.player_id.clone())).map_or(0, |s| s.score);
+            (p.player_id.clone(), score)
+        })
+        .collect();
 
-    let mut rng = SessionRng::new(player.seed());
-    let tick_interval_ms = settings.tick_interval_ms as f32;
-    let mut current_tick: u64 = 0;
+    Ok(ReplaySummary {
+        game: ReplayGame::Snake,
+        ticks: replay.tick,
+        outcome,
+        scores,
+        desync_position,
+    })
+}
+
+pub(crate) async fn run_replay(
+    mut player: ReplayPlayer,
+    command_rx: &mut mpsc::UnboundedReceiver<ReplaySessionCommand>,
+    viewers: &[ClientId],
+    host_only_control: bool,
+    broadcaster: &crate::broadcaster::Broadcaster,
+) -> bool {
+    let tick_interval_ms = match player.lobby_settings() {
+        Some(lobby_settings::Settings::Snake(s)) => s.tick_interval_ms,
+        _ => return false,
+    };
+    let Ok(mut replay) = new_replay(&player) else {
+        return false;
+    };
+    let mut timeline = ReplayTimeline::new(&replay, &player);
+    let tick_interval = tick_interval_ms as f32;
     let mut is_paused = false;
+    let mut is_finished = false;
     let mut speed = 1.0_f32;
 
     let total_ticks = estimate_total_ticks(&player);
 
-    let proto_state = build_snake_proto_state(&game_state, players, current_tick, settings.tick_interval_ms);
-    let state_update = GameStateUpdate { state: Some(game_state_update::State::Snake(proto_state)) };
-    broadcast_state_and_replay_info(broadcaster, viewers, state_update, is_paused, current_tick, total_ticks, speed, false, host_only_control).await;
+    let state_update = build_snake_state(&replay, player.players(), total_ticks, tick_interval_ms);
+    broadcast_state_and_replay_info(broadcaster, viewers, state_update, is_paused, replay.tick, total_ticks, speed, is_finished, host_only_control, timeline.desync_position()).await;
 
-    let mut tick_timer = tokio::time::interval(Duration::from_millis(tick_interval_ms as u64));
+    let mut tick_timer = tokio::time::interval(Duration::from_millis(tick_interval as u64));
 
     loop {
         tokio::select! {
             _ = tick_timer.tick() => {
-                if is_paused {
+                if is_paused || is_finished {
                     continue;
                 }
 
-                let actions = player.actions_for_tick(current_tick as i64);
-                for action in &actions {
-                    apply_snake_action(&mut game_state, action, &player_map);
-                }
-
-                game_state.update(&mut rng);
-                current_tick += 1;
-
-                let alive_count = game_state.snakes.values().filter(|s| s.is_alive()).count();
-                let game_over = if total_players == 1 { alive_count == 0 } else { alive_count <= 1 };
-                let is_finished = game_over || player.is_finished();
-
-                let proto_state = build_snake_proto_state(&game_state, player.players(), current_tick.min(total_ticks), settings.tick_interval_ms);
-                let state_update = GameStateUpdate { state: Some(game_state_update::State::Snake(proto_state)) };
-                broadcast_state_and_replay_info(broadcaster, viewers, state_update, is_paused, current_tick.min(total_ticks), total_ticks, speed, is_finished, host_only_control).await;
-
-                if is_finished {
-                    return wait_for_restart_or_stop(command_rx).await;
-                }
+                timeline.step(&mut replay, &mut player);
+                is_finished = replay.is_finished(&player);
             }
-            Some(cmd) = command_rx.recv() => {
-                match cmd {
-                    ReplaySessionCommand::ReplayCommand(replay_cmd) => {
-                        match handle_replay_command(&replay_cmd, &mut is_paused, &mut speed) {
-                            ReplayCommandResult::StateChanged => {
-                                let proto_state = build_snake_proto_state(&game_state, player.players(), current_tick, settings.tick_interval_ms);
-                                let state_update = GameStateUpdate { state: Some(game_state_update::State::Snake(proto_state)) };
-                                broadcast_state_and_replay_info(broadcaster, viewers, state_update, is_paused, current_tick, total_ticks, speed, false, host_only_control).await;
-                            }
-                            ReplayCommandResult::SpeedChanged => {
-                                let adjusted_interval = Duration::from_millis((tick_interval_ms / speed) as u64);
-                                tick_timer = tokio::time::interval(adjusted_interval);
-                            }
-                            ReplayCommandResult::StepForward => {
-                                let actions = player.actions_for_tick(current_tick as i64);
-                                for action in &actions {
-                                    apply_snake_action(&mut game_state, action, &player_map);
-                                }
-                                game_state.update(&mut rng);
-                                current_tick += 1;
-
-                                let alive_count = game_state.snakes.values().filter(|s| s.is_alive()).count();
-                                let game_over = if total_players == 1 { alive_count == 0 } else { alive_count <= 1 };
-                                let is_finished = game_over || player.is_finished();
-
-                                let proto_state = build_snake_proto_state(&game_state, player.players(), current_tick.min(total_ticks), settings.tick_interval_ms);
-                                let state_update = GameStateUpdate { state: Some(game_state_update::State::Snake(proto_state)) };
-                                broadcast_state_and_replay_info(broadcaster, viewers, state_update, is_paused, current_tick.min(total_ticks), total_ticks, speed, is_finished, host_only_control).await;
-
-                                if is_finished {
-                                    return wait_for_restart_or_stop(command_rx).await;
-                                }
-                            }
-                            ReplayCommandResult::Restart => return true,
-                            ReplayCommandResult::None => {}
+            cmd = command_rx.recv() => {
+                let Some(ReplaySessionCommand::ReplayCommand(replay_cmd)) = cmd else {
+                    return false;
+                };
+                match handle_replay_command(&replay_cmd, &mut is_paused, &mut speed) {
+                    ReplayCommandResult::StateChanged => {}
+                    ReplayCommandResult::SpeedChanged => {
+                        let adjusted_interval = Duration::from_millis((tick_interval / speed) as u64);
+                        tick_timer = tokio::time::interval(adjusted_interval);
+                        continue;
+                    }
+                    ReplayCommandResult::StepForward => {
+                        if is_finished {
+                            continue;
                         }
+                        timeline.step(&mut replay, &mut player);
+                    }
+                    ReplayCommandResult::StepBackward => {
+                        timeline.step_back(&mut replay, &mut player);
                     }
+                    ReplayCommandResult::Seek(target) => {
+                        timeline.seek(&mut replay, &mut player, target);
+                    }
+                    ReplayCommandResult::Restart => return true,
+                    ReplayCommandResult::None => continue,
                 }
+
+                is_finished = replay.is_finished(&player);
             }
         }
+
+        let state_update = build_snake_state(&replay, player.players(), total_ticks, tick_interval_ms);
+        broadcast_state_and_replay_info(broadcaster, viewers, state_update, is_paused, replay.tick.min(total_ticks), total_ticks, speed, is_finished, host_only_control, timeline.desync_position()).await;
     }
 }
+fn build_snake_state(
+    replay: &SnakeReplay,
+    players: &[crate::PlayerIdentity],
+    total_ticks: u64,
+    tick_interval_ms: u32,
+) -> GameStateUpdate {
+    let proto_state = build_snake_proto_state(&replay.game_state, players, replay.tick.min(total_ticks), tick_interval_ms);
+    GameStateUpdate { state: Some(game_state_update::State::Snake(proto_state)) }
+}
 fn build_snake_proto_state(
     state: &SnakeGameState,
     players: &[crate::PlayerIdentity],
@@ -181,12 +258,14 @@ fn build_snake_proto_state(
             segments,
             alive: snake.is_alive(),
             score: snake.score,
+            effects: super::session::proto_effects(snake),
+            team: state.teams.get(id).copied().unwrap_or(0),
         });
     }
 
     let food: Vec<SnakePosition> = state
-        .food_set
-        .iter()
+        .food
+        .keys()
         .map(|p| SnakePosition {
             x: p.x as i32,
             y: p.y as i32,
@@ -212,6 +291,10 @@ fn build_snake_proto_state(
         tick_interval_ms,
         wall_collision_mode: wall_collision_mode_proto as i32,
         dead_snake_behavior: dead_snake_behavior_proto as i32,
+        food_items: super::session::proto_food_items(state),
+        obstacles: super::session::proto_obstacles(state),
+        remaining_time_ms: super::session::proto_remaining_time_ms(state, tick_interval_ms),
+        arena_bounds: super::session::proto_arena_bounds(state),
     }
 }
 
@@ -254,24 +337,6 @@ fn apply_snake_action(
                 log!("[replay] Failed to kill snake {}: {}", player_id, e);
             }
         }
+        player_action_content::Content::TimedOut(_) | player_action_content::Content::ControlChanged(_) => {}
     }
 }
-
-fn calculate_snake_start_position(index: usize, total: usize, width: usize, height: usize) -> Point {
-    let spacing = if total <= 2 {
-        width / (total + 1)
-    } else {
-        width / total
-    };
-
-    let x = if total == 1 {
-        width / 2
-    } else {
-        (index + 1) * spacing
-    };
-
-    Point::new(x.min(width - 1), height / 2)
-}
-
-
-
@@ -8,17 +8,23 @@ use crate::{
     BotId, ClientId, GameOverNotification, GameStateUpdate, PlayerIdentity, PlayerId, ScoreEntry,
     SnakePosition, InGameCommand, in_game_command, game_over_notification, game_state_update, log,
     proto::snake::{
-        Direction as ProtoDirection, SnakeGameEndInfo, SnakeGameEndReason,
-        SnakeGameState as ProtoSnakeGameState, SnakeInGameCommand, snake_in_game_command,
-        TurnCommand,
+        ActiveEffect as ProtoActiveEffect, ArenaBounds, Direction as ProtoDirection, FoodItem, FoodType,
+        SnakeEffect, SnakeGameEndInfo, SnakeGameEndReason, SnakeTeamScore,
+        SnakeBotType, SnakeGameState as ProtoSnakeGameState, SnakeInGameCommand,
+        snake_in_game_command, TurnCommand,
     },
 };
-use crate::games::{BotType, GameBroadcaster, GameSessionConfig, SessionRng};
-use crate::replay::ReplayRecorder;
+use crate::games::{BotTakeover, BotType, GameBroadcaster, GameSessionConfig, RngStream, SessionRng, TakeoverTracker};
+use crate::replay::{ReplayRecorder, STATE_HASH_INTERVAL};
 use super::bot_controller::BotController;
+use super::entity::Snake;
 use super::game_state::SnakeGameState;
 use super::settings::SnakeSessionSettings;
-use super::types::{DeadSnakeBehavior, DeathReason, Direction, FieldSize, Point, WallCollisionMode};
+use super::spawn::plan_spawns;
+use super::types::{
+    DeadSnakeBehavior, DeathReason, Direction, EffectKind, FieldSize, FoodKind, GameMode, Point, Side,
+    WallCollisionMode,
+};
 
 #[derive(Clone)]
 pub struct SnakeSessionState {
@@ -26,9 +32,12 @@ pub struct SnakeSessionState {
     pub game_state: Arc<Mutex<SnakeGameState>>,
     pub tick: Arc<Mutex<u64>>,
     pub rng: Arc<Mutex<SessionRng>>,
+    pub bot_rng: Arc<Mutex<SessionRng>>,
     pub bots: HashMap<BotId, BotType>,
     pub tick_interval: Duration,
     pub replay_recorder: Option<Arc<Mutex<ReplayRecorder>>>,
+    pub bot_takeover: Option<BotTakeover>,
+    pub takeover: Arc<Mutex<TakeoverTracker>>,
 }
 
 impl SnakeSessionState {
@@ -40,45 +49,33 @@ impl SnakeSessionState {
     ) -> Self {
         let rng = SessionRng::new(seed);
 
-        let field_size = FieldSize {
-            width: settings.field_width,
-            height: settings.field_height,
-        };
-        let mut game_state = SnakeGameState::new(
-            field_size,
-            settings.wall_collision_mode,
-            settings.dead_snake_behavior,
-            settings.max_food_count,
-            settings.food_spawn_probability,
-        );
-
-        let total_players = config.human_players.len() + config.bots.len();
-        let mut idx = 0;
-
-        for player_id in &config.human_players {
-            let start_pos =
-                calculate_start_position(idx, total_players, settings.field_width, settings.field_height);
-            let direction = calculate_start_direction(idx, total_players);
-            game_state.add_snake(player_id.clone(), start_pos, direction);
-            idx += 1;
-        }
+        let mut players: Vec<PlayerId> = config.human_players.clone();
+        players.extend(config.bots.keys().map(|bot_id| bot_id.to_player_id()));
+        let game_state = initial_game_state(settings, &players, seed);
 
-        for bot_id in config.bots.keys() {
-            let start_pos =
-                calculate_start_position(idx, total_players, settings.field_width, settings.field_height);
-            let direction = calculate_start_direction(idx, total_players);
-            game_state.add_snake(bot_id.to_player_id(), start_pos, direction);
-            idx += 1;
-        }
+        Self::from_state(config, settings, game_state, rng, 0, replay_recorder)
+    }
 
+    /// Continues a game from `tick` instead of placing fresh snakes.
+    pub fn from_state(
+        config: &GameSessionConfig,
+        settings: &SnakeSessionSettings,
+        game_state: SnakeGameState,
+        rng: SessionRng,
+        tick: u64,
+        replay_recorder: Option<Arc<Mutex<ReplayRecorder>>>,
+    ) -> Self {
         Self {
             session_id: config.session_id.clone(),
             game_state: Arc::new(Mutex::new(game_state)),
-            tick: Arc::new(Mutex::new(0u64)),
+            tick: Arc::new(Mutex::new(tick)),
+            bot_rng: Arc::new(Mutex::new(SessionRng::for_stream(rng.seed(), RngStream::Bots))),
             rng: Arc::new(Mutex::new(rng)),
             bots: config.bots.clone(),
             tick_interval: settings.tick_interval,
             replay_recorder,
+            bot_takeover: config.bot_takeover,
+            takeover: Arc::new(Mutex::new(TakeoverTracker::default())),
         }
     }
 }
@@ -91,7 +88,6 @@ impl SnakeSession {
         session_state: SnakeSessionState,
         broadcaster: impl GameBroadcaster,
     ) -> GameOverNotification {
-        let initial_player_count = config.human_players.len() + config.bots.len();
         let mut tick_interval_timer = interval(session_state.tick_interval);
 
         loop {
@@ -103,53 +99,43 @@ impl SnakeSession {
             };
 
             let mut game_state = session_state.game_state.lock().await;
-            let mut rng = session_state.rng.lock().await;
+            let taken_over = update_takeover(&session_state, &game_state, &config.human_players, current_tick).await;
+            let mut bot_rng = session_state.bot_rng.lock().await;
 
             for (bot_id, bot_type) in &session_state.bots {
                 if let BotType::Snake(snake_bot_type) = bot_type {
-                    let player_id = bot_id.to_player_id();
-                    if let Some(direction) =
-                        BotController::calculate_move(*snake_bot_type, &player_id, &game_state, &mut rng)
-                    {
-                        if let Err(e) = game_state.set_snake_direction(&player_id, direction) {
-                            log!("[session:{}] Bot {} failed to set direction: {}", session_state.session_id, player_id, e);
-                        }
-
-                        if let Some(ref recorder) = session_state.replay_recorder {
-                            let mut recorder = recorder.lock().await;
-                            if let Some(player_index) = recorder.find_player_index(&player_id.to_string())
-                            {
-                                let command = create_turn_command(direction);
-                                recorder.record_command(current_tick as i64, player_index, command);
-                            }
-                        }
-                    }
+                    play_bot_move(&session_state, &mut game_state, &mut bot_rng, *snake_bot_type, &bot_id.to_player_id(), current_tick).await;
                 }
             }
 
+            for player_id in &taken_over {
+                play_bot_move(&session_state, &mut game_state, &mut bot_rng, SnakeBotType::Efficient, player_id, current_tick).await;
+            }
+            drop(bot_rng);
+
+            let mut rng = session_state.rng.lock().await;
             game_state.update(&mut rng);
             drop(rng);
 
             let mut tick_value = session_state.tick.lock().await;
             *tick_value += 1;
 
+            if let Some(ref recorder) = session_state.replay_recorder
+                && tick_value.is_multiple_of(STATE_HASH_INTERVAL)
+            {
+                recorder.lock().await.record_state_hash(*tick_value, game_state.state_hash());
+            }
+
             let proto_state =
                 build_proto_state(&game_state, &session_state.bots, *tick_value, session_state.tick_interval);
             drop(tick_value);
 
-            let recipients = config.get_all_recipients();
             let state_update = GameStateUpdate {
                 state: Some(game_state_update::State::Snake(proto_state)),
             };
-            broadcaster.broadcast_state(state_update, recipients).await;
-
-            let alive_count = game_state.snakes.values().filter(|s| s.is_alive()).count();
-            let game_over = if initial_player_count == 1 {
-                alive_count == 0
-            } else {
-                alive_count <= 1
-            };
+            config.broadcast_state(&broadcaster, state_update).await;
 
+            let game_over = game_state.is_game_over();
             drop(game_state);
 
             if game_over {
@@ -178,6 +164,12 @@ impl SnakeSession {
             _ => return,
         };
 
+        Self::return_control(state, client_id).await;
+
+        // Holding the game state keeps the tick from advancing between recording
+        // the turn and queueing it, so replays queue it on the same tick.
+        let mut state_guard = state.game_state.lock().await;
+
         if let Some(ref recorder) = state.replay_recorder {
             let current_tick = *state.tick.lock().await;
             let mut recorder = recorder.lock().await;
@@ -187,13 +179,44 @@ impl SnakeSession {
             }
         }
 
-        let mut state_guard = state.game_state.lock().await;
         let player_id = PlayerId::new(client_id.to_string());
         if let Err(e) = state_guard.set_snake_direction(&player_id, direction) {
             log!("[session:{}] Player {} failed to set direction: {}", state.session_id, player_id, e);
         }
     }
 
+    pub async fn current_state(state: &SnakeSessionState) -> GameStateUpdate {
+        let game_state = state.game_state.lock().await;
+        let tick = *state.tick.lock().await;
+        let proto_state = build_proto_state(&game_state, &state.bots, tick, state.tick_interval);
+        GameStateUpdate {
+            state: Some(game_state_update::State::Snake(proto_state)),
+        }
+    }
+
+    /// Returns false when the lobby did not enable bot takeover.
+    pub async fn take_over(state: &SnakeSessionState, client_id: &ClientId) -> bool {
+        if state.bot_takeover.is_none() {
+            return false;
+        }
+        let player_id = PlayerId::new(client_id.to_string());
+        if state.takeover.lock().await.take_over(&player_id) {
+            log!("[session:{}] Bot took over for {}", state.session_id, player_id);
+            record_control_change(state, &player_id, true).await;
+        }
+        true
+    }
+
+    pub async fn return_control(state: &SnakeSessionState, client_id: &ClientId) -> bool {
+        let player_id = PlayerId::new(client_id.to_string());
+        if !state.takeover.lock().await.record_input(&player_id) {
+            return false;
+        }
+        log!("[session:{}] Control returned to {}", state.session_id, player_id);
+        record_control_change(state, &player_id, false).await;
+        true
+    }
+
     pub async fn handle_kill_snake(
         state: &SnakeSessionState,
         client_id: &ClientId,
@@ -207,26 +230,115 @@ impl SnakeSession {
     }
 }
 
-fn calculate_start_position(index: usize, total: usize, width: usize, height: usize) -> Point {
-    let spacing = if total <= 2 {
-        width / (total + 1)
-    } else {
-        width / total
+/// Counts idle ticks and returns the humans a bot currently plays for.
+///
+/// Going straight needs no input in Snake, so only ticks on which the player
+/// could have turned count as idle; steering straight through a corridor
+/// doesn't get a player taken over.
+async fn update_takeover(
+    session_state: &SnakeSessionState,
+    game_state: &SnakeGameState,
+    human_players: &[PlayerId],
+    current_tick: u64,
+) -> Vec<PlayerId> {
+    let Some(bot_takeover) = session_state.bot_takeover else {
+        return Vec::new();
     };
 
-    let x = if total == 1 {
-        width / 2
-    } else {
-        (index + 1) * spacing
+    let mut takeover = session_state.takeover.lock().await;
+    for player_id in human_players {
+        if !BotController::can_turn(player_id, game_state) {
+            continue;
+        }
+        if takeover.count_idle(player_id, bot_takeover.idle_limit) && takeover.take_over(player_id) {
+            log!("[session:{}] Bot took over for idle player {}", session_state.session_id, player_id);
+            record_control_change_at(session_state, current_tick, player_id, true).await;
+        }
+    }
+
+    human_players
+        .iter()
+        .filter(|p| takeover.is_controlled(p))
+        .cloned()
+        .collect()
+}
+
+async fn play_bot_move(
+    session_state: &SnakeSessionState,
+    game_state: &mut SnakeGameState,
+    rng: &mut SessionRng,
+    bot_type: SnakeBotType,
+    player_id: &PlayerId,
+    current_tick: u64,
+) {
+    let Some(direction) = BotController::calculate_move(bot_type, player_id, game_state, rng) else {
+        return;
     };
 
-    let y = height / 2;
+    if let Err(e) = game_state.set_snake_direction(player_id, direction) {
+        log!("[session:{}] Bot {} failed to set direction: {}", session_state.session_id, player_id, e);
+    }
+
+    if let Some(ref recorder) = session_state.replay_recorder {
+        let mut recorder = recorder.lock().await;
+        if let Some(player_index) = recorder.find_player_index(&player_id.to_string()) {
+            let command = create_turn_command(direction);
+            recorder.record_command(current_tick as i64, player_index, command);
+        }
+    }
+}
 
-    Point::new(x.min(width - 1), y)
+async fn record_control_change(session_state: &SnakeSessionState, player_id: &PlayerId, bot_controlled: bool) {
+    let current_tick = *session_state.tick.lock().await;
+    record_control_change_at(session_state, current_tick, player_id, bot_controlled).await;
 }
 
-fn calculate_start_direction(_index: usize, _total: usize) -> Direction {
-    Direction::Up
+async fn record_control_change_at(
+    session_state: &SnakeSessionState,
+    current_tick: u64,
+    player_id: &PlayerId,
+    bot_controlled: bool,
+) {
+    if let Some(ref recorder) = session_state.replay_recorder {
+        let mut recorder = recorder.lock().await;
+        if let Some(player_index) = recorder.find_player_index(&player_id.to_string()) {
+            recorder.record_control_change(current_tick as i64, 0, player_index, bot_controlled);
+        }
+    }
+}
+
+/// A fresh field with one snake per player, placed in the given order from
+/// the session `seed`.
+pub fn initial_game_state(settings: &SnakeSessionSettings, players: &[PlayerId], seed: u64) -> SnakeGameState {
+    let field_size = FieldSize {
+        width: settings.field_width,
+        height: settings.field_height,
+    };
+    let mut game_state = SnakeGameState::new(
+        field_size,
+        settings.wall_collision_mode,
+        settings.dead_snake_behavior,
+        settings.max_food_count,
+        settings.food_spawn_probability,
+    );
+    game_state.food_weights = settings.food_weights;
+    game_state.movement_resolution = settings.movement_resolution;
+    game_state.input_buffer_size = settings.input_buffer_size;
+    game_state.game_mode = settings.game_mode;
+    game_state.teams = settings.teams.clone();
+    game_state.teammates_pass_through = settings.teammates_pass_through;
+
+    if let Some(map) = &settings.map {
+        game_state.obstacles = map.obstacles.clone();
+    }
+
+    let mut spawn_rng = SessionRng::for_stream(seed, RngStream::Spawns);
+    let starts = plan_spawns(settings, players.len(), &mut spawn_rng);
+    for (player_id, (start_pos, direction)) in players.iter().zip(starts) {
+        game_state.add_snake(player_id.clone(), start_pos, direction);
+    }
+
+    game_state
 }
 
 fn build_proto_state(
@@ -257,12 +369,14 @@ fn build_proto_state(
             segments,
             alive: snake.is_alive(),
             score: snake.score,
+            effects: proto_effects(snake),
+            team: state.teams.get(id).copied().unwrap_or(0),
         });
     }
 
     let food: Vec<SnakePosition> = state
-        .food_set
-        .iter()
+        .food
+        .keys()
         .map(|p| SnakePosition {
             x: p.x as i32,
             y: p.y as i32,
@@ -288,7 +402,85 @@ fn build_proto_state(
         tick_interval_ms: tick_interval.as_millis() as u32,
         wall_collision_mode: wall_collision_mode_proto as i32,
         dead_snake_behavior: dead_snake_behavior_proto as i32,
+        food_items: proto_food_items(state),
+        obstacles: proto_obstacles(state),
+        remaining_time_ms: proto_remaining_time_ms(state, tick_interval.as_millis() as u32),
+        arena_bounds: proto_arena_bounds(state),
+    }
+}
+
+pub(super) fn proto_remaining_time_ms(state: &SnakeGameState, tick_interval_ms: u32) -> Option<u32> {
+    state
+        .remaining_ticks()
+        .map(|ticks| (ticks * tick_interval_ms as u64).min(u32::MAX as u64) as u32)
+}
+
+pub(super) fn proto_arena_bounds(state: &SnakeGameState) -> Option<ArenaBounds> {
+    if !matches!(state.game_mode, GameMode::BattleRoyale { .. }) {
+        return None;
     }
+    let position = |p: Point| SnakePosition {
+        x: p.x as i32,
+        y: p.y as i32,
+    };
+    Some(ArenaBounds {
+        min: Some(position(state.arena.min)),
+        max: Some(position(state.arena.max)),
+    })
+}
+
+pub(super) fn proto_food_items(state: &SnakeGameState) -> Vec<FoodItem> {
+    state
+        .food
+        .iter()
+        .map(|(p, kind)| {
+            let food_type = match kind {
+                FoodKind::Normal => FoodType::Normal,
+                FoodKind::Golden => FoodType::Golden,
+                FoodKind::SpeedBoost => FoodType::SpeedBoost,
+                FoodKind::Ghost => FoodType::Ghost,
+                FoodKind::Shrink => FoodType::Shrink,
+                FoodKind::ReverseControls => FoodType::ReverseControls,
+            };
+            FoodItem {
+                position: Some(SnakePosition {
+                    x: p.x as i32,
+                    y: p.y as i32,
+                }),
+                r#type: food_type as i32,
+            }
+        })
+        .collect()
+}
+
+pub(super) fn proto_obstacles(state: &SnakeGameState) -> Vec<SnakePosition> {
+    let mut obstacles: Vec<&Point> = state.obstacles.iter().collect();
+    obstacles.sort_by_key(|p| (p.y, p.x));
+    obstacles
+        .into_iter()
+        .map(|p| SnakePosition {
+            x: p.x as i32,
+            y: p.y as i32,
+        })
+        .collect()
+}
+
+pub(super) fn proto_effects(snake: &Snake) -> Vec<ProtoActiveEffect> {
+    snake
+        .effects
+        .iter()
+        .map(|effect| {
+            let kind = match effect.kind {
+                EffectKind::SpeedBoost => SnakeEffect::SpeedBoost,
+                EffectKind::Ghost => SnakeEffect::Ghost,
+                EffectKind::ReverseControls => SnakeEffect::ReverseControls,
+            };
+            ProtoActiveEffect {
+                effect: kind as i32,
+                remaining_ticks: effect.remaining_ticks,
+            }
+        })
+        .collect()
 }
 
 async fn build_game_over_notification(session_state: &SnakeSessionState) -> GameOverNotification {
@@ -313,30 +505,51 @@ async fn build_game_over_notification(session_state: &SnakeSessionState) -> Game
         })
         .collect();
 
-    let winner = game_state
-        .snakes
-        .iter()
-        .find(|(_, snake)| snake.is_alive())
-        .map(|(id, _)| {
+    let (winner, winning_team) = match game_state.winner() {
+        Some(Side::Player(id)) => {
             let is_bot = session_state
                 .bots
                 .iter()
-                .any(|(bot_id, _)| bot_id.to_player_id() == *id);
-            PlayerIdentity {
+                .any(|(bot_id, _)| bot_id.to_player_id() == id);
+            let winner = PlayerIdentity {
                 player_id: id.to_string(),
                 is_bot,
-            }
-        });
+            };
+            (Some(winner), 0)
+        }
+        Some(Side::Team(team)) => (None, team),
+        None => (None, 0),
+    };
 
-    let game_end_reason = game_state
-        .game_end_reason
-        .map(|r| match r {
-            DeathReason::WallCollision => SnakeGameEndReason::WallCollision,
-            DeathReason::SelfCollision => SnakeGameEndReason::SelfCollision,
-            DeathReason::OtherSnakeCollision => SnakeGameEndReason::SnakeCollision,
-            DeathReason::PlayerDisconnected => SnakeGameEndReason::PlayerDisconnected,
+    let team_scores: Vec<SnakeTeamScore> = game_state
+        .sides()
+        .iter()
+        .filter_map(|side| match side {
+            Side::Team(team) => Some(SnakeTeamScore {
+                team: *team,
+                score: game_state.side_score(side),
+            }),
+            Side::Player(_) => None,
         })
-        .unwrap_or(SnakeGameEndReason::GameCompleted);
+        .collect();
+
+    let game_end_reason = if game_state.time_is_up() {
+        SnakeGameEndReason::TimeLimit
+    } else if game_state.score_target_reached() {
+        SnakeGameEndReason::ScoreTarget
+    } else if matches!(game_state.game_mode, GameMode::BattleRoyale { .. }) {
+        SnakeGameEndReason::LastSnakeStanding
+    } else {
+        game_state
+            .game_end_reason
+            .map(|r| match r {
+                DeathReason::WallCollision => SnakeGameEndReason::WallCollision,
+                DeathReason::SelfCollision => SnakeGameEndReason::SelfCollision,
+                DeathReason::OtherSnakeCollision => SnakeGameEndReason::SnakeCollision,
+                DeathReason::PlayerDisconnected => SnakeGameEndReason::PlayerDisconnected,
+            })
+            .unwrap_or(SnakeGameEndReason::GameCompleted)
+    };
 
     GameOverNotification {
         scores,
@@ -344,12 +557,14 @@ async fn build_game_over_notification(session_state: &SnakeSessionState) -> Game
         game_info: Some(game_over_notification::GameInfo::SnakeInfo(
             SnakeGameEndInfo {
                 reason: game_end_reason as i32,
+                winning_team,
+                team_scores,
             },
         )),
     }
 }
 
-fn create_turn_command(direction: Direction) -> InGameCommand {
+pub fn create_turn_command(direction: Direction) -> InGameCommand {
     let proto_direction = match direction {
         Direction::Up => ProtoDirection::Up,
         Direction::Down => ProtoDirection::Down,
@@ -1,17 +1,24 @@
+use std::collections::HashMap;
 use std::sync::Arc;
 use std::time::Duration;
 use tokio::sync::Mutex;
 
 use crate::{
-    ReplayGame, SnakeLobbySettings,
+    PlayerId, ReplayGame, SnakeLobbySettings,
     lobby_details, lobby_settings,
 };
 use crate::games::{
     GameSession, GameSessionConfig, LobbySettings, ReplayMode,
 };
+use crate::proto::snake::SnakeTeamMember;
 use crate::replay::ReplayRecorder;
+use super::map::{SnakeMap, resolve_map, with_embedded_map};
 use super::session::SnakeSessionState;
-use super::types::{DeadSnakeBehavior, WallCollisionMode};
+use super::types::{DeadSnakeBehavior, FoodWeights, GameMode, MovementResolution, SpawnPlacement, WallCollisionMode};
+use super::validate::{validate_food_weights, validate_game_mode, validate_input_buffer_size, validate_map};
+
+/// What new games use when the lobby leaves `input_buffer_size` unset.
+pub const DEFAULT_INPUT_BUFFER_SIZE: u32 = 3;
 
 pub struct SnakeSessionSettings {
     pub field_width: usize,
@@ -20,9 +27,29 @@ pub struct SnakeSessionSettings {
     pub dead_snake_behavior: DeadSnakeBehavior,
     pub max_food_count: usize,
     pub food_spawn_probability: f32,
+    pub food_weights: FoodWeights,
+    pub map: Option<SnakeMap>,
+    pub movement_resolution: MovementResolution,
+    pub input_buffer_size: usize,
+    pub game_mode: GameMode,
+    pub teams: HashMap<PlayerId, u32>,
+    pub teammates_pass_through: bool,
+    pub spawn_placement: SpawnPlacement,
     pub tick_interval: Duration,
 }
 
+impl From<&SnakeLobbySettings> for FoodWeights {
+    fn from(settings: &SnakeLobbySettings) -> Self {
+        Self {
+            golden: settings.golden_food_weight,
+            speed_boost: settings.speed_boost_weight,
+            ghost: settings.ghost_weight,
+            shrink: settings.shrink_weight,
+            reverse_controls: settings.reverse_controls_weight,
+        }
+    }
+}
+
 impl From<&SnakeLobbySettings> for SnakeSessionSettings {
     fn from(settings: &SnakeLobbySettings) -> Self {
         let wall_collision_mode =
@@ -45,6 +72,34 @@ impl From<&SnakeLobbySettings> for SnakeSessionSettings {
                 )
                 | Err(_) => DeadSnakeBehavior::Disappear,
             };
+        let movement_resolution =
+            match crate::proto::snake::MovementResolution::try_from(settings.movement_resolution) {
+                Ok(crate::proto::snake::MovementResolution::Simultaneous) => MovementResolution::Simultaneous,
+                _ => MovementResolution::Sequential,
+            };
+        let spawn_placement = match crate::proto::snake::SpawnPlacement::try_from(settings.spawn_placement) {
+            Ok(crate::proto::snake::SpawnPlacement::Spread) => SpawnPlacement::Spread,
+            _ => SpawnPlacement::Row,
+        };
+        let ticks = |seconds: u32| (seconds as u64 * 1000).div_ceil(settings.tick_interval_ms.max(1) as u64);
+        let game_mode = match crate::proto::snake::SnakeGameMode::try_from(settings.game_mode) {
+            Ok(crate::proto::snake::SnakeGameMode::TimeLimit) => {
+                GameMode::TimeLimit { ticks: ticks(settings.time_limit_seconds) }
+            }
+            Ok(crate::proto::snake::SnakeGameMode::ScoreTarget) => {
+                GameMode::ScoreTarget { score: settings.score_target }
+            }
+            Ok(crate::proto::snake::SnakeGameMode::BattleRoyale) => {
+                GameMode::BattleRoyale { shrink_interval_ticks: ticks(settings.shrink_interval_seconds) }
+            }
+            _ => GameMode::LastStanding,
+        };
+        // Validation already rejected settings whose map doesn't resolve.
+        let map = resolve_map(settings).ok().flatten();
+        let wall_collision_mode = map
+            .as_ref()
+            .and_then(|map| map.wall_collision_mode)
+            .unwrap_or(wall_collision_mode);
 
         Self {
             field_width: settings.field_width as usize,
@@ -53,11 +108,40 @@ impl From<&SnakeLobbySettings> for SnakeSessionSettings {
             dead_snake_behavior,
             max_food_count: settings.max_food_count.max(1) as usize,
             food_spawn_probability: settings.food_spawn_probability.clamp(0.001, 1.0),
+            food_weights: FoodWeights::from(settings),
+            map,
+            movement_resolution,
+            input_buffer_size: settings.input_buffer_size.unwrap_or(0) as usize,
+            game_mode,
+            teams: settings
+                .team_members
+                .iter()
+                .filter(|member| member.team != 0)
+                .map(|member| (PlayerId::new(member.player_id.clone()), member.team))
+                .collect(),
+            teammates_pass_through: settings.teammates_pass_through,
+            spawn_placement,
             tick_interval: Duration::from_millis(settings.tick_interval_ms as u64),
         }
     }
 }
 
+/// The lobby's team assignments for the players of this game, in player id order.
+fn team_members(config: &GameSessionConfig) -> Vec<SnakeTeamMember> {
+    let mut members: Vec<SnakeTeamMember> = config
+        .human_players
+        .iter()
+        .cloned()
+        .chain(config.bots.keys().map(|bot_id| bot_id.to_player_id()))
+        .filter_map(|player_id| {
+            let team = *config.teams.get(&player_id)?;
+            Some(SnakeTeamMember { player_id: player_id.to_string(), team })
+        })
+        .collect();
+    members.sort_by(|a, b| a.player_id.cmp(&b.player_id));
+    members
+}
+
 impl LobbySettings for SnakeLobbySettings {
     fn validate(&self, max_players: u32) -> Result<(), String> {
         if self.field_width < 10 || self.field_width > 100 {
@@ -78,6 +162,10 @@ impl LobbySettings for SnakeLobbySettings {
         if !(0.0..=1.0).contains(&self.food_spawn_probability) {
             return Err("Food spawn probability must be between 0.0 and 1.0".to_string());
         }
+        validate_food_weights(self)?;
+        validate_map(self, max_players)?;
+        validate_input_buffer_size(self)?;
+        validate_game_mode(self)?;
         Ok(())
     }
 
@@ -89,11 +177,11 @@ impl LobbySettings for SnakeLobbySettings {
     }
 
     fn to_proto_details(&self) -> lobby_details::Settings {
-        lobby_details::Settings::Snake(*self)
+        lobby_details::Settings::Snake(self.clone())
     }
 
     fn to_proto_info(&self) -> lobby_settings::Settings {
-        lobby_settings::Settings::Snake(*self)
+        lobby_settings::Settings::Snake(self.clone())
     }
 
     fn game_type(&self) -> ReplayGame {
@@ -106,7 +194,16 @@ impl LobbySettings for SnakeLobbySettings {
         seed: u64,
         replay_mode: ReplayMode,
     ) -> Result<GameSession, String> {
-        let settings = SnakeSessionSettings::from(self);
+        let mut recorded_settings = with_embedded_map(self)?;
+        if recorded_settings.movement_resolution == crate::proto::snake::MovementResolution::Unspecified as i32 {
+            recorded_settings.movement_resolution = crate::proto::snake::MovementResolution::Simultaneous as i32;
+        }
+        recorded_settings.input_buffer_size.get_or_insert(DEFAULT_INPUT_BUFFER_SIZE);
+        if recorded_settings.spawn_placement == crate::proto::snake::SpawnPlacement::Unspecified as i32 {
+            recorded_settings.spawn_placement = crate::proto::snake::SpawnPlacement::Spread as i32;
+        }
+        recorded_settings.team_members = team_members(config);
+        let settings = SnakeSessionSettings::from(&recorded_settings);
 
         let replay_recorder = match replay_mode {
             ReplayMode::Save => {
@@ -127,7 +224,7 @@ impl LobbySettings for SnakeLobbySettings {
                     crate::version::VERSION.to_string(),
                     ReplayGame::Snake,
                     seed,
-                    Some(lobby_settings::Settings::Snake(*self)),
+                    Some(lobby_settings::Settings::Snake(recorded_settings)),
                     players,
                 ))))
             }
@@ -138,3 +235,46 @@ impl LobbySettings for SnakeLobbySettings {
         Ok(GameSession::Snake(session_state))
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashSet;
+
+    async fn started_input_buffer_size(input_buffer_size: Option<u32>) -> (usize, Option<u32>) {
+        let settings = SnakeLobbySettings {
+            field_width: 10,
+            field_height: 10,
+            max_food_count: 1,
+            tick_interval_ms: 100,
+            input_buffer_size,
+            ..Default::default()
+        };
+        let config = GameSessionConfig::new(
+            "lobby_1".to_string(),
+            vec![PlayerId::new("alice".to_string())],
+            HashSet::new(),
+            HashMap::new(),
+            0,
+            None,
+        );
+        let Ok(GameSession::Snake(state)) = settings.create_session(&config, 1, ReplayMode::Save) else {
+            panic!("Expected a Snake session");
+        };
+
+        let size = state.game_state.lock().await.input_buffer_size;
+        let replay = state.replay_recorder.unwrap().lock().await.finalize(None);
+        let recorded = match replay.metadata.unwrap().lobby_settings.unwrap().settings {
+            Some(lobby_settings::Settings::Snake(s)) => s.input_buffer_size,
+            _ => panic!("Expected Snake settings"),
+        };
+        (size, recorded)
+    }
+
+    #[tokio::test]
+    async fn test_input_buffer_size_zero_keeps_single_slot_and_unset_uses_default() {
+        assert_eq!(started_input_buffer_size(Some(0)).await, (0, Some(0)));
+        assert_eq!(started_input_buffer_size(None).await, (3, Some(DEFAULT_INPUT_BUFFER_SIZE)));
+    }
+}
+
@@ -0,0 +1,255 @@
+use std::collections::HashSet;
+
+use crate::games::SessionRng;
+use super::settings::SnakeSessionSettings;
+use super::types::{Direction, FieldSize, Point, SpawnPlacement, WallCollisionMode};
+
+const DIRECTIONS: [Direction; 4] = [Direction::Up, Direction::Down, Direction::Left, Direction::Right];
+/// Spacing rules tried in order until every snake fits: the cells in front
+/// of a placed head that later snakes keep off, and the free cells each snake
+/// needs in front of its own head.
+const SPACINGS: [Spacing; 3] = [
+    Spacing { reserved_ahead: 2, min_clear_ahead: 3 },
+    Spacing { reserved_ahead: 1, min_clear_ahead: 3 },
+    Spacing { reserved_ahead: 1, min_clear_ahead: 1 },
+];
+
+#[derive(Clone, Copy)]
+struct Spacing {
+    reserved_ahead: usize,
+    min_clear_ahead: usize,
+}
+
+/// Where each of `total` snakes starts, in player order. A map's spawn points
+/// win when it has enough of them; otherwise the placement setting decides.
+/// Live sessions and replay playback both place snakes through here.
+pub fn plan_spawns(settings: &SnakeSessionSettings, total: usize, rng: &mut SessionRng) -> Vec<(Point, Direction)> {
+    if let Some(map) = &settings.map
+        && !map.spawn_points.is_empty()
+        && map.spawn_points.len() >= total
+    {
+        return map.spawn_points.iter().copied().take(total).collect();
+    }
+
+    let field_size = FieldSize {
+        width: settings.field_width,
+        height: settings.field_height,
+    };
+    match settings.spawn_placement {
+        SpawnPlacement::Row => row_spawns(total, &field_size),
+        SpawnPlacement::Spread => {
+            let obstacles = settings.map.as_ref().map(|map| map.obstacles.clone()).unwrap_or_default();
+            spread_spawns(total, &field_size, &obstacles, settings.wall_collision_mode, rng)
+                .unwrap_or_else(|| row_spawns(total, &field_size))
+        }
+    }
+}
+
+/// Spread along the middle row heading up, as every game recorded before
+/// spread placement existed was laid out.
+pub fn row_spawns(total: usize, field_size: &FieldSize) -> Vec<(Point, Direction)> {
+    let FieldSize { width, height } = *field_size;
+    let spacing = if total <= 2 {
+        width / (total + 1)
+    } else {
+        width / total
+    };
+
+    (0..total)
+        .map(|index| {
+            let x = if total == 1 {
+                width / 2
+            } else {
+                (index + 1) * spacing
+            };
+            (Point::new(x.min(width - 1), height / 2), Direction::Up)
+        })
+        .collect()
+}
+
+fn spread_spawns(
+    total: usize,
+    field_size: &FieldSize,
+    obstacles: &HashSet<Point>,
+    wall_collision_mode: WallCollisionMode,
+    rng: &mut SessionRng,
+) -> Option<Vec<(Point, Direction)>> {
+    SPACINGS
+        .iter()
+        .find_map(|spacing| spread_with_spacing(total, field_size, obstacles.clone(), wall_collision_mode, *spacing, rng))
+}
+
+/// Splits the field into a roughly square grid with a region per snake and
+/// puts each snake on a random safe cell of a randomly picked region, facing
+/// whichever way has the most room. Regions left without a safe cell are
+/// skipped for spare ones; None when the spares run out too.
+fn spread_with_spacing(
+    total: usize,
+    field_size: &FieldSize,
+    obstacles: HashSet<Point>,
+    wall_collision_mode: WallCollisionMode,
+    spacing: Spacing,
+    rng: &mut SessionRng,
+) -> Option<Vec<(Point, Direction)>> {
+    let FieldSize { width, height } = *field_size;
+    let cols = ((total * width) as f64 / height as f64).sqrt().ceil().clamp(1.0, total.max(1) as f64) as usize;
+    let rows = total.div_ceil(cols);
+
+    let mut regions: Vec<usize> = (0..rows * cols).collect();
+    shuffle(&mut regions, rng);
+
+    let mut occupied = obstacles;
+    let mut spawns = Vec::with_capacity(total);
+    for region in regions {
+        if spawns.len() == total {
+            break;
+        }
+        let (col, row) = (region % cols, region / cols);
+        let xs = col * width / cols..(col + 1) * width / cols;
+        let ys = row * height / rows..(row + 1) * height / rows;
+
+        let mut cells: Vec<Point> = ys.flat_map(|y| xs.clone().map(move |x| Point::new(x, y))).collect();
+        shuffle(&mut cells, rng);
+        let Some((head, direction)) = cells.into_iter().find_map(|head| {
+            let direction = best_heading(head, field_size, &occupied, wall_collision_mode, spacing, rng)?;
+            Some((head, direction))
+        }) else {
+            continue;
+        };
+
+        occupied.extend(body(head, direction, field_size, wall_collision_mode).into_iter().flatten());
+        let mut ahead = head;
+        for _ in 0..spacing.reserved_ahead {
+            let Some(next) = step(ahead, direction, field_size, wall_collision_mode) else {
+                break;
+            };
+            occupied.insert(next);
+            ahead = next;
+        }
+        spawns.push((head, direction));
+    }
+    (spawns.len() == total).then_some(spawns)
+}
+
+fn shuffle<T>(items: &mut [T], rng: &mut SessionRng) {
+    for i in (1..items.len()).rev() {
+        items.swap(i, rng.random_range(0..=i));
+    }
+}
+
+/// The heading with the longest free run ahead among those whose body fits
+/// behind `head`; ties are broken at random.
+fn best_heading(
+    head: Point,
+    field_size: &FieldSize,
+    occupied: &HashSet<Point>,
+    wall_collision_mode: WallCollisionMode,
+    spacing: Spacing,
+    rng: &mut SessionRng,
+) -> Option<Direction> {
+    let runs: Vec<(Direction, usize)> = DIRECTIONS
+        .into_iter()
+        .filter(|direction| {
+            body(head, *direction, field_size, wall_collision_mode)
+                .iter()
+                .all(|segment| segment.is_some_and(|p| !occupied.contains(&p)))
+        })
+        .map(|direction| (direction, clear_ahead(head, direction, field_size, occupied, wall_collision_mode)))
+        .filter(|(_, run)| *run >= spacing.min_clear_ahead)
+        .collect();
+
+    let longest = runs.iter().map(|(_, run)| *run).max()?;
+    let best: Vec<Direction> = runs.into_iter().filter(|(_, run)| *run == longest).map(|(d, _)| d).collect();
+    Some(best[rng.random_range(0..best.len())])
+}
+
+/// The cells a fresh snake covers, as `Snake::new` lays them out; a segment
+/// is None where the body would cross a deadly wall.
+fn body(head: Point, direction: Direction, field_size: &FieldSize, wall_collision_mode: WallCollisionMode) -> [Option<Point>; 3] {
+    let second = step(head, direction.opposite(), field_size, wall_collision_mode);
+    let third = second.and_then(|p| step(p, direction.opposite(), field_size, wall_collision_mode));
+    [Some(head), second, third]
+}
+
+fn clear_ahead(
+    head: Point,
+    direction: Direction,
+    field_size: &FieldSize,
+    occupied: &HashSet<Point>,
+    wall_collision_mode: WallCollisionMode,
+) -> usize {
+    let limit = field_size.width.max(field_size.height);
+    let mut pos = head;
+    let mut run = 0;
+    while run < limit {
+        match step(pos, direction, field_size, wall_collision_mode) {
+            Some(next) if !occupied.contains(&next) => {
+                pos = next;
+                run += 1;
+            }
+            _ => break,
+        }
+    }
+    run
+}
+
+fn step(from: Point, direction: Direction, field_size: &FieldSize, wall_collision_mode: WallCollisionMode) -> Option<Point> {
+    let FieldSize { width, height } = *field_size;
+    let wraps = wall_collision_mode == WallCollisionMode::WrapAround;
+    match direction {
+        Direction::Up if from.y > 0 => Some(Point::new(from.x, from.y - 1)),
+        Direction::Up if wraps => Some(Point::new(from.x, height - 1)),
+        Direction::Down if from.y + 1 < height => Some(Point::new(from.x, from.y + 1)),
+        Direction::Down if wraps => Some(Point::new(from.x, 0)),
+        Direction::Left if from.x > 0 => Some(Point::new(from.x - 1, from.y)),
+        Direction::Left if wraps => Some(Point::new(width - 1, from.y)),
+        Direction::Right if from.x + 1 < width => Some(Point::new(from.x + 1, from.y)),
+        Direction::Right if wraps => Some(Point::new(0, from.y)),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::games::snake::Snake;
+
+    fn spread(total: usize, seed: u64) -> Vec<(Point, Direction)> {
+        let field_size = FieldSize { width: 10, height: 10 };
+        spread_spawns(total, &field_size, &HashSet::new(), WallCollisionMode::Death, &mut SessionRng::new(seed))
+            .expect("an open field fits every snake")
+    }
+
+    #[test]
+    fn test_spread_is_seeded() {
+        assert_eq!(spread(4, 1), spread(4, 1));
+        assert_ne!(spread(4, 1), spread(4, 2));
+    }
+
+    #[test]
+    fn test_spread_keeps_ten_snakes_apart_and_facing_open_space() {
+        let field_size = FieldSize { width: 10, height: 10 };
+        for seed in 0..100 {
+            let spawns = spread(10, seed);
+            let mut cells = HashSet::new();
+            for (head, direction) in &spawns {
+                let snake = Snake::new(*head, *direction, &field_size);
+                assert!(snake.body.iter().all(|segment| cells.insert(*segment)), "seed {}", seed);
+                assert!(head.x.abs_diff(snake.tail().x) + head.y.abs_diff(snake.tail().y) == 2, "seed {}", seed);
+            }
+            for (head, direction) in &spawns {
+                let next = step(*head, *direction, &field_size, WallCollisionMode::Death);
+                assert!(next.is_some_and(|p| !cells.contains(&p)), "seed {}", seed);
+            }
+        }
+    }
+
+    #[test]
+    fn test_row_matches_legacy_layout() {
+        let field_size = FieldSize { width: 20, height: 20 };
+        assert_eq!(
+            row_spawns(2, &field_size),
+            vec![(Point::new(6, 10), Direction::Up), (Point::new(12, 10), Direction::Up)]
+        );
+    }
+}
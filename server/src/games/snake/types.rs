@@ -1,3 +1,5 @@
+use crate::PlayerId;
+
 #[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
 pub struct Point {
     pub x: usize,
@@ -19,6 +21,15 @@ pub enum Direction {
 }
 
 impl Direction {
+    pub fn opposite(&self) -> Direction {
+        match self {
+            Direction::Left => Direction::Right,
+            Direction::Right => Direction::Left,
+            Direction::Up => Direction::Down,
+            Direction::Down => Direction::Up,
+        }
+    }
+
     pub fn is_opposite(&self, other: &Direction) -> bool {
         matches!(
             (self, other),
@@ -42,6 +53,54 @@ pub enum DeadSnakeBehavior {
     StayOnField,
 }
 
+/// How snakes moving in the same tick settle contested cells.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum MovementResolution {
+    /// One snake at a time in `player_order`, so earlier players claim cells first.
+    Sequential,
+    /// Everyone moves at once; collisions are judged against where all snakes end up.
+    Simultaneous,
+}
+
+/// How snakes are laid out when a map has no spawn points of its own.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum SpawnPlacement {
+    Row,
+    Spread,
+}
+
+/// How a match is won.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum GameMode {
+    LastStanding,
+    /// Highest score once `ticks` ticks have been played.
+    TimeLimit { ticks: u64 },
+    /// First to `score` points.
+    ScoreTarget { score: u32 },
+    /// Last standing while the arena loses its outer ring every `shrink_interval_ticks`.
+    BattleRoyale { shrink_interval_ticks: u64 },
+}
+
+/// Inclusive corners of the playable area.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct ArenaBounds {
+    pub min: Point,
+    pub max: Point,
+}
+
+impl ArenaBounds {
+    pub fn contains(&self, point: Point) -> bool {
+        (self.min.x..=self.max.x).contains(&point.x) && (self.min.y..=self.max.y).contains(&point.y)
+    }
+}
+
+/// Who a result is credited to: a whole team, or a player who isn't on one.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub enum Side {
+    Team(u32),
+    Player(PlayerId),
+}
+
 #[derive(Clone, Copy, Debug, PartialEq, Eq)]
 pub enum DeathReason {
     WallCollision,
@@ -50,6 +109,52 @@ pub enum DeathReason {
     PlayerDisconnected,
 }
 
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum FoodKind {
+    Normal,
+    /// Worth `GOLDEN_FOOD_SCORE` points.
+    Golden,
+    SpeedBoost,
+    Ghost,
+    /// Cuts `SHRINK_SEGMENTS` off the tail.
+    Shrink,
+    /// A trap: turn commands are mirrored while it lasts.
+    ReverseControls,
+}
+
+impl FoodKind {
+    pub fn is_trap(&self) -> bool {
+        matches!(self, FoodKind::ReverseControls)
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum EffectKind {
+    /// Two moves per tick.
+    SpeedBoost,
+    /// Moves through other snakes.
+    Ghost,
+    ReverseControls,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct ActiveEffect {
+    pub kind: EffectKind,
+    /// Upcoming ticks the effect applies to.
+    pub remaining_ticks: u32,
+}
+
+/// Spawn weights of the special items, each relative to `NORMAL_FOOD_WEIGHT`.
+/// All zero means every spawn is normal food.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct FoodWeights {
+    pub golden: u32,
+    pub speed_boost: u32,
+    pub ghost: u32,
+    pub shrink: u32,
+    pub reverse_controls: u32,
+}
+
 #[derive(Clone, Debug)]
 pub struct FieldSize {
     pub width: usize,
@@ -1,5 +1,9 @@
-use crate::proto::snake::SnakeLobbySettings;
+use crate::proto::snake::{SnakeGameMode, SnakeLobbySettings};
 use crate::validate_lobby_settings::ValidateLobbySettings;
+use super::map::resolve_map;
+
+const MAX_FOOD_WEIGHT: u32 = 1000;
+const MAX_INPUT_BUFFER_SIZE: u32 = 8;
 
 impl ValidateLobbySettings for SnakeLobbySettings {
     fn validate(&self, max_players: u32) -> Result<(), String> {
@@ -21,6 +25,60 @@ impl ValidateLobbySettings for SnakeLobbySettings {
         if !(0.0..=1.0).contains(&self.food_spawn_probability) {
             return Err("Food spawn probability must be between 0.0 and 1.0".to_string());
         }
+        validate_food_weights(self)?;
+        validate_map(self, max_players)?;
+        validate_input_buffer_size(self)?;
+        validate_game_mode(self)?;
         Ok(())
     }
 }
+
+pub(super) fn validate_food_weights(settings: &SnakeLobbySettings) -> Result<(), String> {
+    let weights = [
+        settings.golden_food_weight,
+        settings.speed_boost_weight,
+        settings.ghost_weight,
+        settings.shrink_weight,
+        settings.reverse_controls_weight,
+    ];
+    if weights.iter().any(|w| *w > MAX_FOOD_WEIGHT) {
+        return Err(format!("Item weights must be between 0 and {}", MAX_FOOD_WEIGHT));
+    }
+    Ok(())
+}
+
+pub(super) fn validate_map(settings: &SnakeLobbySettings, max_players: u32) -> Result<(), String> {
+    let Some(map) = resolve_map(settings)? else {
+        return Ok(());
+    };
+    if map.field_size.width != settings.field_width as usize || map.field_size.height != settings.field_height as usize {
+        return Err(format!(
+            "Map '{}' needs a {}x{} field",
+            map.name, map.field_size.width, map.field_size.height
+        ));
+    }
+    map.check_players(max_players as usize)
+}
+
+pub(super) fn validate_input_buffer_size(settings: &SnakeLobbySettings) -> Result<(), String> {
+    if settings.input_buffer_size.is_some_and(|size| size > MAX_INPUT_BUFFER_SIZE) {
+        return Err(format!("Input buffer size must be between 0 and {}", MAX_INPUT_BUFFER_SIZE));
+    }
+    Ok(())
+}
+
+pub(super) fn validate_game_mode(settings: &SnakeLobbySettings) -> Result<(), String> {
+    match SnakeGameMode::try_from(settings.game_mode) {
+        Ok(SnakeGameMode::TimeLimit) if !(10..=3600).contains(&settings.time_limit_seconds) => {
+            Err("Time limit must be between 10 and 3600 seconds".to_string())
+        }
+        Ok(SnakeGameMode::ScoreTarget) if !(1..=1000).contains(&settings.score_target) => {
+            Err("Score target must be between 1 and 1000".to_string())
+        }
+        Ok(SnakeGameMode::BattleRoyale) if !(1..=120).contains(&settings.shrink_interval_seconds) => {
+            Err("Shrink interval must be between 1 and 120 seconds".to_string())
+        }
+        Err(_) => Err("Unknown game mode".to_string()),
+        Ok(_) => Ok(()),
+    }
+}
@@ -9,6 +9,7 @@ pub struct BoxLandedInfo {
     pub y: i32,
 }
 
+#[derive(Clone)]
 pub struct Field {
     width: u32,
     height: u32,
@@ -1,7 +1,7 @@
 use std::collections::HashMap;
 
 use crate::PlayerId;
-use crate::games::SessionRng;
+use crate::games::{SessionRng, StateHasher};
 use crate::proto::stack_attack::{
     self as proto, BoxDroppedEvent, BoxLandedEvent, BoxPushedEvent, CraneSpawnedEvent,
     GameEvent, LineClearedEvent, WorkerCrushedEvent, WorkerJumpedEvent, WorkerLandedEvent,
@@ -25,6 +25,7 @@ pub const MAX_SIMULTANEOUS_CRANES_CAP: u32 = 5;
 
 pub const PATTERN_COUNT: u32 = 8;
 
+#[derive(Clone)]
 pub struct StackAttackGameState {
     pub field: Field,
     pub workers: HashMap<PlayerId, Worker>,
@@ -70,6 +71,19 @@ impl StackAttackGameState {
         }
     }
 
+    /// Hands each worker to the player `renames` maps it to.
+    pub fn rename_players(&mut self, renames: &HashMap<PlayerId, PlayerId>) {
+        self.workers = std::mem::take(&mut self.workers)
+            .into_values()
+            .map(|mut worker| {
+                if let Some(new_id) = renames.get(&worker.player_id) {
+                    worker.player_id = new_id.clone();
+                }
+                (worker.player_id.clone(), worker)
+            })
+            .collect();
+    }
+
     pub fn update(&mut self, rng: &mut SessionRng) -> Vec<GameEvent> {
         if self.game_over {
             return Vec::new();
@@ -390,6 +404,43 @@ impl StackAttackGameState {
             })
             .collect()
     }
+
+    pub fn state_hash(&self) -> u64 {
+        let mut hasher = StateHasher::new();
+        hasher.write_u64(self.score as u64);
+        hasher.write_u64(self.lines_cleared as u64);
+        hasher.write_u64(self.boxes_pushed as u64);
+        hasher.write_bool(self.game_over);
+        hasher.write_u64(self.difficulty_level as u64);
+
+        let mut workers: Vec<&Worker> = self.workers.values().collect();
+        workers.sort_by_key(|w| w.player_id.to_string());
+        for worker in workers {
+            hasher.write_str(&worker.player_id.to_string());
+            hasher.write_i64(worker.position.x as i64);
+            hasher.write_i64(worker.position.y as i64);
+            hasher.write_u64(worker.state as u64);
+            hasher.write_bool(worker.alive);
+        }
+
+        let mut boxes: Vec<_> = self.field.boxes().collect();
+        boxes.sort_by_key(|b| b.id);
+        for box_entity in boxes {
+            hasher.write_u64(box_entity.id as u64);
+            hasher.write_i64(box_entity.position.x as i64);
+            hasher.write_i64(box_entity.position.y as i64);
+            hasher.write_u64(box_entity.pattern_id as u64);
+            hasher.write_bool(box_entity.falling);
+        }
+
+        for crane in &self.cranes {
+            hasher.write_u64(crane.id as u64);
+            hasher.write_i64(crane.x as i64);
+            hasher.write_i64(crane.target_x as i64);
+            hasher.write_u64(crane.state as u64);
+        }
+        hasher.finish()
+    }
 }
 
 fn calculate_spawn_x(index: usize, total: usize, field_width: u32) -> i32 {
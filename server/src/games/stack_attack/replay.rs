@@ -1,29 +1,61 @@
 use std::collections::HashMap;
+use std::sync::Arc;
 use std::time::Duration;
 
 use tokio::sync::mpsc;
 
-use crate::games::SessionRng;
-use crate::games::stack_attack::{HorizontalDirection as StackAttackDirection, StackAttackGameState};
+use crate::games::{GameSession, GameSessionConfig, SessionRng};
+use crate::games::stack_attack::{
+    HorizontalDirection as StackAttackDirection, StackAttackGameState, StackAttackSessionState,
+};
 use crate::replay::ReplayPlayer;
+use crate::replay::branch::advance_to;
 use crate::replay::session::{
     ReplayCommandResult, ReplaySessionCommand, broadcast_state_and_replay_info,
-    estimate_total_ticks, handle_replay_command, wait_for_restart_or_stop,
+    estimate_total_ticks, handle_replay_command,
 };
+use crate::replay::timeline::{ReplaySimulation, ReplayTimeline};
+use crate::replay::verify::{ReplaySummary, play_to_end};
 use crate::{
-    ClientId, GameStateUpdate, PlayerAction, PlayerId, game_state_update, in_game_command,
+    ClientId, GameStateUpdate, PlayerAction, PlayerId, ReplayGame, game_state_update, in_game_command,
     player_action_content,
 };
-pub(crate) async fn run_replay(
-    mut player: ReplayPlayer,
-    command_rx: &mut mpsc::UnboundedReceiver<ReplaySessionCommand>,
-    viewers: &[ClientId],
-    host_only_control: bool,
-    broadcaster: &crate::broadcaster::Broadcaster,
-) -> bool {
+
+#[derive(Clone)]
+struct StackAttackReplay {
+    game_state: StackAttackGameState,
+    rng: SessionRng,
+    tick: u64,
+    player_map: Arc<HashMap<i32, PlayerId>>,
+}
+
+impl ReplaySimulation for StackAttackReplay {
+    fn position(&self) -> u64 {
+        self.tick
+    }
+
+    fn is_finished(&self, player: &ReplayPlayer) -> bool {
+        self.game_state.is_game_over() || player.is_finished()
+    }
+
+    fn step(&mut self, player: &mut ReplayPlayer) {
+        let actions = player.actions_for_tick(self.tick as i64);
+        for action in &actions {
+            apply_stack_attack_action(&mut self.game_state, action, &self.player_map);
+        }
+        let _events = self.game_state.update(&mut self.rng);
+        self.tick += 1;
+    }
+
+    fn state_hash(&self) -> u64 {
+        self.game_state.state_hash()
+    }
+}
+
+fn new_replay(player: &ReplayPlayer) -> Result<StackAttackReplay, String> {
     let players = player.players();
     if players.is_empty() {
-        return false;
+        return Err("StackAttack replay has no players".to_string());
     }
 
     let player_ids: Vec<PlayerId> = players
@@ -36,25 +68,80 @@ pub(crate) async fn run_replay(
         .map(|(i, p)| (i as i32, PlayerId::new(p.player_id.clone())))
         .collect();
 
-    let mut game_state = StackAttackGameState::new(&player_ids);
-    let mut rng = SessionRng::new(player.seed());
+    Ok(StackAttackReplay {
+        game_state: StackAttackGameState::new(&player_ids),
+        rng: player.rng(),
+        tick: 0,
+        player_map: Arc::new(player_map),
+    })
+}
+
+pub(crate) fn branch(
+    mut player: ReplayPlayer,
+    position: u64,
+    renames: &HashMap<PlayerId, PlayerId>,
+    config: &GameSessionConfig,
+) -> Result<GameSession, String> {
+    let mut replay = new_replay(&player)?;
+    advance_to(&mut replay, &mut player, position);
+    if replay.game_state.is_game_over() {
+        return Err(format!("The game is already over at tick {}", replay.tick));
+    }
+    replay.game_state.rename_players(renames);
+
+    Ok(GameSession::StackAttack(StackAttackSessionState::from_state(
+        config,
+        replay.game_state,
+        replay.rng,
+        replay.tick,
+        None,
+    )))
+}
+
+pub(crate) fn simulate(mut player: ReplayPlayer) -> Result<ReplaySummary, String> {
+    let mut replay = new_replay(&player)?;
+    let desync_position = play_to_end(&mut replay, &mut player);
+
+    let outcome = if replay.game_state.is_game_over() { "Game over" } else { "Unfinished" };
+
+    Ok(ReplaySummary {
+        game: ReplayGame::StackAttack,
+        ticks: replay.tick,
+        outcome: outcome.to_string(),
+        scores: vec![("team".to_string(), replay.game_state.score)],
+        desync_position,
+    })
+}
+
+pub(crate) async fn run_replay(
+    mut player: ReplayPlayer,
+    command_rx: &mut mpsc::UnboundedReceiver<ReplaySessionCommand>,
+    viewers: &[ClientId],
+    host_only_control: bool,
+    broadcaster: &crate::broadcaster::Broadcaster,
+) -> bool {
+    let Ok(mut replay) = new_replay(&player) else {
+        return false;
+    };
+    let mut timeline = ReplayTimeline::new(&replay, &player);
     let tick_interval_ms = crate::games::stack_attack::settings::TICK_INTERVAL_MS as f32;
-    let mut current_tick: u64 = 0;
     let mut is_paused = false;
+    let mut is_finished = false;
     let mut speed = 1.0_f32;
     let total_ticks = estimate_total_ticks(&player);
 
-    let state_update = build_stack_attack_state(&game_state, players, current_tick);
+    let state_update = build_stack_attack_state(&replay.game_state, player.players(), replay.tick);
     broadcast_state_and_replay_info(
         broadcaster,
         viewers,
         state_update,
         is_paused,
-        current_tick,
+        replay.tick,
         total_ticks,
         speed,
-        false,
+        is_finished,
         host_only_control,
+        timeline.desync_position(),
     )
     .await;
 
@@ -63,97 +150,58 @@ pub(crate) async fn run_replay(
     loop {
         tokio::select! {
             _ = tick_timer.tick() => {
-                if is_paused {
+                if is_paused || is_finished {
                     continue;
                 }
 
-                let actions = player.actions_for_tick(current_tick as i64);
-                for action in &actions {
-                    apply_stack_attack_action(&mut game_state, action, &player_map);
-                }
-
-                let _events = game_state.update(&mut rng);
-                current_tick += 1;
-
-                let is_finished = game_state.is_game_over() || player.is_finished();
-                let state_update =
-                    build_stack_attack_state(&game_state, player.players(), current_tick.min(total_ticks));
-                broadcast_state_and_replay_info(
-                    broadcaster,
-                    viewers,
-                    state_update,
-                    is_paused,
-                    current_tick.min(total_ticks),
-                    total_ticks,
-                    speed,
-                    is_finished,
-                    host_only_control,
-                )
-                .await;
-
-                if is_finished {
-                    return wait_for_restart_or_stop(command_rx).await;
-                }
+                timeline.step(&mut replay, &mut player);
+                is_finished = replay.is_finished(&player);
             }
-            Some(cmd) = command_rx.recv() => {
-                match cmd {
-                    ReplaySessionCommand::ReplayCommand(replay_cmd) => {
-                        match handle_replay_command(&replay_cmd, &mut is_paused, &mut speed) {
-                            ReplayCommandResult::StateChanged => {
-                                let state_update = build_stack_attack_state(&game_state, player.players(), current_tick);
-                                broadcast_state_and_replay_info(
-                                    broadcaster,
-                                    viewers,
-                                    state_update,
-                                    is_paused,
-                                    current_tick,
-                                    total_ticks,
-                                    speed,
-                                    false,
-                                    host_only_control,
-                                )
-                                .await;
-                            }
-                            ReplayCommandResult::SpeedChanged => {
-                                let adjusted_interval = Duration::from_millis((tick_interval_ms / speed) as u64);
-                                tick_timer = tokio::time::interval(adjusted_interval);
-                            }
-                            ReplayCommandResult::StepForward => {
-                                let actions = player.actions_for_tick(current_tick as i64);
-                                for action in &actions {
-                                    apply_stack_attack_action(&mut game_state, action, &player_map);
-                                }
-
-                                let _events = game_state.update(&mut rng);
-                                current_tick += 1;
-
-                                let is_finished = game_state.is_game_over() || player.is_finished();
-                                let state_update =
-                                    build_stack_attack_state(&game_state, player.players(), current_tick.min(total_ticks));
-                                broadcast_state_and_replay_info(
-                                    broadcaster,
-                                    viewers,
-                                    state_update,
-                                    is_paused,
-                                    current_tick.min(total_ticks),
-                                    total_ticks,
-                                    speed,
-                                    is_finished,
-                                    host_only_control,
-                                )
-                                .await;
-
-                                if is_finished {
-                                    return wait_for_restart_or_stop(command_rx).await;
-                                }
-                            }
-                            ReplayCommandResult::Restart => return true,
-                            ReplayCommandResult::None => {}
+            cmd = command_rx.recv() => {
+                let Some(ReplaySessionCommand::ReplayCommand(replay_cmd)) = cmd else {
+                    return false;
+                };
+                match handle_replay_command(&replay_cmd, &mut is_paused, &mut speed) {
+                    ReplayCommandResult::StateChanged => {}
+                    ReplayCommandResult::SpeedChanged => {
+                        let adjusted_interval = Duration::from_millis((tick_interval_ms / speed) as u64);
+                        tick_timer = tokio::time::interval(adjusted_interval);
+                        continue;
+                    }
+                    ReplayCommandResult::StepForward => {
+                        if is_finished {
+                            continue;
                         }
+                        timeline.step(&mut replay, &mut player);
                     }
+                    ReplayCommandResult::StepBackward => {
+                        timeline.step_back(&mut replay, &mut player);
+                    }
+                    ReplayCommandResult::Seek(target) => {
+                        timeline.seek(&mut replay, &mut player, target);
+                    }
+                    ReplayCommandResult::Restart => return true,
+                    ReplayCommandResult::None => continue,
                 }
+                is_finished = replay.is_finished(&player);
             }
         }
+
+        let tick = replay.tick.min(total_ticks);
+        let state_update = build_stack_attack_state(&replay.game_state, player.players(), tick);
+        broadcast_state_and_replay_info(
+            broadcaster,
+            viewers,
+            state_update,
+            is_paused,
+            tick,
+            total_ticks,
+            speed,
+            is_finished,
+            host_only_control,
+            timeline.desync_position(),
+        )
+        .await;
     }
 }
 fn build_stack_attack_state(
@@ -216,6 +264,7 @@ fn apply_stack_attack_action(
         player_action_content::Content::Disconnected(_) => {
             game_state.handle_player_disconnect();
         }
+        player_action_content::Content::TimedOut(_) | player_action_content::Content::ControlChanged(_) => {}
     }
 }
 
@@ -12,7 +12,7 @@ use crate::{
     },
 };
 use crate::games::{BotType, GameBroadcaster, GameSessionConfig, SessionRng};
-use crate::replay::ReplayRecorder;
+use crate::replay::{ReplayRecorder, STATE_HASH_INTERVAL};
 
 use super::game_state::StackAttackGameState;
 use super::settings::{StackAttackSessionSettings, TICK_INTERVAL_MS};
@@ -45,14 +45,28 @@ impl StackAttackSessionState {
 
         let game_state = StackAttackGameState::new(&players);
 
+        Self::from_state(config, game_state, rng, 0, replay_recorder)
+    }
+
+    /// Continues a game from `tick`; survival time counts the ticks already played.
+    pub fn from_state(
+        config: &GameSessionConfig,
+        game_state: StackAttackGameState,
+        rng: SessionRng,
+        tick: u64,
+        replay_recorder: Option<Arc<Mutex<ReplayRecorder>>>,
+    ) -> Self {
+        let now = std::time::Instant::now();
+        let played = std::time::Duration::from_millis(tick * TICK_INTERVAL_MS as u64);
+
         Self {
             session_id: config.session_id.clone(),
             game_state: Arc::new(Mutex::new(game_state)),
-            tick: Arc::new(Mutex::new(0)),
+            tick: Arc::new(Mutex::new(tick)),
             rng: Arc::new(Mutex::new(rng)),
             bots: config.bots.clone(),
             replay_recorder,
-            start_time: std::time::Instant::now(),
+            start_time: now.checked_sub(played).unwrap_or(now),
         }
     }
 }
@@ -82,15 +96,20 @@ impl StackAttackSession {
             let current_tick = *tick_value;
             drop(tick_value);
 
+            if let Some(ref recorder) = session_state.replay_recorder
+                && current_tick.is_multiple_of(STATE_HASH_INTERVAL)
+            {
+                recorder.lock().await.record_state_hash(current_tick, game_state.state_hash());
+            }
+
             let proto_state = game_state.to_proto(current_tick, &session_state.bots);
             let game_over = game_state.is_game_over();
             drop(game_state);
 
-            let recipients = config.get_all_recipients();
             let state_update = GameStateUpdate {
                 state: Some(game_state_update::State::StackAttack(proto_state)),
             };
-            broadcaster.broadcast_state(state_update, recipients).await;
+            config.broadcast_state(&broadcaster, state_update).await;
 
             if game_over {
                 break;
@@ -143,6 +162,14 @@ impl StackAttackSession {
         }
     }
 
+    pub async fn current_state(state: &StackAttackSessionState) -> GameStateUpdate {
+        let game_state = state.game_state.lock().await;
+        let tick = *state.tick.lock().await;
+        GameStateUpdate {
+            state: Some(game_state_update::State::StackAttack(game_state.to_proto(tick, &state.bots))),
+        }
+    }
+
     pub async fn handle_player_disconnect(state: &StackAttackSessionState) {
         let mut game_state = state.game_state.lock().await;
         game_state.handle_player_disconnect();
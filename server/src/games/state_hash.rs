@@ -0,0 +1,66 @@
+use prost::Message;
+
+const FNV_OFFSET_BASIS: u64 = 0xcbf2_9ce4_8422_2325;
+const FNV_PRIME: u64 = 0x0000_0100_0000_01b3;
+
+/// FNV-1a, so hashes stored in replays stay comparable across platforms and Rust releases.
+#[derive(Debug, Clone)]
+pub struct StateHasher {
+    hash: u64,
+}
+
+impl Default for StateHasher {
+    fn default() -> Self {
+        Self { hash: FNV_OFFSET_BASIS }
+    }
+}
+
+impl StateHasher {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn write(&mut self, bytes: &[u8]) {
+        for byte in bytes {
+            self.hash ^= *byte as u64;
+            self.hash = self.hash.wrapping_mul(FNV_PRIME);
+        }
+    }
+
+    pub fn write_u64(&mut self, value: u64) {
+        self.write(&value.to_le_bytes());
+    }
+
+    pub fn write_i64(&mut self, value: i64) {
+        self.write(&value.to_le_bytes());
+    }
+
+    pub fn write_bool(&mut self, value: bool) {
+        self.write(&[value as u8]);
+    }
+
+    pub fn write_str(&mut self, value: &str) {
+        self.write_u64(value.len() as u64);
+        self.write(value.as_bytes());
+    }
+
+    pub fn write_message(&mut self, message: &impl Message) {
+        self.write(&message.encode_to_vec());
+    }
+
+    pub fn finish(&self) -> u64 {
+        self.hash
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_finish_known_fnv1a_vector() {
+        let mut hasher = StateHasher::new();
+        hasher.write(b"a");
+        assert_eq!(hasher.finish(), 0xaf63_dc4c_8601_ec8c);
+    }
+}
@@ -40,5 +40,7 @@ pub fn get_available_moves(board: &[Vec<Mark>]) -> Vec<(usize, usize)> {
         return vec![(width / 2, height / 2)];
     }
 
-    near_moves.into_iter().collect()
+    let mut moves: Vec<(usize, usize)> = near_moves.into_iter().collect();
+    moves.sort_unstable_by_key(|&(x, y)| (y, x));
+    moves
 }
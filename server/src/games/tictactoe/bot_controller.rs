@@ -1,13 +1,19 @@
+use std::time::{Duration, Instant};
+
 use crate::games::SessionRng;
-use crate::proto::tictactoe::TicTacToeBotType;
+use crate::proto::tictactoe::{Swap2Choice, TicTacToeBotType};
 use super::board::get_available_moves;
+use super::bot_strength::BotStrength;
 use super::game_state::TicTacToeGameState;
-use super::types::{Mark, Position};
+use super::rules::Rules;
+use super::types::{Mark, Position, Swap2Phase, WinRule};
 
 pub struct BotInput {
     pub board: Vec<Vec<Mark>>,
     pub win_count: usize,
     pub current_mark: Mark,
+    pub win_rule: WinRule,
+    pub swap2_phase: Swap2Phase,
 }
 
 impl BotInput {
@@ -16,24 +22,48 @@ impl BotInput {
             board: state.board.clone(),
             win_count: state.win_count,
             current_mark: state.current_mark,
+            win_rule: state.win_rule,
+            swap2_phase: state.swap2_phase,
         }
     }
+
+    fn rules(&self) -> Rules {
+        Rules::new(self.win_count, self.win_rule)
+    }
 }
 
 pub fn calculate_move(
     bot_type: TicTacToeBotType,
+    strength: &BotStrength,
     input: BotInput,
     rng: &mut SessionRng,
 ) -> Option<Position> {
     match bot_type {
         TicTacToeBotType::TictactoeBotTypeRandom => calculate_random_move(&input, rng),
-        TicTacToeBotType::TictactoeBotTypeMinimax => calculate_minimax_move(&input),
+        TicTacToeBotType::TictactoeBotTypeMinimax if strength.blunders(rng) => calculate_random_move(&input, rng),
+        TicTacToeBotType::TictactoeBotTypeMinimax => calculate_minimax_move(&input, strength),
         _ => None,
     }
 }
 
-fn calculate_random_move(input: &BotInput, rng: &mut SessionRng) -> Option<Position> {
-    let available_moves = get_available_moves(&input.board);
+/// The random bot picks any option; the minimax bot takes whichever side the
+/// board evaluation favours, and never hands the choice back.
+pub fn calculate_swap2_choice(bot_type: TicTacToeBotType, input: &BotInput, rng: &mut SessionRng) -> Swap2Choice {
+    match bot_type {
+        TicTacToeBotType::TictactoeBotTypeRandom => {
+            let choices: &[Swap2Choice] = match input.swap2_phase {
+                Swap2Phase::Choice => &[Swap2Choice::PlayX, Swap2Choice::PlayO, Swap2Choice::PlaceTwo],
+                _ => &[Swap2Choice::PlayX, Swap2Choice::PlayO],
+            };
+            choices[rng.random_range(0..choices.len())]
+        }
+        _ if evaluate_board(&input.board, Mark::X, input.win_count) > 0 => Swap2Choice::PlayX,
+        _ => Swap2Choice::PlayO,
+    }
+}
+
+pub fn calculate_random_move(input: &BotInput, rng: &mut SessionRng) -> Option<Position> {
+    let available_moves = legal_moves(&mut input.board.clone(), &input.rules(), input.current_mark);
     if available_moves.is_empty() {
         return None;
     }
@@ -42,24 +72,67 @@ fn calculate_random_move(input: &BotInput, rng: &mut SessionRng) -> Option<Posit
     Some(Position::new(x, y))
 }
 
-pub fn calculate_minimax_move(input: &BotInput) -> Option<Position> {
+/// Swap2 opening stones: the cell that leaves the position closest to even,
+/// since the other player gets to pick a side afterwards.
+fn calculate_balanced_move(input: &BotInput) -> Option<Position> {
+    let mut board = input.board.clone();
+    get_available_moves(&board)
+        .into_iter()
+        .min_by_key(|&(x, y)| {
+            board[y][x] = input.current_mark;
+            let imbalance = evaluate_board(&board, Mark::X, input.win_count).abs();
+            board[y][x] = Mark::Empty;
+            imbalance
+        })
+        .map(|(x, y)| Position::new(x, y))
+}
+
+/// Candidate cells near the stones that `mark` may take. Should Renju forbid
+/// every one of them for X, any allowed empty cell will do.
+fn legal_moves(board: &mut [Vec<Mark>], rules: &Rules, mark: Mark) -> Vec<(usize, usize)> {
+    let moves = get_available_moves(board);
+    if rules.win_rule != WinRule::Renju || mark != Mark::X {
+        return moves;
+    }
+
+    let legal: Vec<(usize, usize)> = moves
+        .iter()
+        .copied()
+        .filter(|&(x, y)| rules.forbidden_move(board, x, y, mark).is_none())
+        .collect();
+    if !legal.is_empty() || moves.is_empty() {
+        return legal;
+    }
+
+    let width = board[0].len();
+    (0..board.len())
+        .flat_map(|y| (0..width).map(move |x| (x, y)))
+        .filter(|&(x, y)| board[y][x] == Mark::Empty && rules.forbidden_move(board, x, y, mark).is_none())
+        .take(1)
+        .collect()
+}
+
+pub fn calculate_minimax_move(input: &BotInput, strength: &BotStrength) -> Option<Position> {
+    if matches!(input.swap2_phase, Swap2Phase::Opening | Swap2Phase::ExtraStones) {
+        return calculate_balanced_move(input);
+    }
+
     let bot_mark = input.current_mark;
     let opponent_mark = bot_mark.opponent()?;
-    let available_moves = get_available_moves(&input.board);
+    let rules = input.rules();
+    let mut board = input.board.clone();
+    let available_moves = legal_moves(&mut board, &rules, bot_mark);
 
     if available_moves.is_empty() {
         return None;
     }
 
-    let mut board = input.board.clone();
-
-    if let Some((x, y)) = find_winning_move(&mut board, bot_mark, input.win_count, &available_moves)
-    {
+    if let Some((x, y)) = find_winning_move(&mut board, bot_mark, &rules, &available_moves) {
         return Some(Position::new(x, y));
     }
 
     if let Some((x, y)) =
-        find_winning_move(&mut board, opponent_mark, input.win_count, &available_moves)
+        find_winning_move(&mut board, opponent_mark, &rules, &available_moves)
     {
         return Some(Position::new(x, y));
     }
@@ -77,27 +150,57 @@ pub fn calculate_minimax_move(input: &BotInput) -> Option<Position> {
     }
 
     if let Some((x, y)) =
-        find_double_block_move(&mut board, opponent_mark, input.win_count, &available_moves)
+        find_double_block_move(&mut board, opponent_mark, &rules, &available_moves)
     {
         return Some(Position::new(x, y));
     }
 
-    let depth_limit = calculate_depth_limit(available_moves.len());
+    let depth_limit = search_depth_limit(strength, available_moves.len());
     let initial_score = evaluate_board(&board, bot_mark, input.win_count);
 
+    let Some(budget_ms) = strength.time_budget_ms else {
+        return search_root(&mut board, &rules, &available_moves, depth_limit, bot_mark, initial_score, None);
+    };
+
+    // Deepen one ply at a time and keep the deepest search that finished.
+    let deadline = Instant::now() + Duration::from_millis(budget_ms);
+    let mut best_move = search_root(&mut board, &rules, &available_moves, 0, bot_mark, initial_score, None);
+    for depth in 1..=depth_limit {
+        match search_root(&mut board, &rules, &available_moves, depth, bot_mark, initial_score, Some(deadline)) {
+            Some(pos) => best_move = Some(pos),
+            None => break,
+        }
+    }
+    best_move
+}
+
+/// The best of `moves` searched to `depth`, or `None` if the deadline passed
+/// before the search finished.
+fn search_root(
+    board: &mut [Vec<Mark>],
+    rules: &Rules,
+    moves: &[(usize, usize)],
+    depth: usize,
+    bot_mark: Mark,
+    initial_score: i32,
+    deadline: Option<Instant>,
+) -> Option<Position> {
     let mut best_move = None;
     let mut best_score = i32::MIN;
 
-    for (x, y) in &available_moves {
-        let (x, y) = (*x, *y);
-        let delta = eval_delta_before_move(&board, bot_mark, input.win_count, x, y, bot_mark);
+    for &(x, y) in moves {
+        if deadline.is_some_and(|deadline| Instant::now() >= deadline) {
+            return None;
+        }
+
+        let delta = eval_delta_before_move(board, bot_mark, rules.win_count, x, y, bot_mark);
         board[y][x] = bot_mark;
 
         let score = minimax(
-            &mut board,
-            input.win_count,
+            board,
+            rules,
             0,
-            depth_limit,
+            depth,
             false,
             bot_mark,
             i32::MIN,
@@ -105,9 +208,11 @@ pub fn calculate_minimax_move(input: &BotInput) -> Option<Position> {
             x,
             y,
             initial_score + delta,
+            deadline,
         );
 
         board[y][x] = Mark::Empty;
+        let score = score?;
 
         if score > best_score {
             best_score = score;
@@ -121,12 +226,12 @@ pub fn calculate_minimax_move(input: &BotInput) -> Option<Position> {
 fn find_winning_move(
     board: &mut [Vec<Mark>],
     mark: Mark,
-    win_count: usize,
+    rules: &Rules,
     moves: &[(usize, usize)],
 ) -> Option<(usize, usize)> {
     for &(x, y) in moves {
         board[y][x] = mark;
-        let winner = check_win_at(board, win_count, x, y);
+        let winner = check_win_at(board, rules, x, y);
         board[y][x] = Mark::Empty;
 
         if winner == Some(mark) {
@@ -156,12 +261,12 @@ fn find_open_threat_move(
 fn find_double_block_move(
     board: &mut [Vec<Mark>],
     opponent_mark: Mark,
-    win_count: usize,
+    rules: &Rules,
     moves: &[(usize, usize)],
 ) -> Option<(usize, usize)> {
     for &(x, y) in moves {
         board[y][x] = opponent_mark;
-        let winning_moves = count_winning_moves(board, opponent_mark, win_count, moves, x, y);
+        let winning_moves = count_winning_moves(board, opponent_mark, rules, moves, x, y);
         board[y][x] = Mark::Empty;
 
         if winning_moves >= 2 {
@@ -174,7 +279,7 @@ fn find_double_block_move(
 fn count_winning_moves(
     board: &mut [Vec<Mark>],
     mark: Mark,
-    win_count: usize,
+    rules: &Rules,
     moves: &[(usize, usize)],
     exclude_x: usize,
     exclude_y: usize,
@@ -189,7 +294,7 @@ fn count_winning_moves(
         }
 
         board[y][x] = mark;
-        if check_win_at(board, win_count, x, y) == Some(mark) {
+        if check_win_at(board, rules, x, y) == Some(mark) {
             count += 1;
         }
         board[y][x] = Mark::Empty;
@@ -271,6 +376,17 @@ fn has_open_threat(
     false
 }
 
+/// A custom depth deeper than the board allows needs a time budget; without
+/// one nothing would stop the search on a large board.
+fn search_depth_limit(strength: &BotStrength, moves_count: usize) -> usize {
+    let board_depth = calculate_depth_limit(moves_count);
+    match (strength.search_depth, strength.time_budget_ms) {
+        (None, _) => board_depth,
+        (Some(depth), Some(_)) => depth,
+        (Some(depth), None) => depth.min(board_depth),
+    }
+}
+
 fn calculate_depth_limit(moves_count: usize) -> usize {
     match moves_count {
         0..=4 => moves_count,
@@ -281,60 +397,13 @@ fn calculate_depth_limit(moves_count: usize) -> usize {
     }
 }
 
-fn check_win_at(board: &[Vec<Mark>], win_count: usize, x: usize, y: usize) -> Option<Mark> {
-    let mark = board[y][x];
-    if mark == Mark::Empty {
-        return None;
-    }
-
-    let height = board.len();
-    let width = board[0].len();
-    let win_count_i = win_count as isize;
-
-    let directions: [(isize, isize); 4] = [(1, 0), (0, 1), (1, 1), (1, -1)];
-
-    for (dx, dy) in directions {
-        let mut count = 1;
-
-        let mut i = 1isize;
-        while i < win_count_i {
-            let nx = x as isize + dx * i;
-            let ny = y as isize + dy * i;
-            if nx < 0 || ny < 0 || nx >= width as isize || ny >= height as isize {
-                break;
-            }
-            if board[ny as usize][nx as usize] != mark {
-                break;
-            }
-            count += 1;
-            i += 1;
-        }
-
-        let mut i = 1isize;
-        while i < win_count_i {
-            let nx = x as isize - dx * i;
-            let ny = y as isize - dy * i;
-            if nx < 0 || ny < 0 || nx >= width as isize || ny >= height as isize {
-                break;
-            }
-            if board[ny as usize][nx as usize] != mark {
-                break;
-            }
-            count += 1;
-            i += 1;
-        }
-
-        if count >= win_count {
-            return Some(mark);
-        }
-    }
-
-    None
+fn check_win_at(board: &[Vec<Mark>], rules: &Rules, x: usize, y: usize) -> Option<Mark> {
+    rules.is_win_at(board, x, y).then(|| board[y][x])
 }
 
 fn minimax(
     board: &mut [Vec<Mark>],
-    win_count: usize,
+    rules: &Rules,
     depth: usize,
     max_depth: usize,
     is_maximizing: bool,
@@ -344,29 +413,33 @@ fn minimax(
     last_x: usize,
     last_y: usize,
     current_score: i32,
-) -> i32 {
-    if let Some(winner) = check_win_at(board, win_count, last_x, last_y) {
-        return if winner == bot_mark {
+    deadline: Option<Instant>,
+) -> Option<i32> {
+    if let Some(winner) = check_win_at(board, rules, last_x, last_y) {
+        return Some(if winner == bot_mark {
             1000 - depth as i32
         } else {
             -1000 + depth as i32
-        };
+        });
     }
 
     if depth >= max_depth {
-        return current_score;
+        return Some(current_score);
+    }
+    if deadline.is_some_and(|deadline| Instant::now() >= deadline) {
+        return None;
     }
 
-    let moves = get_available_moves(board);
+    let win_count = rules.win_count;
 
     if is_maximizing {
         let mut max_eval = i32::MIN;
-        for (x, y) in moves {
+        for (x, y) in legal_moves(board, rules, bot_mark) {
             let delta = eval_delta_before_move(board, bot_mark, win_count, x, y, bot_mark);
             board[y][x] = bot_mark;
             let eval = minimax(
                 board,
-                win_count,
+                rules,
                 depth + 1,
                 max_depth,
                 false,
@@ -376,25 +449,27 @@ fn minimax(
                 x,
                 y,
                 current_score + delta,
+                deadline,
             );
             board[y][x] = Mark::Empty;
+            let eval = eval?;
 
             max_eval = max_eval.max(eval);
             alpha = alpha.max(eval);
             if beta <= alpha {
-                return max_eval;
+                return Some(max_eval);
             }
         }
-        if max_eval == i32::MIN { 0 } else { max_eval }
+        Some(if max_eval == i32::MIN { 0 } else { max_eval })
     } else {
         let opponent_mark = bot_mark.opponent().unwrap();
         let mut min_eval = i32::MAX;
-        for (x, y) in moves {
+        for (x, y) in legal_moves(board, rules, opponent_mark) {
             let delta = eval_delta_before_move(board, bot_mark, win_count, x, y, opponent_mark);
             board[y][x] = opponent_mark;
             let eval = minimax(
                 board,
-                win_count,
+                rules,
                 depth + 1,
                 max_depth,
                 true,
@@ -404,16 +479,18 @@ fn minimax(
                 x,
                 y,
                 current_score + delta,
+                deadline,
             );
             board[y][x] = Mark::Empty;
+            let eval = eval?;
 
             min_eval = min_eval.min(eval);
             beta = beta.min(eval);
             if beta <= alpha {
-                return min_eval;
+                return Some(min_eval);
             }
         }
-        if min_eval == i32::MAX { 0 } else { min_eval }
+        Some(if min_eval == i32::MAX { 0 } else { min_eval })
     }
 }
 
@@ -590,3 +667,44 @@ fn check_line_threat(
 
     score
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::proto::tictactoe::TicTacToeBotLevel;
+
+    fn custom(search_depth: usize, time_budget_ms: Option<u64>) -> BotStrength {
+        BotStrength {
+            level: TicTacToeBotLevel::TictactoeBotLevelCustom,
+            search_depth: Some(search_depth),
+            time_budget_ms,
+            error_rate_percent: 0,
+        }
+    }
+
+    #[test]
+    fn test_search_depth_limit_custom_depth_capped_by_board_without_budget() {
+        assert_eq!(search_depth_limit(&custom(8, None), 225), 3);
+        assert_eq!(search_depth_limit(&custom(2, None), 225), 2);
+        assert_eq!(search_depth_limit(&custom(8, Some(500)), 225), 8);
+        assert_eq!(search_depth_limit(&BotStrength::default(), 225), 3);
+    }
+
+    #[test]
+    fn test_deep_search_stops_at_time_budget() {
+        let mut board = vec![vec![Mark::Empty; 15]; 15];
+        board[7][7] = Mark::X;
+        board[7][8] = Mark::O;
+        let input = BotInput {
+            board,
+            win_count: 5,
+            current_mark: Mark::X,
+            win_rule: WinRule::Freestyle,
+            swap2_phase: Swap2Phase::None,
+        };
+
+        let started = Instant::now();
+        assert!(calculate_minimax_move(&input, &custom(8, Some(50))).is_some());
+        assert!(started.elapsed() < Duration::from_secs(2));
+    }
+}
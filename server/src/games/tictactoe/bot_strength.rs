@@ -0,0 +1,132 @@
+use crate::games::SessionRng;
+use crate::proto::tictactoe::{TicTacToeBotLevel, TicTacToeBotStrength};
+
+const MAX_SEARCH_DEPTH: u32 = 8;
+const MAX_TIME_BUDGET_MS: u32 = 60 * 1_000;
+
+/// How well a minimax bot plays. The random bot ignores it.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct BotStrength {
+    pub level: TicTacToeBotLevel,
+    /// Plies searched after the bot's own move; `None` picks the depth from
+    /// the number of candidate moves, which also caps it without a time budget.
+    pub search_depth: Option<usize>,
+    /// Stops deepening once the search has run this long.
+    pub time_budget_ms: Option<u64>,
+    /// Chance in percent of playing a random legal move instead.
+    pub error_rate_percent: u32,
+}
+
+impl Default for BotStrength {
+    fn default() -> Self {
+        Self::preset(TicTacToeBotLevel::TictactoeBotLevelHard)
+    }
+}
+
+impl BotStrength {
+    /// Hard is how minimax bots played before levels were added, and stands
+    /// in for anything that is not easy or medium.
+    pub fn preset(level: TicTacToeBotLevel) -> Self {
+        let (level, search_depth, error_rate_percent) = match level {
+            TicTacToeBotLevel::TictactoeBotLevelEasy => (level, Some(0), 30),
+            TicTacToeBotLevel::TictactoeBotLevelMedium => (level, Some(2), 10),
+            _ => (TicTacToeBotLevel::TictactoeBotLevelHard, None, 0),
+        };
+        Self {
+            level,
+            search_depth,
+            time_budget_ms: None,
+            error_rate_percent,
+        }
+    }
+
+    pub fn from_proto(strength: Option<&TicTacToeBotStrength>) -> Result<Self, String> {
+        let Some(strength) = strength else {
+            return Ok(Self::default());
+        };
+        match TicTacToeBotLevel::try_from(strength.level) {
+            Ok(TicTacToeBotLevel::TictactoeBotLevelCustom) => {
+                if strength.search_depth.is_some_and(|depth| depth > MAX_SEARCH_DEPTH) {
+                    return Err(format!("Search depth must be at most {}", MAX_SEARCH_DEPTH));
+                }
+                if strength.time_budget_ms > MAX_TIME_BUDGET_MS {
+                    return Err(format!("Time budget must be at most {} ms", MAX_TIME_BUDGET_MS));
+                }
+                if strength.error_rate_percent > 100 {
+                    return Err("Error rate must be at most 100 percent".to_string());
+                }
+                Ok(Self {
+                    level: TicTacToeBotLevel::TictactoeBotLevelCustom,
+                    search_depth: strength.search_depth.map(|depth| depth as usize),
+                    time_budget_ms: (strength.time_budget_ms > 0).then_some(strength.time_budget_ms as u64),
+                    error_rate_percent: strength.error_rate_percent,
+                })
+            }
+            Ok(level) => Ok(Self::preset(level)),
+            Err(_) => Err("Unknown tictactoe bot level".to_string()),
+        }
+    }
+
+    pub fn to_proto(self) -> TicTacToeBotStrength {
+        TicTacToeBotStrength {
+            level: self.level as i32,
+            search_depth: self.search_depth.map(|depth| depth as u32),
+            time_budget_ms: self.time_budget_ms.unwrap_or(0) as u32,
+            error_rate_percent: self.error_rate_percent,
+        }
+    }
+
+    /// Whether this move should be a random one. Draws nothing from `rng`
+    /// for bots that never err, so hard bots leave the stream untouched.
+    pub fn blunders(&self, rng: &mut SessionRng) -> bool {
+        self.error_rate_percent > 0 && rng.random_range(0..100) < self.error_rate_percent
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_missing_or_unspecified_strength_plays_as_hard() {
+        let hard = BotStrength::preset(TicTacToeBotLevel::TictactoeBotLevelHard);
+        assert_eq!(BotStrength::from_proto(None), Ok(hard));
+        assert_eq!(BotStrength::from_proto(Some(&TicTacToeBotStrength::default())), Ok(hard));
+        assert_eq!(hard.search_depth, None);
+        assert_eq!(hard.error_rate_percent, 0);
+    }
+
+    #[test]
+    fn test_presets_ignore_custom_fields() {
+        let request = TicTacToeBotStrength {
+            level: TicTacToeBotLevel::TictactoeBotLevelEasy as i32,
+            search_depth: Some(6),
+            time_budget_ms: 500,
+            error_rate_percent: 0,
+        };
+        assert_eq!(BotStrength::from_proto(Some(&request)), Ok(BotStrength::preset(TicTacToeBotLevel::TictactoeBotLevelEasy)));
+    }
+
+    #[test]
+    fn test_custom_strength_is_validated_and_round_trips() {
+        let request = TicTacToeBotStrength {
+            level: TicTacToeBotLevel::TictactoeBotLevelCustom as i32,
+            search_depth: Some(3),
+            time_budget_ms: 250,
+            error_rate_percent: 15,
+        };
+        let strength = BotStrength::from_proto(Some(&request)).unwrap();
+        assert_eq!(strength.search_depth, Some(3));
+        assert_eq!(strength.time_budget_ms, Some(250));
+        assert_eq!(strength.to_proto(), request);
+
+        let too_deep = TicTacToeBotStrength { search_depth: Some(MAX_SEARCH_DEPTH + 1), ..request };
+        assert!(BotStrength::from_proto(Some(&too_deep)).is_err());
+        let too_slow = TicTacToeBotStrength { time_budget_ms: MAX_TIME_BUDGET_MS + 1, ..request };
+        assert!(BotStrength::from_proto(Some(&too_slow)).is_err());
+        let too_sloppy = TicTacToeBotStrength { error_rate_percent: 101, ..request };
+        assert!(BotStrength::from_proto(Some(&too_sloppy)).is_err());
+        let unknown = TicTacToeBotStrength { level: 99, ..request };
+        assert!(BotStrength::from_proto(Some(&unknown)).is_err());
+    }
+}
@@ -0,0 +1,146 @@
+use crate::proto::tictactoe::{TicTacToeLobbySettings, TimeControlMode};
+use super::types::Mark;
+
+pub(super) const MIN_TIME_MS: u32 = 1_000;
+pub(super) const MAX_TIME_MS: u32 = 2 * 60 * 60 * 1_000;
+const MAX_INCREMENT_MS: u32 = 60 * 1_000;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum TimeControl {
+    Unlimited,
+    Fischer { initial_ms: u64, increment_ms: u64 },
+    PerMove { move_time_ms: u64 },
+}
+
+impl TimeControl {
+    pub fn from_settings(settings: &TicTacToeLobbySettings) -> Result<Self, String> {
+        match TimeControlMode::try_from(settings.time_control) {
+            Ok(TimeControlMode::Fischer) => {
+                if !(MIN_TIME_MS..=MAX_TIME_MS).contains(&settings.initial_time_ms) {
+                    return Err(format!("Initial time must be between {} and {} ms", MIN_TIME_MS, MAX_TIME_MS));
+                }
+                if settings.increment_ms > MAX_INCREMENT_MS {
+                    return Err(format!("Increment must be at most {} ms", MAX_INCREMENT_MS));
+                }
+                Ok(TimeControl::Fischer {
+                    initial_ms: settings.initial_time_ms as u64,
+                    increment_ms: settings.increment_ms as u64,
+                })
+            }
+            Ok(TimeControlMode::PerMove) => {
+                if !(MIN_TIME_MS..=MAX_TIME_MS).contains(&settings.move_time_ms) {
+                    return Err(format!("Move time must be between {} and {} ms", MIN_TIME_MS, MAX_TIME_MS));
+                }
+                Ok(TimeControl::PerMove {
+                    move_time_ms: settings.move_time_ms as u64,
+                })
+            }
+            Ok(TimeControlMode::Unlimited | TimeControlMode::Unspecified) => Ok(TimeControl::Unlimited),
+            Err(_) => Err("Unknown time control mode".to_string()),
+        }
+    }
+}
+
+/// Times are milliseconds since the game started, so replays can drive the clock exactly.
+#[derive(Clone, Debug)]
+pub struct GameClock {
+    control: TimeControl,
+    remaining_x_ms: u64,
+    remaining_o_ms: u64,
+    turn_started_ms: u64,
+}
+
+impl GameClock {
+    pub fn new(control: TimeControl) -> Option<Self> {
+        let starting_ms = match control {
+            TimeControl::Unlimited => return None,
+            TimeControl::Fischer { initial_ms, .. } => initial_ms,
+            TimeControl::PerMove { move_time_ms } => move_time_ms,
+        };
+
+        Some(Self {
+            control,
+            remaining_x_ms: starting_ms,
+            remaining_o_ms: starting_ms,
+            turn_started_ms: 0,
+        })
+    }
+
+    fn remaining_mut(&mut self, mark: Mark) -> &mut u64 {
+        match mark {
+            Mark::O => &mut self.remaining_o_ms,
+            Mark::X | Mark::Empty => &mut self.remaining_x_ms,
+        }
+    }
+
+    pub fn remaining_ms(&self, mark: Mark, on_move: Mark, now_ms: u64) -> u64 {
+        let remaining = match mark {
+            Mark::O => self.remaining_o_ms,
+            Mark::X | Mark::Empty => self.remaining_x_ms,
+        };
+        if mark == on_move {
+            remaining.saturating_sub(now_ms.saturating_sub(self.turn_started_ms))
+        } else {
+            remaining
+        }
+    }
+
+    pub fn deadline_ms(&self, on_move: Mark) -> u64 {
+        self.turn_started_ms + self.remaining_ms(on_move, on_move, self.turn_started_ms)
+    }
+
+    pub fn is_flagged(&self, on_move: Mark, now_ms: u64) -> bool {
+        now_ms >= self.deadline_ms(on_move)
+    }
+
+    pub fn flag(&mut self, mark: Mark) {
+        *self.remaining_mut(mark) = 0;
+    }
+
+    /// Hands each side's remaining time to the other, for when players trade marks.
+    pub fn swap_sides(&mut self) {
+        std::mem::swap(&mut self.remaining_x_ms, &mut self.remaining_o_ms);
+    }
+
+    pub fn complete_move(&mut self, mover: Mark, now_ms: u64) {
+        let spent = now_ms.saturating_sub(self.turn_started_ms);
+        let control = self.control;
+        let remaining = self.remaining_mut(mover);
+
+        *remaining = match control {
+            TimeControl::Fischer { increment_ms, .. } => remaining.saturating_sub(spent) + increment_ms,
+            TimeControl::PerMove { move_time_ms } => move_time_ms,
+            TimeControl::Unlimited => *remaining,
+        };
+        self.turn_started_ms = now_ms;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_complete_move_fischer_increment_added_after_spent_time() {
+        let mut clock = GameClock::new(TimeControl::Fischer { initial_ms: 10_000, increment_ms: 2_000 }).unwrap();
+
+        clock.complete_move(Mark::X, 3_000);
+
+        assert_eq!(clock.remaining_ms(Mark::X, Mark::O, 3_000), 9_000);
+        assert_eq!(clock.remaining_ms(Mark::O, Mark::O, 4_000), 9_000);
+        assert_eq!(clock.deadline_ms(Mark::O), 13_000);
+        assert!(!clock.is_flagged(Mark::O, 12_999));
+        assert!(clock.is_flagged(Mark::O, 13_000));
+    }
+
+    #[test]
+    fn test_complete_move_per_move_time_reset_each_move() {
+        let mut clock = GameClock::new(TimeControl::PerMove { move_time_ms: 5_000 }).unwrap();
+
+        clock.complete_move(Mark::X, 4_000);
+        clock.complete_move(Mark::O, 8_500);
+
+        assert_eq!(clock.remaining_ms(Mark::X, Mark::X, 8_500), 5_000);
+        assert_eq!(clock.deadline_ms(Mark::X), 13_500);
+    }
+}
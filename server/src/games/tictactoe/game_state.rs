@@ -1,9 +1,19 @@
+use std::collections::HashMap;
+
 use crate::PlayerId;
-use crate::games::SessionRng;
-use super::types::{FirstPlayerMode, GameStatus, Mark, Position};
+use crate::games::{SessionRng, StateHasher};
+use crate::proto::tictactoe::Swap2Choice;
+use super::clock::{GameClock, TimeControl};
+use super::rules::Rules;
+use super::types::{FirstPlayerMode, GameStatus, Mark, OpeningRule, Position, Swap2Phase, WinRule};
 use super::win_detector::check_win;
 
-#[derive(Debug)]
+/// Stones the Swap2 opener places, in order, before the first choice.
+const SWAP2_OPENING_MARKS: [Mark; 3] = [Mark::X, Mark::O, Mark::X];
+/// Stones the other player places when leaving the choice to the opener.
+const SWAP2_EXTRA_MARKS: [Mark; 2] = [Mark::O, Mark::X];
+
+#[derive(Clone, Debug)]
 pub struct TicTacToeGameState {
     pub board: Vec<Vec<Mark>>,
     pub width: usize,
@@ -15,6 +25,14 @@ pub struct TicTacToeGameState {
     pub current_mark: Mark,
     pub status: GameStatus,
     pub last_move: Option<Position>,
+    pub clock: Option<GameClock>,
+    pub timed_out: bool,
+    pub win_rule: WinRule,
+    pub opening: OpeningRule,
+    /// During a Swap2 opening `player_x` is the opener and `current_mark` the
+    /// next stone to place, whoever places it.
+    pub swap2_phase: Swap2Phase,
+    stones_placed: usize,
 }
 
 impl TicTacToeGameState {
@@ -55,7 +73,97 @@ impl TicTacToeGameState {
             current_mark: Mark::X,
             status: GameStatus::InProgress,
             last_move: None,
+            clock: None,
+            timed_out: false,
+            win_rule: WinRule::Freestyle,
+            opening: OpeningRule::Standard,
+            swap2_phase: Swap2Phase::None,
+            stones_placed: 0,
+        }
+    }
+
+    pub fn set_time_control(&mut self, control: TimeControl) {
+        self.clock = GameClock::new(control);
+    }
+
+    pub fn set_rules(&mut self, win_rule: WinRule, opening: OpeningRule) {
+        self.win_rule = win_rule;
+        self.opening = opening;
+        self.swap2_phase = match opening {
+            OpeningRule::Swap2 => Swap2Phase::Opening,
+            OpeningRule::Standard => Swap2Phase::None,
+        };
+    }
+
+    pub fn rules(&self) -> Rules {
+        Rules::new(self.win_count, self.win_rule)
+    }
+
+    /// The side whose clock runs: the mover's own, which differs from the
+    /// stone being placed while a Swap2 opening is under way.
+    pub fn seat_on_move(&self) -> Mark {
+        if self.current_player == self.player_x {
+            Mark::X
+        } else {
+            Mark::O
+        }
+    }
+
+    pub fn place_mark_at(&mut self, player_id: &PlayerId, x: usize, y: usize, now_ms: u64) -> Result<(), String> {
+        if self.check_timeout(now_ms) {
+            return Err("Time is up".to_string());
+        }
+
+        let mover = self.seat_on_move();
+        self.place_mark(player_id, x, y)?;
+
+        if let Some(clock) = &mut self.clock {
+            clock.complete_move(mover, now_ms);
+        }
+        Ok(())
+    }
+
+    pub fn choose_swap2_at(&mut self, player_id: &PlayerId, choice: Swap2Choice, now_ms: u64) -> Result<(), String> {
+        if self.check_timeout(now_ms) {
+            return Err("Time is up".to_string());
         }
+
+        let mover = self.seat_on_move();
+        let opener = self.player_x.clone();
+        self.choose_swap2(player_id, choice)?;
+
+        if let Some(clock) = &mut self.clock {
+            clock.complete_move(mover, now_ms);
+            if self.player_x != opener {
+                clock.swap_sides();
+            }
+        }
+        Ok(())
+    }
+
+    pub fn check_timeout(&mut self, now_ms: u64) -> bool {
+        let seat = self.seat_on_move();
+        let Some(clock) = &mut self.clock else {
+            return false;
+        };
+        if self.status != GameStatus::InProgress || !clock.is_flagged(seat, now_ms) {
+            return false;
+        }
+
+        clock.flag(seat);
+        self.status = match seat {
+            Mark::X => GameStatus::OWon,
+            Mark::O | Mark::Empty => GameStatus::XWon,
+        };
+        self.timed_out = true;
+        true
+    }
+
+    pub fn deadline_ms(&self) -> Option<u64> {
+        if self.status != GameStatus::InProgress {
+            return None;
+        }
+        self.clock.as_ref().map(|clock| clock.deadline_ms(self.seat_on_move()))
     }
 
     pub fn place_mark(&mut self, player_id: &PlayerId, x: usize, y: usize) -> Result<(), String> {
@@ -67,6 +175,10 @@ impl TicTacToeGameState {
             return Err("Not your turn".to_string());
         }
 
+        if self.swap2_phase.awaits_choice() {
+            return Err("Waiting for the Swap2 choice".to_string());
+        }
+
         if x >= self.width || y >= self.height {
             return Err("Position out of bounds".to_string());
         }
@@ -75,18 +187,106 @@ impl TicTacToeGameState {
             return Err("Cell is already marked".to_string());
         }
 
+        if self.swap2_phase == Swap2Phase::None
+            && let Some(forbidden) = self.rules().forbidden_move(&mut self.board, x, y, self.current_mark)
+        {
+            return Err(format!("Forbidden move for X: {}", forbidden));
+        }
+
         self.board[y][x] = self.current_mark;
         self.last_move = Some(Position::new(x, y));
+        self.stones_placed += 1;
 
         self.check_game_over();
 
         if self.status == GameStatus::InProgress {
-            self.switch_turn();
+            match self.swap2_phase {
+                Swap2Phase::None => self.switch_turn(),
+                _ => self.advance_swap2_opening(),
+            }
         }
 
         Ok(())
     }
 
+    pub fn choose_swap2(&mut self, player_id: &PlayerId, choice: Swap2Choice) -> Result<(), String> {
+        if self.status != GameStatus::InProgress {
+            return Err("Game is already over".to_string());
+        }
+
+        if player_id != &self.current_player {
+            return Err("Not your turn".to_string());
+        }
+
+        let chooser_is_x = match (self.swap2_phase, choice) {
+            (Swap2Phase::Choice, Swap2Choice::PlaceTwo) => {
+                self.swap2_phase = Swap2Phase::ExtraStones;
+                self.current_mark = SWAP2_EXTRA_MARKS[0];
+                return Ok(());
+            }
+            (Swap2Phase::Choice | Swap2Phase::FinalChoice, Swap2Choice::PlayX) => true,
+            (Swap2Phase::Choice | Swap2Phase::FinalChoice, Swap2Choice::PlayO) => false,
+            (Swap2Phase::Choice | Swap2Phase::FinalChoice, _) => return Err("Invalid Swap2 choice".to_string()),
+            _ => return Err("No Swap2 choice is pending".to_string()),
+        };
+
+        if chooser_is_x != (self.current_player == self.player_x) {
+            std::mem::swap(&mut self.player_x, &mut self.player_o);
+        }
+        self.swap2_phase = Swap2Phase::None;
+        self.current_mark = Mark::O;
+        self.current_player = self.player_o.clone();
+        Ok(())
+    }
+
+    /// The opener places X, O, X, then the other player chooses; after
+    /// placing the extra O and X the choice passes back to the opener.
+    fn advance_swap2_opening(&mut self) {
+        let opening_stones = SWAP2_OPENING_MARKS.len();
+        match self.swap2_phase {
+            Swap2Phase::Opening if self.stones_placed < opening_stones => {
+                self.current_mark = SWAP2_OPENING_MARKS[self.stones_placed];
+            }
+            Swap2Phase::Opening => {
+                self.swap2_phase = Swap2Phase::Choice;
+                self.current_mark = Mark::O;
+                self.current_player = self.player_o.clone();
+            }
+            Swap2Phase::ExtraStones if self.stones_placed < opening_stones + SWAP2_EXTRA_MARKS.len() => {
+                self.current_mark = SWAP2_EXTRA_MARKS[self.stones_placed - opening_stones];
+            }
+            Swap2Phase::ExtraStones => {
+                self.swap2_phase = Swap2Phase::FinalChoice;
+                self.current_mark = Mark::O;
+                self.current_player = self.player_x.clone();
+            }
+            Swap2Phase::None | Swap2Phase::Choice | Swap2Phase::FinalChoice => {}
+        }
+    }
+
+    /// Empty cells X may not take on this turn under Renju.
+    pub fn forbidden_moves(&self) -> Vec<Position> {
+        if self.status != GameStatus::InProgress
+            || self.swap2_phase != Swap2Phase::None
+            || self.current_mark != Mark::X
+            || self.win_rule != WinRule::Renju
+        {
+            return Vec::new();
+        }
+
+        let rules = self.rules();
+        let mut board = self.board.clone();
+        let mut forbidden = Vec::new();
+        for y in 0..self.height {
+            for x in 0..self.width {
+                if rules.forbidden_move(&mut board, x, y, Mark::X).is_some() {
+                    forbidden.push(Position::new(x, y));
+                }
+            }
+        }
+        forbidden
+    }
+
     fn switch_turn(&mut self) {
         if self.current_mark == Mark::X {
             self.current_mark = Mark::O;
@@ -98,7 +298,7 @@ impl TicTacToeGameState {
     }
 
     fn check_game_over(&mut self) {
-        if let Some(winner_mark) = check_win(&self.board, self.win_count) {
+        if let Some(winner_mark) = check_win(&self.board, &self.rules()) {
             self.status = match winner_mark {
                 Mark::X => GameStatus::XWon,
                 Mark::O => GameStatus::OWon,
@@ -118,6 +318,15 @@ impl TicTacToeGameState {
             .all(|row| row.iter().all(|&cell| cell != Mark::Empty))
     }
 
+    /// Hands both sides to the players `renames` maps them to.
+    pub fn rename_players(&mut self, renames: &HashMap<PlayerId, PlayerId>) {
+        for id in [&mut self.player_x, &mut self.player_o, &mut self.current_player] {
+            if let Some(new_id) = renames.get(id) {
+                *id = new_id.clone();
+            }
+        }
+    }
+
     pub fn get_winner(&self) -> Option<PlayerId> {
         match self.status {
             GameStatus::XWon => Some(self.player_x.clone()),
@@ -146,6 +355,7 @@ impl TicTacToeGameState {
         player_x_is_bot: bool,
         player_o_is_bot: bool,
         current_player_is_bot: bool,
+        now_ms: u64,
     ) -> crate::proto::tictactoe::TicTacToeGameState {
         let board: Vec<crate::proto::tictactoe::CellMark> = self
             .board
@@ -185,6 +395,124 @@ impl TicTacToeGameState {
                 is_bot: current_player_is_bot,
             }),
             last_move: self.last_move.map(|pos| pos.to_proto()),
+            player_x_time_ms: self.remaining_time_ms(Mark::X, now_ms),
+            player_o_time_ms: self.remaining_time_ms(Mark::O, now_ms),
+            win_rule: self.win_rule.to_proto(),
+            swap2_phase: self.swap2_phase.to_proto(),
+            forbidden_moves: self.forbidden_moves().into_iter().map(|pos| pos.to_proto()).collect(),
+        }
+    }
+
+    fn remaining_time_ms(&self, mark: Mark, now_ms: u64) -> Option<u32> {
+        let on_move = if self.status == GameStatus::InProgress {
+            self.seat_on_move()
+        } else {
+            Mark::Empty
+        };
+        self.clock
+            .as_ref()
+            .map(|clock| clock.remaining_ms(mark, on_move, now_ms).min(u32::MAX as u64) as u32)
+    }
+
+    /// Clocks are left out: replays restore them from the recorded action times.
+    /// Swap2 state is only mixed in for Swap2 games, so older replays keep
+    /// their recorded hashes.
+    pub fn state_hash(&self) -> u64 {
+        let mut hasher = StateHasher::new();
+        for row in &self.board {
+            for mark in row {
+                hasher.write_u64(*mark as u64);
+            }
+        }
+        hasher.write_u64(self.current_mark as u64);
+        hasher.write_u64(self.status as u64);
+        hasher.write_bool(self.timed_out);
+        if self.opening == OpeningRule::Swap2 {
+            hasher.write_u64(self.swap2_phase as u64);
+            hasher.write_str(&self.player_x.to_string());
+        }
+        hasher.finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn new_game(win_rule: WinRule, opening: OpeningRule) -> (TicTacToeGameState, PlayerId, PlayerId) {
+        let (alice, bob) = (PlayerId::new("alice".to_string()), PlayerId::new("bob".to_string()));
+        let mut game_state =
+            TicTacToeGameState::new(15, 15, 5, vec![alice.clone(), bob.clone()], FirstPlayerMode::Host, &mut SessionRng::new(1));
+        game_state.set_rules(win_rule, opening);
+        (game_state, alice, bob)
+    }
+
+    fn play(game_state: &mut TicTacToeGameState, moves: &[(usize, usize)]) {
+        for &(x, y) in moves {
+            let player = game_state.current_player.clone();
+            game_state.place_mark(&player, x, y).unwrap();
         }
     }
+
+    #[test]
+    fn test_swap2_place_two_hands_the_choice_back_to_the_opener() {
+        let (mut game_state, alice, bob) = new_game(WinRule::Freestyle, OpeningRule::Swap2);
+
+        play(&mut game_state, &[(7, 7), (8, 8), (8, 7)]);
+        assert_eq!(game_state.swap2_phase, Swap2Phase::Choice);
+        assert_eq!(game_state.current_player, bob);
+        assert!(game_state.place_mark(&bob, 0, 0).is_err());
+
+        game_state.choose_swap2(&bob, Swap2Choice::PlaceTwo).unwrap();
+        play(&mut game_state, &[(6, 6), (9, 7)]);
+        assert_eq!(game_state.swap2_phase, Swap2Phase::FinalChoice);
+        assert_eq!(game_state.current_player, alice);
+        assert!(game_state.choose_swap2(&alice, Swap2Choice::PlaceTwo).is_err());
+
+        game_state.choose_swap2(&alice, Swap2Choice::PlayO).unwrap();
+        assert_eq!((game_state.player_x.clone(), game_state.player_o.clone()), (bob, alice.clone()));
+        assert_eq!((game_state.current_player.clone(), game_state.current_mark), (alice, Mark::O));
+        assert_eq!(game_state.board[7][9], Mark::X);
+        assert_eq!(game_state.board[6][6], Mark::O);
+    }
+
+    #[test]
+    fn test_swap2_play_x_swaps_sides_and_clocks() {
+        let (mut game_state, alice, bob) = new_game(WinRule::Freestyle, OpeningRule::Swap2);
+        game_state.set_time_control(TimeControl::Fischer { initial_ms: 60_000, increment_ms: 0 });
+
+        for (i, (x, y)) in [(7, 7), (8, 8), (8, 7)].into_iter().enumerate() {
+            game_state.place_mark_at(&alice, x, y, (i as u64 + 1) * 10_000).unwrap();
+        }
+        game_state.choose_swap2_at(&bob, Swap2Choice::PlayX, 31_000).unwrap();
+
+        assert_eq!(game_state.player_x, bob);
+        assert_eq!(game_state.current_player, alice);
+        let clock = game_state.clock.as_ref().unwrap();
+        assert_eq!(clock.remaining_ms(Mark::X, Mark::O, 31_000), 59_000);
+        assert_eq!(clock.remaining_ms(Mark::O, Mark::O, 31_000), 30_000);
+    }
+
+    #[test]
+    fn test_exact_rule_overline_does_not_win() {
+        let (mut game_state, _, _) = new_game(WinRule::Exact, OpeningRule::Standard);
+
+        play(&mut game_state, &[(0, 0), (0, 1), (1, 0), (1, 1), (2, 0), (2, 1), (4, 0), (3, 1), (5, 0), (9, 9), (3, 0)]);
+        assert_eq!(game_state.status, GameStatus::InProgress);
+
+        play(&mut game_state, &[(4, 1)]);
+        assert_eq!(game_state.status, GameStatus::OWon);
+    }
+
+    #[test]
+    fn test_renju_rejects_forbidden_x_move() {
+        let (mut game_state, alice, _) = new_game(WinRule::Renju, OpeningRule::Standard);
+
+        play(&mut game_state, &[(7, 5), (0, 0), (7, 6), (0, 2), (5, 7), (0, 4), (6, 7), (0, 6)]);
+        assert_eq!(game_state.forbidden_moves(), vec![Position::new(7, 7)]);
+        assert_eq!(
+            game_state.place_mark(&alice, 7, 7),
+            Err("Forbidden move for X: double three".to_string())
+        );
+    }
 }
@@ -1,7 +1,10 @@
 mod board;
 mod bot_controller;
+mod bot_strength;
+mod clock;
 mod game_state;
 pub(crate) mod replay;
+mod rules;
 mod session;
 mod settings;
 mod types;
@@ -9,9 +12,13 @@ mod validate;
 mod win_detector;
 
 pub use board::get_available_moves;
-pub use bot_controller::{BotInput, calculate_minimax_move, calculate_move};
+pub use bot_controller::{BotInput, calculate_minimax_move, calculate_move, calculate_random_move, calculate_swap2_choice};
+pub use bot_strength::BotStrength;
+pub use clock::{GameClock, TimeControl};
 pub use game_state::TicTacToeGameState;
 pub use session::{TicTacToeSession, TicTacToeSessionState};
+pub use rules::{Forbidden, Rules};
+pub use session::{create_place_command, create_swap2_choice_command};
 pub use settings::TicTacToeSessionSettings;
-pub use types::{FirstPlayerMode, GameStatus, Mark, Position, WinningLine};
+pub use types::{FirstPlayerMode, GameStatus, Mark, OpeningRule, Position, Swap2Phase, WinRule, WinningLine};
 pub use win_detector::{check_win, check_win_with_line};
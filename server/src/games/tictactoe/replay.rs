@@ -1,34 +1,65 @@
 use std::collections::HashMap;
+use std::sync::Arc;
 use std::time::Duration;
 
 use tokio::sync::mpsc;
 
-use crate::games::SessionRng;
-use crate::games::tictactoe::{FirstPlayerMode, GameStatus, TicTacToeGameState};
+use crate::games::{GameSession, GameSessionConfig, SessionRng};
+use crate::games::tictactoe::{GameStatus, TicTacToeGameState, TicTacToeSessionSettings, TicTacToeSessionState};
+use crate::proto::tictactoe::tic_tac_toe_in_game_command;
 use crate::replay::ReplayPlayer;
+use crate::replay::branch::advance_to;
 use crate::replay::session::{
     ReplayCommandResult, ReplaySessionCommand, broadcast_state_and_replay_info,
-    handle_replay_command, wait_for_restart_or_stop,
+    handle_replay_command,
 };
+use crate::replay::timeline::{ReplaySimulation, ReplayTimeline};
+use crate::replay::verify::{ReplaySummary, play_to_end};
 use crate::{
-    ClientId, GameStateUpdate, PlayerAction, PlayerId, game_state_update, in_game_command,
+    ClientId, GameStateUpdate, PlayerAction, PlayerId, ReplayGame, game_state_update, in_game_command,
     lobby_settings, player_action_content, log,
 };
-pub(crate) async fn run_replay(
-    mut player: ReplayPlayer,
-    command_rx: &mut mpsc::UnboundedReceiver<ReplaySessionCommand>,
-    viewers: &[ClientId],
-    host_only_control: bool,
-    broadcaster: &crate::broadcaster::Broadcaster,
-) -> bool {
+
+#[derive(Clone)]
+struct TicTacToeReplay {
+    game_state: TicTacToeGameState,
+    rng: SessionRng,
+    clock_ms: u64,
+    moves: u64,
+    idle_turn: Duration,
+    player_map: Arc<HashMap<i32, PlayerId>>,
+}
+
+impl ReplaySimulation for TicTacToeReplay {
+    fn position(&self) -> u64 {
+        self.moves
+    }
+
+    fn is_finished(&self, player: &ReplayPlayer) -> bool {
+        player.is_finished() || self.game_state.status != GameStatus::InProgress
+    }
+
+    fn step(&mut self, player: &mut ReplayPlayer) {
+        if let Some(action) = player.next_action() {
+            self.clock_ms = apply_tictactoe_action(&mut self.game_state, action, &self.player_map);
+            self.moves += 1;
+        }
+    }
+
+    fn state_hash(&self) -> u64 {
+        self.game_state.state_hash()
+    }
+}
+
+fn new_replay(player: &ReplayPlayer) -> Result<TicTacToeReplay, String> {
     let settings = match player.lobby_settings() {
         Some(lobby_settings::Settings::Tictactoe(s)) => *s,
-        _ => return false,
+        _ => return Err("Replay has no TicTacToe settings".to_string()),
     };
 
     let players = player.players();
     if players.len() != 2 {
-        return false;
+        return Err(format!("TicTacToe replay has {} players", players.len()));
     }
 
     let player_ids: Vec<PlayerId> = players.iter().map(|p| PlayerId::new(p.player_id.clone())).collect();
@@ -38,81 +69,138 @@ pub(crate) async fn run_replay(
         .map(|(i, p)| (i as i32, PlayerId::new(p.player_id.clone())))
         .collect();
 
-    let mut rng = SessionRng::new(player.seed());
+    let session_settings = TicTacToeSessionSettings::from(&settings);
+    let mut rng = player.rng();
     let mut game_state = TicTacToeGameState::new(
-        settings.field_width as usize,
-        settings.field_height as usize,
-        settings.win_count as usize,
+        session_settings.field_width,
+        session_settings.field_height,
+        session_settings.win_count,
         player_ids,
-        FirstPlayerMode::Random,
+        session_settings.first_player_mode,
         &mut rng,
     );
+    game_state.set_time_control(session_settings.time_control);
+    game_state.set_rules(session_settings.win_rule, session_settings.opening);
+
+    Ok(TicTacToeReplay {
+        game_state,
+        rng,
+        clock_ms: 0,
+        moves: 0,
+        idle_turn: session_settings.idle_turn,
+        player_map: Arc::new(player_map),
+    })
+}
+
+pub(crate) fn branch(
+    mut player: ReplayPlayer,
+    position: u64,
+    renames: &HashMap<PlayerId, PlayerId>,
+    config: &GameSessionConfig,
+) -> Result<GameSession, String> {
+    let mut replay = new_replay(&player)?;
+    advance_to(&mut replay, &mut player, position);
+    if replay.game_state.status != GameStatus::InProgress {
+        return Err(format!("The game is already over at move {}", replay.moves));
+    }
+    replay.game_state.rename_players(renames);
+
+    Ok(GameSession::TicTacToe(TicTacToeSessionState::from_state(
+        config,
+        replay.game_state,
+        replay.rng,
+        replay.moves,
+        replay.clock_ms,
+        replay.idle_turn,
+        None,
+    )))
+}
+
+pub(crate) fn simulate(mut player: ReplayPlayer) -> Result<ReplaySummary, String> {
+    let mut replay = new_replay(&player)?;
+    let desync_position = play_to_end(&mut replay, &mut player);
+
+    let outcome = match (replay.game_state.status, replay.game_state.get_winner()) {
+        (_, Some(winner)) => format!("{} won", winner),
+        (GameStatus::Draw, None) => "Draw".to_string(),
+        _ => "Unfinished".to_string(),
+    };
 
+    Ok(ReplaySummary {
+        game: ReplayGame::Tictactoe,
+        ticks: replay.moves,
+        outcome,
+        scores: Vec::new(),
+        desync_position,
+    })
+}
+
+pub(crate) async fn run_replay(
+    mut player: ReplayPlayer,
+    command_rx: &mut mpsc::UnboundedReceiver<ReplaySessionCommand>,
+    viewers: &[ClientId],
+    host_only_control: bool,
+    broadcaster: &crate::broadcaster::Broadcaster,
+) -> bool {
+    let Ok(mut replay) = new_replay(&player) else {
+        return false;
+    };
+    let mut timeline = ReplayTimeline::new(&replay, &player);
     let total_actions = player.total_actions() as u64;
-    let mut current_action: u64 = 0;
     let mut is_paused = false;
+    let mut is_finished = false;
     let mut speed = 1.0_f32;
     let base_delay_ms = 500.0;
 
-    let state_update = build_tictactoe_state(&game_state, players);
-    broadcast_state_and_replay_info(broadcaster, viewers, state_update, is_paused, current_action, total_actions, speed, false, host_only_control).await;
+    let state_update = build_tictactoe_state(&replay.game_state, player.players(), replay.clock_ms);
+    broadcast_state_and_replay_info(broadcaster, viewers, state_update, is_paused, replay.moves, total_actions, speed, is_finished, host_only_control, timeline.desync_position()).await;
 
     loop {
         let current_delay = Duration::from_millis((base_delay_ms / speed) as u64);
 
         tokio::select! {
             _ = tokio::time::sleep(current_delay) => {
-                if is_paused {
+                if is_paused || is_finished {
                     continue;
                 }
 
-                if let Some(action) = player.next_action() {
-                    apply_tictactoe_action(&mut game_state, action, &player_map);
-                    current_action += 1;
-                }
-
-                let is_finished = player.is_finished() || game_state.status != GameStatus::InProgress;
-                let state_update = build_tictactoe_state(&game_state, player.players());
-                broadcast_state_and_replay_info(broadcaster, viewers, state_update, is_paused, current_action.min(total_actions), total_actions, speed, is_finished, host_only_control).await;
-
-                if is_finished {
-                    return wait_for_restart_or_stop(command_rx).await;
-                }
+                timeline.step(&mut replay, &mut player);
+                is_finished = replay.is_finished(&player);
             }
-            Some(cmd) = command_rx.recv() => {
-                match cmd {
-                    ReplaySessionCommand::ReplayCommand(replay_cmd) => {
-                        match handle_replay_command(&replay_cmd, &mut is_paused, &mut speed) {
-                            ReplayCommandResult::StateChanged => {
-                                let state_update = build_tictactoe_state(&game_state, player.players());
-                                broadcast_state_and_replay_info(broadcaster, viewers, state_update, is_paused, current_action, total_actions, speed, false, host_only_control).await;
-                            }
-                            ReplayCommandResult::SpeedChanged => {}
-                            ReplayCommandResult::StepForward => {
-                                if let Some(action) = player.next_action() {
-                                    apply_tictactoe_action(&mut game_state, action, &player_map);
-                                    current_action += 1;
-                                }
-                                let is_finished = player.is_finished() || game_state.status != GameStatus::InProgress;
-                                let state_update = build_tictactoe_state(&game_state, player.players());
-                                broadcast_state_and_replay_info(broadcaster, viewers, state_update, is_paused, current_action.min(total_actions), total_actions, speed, is_finished, host_only_control).await;
-
-                                if is_finished {
-                                    return wait_for_restart_or_stop(command_rx).await;
-                                }
-                            }
-                            ReplayCommandResult::Restart => return true,
-                            ReplayCommandResult::None => {}
+            cmd = command_rx.recv() => {
+                let Some(ReplaySessionCommand::ReplayCommand(replay_cmd)) = cmd else {
+                    return false;
+                };
+                match handle_replay_command(&replay_cmd, &mut is_paused, &mut speed) {
+                    ReplayCommandResult::StateChanged => {}
+                    ReplayCommandResult::SpeedChanged => continue,
+                    ReplayCommandResult::StepForward => {
+                        if is_finished {
+                            continue;
                         }
+                        timeline.step(&mut replay, &mut player);
                     }
+                    ReplayCommandResult::StepBackward => {
+                        timeline.step_back(&mut replay, &mut player);
+                    }
+                    ReplayCommandResult::Seek(target) => {
+                        timeline.seek(&mut replay, &mut player, target);
+                    }
+                    ReplayCommandResult::Restart => return true,
+                    ReplayCommandResult::None => continue,
                 }
+                is_finished = replay.is_finished(&player);
             }
         }
+
+        let state_update = build_tictactoe_state(&replay.game_state, player.players(), replay.clock_ms);
+        broadcast_state_and_replay_info(broadcaster, viewers, state_update, is_paused, replay.moves.min(total_actions), total_actions, speed, is_finished, host_only_control, timeline.desync_position()).await;
     }
 }
 fn build_tictactoe_state(
     game_state: &TicTacToeGameState,
     players: &[crate::PlayerIdentity],
+    clock_ms: u64,
 ) -> GameStateUpdate {
     let player_x_is_bot = players
         .iter()
@@ -130,7 +218,7 @@ fn build_tictactoe_state(
         .map(|p| p.is_bot)
         .unwrap_or(false);
 
-    let proto_state = game_state.to_proto_state(player_x_is_bot, player_o_is_bot, current_player_is_bot);
+    let proto_state = game_state.to_proto_state(player_x_is_bot, player_o_is_bot, current_player_is_bot, clock_ms);
     GameStateUpdate {
         state: Some(game_state_update::State::Tictactoe(proto_state)),
     }
@@ -139,26 +227,45 @@ fn apply_tictactoe_action(
     game_state: &mut TicTacToeGameState,
     action: &PlayerAction,
     player_map: &HashMap<i32, PlayerId>,
-) {
-    let Some(player_id) = player_map.get(&action.player_index) else {
-        return;
-    };
+) -> u64 {
+    let now_ms = action.elapsed_ms.max(0) as u64;
 
-    let Some(content) = &action.content else {
-        return;
+    let Some(player_id) = player_map.get(&action.player_index) else {
+        return now_ms;
     };
 
-    let Some(inner) = &content.content else {
-        return;
+    let Some(inner) = action.content.as_ref().and_then(|c| c.content.as_ref()) else {
+        return now_ms;
     };
 
-    if let player_action_content::Content::Command(cmd) = inner
-        && let Some(in_game_command::Command::Tictactoe(ttt_cmd)) = &cmd.command
-        && let Some(crate::proto::tictactoe::tic_tac_toe_in_game_command::Command::Place(place)) = &ttt_cmd.command
-        && let Err(e) = game_state.place_mark(player_id, place.x as usize, place.y as usize)
-    {
-        log!("[replay] Failed to place mark for {} at ({}, {}): {}", player_id, place.x, place.y, e);
+    match inner {
+        player_action_content::Content::Command(cmd) => {
+            let Some(in_game_command::Command::Tictactoe(ttt_cmd)) = &cmd.command else {
+                return now_ms;
+            };
+            match &ttt_cmd.command {
+                Some(tic_tac_toe_in_game_command::Command::Place(place)) => {
+                    if let Err(e) = game_state.place_mark_at(player_id, place.x as usize, place.y as usize, now_ms) {
+                        log!("[replay] Failed to place mark for {} at ({}, {}): {}", player_id, place.x, place.y, e);
+                    }
+                }
+                Some(tic_tac_toe_in_game_command::Command::Swap2Choice(choice)) => {
+                    if let Err(e) = game_state.choose_swap2_at(player_id, choice.choice(), now_ms) {
+                        log!("[replay] Failed to apply Swap2 choice for {}: {}", player_id, e);
+                    }
+                }
+                None => {}
+            }
+        }
+        player_action_content::Content::TimedOut(_) => {
+            if !game_state.check_timeout(now_ms) {
+                log!("[replay] Recorded timeout for {} does not match the clock", player_id);
+            }
+        }
+        player_action_content::Content::Disconnected(_) | player_action_content::Content::ControlChanged(_) => {}
     }
+
+    now_ms
 }
 
 
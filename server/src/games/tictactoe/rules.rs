@@ -0,0 +1,246 @@
+use std::fmt;
+
+use super::types::{Mark, WinRule};
+
+const DIRECTIONS: [(isize, isize); 4] = [(1, 0), (0, 1), (1, 1), (1, -1)];
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Forbidden {
+    DoubleThree,
+    DoubleFour,
+    Overline,
+}
+
+impl fmt::Display for Forbidden {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Forbidden::DoubleThree => write!(f, "double three"),
+            Forbidden::DoubleFour => write!(f, "double four"),
+            Forbidden::Overline => write!(f, "overline"),
+        }
+    }
+}
+
+/// Which runs win and, under Renju, which X moves are off limits.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Rules {
+    pub win_count: usize,
+    pub win_rule: WinRule,
+}
+
+impl Rules {
+    pub fn new(win_count: usize, win_rule: WinRule) -> Self {
+        Self { win_count, win_rule }
+    }
+
+    pub fn wins(&self, mark: Mark, run: usize) -> bool {
+        match self.win_rule {
+            WinRule::Freestyle => run >= self.win_count,
+            WinRule::Exact => run == self.win_count,
+            WinRule::Renju if mark == Mark::X => run == self.win_count,
+            WinRule::Renju => run >= self.win_count,
+        }
+    }
+
+    /// Whether the mark at (x, y) is part of a winning run.
+    pub fn is_win_at(&self, board: &[Vec<Mark>], x: usize, y: usize) -> bool {
+        let mark = board[y][x];
+        mark != Mark::Empty
+            && DIRECTIONS
+                .iter()
+                .any(|&(dx, dy)| self.wins(mark, run_length(board, x, y, dx, dy, mark)))
+    }
+
+    /// Why `mark` may not take the empty cell (x, y), if it may not. Only X
+    /// under Renju has forbidden moves, and a move that makes exactly five is
+    /// always allowed. A three counts when one more stone turns it into a
+    /// straight four; whether that stone would itself be forbidden is not
+    /// looked into.
+    pub fn forbidden_move(&self, board: &mut [Vec<Mark>], x: usize, y: usize, mark: Mark) -> Option<Forbidden> {
+        if self.win_rule != WinRule::Renju || mark != Mark::X || board[y][x] != Mark::Empty {
+            return None;
+        }
+        if !self.may_be_forbidden(board, x, y) {
+            return None;
+        }
+
+        board[y][x] = Mark::X;
+        let forbidden = self.renju_violation(board, x, y);
+        board[y][x] = Mark::Empty;
+        forbidden
+    }
+
+    /// Cheap pre-check: every forbidden pattern needs either two lines with
+    /// at least two more X stones near (x, y) or one line with at least three.
+    fn may_be_forbidden(&self, board: &[Vec<Mark>], x: usize, y: usize) -> bool {
+        let reach = self.win_count as isize;
+        let counts = DIRECTIONS.map(|(dx, dy)| {
+            (-reach..=reach)
+                .filter(|&i| i != 0 && cell(board, x, y, dx * i, dy * i) == Some(Mark::X))
+                .count()
+        });
+        counts.iter().filter(|&&count| count >= 2).count() >= 2 || counts.iter().any(|&count| count >= 3)
+    }
+
+    fn renju_violation(&self, board: &mut [Vec<Mark>], x: usize, y: usize) -> Option<Forbidden> {
+        let runs = DIRECTIONS.map(|(dx, dy)| run_length(board, x, y, dx, dy, Mark::X));
+        if runs.contains(&self.win_count) {
+            return None;
+        }
+        if runs.iter().any(|&run| run > self.win_count) {
+            return Some(Forbidden::Overline);
+        }
+
+        let mut fours = 0;
+        let mut threes = 0;
+        for (dx, dy) in DIRECTIONS {
+            let line_fours = self.fours_in_line(board, x, y, dx, dy);
+            if line_fours > 0 {
+                fours += line_fours;
+            } else if self.has_open_three(board, x, y, dx, dy) {
+                threes += 1;
+            }
+        }
+
+        if fours >= 2 {
+            Some(Forbidden::DoubleFour)
+        } else if threes >= 2 {
+            Some(Forbidden::DoubleThree)
+        } else {
+            None
+        }
+    }
+
+    /// Fours through (x, y) along one line: the empty cells that would
+    /// complete exactly five with it, where both ends of a straight four
+    /// count as one four.
+    fn fours_in_line(&self, board: &mut [Vec<Mark>], x: usize, y: usize, dx: isize, dy: isize) -> usize {
+        let reach = self.win_count as isize - 1;
+        let completions: Vec<isize> = (-reach..=reach)
+            .filter(|&i| i != 0 && Self::completes_run(board, (x, y), (dx, dy), i, self.win_count))
+            .collect();
+
+        match completions.as_slice() {
+            [a, b] if (b - a) as usize == self.win_count => 1,
+            _ => completions.len(),
+        }
+    }
+
+    /// Whether one more X on this line would make a straight four through
+    /// (x, y): a run one short of five that can be completed to exactly five
+    /// at either end.
+    fn has_open_three(&self, board: &mut [Vec<Mark>], x: usize, y: usize, dx: isize, dy: isize) -> bool {
+        let reach = self.win_count as isize - 1;
+        (-reach..=reach).any(|i| {
+            if i == 0 || !Self::completes_run(board, (x, y), (dx, dy), i, self.win_count - 1) {
+                return false;
+            }
+            let (cx, cy) = offset(x, y, dx * i, dy * i);
+            board[cy][cx] = Mark::X;
+            let (back, forward) = run_extent(board, cx, cy, dx, dy, Mark::X);
+            let open = [(-(back + 1), -(back + 2)), (forward + 1, forward + 2)].iter().all(|&(end, beyond)| {
+                cell(board, cx, cy, dx * end, dy * end) == Some(Mark::Empty)
+                    && cell(board, cx, cy, dx * beyond, dy * beyond) != Some(Mark::X)
+            });
+            board[cy][cx] = Mark::Empty;
+            open
+        })
+    }
+
+    /// Whether an X on the empty cell `i` steps from (x, y) makes a run of
+    /// exactly `length` that still covers (x, y).
+    fn completes_run(board: &mut [Vec<Mark>], (x, y): (usize, usize), (dx, dy): (isize, isize), i: isize, length: usize) -> bool {
+        if cell(board, x, y, dx * i, dy * i) != Some(Mark::Empty) {
+            return false;
+        }
+        let (cx, cy) = offset(x, y, dx * i, dy * i);
+        board[cy][cx] = Mark::X;
+        let (back, forward) = run_extent(board, cx, cy, dx, dy, Mark::X);
+        board[cy][cx] = Mark::Empty;
+        (back + forward + 1) as usize == length && i - back <= 0 && 0 <= i + forward
+    }
+}
+
+/// Length of the run of `mark` along (dx, dy) through (x, y), counting
+/// (x, y) itself.
+pub fn run_length(board: &[Vec<Mark>], x: usize, y: usize, dx: isize, dy: isize, mark: Mark) -> usize {
+    let (back, forward) = run_extent(board, x, y, dx, dy, mark);
+    (back + forward + 1) as usize
+}
+
+fn run_extent(board: &[Vec<Mark>], x: usize, y: usize, dx: isize, dy: isize, mark: Mark) -> (isize, isize) {
+    let count = |dx: isize, dy: isize| {
+        let mut steps = 0;
+        while cell(board, x, y, dx * (steps + 1), dy * (steps + 1)) == Some(mark) {
+            steps += 1;
+        }
+        steps
+    };
+    (count(-dx, -dy), count(dx, dy))
+}
+
+fn cell(board: &[Vec<Mark>], x: usize, y: usize, dx: isize, dy: isize) -> Option<Mark> {
+    let nx = x.checked_add_signed(dx)?;
+    let ny = y.checked_add_signed(dy)?;
+    board.get(ny).and_then(|row| row.get(nx)).copied()
+}
+
+fn offset(x: usize, y: usize, dx: isize, dy: isize) -> (usize, usize) {
+    (x.wrapping_add_signed(dx), y.wrapping_add_signed(dy))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn board(rows: &[&str]) -> Vec<Vec<Mark>> {
+        rows.iter()
+            .map(|row| {
+                row.chars()
+                    .map(|c| match c {
+                        'X' => Mark::X,
+                        'O' => Mark::O,
+                        _ => Mark::Empty,
+                    })
+                    .collect()
+            })
+            .collect()
+    }
+
+    const RENJU: Rules = Rules { win_count: 5, win_rule: WinRule::Renju };
+
+    #[test]
+    fn test_overline_wins_only_in_freestyle() {
+        let board = board(&["XXXXXX.", ".......", "OOOOOO."]);
+
+        assert!(Rules::new(5, WinRule::Freestyle).is_win_at(&board, 0, 0));
+        assert!(!Rules::new(5, WinRule::Exact).is_win_at(&board, 0, 0));
+        assert!(!RENJU.is_win_at(&board, 0, 0));
+        assert!(RENJU.is_win_at(&board, 0, 2));
+    }
+
+    #[test]
+    fn test_renju_forbids_double_three_double_four_and_overline_for_x() {
+        let mut double_three = board(&[".......", "...X...", "...X...", ".XX....", ".......", ".......", "......."]);
+        assert_eq!(RENJU.forbidden_move(&mut double_three, 3, 3, Mark::X), Some(Forbidden::DoubleThree));
+        assert_eq!(RENJU.forbidden_move(&mut double_three, 3, 3, Mark::O), None);
+
+        let mut same_line_fours = board(&["X.X.X.X"]);
+        assert_eq!(RENJU.forbidden_move(&mut same_line_fours, 3, 0, Mark::X), Some(Forbidden::DoubleFour));
+
+        let mut crossing_fours = board(&[".......", "...X...", "...X...", "...X...", "XXX....", "......."]);
+        assert_eq!(RENJU.forbidden_move(&mut crossing_fours, 3, 4, Mark::X), Some(Forbidden::DoubleFour));
+
+        let mut overline = board(&["XXX.XX."]);
+        assert_eq!(RENJU.forbidden_move(&mut overline, 3, 0, Mark::X), Some(Forbidden::Overline));
+    }
+
+    #[test]
+    fn test_renju_allows_five_and_a_four_with_a_blocked_three() {
+        let mut five_with_three = board(&[".......", "...X...", "...X...", "XXX.X..", "......."]);
+        assert_eq!(RENJU.forbidden_move(&mut five_with_three, 3, 3, Mark::X), None);
+
+        let mut blocked = board(&[".......", "...O...", "...X...", ".XX.X..", "...X...", "......."]);
+        assert_eq!(RENJU.forbidden_move(&mut blocked, 3, 3, Mark::X), None);
+    }
+}
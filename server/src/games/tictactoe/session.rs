@@ -1,18 +1,20 @@
 use std::collections::HashMap;
 use std::sync::Arc;
+use std::time::{Duration, Instant};
 use tokio::sync::{Mutex, Notify};
 
 use crate::{
     BotId, ClientId, GameOverNotification, GameStateUpdate, InGameCommand, PlayerIdentity,
     PlayerId, ScoreEntry, game_over_notification, game_state_update, in_game_command, log,
     proto::tictactoe::{
-        PlaceMarkCommand, TicTacToeGameEndInfo, TicTacToeGameEndReason, TicTacToeBotType,
-        TicTacToeInGameCommand, tic_tac_toe_in_game_command,
+        PlaceMarkCommand, Swap2Choice, Swap2ChoiceCommand, TicTacToeGameEndInfo, TicTacToeGameEndReason,
+        TicTacToeBotType, TicTacToeInGameCommand, tic_tac_toe_in_game_command,
     },
 };
-use crate::games::{BotType, GameBroadcaster, GameSessionConfig, SessionRng};
+use crate::games::{BotTakeover, BotType, GameBroadcaster, GameSessionConfig, RngStream, SessionRng, TakeoverTracker};
 use crate::replay::ReplayRecorder;
-use super::bot_controller::{BotInput, calculate_minimax_move, calculate_move};
+use super::bot_controller::{BotInput, calculate_minimax_move, calculate_move, calculate_random_move, calculate_swap2_choice};
+use super::bot_strength::BotStrength;
 use super::game_state::TicTacToeGameState;
 use super::settings::TicTacToeSessionSettings;
 use super::types::GameStatus;
@@ -23,10 +25,16 @@ pub struct TicTacToeSessionState {
     pub session_id: String,
     pub game_state: Arc<Mutex<TicTacToeGameState>>,
     pub rng: Arc<Mutex<SessionRng>>,
+    pub bot_rng: Arc<Mutex<SessionRng>>,
     pub bots: HashMap<BotId, BotType>,
     pub turn_notify: Arc<Notify>,
     pub replay_recorder: Option<Arc<Mutex<ReplayRecorder>>>,
     pub tick: Arc<Mutex<u64>>,
+    pub started_at: Instant,
+    pub bot_takeover: Option<BotTakeover>,
+    /// A human turn without a move for this long counts as one idle turn.
+    pub idle_turn: Duration,
+    pub takeover: Arc<Mutex<TakeoverTracker>>,
 }
 
 impl TicTacToeSessionState {
@@ -51,7 +59,7 @@ impl TicTacToeSessionState {
             all_players.push(bot_id.to_player_id());
         }
 
-        let game_state = TicTacToeGameState::new(
+        let mut game_state = TicTacToeGameState::new(
             settings.field_width,
             settings.field_height,
             settings.win_count,
@@ -59,16 +67,41 @@ impl TicTacToeSessionState {
             settings.first_player_mode,
             &mut rng,
         );
+        game_state.set_time_control(settings.time_control);
+        game_state.set_rules(settings.win_rule, settings.opening);
 
-        Ok(Self {
+        Ok(Self::from_state(config, game_state, rng, 0, 0, settings.idle_turn, replay_recorder))
+    }
+
+    /// Continues a game already `moves` in, with its clocks at `elapsed_ms`.
+    pub fn from_state(
+        config: &GameSessionConfig,
+        game_state: TicTacToeGameState,
+        rng: SessionRng,
+        moves: u64,
+        elapsed_ms: u64,
+        idle_turn: Duration,
+        replay_recorder: Option<Arc<Mutex<ReplayRecorder>>>,
+    ) -> Self {
+        let now = Instant::now();
+        Self {
             session_id: config.session_id.clone(),
             game_state: Arc::new(Mutex::new(game_state)),
+            bot_rng: Arc::new(Mutex::new(SessionRng::for_stream(rng.seed(), RngStream::Bots))),
             rng: Arc::new(Mutex::new(rng)),
             bots: config.bots.clone(),
             turn_notify: Arc::new(Notify::new()),
             replay_recorder,
-            tick: Arc::new(Mutex::new(0)),
-        })
+            tick: Arc::new(Mutex::new(moves)),
+            started_at: now.checked_sub(Duration::from_millis(elapsed_ms)).unwrap_or(now),
+            bot_takeover: config.bot_takeover,
+            idle_turn,
+            takeover: Arc::new(Mutex::new(TakeoverTracker::default())),
+        }
+    }
+
+    pub fn elapsed_ms(&self) -> u64 {
+        self.started_at.elapsed().as_millis() as u64
     }
 }
 
@@ -83,14 +116,11 @@ impl TicTacToeSession {
         loop {
             broadcast_state(&session_state, &config, &broadcaster).await;
 
-            let (is_game_over, is_bot_turn) = {
+            let (is_game_over, is_bot_turn, deadline_ms, current_player) = {
                 let game_state = session_state.game_state.lock().await;
                 let is_over = game_state.status != GameStatus::InProgress;
-                let is_bot = session_state
-                    .bots
-                    .iter()
-                    .any(|(bot_id, _)| bot_id.to_player_id() == game_state.current_player);
-                (is_over, is_bot)
+                let is_bot = plays_as_bot(&session_state, &game_state.current_player).await;
+                (is_over, is_bot, game_state.deadline_ms(), game_state.current_player.clone())
             };
 
             if is_game_over {
@@ -99,8 +129,32 @@ impl TicTacToeSession {
 
             if is_bot_turn {
                 play_bot_turn(&session_state).await;
-            } else {
+                continue;
+            }
+
+            let clock_wait = deadline_ms
+                .map(|deadline_ms| Duration::from_millis(deadline_ms.saturating_sub(session_state.elapsed_ms())));
+            let idle_wait = session_state
+                .bot_takeover
+                .filter(|t| t.idle_limit > 0)
+                .map(|_| session_state.idle_turn);
+
+            let Some(wait) = clock_wait.into_iter().chain(idle_wait).min() else {
                 session_state.turn_notify.notified().await;
+                continue;
+            };
+
+            if tokio::time::timeout(wait, session_state.turn_notify.notified()).await.is_err() {
+                let mut game_state = session_state.game_state.lock().await;
+                if flag_if_out_of_time(&session_state, &mut game_state, session_state.elapsed_ms()).await {
+                    continue;
+                }
+                drop(game_state);
+                if let Some(bot_takeover) = session_state.bot_takeover
+                    && idle_wait == Some(wait)
+                {
+                    take_over_if_idle(&session_state, &current_player, bot_takeover.idle_limit).await;
+                }
             }
         }
 
@@ -112,25 +166,45 @@ impl TicTacToeSession {
         client_id: &ClientId,
         command: &TicTacToeInGameCommand,
     ) {
-        let (x, y) = match &command.command {
-            Some(tic_tac_toe_in_game_command::Command::Place(place_cmd)) => {
-                (place_cmd.x, place_cmd.y)
-            }
-            _ => return,
+        let Some(inner_command) = &command.command else {
+            return;
         };
 
+        Self::return_control(state, client_id).await;
+
         let mut state_guard = state.game_state.lock().await;
         let player_id = PlayerId::new(client_id.to_string());
-        match state_guard.place_mark(&player_id, x as usize, y as usize) {
+        let now_ms = state.elapsed_ms();
+        if flag_if_out_of_time(state, &mut state_guard, now_ms).await {
+            drop(state_guard);
+            state.turn_notify.notify_one();
+            return;
+        }
+
+        let result = match inner_command {
+            tic_tac_toe_in_game_command::Command::Place(place_cmd) => {
+                state_guard.place_mark_at(&player_id, place_cmd.x as usize, place_cmd.y as usize, now_ms)
+            }
+            tic_tac_toe_in_game_command::Command::Swap2Choice(choice_cmd) => {
+                state_guard.choose_swap2_at(&player_id, choice_cmd.choice(), now_ms)
+            }
+        };
+
+        match result {
             Ok(()) => {
+                let state_hash = state_guard.state_hash();
                 drop(state_guard);
 
                 let mut tick = state.tick.lock().await;
                 if let Some(ref recorder) = state.replay_recorder {
                     let mut recorder = recorder.lock().await;
                     if let Some(player_index) = recorder.find_player_index(&client_id.to_string()) {
-                        let in_game_command = create_place_command(x, y);
-                        recorder.record_command(*tick as i64, player_index, in_game_command);
+                        let in_game_command = InGameCommand {
+                            command: Some(in_game_command::Command::Tictactoe(*command)),
+                        };
+                        recorder.record_timed_command(*tick as i64, now_ms as i64, player_index, in_game_command);
+                        let position = recorder.actions_count() as u64;
+                        recorder.record_state_hash(position, state_hash);
                     }
                 }
                 *tick += 1;
@@ -139,11 +213,39 @@ impl TicTacToeSession {
                 state.turn_notify.notify_one();
             }
             Err(e) => {
-                log!("[session:{}] Player {} failed to place mark at ({}, {}): {}", state.session_id, player_id, x, y, e);
+                log!("[session:{}] Player {} failed to play {:?}: {}", state.session_id, player_id, inner_command, e);
             }
         }
     }
 
+    pub async fn current_state(state: &TicTacToeSessionState) -> GameStateUpdate {
+        build_state_update(state).await
+    }
+
+    /// Returns false when the lobby did not enable bot takeover.
+    pub async fn take_over(state: &TicTacToeSessionState, client_id: &ClientId) -> bool {
+        if state.bot_takeover.is_none() {
+            return false;
+        }
+        let player_id = PlayerId::new(client_id.to_string());
+        if state.takeover.lock().await.take_over(&player_id) {
+            log!("[session:{}] Bot took over for {}", state.session_id, player_id);
+            record_control_change(state, &player_id, true).await;
+            state.turn_notify.notify_one();
+        }
+        true
+    }
+
+    pub async fn return_control(state: &TicTacToeSessionState, client_id: &ClientId) -> bool {
+        let player_id = PlayerId::new(client_id.to_string());
+        if !state.takeover.lock().await.record_input(&player_id) {
+            return false;
+        }
+        log!("[session:{}] Control returned to {}", state.session_id, player_id);
+        record_control_change(state, &player_id, false).await;
+        true
+    }
+
     pub async fn handle_player_disconnect(state: &TicTacToeSessionState, client_id: &ClientId) {
         let mut game_state = state.game_state.lock().await;
         let player_id = PlayerId::new(client_id.to_string());
@@ -155,6 +257,20 @@ impl TicTacToeSession {
     }
 }
 
+async fn plays_as_bot(session_state: &TicTacToeSessionState, player_id: &PlayerId) -> bool {
+    session_state.bots.keys().any(|bot_id| bot_id.to_player_id() == *player_id)
+        || session_state.takeover.lock().await.is_controlled(player_id)
+}
+
+async fn take_over_if_idle(session_state: &TicTacToeSessionState, player_id: &PlayerId, idle_limit: u32) {
+    let mut takeover = session_state.takeover.lock().await;
+    if takeover.count_idle(player_id, idle_limit) && takeover.take_over(player_id) {
+        drop(takeover);
+        log!("[session:{}] Bot took over for idle player {}", session_state.session_id, player_id);
+        record_control_change(session_state, player_id, true).await;
+    }
+}
+
 async fn play_bot_turn(session_state: &TicTacToeSessionState) {
     let mut game_state = session_state.game_state.lock().await;
 
@@ -165,32 +281,76 @@ async fn play_bot_turn(session_state: &TicTacToeSessionState) {
         .iter()
         .find(|(bot_id, _)| bot_id.to_player_id() == current_player)
         .and_then(|(_, bot_type)| match bot_type {
-            BotType::TicTacToe(ttt_bot) => Some(*ttt_bot),
+            BotType::TicTacToe(ttt_bot, strength) => Some((*ttt_bot, *strength)),
             _ => None,
         });
+    let bot_type = match bot_type {
+        Some(bot_type) => Some(bot_type),
+        None if session_state.takeover.lock().await.is_controlled(&current_player) => {
+            Some((TicTacToeBotType::TictactoeBotTypeMinimax, BotStrength::default()))
+        }
+        None => None,
+    };
 
-    let Some(bot_type) = bot_type else {
+    let Some((bot_type, strength)) = bot_type else {
         return;
     };
 
     let bot_input = BotInput::from_game_state(&game_state);
 
+    if game_state.swap2_phase.awaits_choice() {
+        let choice = calculate_swap2_choice(bot_type, &bot_input, &mut *session_state.bot_rng.lock().await);
+        let now_ms = session_state.elapsed_ms();
+        if flag_if_out_of_time(session_state, &mut game_state, now_ms).await {
+            return;
+        }
+        match game_state.choose_swap2_at(&current_player, choice, now_ms) {
+            Ok(()) => {
+                let state_hash = game_state.state_hash();
+                drop(game_state);
+                record_bot_command(session_state, &current_player, create_swap2_choice_command(choice), now_ms, state_hash).await;
+            }
+            Err(e) => {
+                log!("[session:{}] Bot {} failed to choose {:?}: {}", session_state.session_id, current_player, choice, e);
+            }
+        }
+        return;
+    }
+
+    // Rolled here rather than in the blocking task so the bot stream is
+    // only ever drawn from under the lock.
+    let blunders = bot_type == TicTacToeBotType::TictactoeBotTypeMinimax
+        && strength.blunders(&mut *session_state.bot_rng.lock().await);
+
     let calculated_move = match bot_type {
         TicTacToeBotType::TictactoeBotTypeRandom => {
-            let mut rng = session_state.rng.lock().await;
-            calculate_move(bot_type, bot_input, &mut rng)
+            let mut rng = session_state.bot_rng.lock().await;
+            calculate_move(bot_type, &strength, bot_input, &mut rng)
+        }
+        TicTacToeBotType::TictactoeBotTypeMinimax if blunders => {
+            calculate_random_move(&bot_input, &mut *session_state.bot_rng.lock().await)
         }
         TicTacToeBotType::TictactoeBotTypeMinimax => {
             drop(game_state);
             let session_id = session_state.session_id.clone();
             let result =
-                tokio::task::spawn_blocking(move || calculate_minimax_move(&bot_input)).await;
+                tokio::task::spawn_blocking(move || calculate_minimax_move(&bot_input, &strength)).await;
 
             if let Ok(Some(pos)) = result {
+                // The player may have reclaimed their seat while the bot was thinking.
+                if !plays_as_bot(session_state, &current_player).await {
+                    return;
+                }
                 let mut game_state = session_state.game_state.lock().await;
-                match game_state.place_mark(&current_player, pos.x, pos.y) {
+                let now_ms = session_state.elapsed_ms();
+                if flag_if_out_of_time(session_state, &mut game_state, now_ms).await {
+                    return;
+                }
+                match game_state.place_mark_at(&current_player, pos.x, pos.y, now_ms) {
                     Ok(()) => {
-                        record_bot_move(session_state, &current_player, pos.x, pos.y).await;
+                        let state_hash = game_state.state_hash();
+                        let command = create_place_command(pos.x as u32, pos.y as u32);
+                        record_bot_command(session_state, &current_player, command, now_ms, state_hash).await;
                     }
                     Err(e) => {
                         log!("[session:{}] Bot {} failed to place mark at ({}, {}): {}", session_id, current_player, pos.x, pos.y, e);
@@ -203,10 +363,16 @@ async fn play_bot_turn(session_state: &TicTacToeSessionState) {
     };
 
     if let Some(pos) = calculated_move {
-        match game_state.place_mark(&current_player, pos.x, pos.y) {
+        let now_ms = session_state.elapsed_ms();
+        if flag_if_out_of_time(session_state, &mut game_state, now_ms).await {
+            return;
+        }
+        match game_state.place_mark_at(&current_player, pos.x, pos.y, now_ms) {
             Ok(()) => {
+                let state_hash = game_state.state_hash();
                 drop(game_state);
-                record_bot_move(session_state, &current_player, pos.x, pos.y).await;
+                let command = create_place_command(pos.x as u32, pos.y as u32);
+                record_bot_command(session_state, &current_player, command, now_ms, state_hash).await;
             }
             Err(e) => {
                 log!("[session:{}] Bot {} failed to place mark at ({}, {}): {}", session_state.session_id, current_player, pos.x, pos.y, e);
@@ -215,24 +381,58 @@ async fn play_bot_turn(session_state: &TicTacToeSessionState) {
     }
 }
 
-async fn record_bot_move(
+async fn record_bot_command(
     session_state: &TicTacToeSessionState,
     player_id: &PlayerId,
-    x: usize,
-    y: usize,
+    command: InGameCommand,
+    now_ms: u64,
+    state_hash: u64,
 ) {
     let mut tick = session_state.tick.lock().await;
     if let Some(ref recorder) = session_state.replay_recorder {
         let mut recorder = recorder.lock().await;
         if let Some(player_index) = recorder.find_player_index(&player_id.to_string()) {
-            let command = create_place_command(x as u32, y as u32);
-            recorder.record_command(*tick as i64, player_index, command);
+            recorder.record_timed_command(*tick as i64, now_ms as i64, player_index, command);
+            let position = recorder.actions_count() as u64;
+            recorder.record_state_hash(position, state_hash);
         }
     }
     *tick += 1;
 }
 
-fn create_place_command(x: u32, y: u32) -> InGameCommand {
+async fn record_control_change(session_state: &TicTacToeSessionState, player_id: &PlayerId, bot_controlled: bool) {
+    let tick = session_state.tick.lock().await;
+    if let Some(ref recorder) = session_state.replay_recorder {
+        let mut recorder = recorder.lock().await;
+        if let Some(player_index) = recorder.find_player_index(&player_id.to_string()) {
+            recorder.record_control_change(*tick as i64, session_state.elapsed_ms() as i64, player_index, bot_controlled);
+        }
+    }
+}
+
+async fn flag_if_out_of_time(
+    session_state: &TicTacToeSessionState,
+    game_state: &mut TicTacToeGameState,
+    now_ms: u64,
+) -> bool {
+    let flagged_player = game_state.current_player.clone();
+    if !game_state.check_timeout(now_ms) {
+        return false;
+    }
+
+    log!("[session:{}] Player {} ran out of time", session_state.session_id, flagged_player);
+
+    let tick = session_state.tick.lock().await;
+    if let Some(ref recorder) = session_state.replay_recorder {
+        let mut recorder = recorder.lock().await;
+        if let Some(player_index) = recorder.find_player_index(&flagged_player.to_string()) {
+            recorder.record_timeout(*tick as i64, now_ms as i64, player_index);
+        }
+    }
+    true
+}
+
+pub fn create_place_command(x: u32, y: u32) -> InGameCommand {
     InGameCommand {
         command: Some(in_game_command::Command::Tictactoe(TicTacToeInGameCommand {
             command: Some(tic_tac_toe_in_game_command::Command::Place(
@@ -242,11 +442,26 @@ fn create_place_command(x: u32, y: u32) -> InGameCommand {
     }
 }
 
+pub fn create_swap2_choice_command(choice: Swap2Choice) -> InGameCommand {
+    InGameCommand {
+        command: Some(in_game_command::Command::Tictactoe(TicTacToeInGameCommand {
+            command: Some(tic_tac_toe_in_game_command::Command::Swap2Choice(
+                Swap2ChoiceCommand { choice: choice as i32 },
+            )),
+        })),
+    }
+}
+
 async fn broadcast_state(
     session_state: &TicTacToeSessionState,
     config: &GameSessionConfig,
     broadcaster: &impl GameBroadcaster,
 ) {
+    let state_update = TicTacToeSession::current_state(session_state).await;
+    config.broadcast_state(broadcaster, state_update).await;
+}
+
+async fn build_state_update(session_state: &TicTacToeSessionState) -> GameStateUpdate {
     let game_state = session_state.game_state.lock().await;
 
     let player_x_is_bot = session_state
@@ -262,16 +477,17 @@ async fn broadcast_state(
         .iter()
         .any(|(bot_id, _)| bot_id.to_player_id() == game_state.current_player);
 
-    let proto_state =
-        game_state.to_proto_state(player_x_is_bot, player_o_is_bot, current_player_is_bot);
+    let proto_state = game_state.to_proto_state(
+        player_x_is_bot,
+        player_o_is_bot,
+        current_player_is_bot,
+        session_state.elapsed_ms(),
+    );
     drop(game_state);
 
-    let state_update = GameStateUpdate {
+    GameStateUpdate {
         state: Some(game_state_update::State::Tictactoe(proto_state)),
-    };
-
-    let recipients = config.get_all_recipients();
-    broadcaster.broadcast_state(state_update, recipients).await;
+    }
 }
 
 async fn build_game_over_notification(
@@ -315,13 +531,16 @@ async fn build_game_over_notification(
     });
 
     let game_end_reason = match game_state.status {
+        GameStatus::XWon | GameStatus::OWon if game_state.timed_out => {
+            TicTacToeGameEndReason::TictactoeGameEndReasonTimeout
+        }
         GameStatus::XWon | GameStatus::OWon => TicTacToeGameEndReason::TictactoeGameEndReasonWin,
         GameStatus::Draw => TicTacToeGameEndReason::TictactoeGameEndReasonDraw,
         _ => TicTacToeGameEndReason::TictactoeGameEndReasonUnspecified,
     };
 
-    let winning_line = if matches!(game_state.status, GameStatus::XWon | GameStatus::OWon) {
-        check_win_with_line(&game_state.board, game_state.win_count).map(|line| line.to_proto())
+    let winning_line = if matches!(game_state.status, GameStatus::XWon | GameStatus::OWon) && !game_state.timed_out {
+        check_win_with_line(&game_state.board, &game_state.rules()).map(|line| line.to_proto())
     } else {
         None
     };
@@ -1,17 +1,29 @@
 use std::sync::Arc;
+use std::time::Duration;
 use tokio::sync::Mutex;
 
 use crate::{ReplayGame, TicTacToeLobbySettings, lobby_details, lobby_settings};
 use crate::games::{GameSession, GameSessionConfig, LobbySettings, ReplayMode};
 use crate::replay::ReplayRecorder;
+use super::clock::TimeControl;
 use super::session::TicTacToeSessionState;
-use super::types::FirstPlayerMode;
+use super::types::{FirstPlayerMode, OpeningRule, WinRule};
+use super::validate::{validate_idle_turn, validate_rules};
+
+/// Idle turn length for lobbies that don't set `idle_turn_ms`.
+pub const DEFAULT_IDLE_TURN_MS: u32 = 10_000;
 
 pub struct TicTacToeSessionSettings {
     pub field_width: usize,
     pub field_height: usize,
     pub win_count: usize,
     pub first_player_mode: FirstPlayerMode,
+    pub time_control: TimeControl,
+    pub win_rule: WinRule,
+    pub opening: OpeningRule,
+    /// How long a human turn may pass without a move before it counts as one
+    /// idle turn for bot takeover.
+    pub idle_turn: Duration,
 }
 
 impl From<&TicTacToeLobbySettings> for TicTacToeSessionSettings {
@@ -26,11 +38,30 @@ impl From<&TicTacToeLobbySettings> for TicTacToeSessionSettings {
                 | Err(_) => FirstPlayerMode::Random,
             };
 
+        let win_rule = match crate::proto::tictactoe::WinRule::try_from(settings.win_rule) {
+            Ok(crate::proto::tictactoe::WinRule::Exact) => WinRule::Exact,
+            Ok(crate::proto::tictactoe::WinRule::Renju) => WinRule::Renju,
+            Ok(crate::proto::tictactoe::WinRule::Freestyle | crate::proto::tictactoe::WinRule::Unspecified)
+            | Err(_) => WinRule::Freestyle,
+        };
+        let opening = match crate::proto::tictactoe::OpeningRule::try_from(settings.opening) {
+            Ok(crate::proto::tictactoe::OpeningRule::Swap2) => OpeningRule::Swap2,
+            Ok(crate::proto::tictactoe::OpeningRule::Standard | crate::proto::tictactoe::OpeningRule::Unspecified)
+            | Err(_) => OpeningRule::Standard,
+        };
+
         Self {
             field_width: settings.field_width as usize,
             field_height: settings.field_height as usize,
             win_count: settings.win_count as usize,
             first_player_mode,
+            time_control: TimeControl::from_settings(settings).unwrap_or(TimeControl::Unlimited),
+            win_rule,
+            opening,
+            idle_turn: Duration::from_millis(match settings.idle_turn_ms {
+                0 => DEFAULT_IDLE_TURN_MS,
+                idle_turn_ms => idle_turn_ms,
+            } as u64),
         }
     }
 }
@@ -56,6 +87,9 @@ impl LobbySettings for TicTacToeLobbySettings {
                 self.win_count, min_dimension
             ));
         }
+        TimeControl::from_settings(self)?;
+        validate_rules(self)?;
+        validate_idle_turn(self)?;
         Ok(())
     }
 
@@ -48,6 +48,54 @@ pub enum FirstPlayerMode {
     Host,
 }
 
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum WinRule {
+    Freestyle,
+    Exact,
+    Renju,
+}
+
+impl WinRule {
+    pub fn to_proto(self) -> i32 {
+        match self {
+            WinRule::Freestyle => 1,
+            WinRule::Exact => 2,
+            WinRule::Renju => 3,
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum OpeningRule {
+    Standard,
+    Swap2,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Swap2Phase {
+    None,
+    Opening,
+    Choice,
+    ExtraStones,
+    FinalChoice,
+}
+
+impl Swap2Phase {
+    pub fn to_proto(self) -> i32 {
+        match self {
+            Swap2Phase::None => 1,
+            Swap2Phase::Opening => 2,
+            Swap2Phase::Choice => 3,
+            Swap2Phase::ExtraStones => 4,
+            Swap2Phase::FinalChoice => 5,
+        }
+    }
+
+    pub fn awaits_choice(self) -> bool {
+        matches!(self, Swap2Phase::Choice | Swap2Phase::FinalChoice)
+    }
+}
+
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub struct Position {
     pub x: usize,
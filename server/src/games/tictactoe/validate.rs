@@ -1,5 +1,6 @@
-use crate::proto::tictactoe::TicTacToeLobbySettings;
+use crate::proto::tictactoe::{OpeningRule, TicTacToeLobbySettings, WinRule};
 use crate::validate_lobby_settings::ValidateLobbySettings;
+use super::clock::{MAX_TIME_MS, MIN_TIME_MS, TimeControl};
 
 impl ValidateLobbySettings for TicTacToeLobbySettings {
     fn validate(&self, max_players: u32) -> Result<(), String> {
@@ -22,6 +23,37 @@ impl ValidateLobbySettings for TicTacToeLobbySettings {
                 self.win_count, min_dimension
             ));
         }
+        TimeControl::from_settings(self)?;
+        validate_rules(self)?;
+        validate_idle_turn(self)?;
         Ok(())
     }
 }
+
+/// Unset means the default; a set idle turn is held to the same bounds as
+/// the clock's time settings.
+pub(super) fn validate_idle_turn(settings: &TicTacToeLobbySettings) -> Result<(), String> {
+    if settings.idle_turn_ms != 0 && !(MIN_TIME_MS..=MAX_TIME_MS).contains(&settings.idle_turn_ms) {
+        return Err(format!("Idle turn must be between {} and {} ms", MIN_TIME_MS, MAX_TIME_MS));
+    }
+    Ok(())
+}
+
+/// Renju's forbidden patterns are defined for five in a row, and a Swap2
+/// opening must not be able to win outright.
+pub(super) fn validate_rules(settings: &TicTacToeLobbySettings) -> Result<(), String> {
+    match WinRule::try_from(settings.win_rule) {
+        Ok(WinRule::Renju) if settings.win_count != 5 => {
+            return Err("Renju rules require a win count of 5".to_string());
+        }
+        Err(_) => return Err("Unknown win rule".to_string()),
+        _ => {}
+    }
+    match OpeningRule::try_from(settings.opening) {
+        Ok(OpeningRule::Swap2) if settings.win_count < 5 => {
+            Err("Swap2 opening requires a win count of at least 5".to_string())
+        }
+        Err(_) => Err("Unknown opening rule".to_string()),
+        _ => Ok(()),
+    }
+}
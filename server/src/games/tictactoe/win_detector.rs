@@ -1,10 +1,15 @@
+use super::rules::{Rules, run_length};
 use super::types::{Mark, Position, WinningLine};
 
-pub fn check_win(board: &[Vec<Mark>], win_count: usize) -> Option<Mark> {
-    check_win_with_line(board, win_count).map(|line| line.mark)
+const DIRECTIONS: [(isize, isize); 4] = [(1, 0), (0, 1), (1, 1), (-1, 1)];
+
+pub fn check_win(board: &[Vec<Mark>], rules: &Rules) -> Option<Mark> {
+    check_win_with_line(board, rules).map(|line| line.mark)
 }
 
-pub fn check_win_with_line(board: &[Vec<Mark>], win_count: usize) -> Option<WinningLine> {
+/// The first winning run in reading order, checking each run from its start.
+/// An overline that wins is reported by its first `win_count` cells.
+pub fn check_win_with_line(board: &[Vec<Mark>], rules: &Rules) -> Option<WinningLine> {
     let height = board.len();
     if height == 0 {
         return None;
@@ -18,25 +23,16 @@ pub fn check_win_with_line(board: &[Vec<Mark>], win_count: usize) -> Option<Winn
                 continue;
             }
 
-            if check_horizontal(board, x, y, mark, win_count) {
-                let start = Position::new(x, y);
-                let end = Position::new(x + win_count - 1, y);
-                return Some(WinningLine::new(mark, start, end));
-            }
-            if check_vertical(board, x, y, mark, win_count) {
-                let start = Position::new(x, y);
-                let end = Position::new(x, y + win_count - 1);
-                return Some(WinningLine::new(mark, start, end));
-            }
-            if check_diagonal_down_right(board, x, y, mark, win_count) {
-                let start = Position::new(x, y);
-                let end = Position::new(x + win_count - 1, y + win_count - 1);
-                return Some(WinningLine::new(mark, start, end));
-            }
-            if check_diagonal_down_left(board, x, y, mark, win_count) {
-                let start = Position::new(x, y);
-                let end = Position::new(x - (win_count - 1), y + win_count - 1);
-                return Some(WinningLine::new(mark, start, end));
+            for (dx, dy) in DIRECTIONS {
+                if continues_run(board, x, y, -dx, -dy, mark) {
+                    continue;
+                }
+                let run = run_length(board, x, y, dx, dy, mark);
+                if rules.wins(mark, run) {
+                    let reach = rules.win_count as isize - 1;
+                    let end = Position::new(x.wrapping_add_signed(dx * reach), y.wrapping_add_signed(dy * reach));
+                    return Some(WinningLine::new(mark, Position::new(x, y), end));
+                }
             }
         }
     }
@@ -44,71 +40,9 @@ pub fn check_win_with_line(board: &[Vec<Mark>], win_count: usize) -> Option<Winn
     None
 }
 
-fn check_horizontal(board: &[Vec<Mark>], x: usize, y: usize, mark: Mark, win_count: usize) -> bool {
-    let width = board[0].len();
-    if x + win_count > width {
-        return false;
-    }
-
-    for i in 0..win_count {
-        if board[y][x + i] != mark {
-            return false;
-        }
-    }
-    true
-}
-
-fn check_vertical(board: &[Vec<Mark>], x: usize, y: usize, mark: Mark, win_count: usize) -> bool {
-    let height = board.len();
-    if y + win_count > height {
-        return false;
-    }
-
-    for i in 0..win_count {
-        if board[y + i][x] != mark {
-            return false;
-        }
-    }
-    true
-}
-
-fn check_diagonal_down_right(
-    board: &[Vec<Mark>],
-    x: usize,
-    y: usize,
-    mark: Mark,
-    win_count: usize,
-) -> bool {
-    let height = board.len();
-    let width = board[0].len();
-    if x + win_count > width || y + win_count > height {
+fn continues_run(board: &[Vec<Mark>], x: usize, y: usize, dx: isize, dy: isize, mark: Mark) -> bool {
+    let (Some(nx), Some(ny)) = (x.checked_add_signed(dx), y.checked_add_signed(dy)) else {
         return false;
-    }
-
-    for i in 0..win_count {
-        if board[y + i][x + i] != mark {
-            return false;
-        }
-    }
-    true
-}
-
-fn check_diagonal_down_left(
-    board: &[Vec<Mark>],
-    x: usize,
-    y: usize,
-    mark: Mark,
-    win_count: usize,
-) -> bool {
-    let height = board.len();
-    if x + 1 < win_count || y + win_count > height {
-        return false;
-    }
-
-    for i in 0..win_count {
-        if board[y + i][x - i] != mark {
-            return false;
-        }
-    }
-    true
+    };
+    board.get(ny).and_then(|row| row.get(nx)) == Some(&mark)
 }
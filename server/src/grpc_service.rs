@@ -8,6 +8,8 @@ use crate::{log, proto::game_service::game_service_server::GameService, ClientId
 use crate::broadcaster::Broadcaster;
 use crate::game_session_manager::GameSessionManager;
 use crate::lobby::LobbyManager;
+use crate::matchmaking::MatchmakingManager;
+use crate::tournament::TournamentManager;
 use crate::message_handler::{HandleResult, MessageHandler};
 
 #[derive(Debug)]
@@ -15,6 +17,8 @@ pub struct GrpcService {
     lobby_manager: LobbyManager,
     broadcaster: Broadcaster,
     session_manager: GameSessionManager,
+    matchmaking_manager: MatchmakingManager,
+    tournament_manager: TournamentManager,
 }
 
 impl GrpcService {
@@ -22,11 +26,15 @@ impl GrpcService {
         lobby_manager: LobbyManager,
         broadcaster: Broadcaster,
         session_manager: GameSessionManager,
+        matchmaking_manager: MatchmakingManager,
+        tournament_manager: TournamentManager,
     ) -> Self {
         Self {
             lobby_manager,
             broadcaster,
             session_manager,
+            matchmaking_manager,
+            tournament_manager,
         }
     }
 }
@@ -46,6 +54,8 @@ impl GameService for GrpcService {
             self.lobby_manager.clone(),
             self.broadcaster.clone(),
             self.session_manager.clone(),
+            self.matchmaking_manager.clone(),
+            self.tournament_manager.clone(),
         );
 
         tokio::spawn(async move {
@@ -41,6 +41,8 @@ define_id!(LobbyId);
 
 define_id!(SessionId);
 
+define_id!(TournamentId);
+
 define_id!(PlayerId);
 define_id!(BotId);
 
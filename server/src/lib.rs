@@ -50,8 +50,8 @@ pub mod proto {
         Puzzle2048GameEndReason, Puzzle2048GameEndInfo,
     };
     pub use replay::{
-        Game as ReplayGame, PlayerAction, PlayerActionContent, PlayerDisconnected,
-        ReplayV1, ReplayV1Metadata, ReplayV1Header,
+        Game as ReplayGame, PlayerAction, PlayerActionContent, PlayerDisconnected, PlayerTimedOut,
+        PlayerControlChanged, ReplayV1, StateHash, ReplayV1Metadata, ReplayV1Header,
     };
     pub use replay::player_action_content;
 }
@@ -66,6 +66,9 @@ pub mod config;
 pub mod version;
 pub mod validate_lobby_settings;
 pub mod lobby;
+pub mod matchmaking;
+pub mod tournament;
 pub mod replay;
 pub mod games;
+pub mod arena;
 pub(crate) mod broadcaster;
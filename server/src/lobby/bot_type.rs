@@ -1,19 +1,25 @@
+use crate::games::tictactoe::BotStrength;
+use crate::proto::tictactoe::TicTacToeBotStrength;
 use crate::{add_bot_request, SnakeBotType, TicTacToeBotType};
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum BotType {
     Snake(SnakeBotType),
-    TicTacToe(TicTacToeBotType),
+    TicTacToe(TicTacToeBotType, BotStrength),
 }
 
 impl BotType {
-    pub fn from_proto(bot_type: Option<add_bot_request::BotType>) -> Result<Self, String> {
+    pub fn from_proto(
+        bot_type: Option<add_bot_request::BotType>,
+        tictactoe_strength: Option<&TicTacToeBotStrength>,
+    ) -> Result<Self, String> {
         match bot_type {
             Some(add_bot_request::BotType::SnakeBot(t)) => Ok(BotType::Snake(
                 SnakeBotType::try_from(t).map_err(|_| "Invalid snake bot type")?
             )),
             Some(add_bot_request::BotType::TictactoeBot(t)) => Ok(BotType::TicTacToe(
-                TicTacToeBotType::try_from(t).map_err(|_| "Invalid tictactoe bot type")?
+                TicTacToeBotType::try_from(t).map_err(|_| "Invalid tictactoe bot type")?,
+                BotStrength::from_proto(tictactoe_strength)?,
             )),
             None => Err("No bot type provided".to_string()),
         }
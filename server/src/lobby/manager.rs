@@ -5,7 +5,8 @@ use tokio::sync::Mutex;
 use crate::{LobbyInfo, LobbyDetails, ClientId, LobbyId, PlayerId, BotId};
 use crate::id_generator::generate_client_id;
 use super::{
-    BotType, Lobby, LobbySettings, LobbyStateAfterLeave, PlayAgainStatus, PlayerIdentity,
+    BotTakeover, BotType, Lobby, LobbySettings, LobbyStateAfterLeave, PlayAgainStatus, PlayerIdentity,
+    validate_spectator_delay,
 };
 
 #[derive(Debug)]
@@ -17,6 +18,9 @@ struct LobbyManagerState {
     next_lobby_id: u64,
     last_client_activity: HashMap<ClientId, Instant>,
     last_lobby_activity: HashMap<LobbyId, Instant>,
+    /// Disconnected players a bot is playing for. They keep their lobby place
+    /// until they reconnect or the game ends.
+    away_clients: HashSet<ClientId>,
 }
 
 #[derive(Debug, Clone)]
@@ -41,6 +45,7 @@ impl LobbyManager {
                 next_lobby_id: 1,
                 last_client_activity: HashMap::new(),
                 last_lobby_activity: HashMap::new(),
+                away_clients: HashSet::new(),
             })),
         }
     }
@@ -48,6 +53,11 @@ impl LobbyManager {
     pub async fn add_client(&self, client_id: &ClientId) -> bool {
         let mut state = self.state.lock().await;
 
+        if state.away_clients.remove(client_id) {
+            state.last_client_activity.insert(client_id.clone(), Instant::now());
+            return true;
+        }
+
         if state.client_to_lobby.contains_key(client_id) || state.clients_not_in_lobby.contains(client_id) {
             return false;
         }
@@ -61,6 +71,30 @@ impl LobbyManager {
         let mut state = self.state.lock().await;
         state.clients_not_in_lobby.remove(client_id);
         state.last_client_activity.remove(client_id);
+        state.away_clients.remove(client_id);
+    }
+
+    /// Keeps a disconnected player in their lobby while a bot holds their seat.
+    pub async fn hold_seat(&self, client_id: &ClientId) {
+        let mut state = self.state.lock().await;
+        if state.client_to_lobby.contains_key(client_id) {
+            state.away_clients.insert(client_id.clone());
+        }
+    }
+
+    /// Hands back the held seats in a lobby once its game is over, so the away
+    /// players can be removed like any other disconnect.
+    pub async fn release_held_seats(&self, lobby_id: &LobbyId) -> Vec<ClientId> {
+        let mut state = self.state.lock().await;
+        let released: Vec<ClientId> = state.away_clients
+            .iter()
+            .filter(|client_id| state.client_to_lobby.get(*client_id) == Some(lobby_id))
+            .cloned()
+            .collect();
+        for client_id in &released {
+            state.away_clients.remove(client_id);
+        }
+        released
     }
 
     pub async fn get_clients_not_in_lobbies(&self) -> Vec<ClientId> {
@@ -110,8 +144,20 @@ impl LobbyManager {
             .collect()
     }
 
-    pub async fn create_lobby(&self, name: String, max_players: u32, settings: LobbySettings, creator_id: ClientId) -> Result<LobbyDetails, String> {
+    pub async fn create_lobby(
+        &self,
+        name: String,
+        max_players: u32,
+        settings: LobbySettings,
+        spectator_delay: u32,
+        bot_takeover: Option<BotTakeover>,
+        creator_id: ClientId,
+    ) -> Result<LobbyDetails, String> {
         settings.validate(max_players)?;
+        validate_spectator_delay(spectator_delay)?;
+        if bot_takeover.is_some() && !matches!(settings, LobbySettings::Snake(_) | LobbySettings::TicTacToe(_)) {
+            return Err("Bot takeover is only supported for Snake and TicTacToe".to_string());
+        }
 
         let mut state = self.state.lock().await;
 
@@ -123,6 +169,8 @@ impl LobbyManager {
         state.next_lobby_id += 1;
 
         let mut lobby = Lobby::new(lobby_id.clone(), name, creator_id.clone(), max_players, settings);
+        lobby.spectator_delay = spectator_delay;
+        lobby.bot_takeover = bot_takeover;
         let creator_player_id = PlayerId::new(creator_id.to_string());
         lobby.add_player(creator_player_id.clone());
         lobby.set_ready(&creator_player_id, true);
@@ -159,7 +207,8 @@ impl LobbyManager {
 
         let lobby = state.lobbies.get_mut(&lobby_id).ok_or("Lobby not found")?;
 
-        if lobby.has_ever_started() {
+        let joins_running_game = join_as_observer && lobby.in_game && !lobby.is_replay_lobby;
+        if lobby.has_ever_started() && !joins_running_game {
             return Err("Cannot join: Lobby no longer accepting new players".to_string());
         }
 
@@ -473,6 +522,30 @@ impl LobbyManager {
         Ok(lobby.to_details())
     }
 
+    pub async fn assign_team(&self, client_id: &ClientId, target_id: String, team: u32) -> Result<LobbyDetails, String> {
+        let mut state = self.state.lock().await;
+        let lobby_id = state.client_to_lobby.get(client_id).cloned().ok_or("Not in a lobby")?;
+        let lobby = state.lobbies.get_mut(&lobby_id).ok_or("Lobby not found")?;
+
+        if !lobby.is_host(client_id) {
+            return Err("Only the host can assign teams".to_string());
+        }
+
+        if !matches!(lobby.settings, LobbySettings::Snake(_)) {
+            return Err("Teams are only supported in Snake".to_string());
+        }
+
+        if team > lobby.max_players {
+            return Err(format!("Team must be between 1 and {}", lobby.max_players));
+        }
+
+        if !lobby.assign_team(&PlayerId::new(target_id), team) {
+            return Err("Target is not a player in this lobby".to_string());
+        }
+
+        Ok(lobby.to_details())
+    }
+
     pub async fn get_client_lobby(&self, client_id: &ClientId) -> Option<LobbyDetails> {
         let state = self.state.lock().await;
         let lobby_id = state.client_to_lobby.get(client_id);
@@ -495,12 +568,79 @@ impl LobbyManager {
         state.last_lobby_activity.remove(lobby_id);
     }
 
+    pub async fn close_lobby(&self, lobby_id: &LobbyId) -> Vec<ClientId> {
+        let mut state = self.state.lock().await;
+        state.lobbies.remove(lobby_id);
+        state.last_lobby_activity.remove(lobby_id);
+
+        let members: Vec<ClientId> = state.client_to_lobby
+            .iter()
+            .filter(|(_, lid)| *lid == lobby_id)
+            .map(|(cid, _)| cid.clone())
+            .collect();
+
+        for client_id in &members {
+            state.client_to_lobby.remove(client_id);
+            state.clients_not_in_lobby.insert(client_id.clone());
+        }
+
+        members
+    }
+
     pub async fn remove_from_current_lobby(&self, client_id: &ClientId) {
         let mut state = self.state.lock().await;
         state.client_to_lobby.remove(client_id);
         state.clients_not_in_lobby.insert(client_id.clone());
     }
 
+    pub async fn create_started_lobby(
+        &self,
+        name: String,
+        settings: LobbySettings,
+        max_players: u32,
+        players: &[ClientId],
+        bots: &[BotType],
+        spectator_delay: u32,
+    ) -> Result<LobbyId, String> {
+        let creator_id = players.first().cloned().ok_or("No players to create lobby for")?;
+        validate_spectator_delay(spectator_delay)?;
+
+        let mut state = self.state.lock().await;
+
+        if let Some(busy) = players.iter().find(|p| state.client_to_lobby.contains_key(*p)) {
+            return Err(format!("{} is already in a lobby", busy));
+        }
+
+        let lobby_id = LobbyId::new(format!("lobby_{}", state.next_lobby_id));
+        state.next_lobby_id += 1;
+
+        let mut lobby = Lobby::new(lobby_id.clone(), name, creator_id, max_players, settings);
+        lobby.spectator_delay = spectator_delay;
+
+        for client_id in players {
+            let player_id = PlayerId::new(client_id.to_string());
+            lobby.add_player(player_id.clone());
+            lobby.set_ready(&player_id, true);
+        }
+
+        for bot_type in bots {
+            let bot_id = BotId::new(format!("{} Bot-{}", generate_client_id(), state.next_bot_id));
+            state.next_bot_id += 1;
+            lobby.add_bot_with_id(bot_id, *bot_type);
+        }
+
+        lobby.start_game();
+
+        for client_id in players {
+            state.client_to_lobby.insert(client_id.clone(), lobby_id.clone());
+            state.clients_not_in_lobby.remove(client_id);
+        }
+        state.lobbies.insert(lobby_id.clone(), lobby);
+        state.last_lobby_activity.insert(lobby_id.clone(), Instant::now());
+
+        Ok(lobby_id)
+    }
+
     pub async fn create_replay_lobby(
         &self,
         name: String,
@@ -546,6 +686,7 @@ mod tests {
             max_food_count: 5,
             food_spawn_probability: 0.5,
             dead_snake_behavior: DeadSnakeBehavior::Disappear.into(),
+            ..Default::default()
         })
     }
 
@@ -558,6 +699,8 @@ mod tests {
             "Test Lobby".to_string(),
             4,
             default_test_settings(),
+            0,
+            None,
             creator_id.clone(),
         ).await;
 
@@ -579,6 +722,8 @@ mod tests {
             "First Lobby".to_string(),
             4,
             default_test_settings(),
+            0,
+            None,
             creator_id.clone(),
         ).await.unwrap();
 
@@ -586,6 +731,8 @@ mod tests {
             "Second Lobby".to_string(),
             4,
             default_test_settings(),
+            0,
+            None,
             creator_id,
         ).await;
 
@@ -593,6 +740,215 @@ mod tests {
         assert_eq!(result.unwrap_err(), "Already in a lobby");
     }
 
+    #[tokio::test]
+    async fn test_create_lobby_bot_takeover_unsupported_game_error_returned() {
+        let manager = LobbyManager::new();
+        let creator_id = ClientId::new("creator".to_string());
+
+        let result = manager.create_lobby(
+            "Puzzle".to_string(),
+            1,
+            LobbySettings::Puzzle2048(crate::Puzzle2048LobbySettings {
+                field_width: 4,
+                field_height: 4,
+                target_value: 2048,
+            }),
+            0,
+            Some(BotTakeover { idle_limit: 0 }),
+            creator_id.clone(),
+        ).await;
+        assert!(result.is_err());
+
+        let details = manager.create_lobby(
+            "Snake".to_string(),
+            4,
+            default_test_settings(),
+            0,
+            Some(BotTakeover { idle_limit: 20 }),
+            creator_id,
+        ).await.unwrap();
+        assert!(details.bot_takeover);
+        assert_eq!(details.bot_takeover_idle, 20);
+    }
+
+    #[tokio::test]
+    async fn test_create_started_lobby_players_and_bots_in_started_lobby() {
+        let manager = LobbyManager::new();
+        let first = ClientId::new("first".to_string());
+        let second = ClientId::new("second".to_string());
+        manager.add_client(&first).await;
+        manager.add_client(&second).await;
+
+        let lobby_id = manager.create_started_lobby(
+            "Quick Play".to_string(),
+            default_test_settings(),
+            4,
+            &[first.clone(), second.clone()],
+            &[BotType::Snake(crate::SnakeBotType::Efficient); 2],
+            0,
+        ).await.unwrap();
+
+        let lobby = manager.get_lobby(&lobby_id).await.unwrap();
+        assert!(lobby.in_game);
+        assert_eq!(lobby.players.len(), 2);
+        assert_eq!(lobby.bots.len(), 2);
+        assert!(lobby.is_host(&first));
+        assert!(manager.get_client_lobby(&second).await.is_some());
+        assert!(manager.list_lobbies().await.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_join_lobby_running_game_only_observers_accepted() {
+        let manager = LobbyManager::new();
+        let host = ClientId::new("host".to_string());
+        let observer = ClientId::new("observer".to_string());
+        let latecomer = ClientId::new("latecomer".to_string());
+        manager.add_client(&host).await;
+        manager.add_client(&observer).await;
+        manager.add_client(&latecomer).await;
+
+        let lobby_id = manager.create_started_lobby(
+            "Running".to_string(),
+            default_test_settings(),
+            4,
+            &[host],
+            &[],
+            0,
+        ).await.unwrap();
+
+        let player_result = manager.join_lobby(lobby_id.clone(), latecomer, false).await;
+        assert!(player_result.is_err());
+
+        let details = manager.join_lobby(lobby_id, observer, true).await.unwrap();
+        assert_eq!(details.observers.len(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_assign_team_host_assigns_team_shown_in_details() {
+        let manager = LobbyManager::new();
+        let host = ClientId::new("host".to_string());
+        let guest = ClientId::new("guest".to_string());
+        manager.add_client(&host).await;
+        manager.add_client(&guest).await;
+
+        let details = manager.create_lobby(
+            "Teams".to_string(),
+            4,
+            default_test_settings(),
+            0,
+            None,
+            host.clone(),
+        ).await.unwrap();
+        manager.join_lobby(LobbyId::new(details.lobby_id), guest.clone(), false).await.unwrap();
+
+        assert!(manager.assign_team(&guest, "host".to_string(), 1).await.is_err());
+        assert!(manager.assign_team(&host, "guest".to_string(), 5).await.is_err());
+        assert!(manager.assign_team(&host, "nobody".to_string(), 1).await.is_err());
+
+        let details = manager.assign_team(&host, "guest".to_string(), 2).await.unwrap();
+        let guest_info = details.players.iter()
+            .find(|p| p.identity.as_ref().unwrap().player_id == "guest")
+            .unwrap();
+        assert_eq!(guest_info.team, 2);
+
+        let details = manager.assign_team(&host, "guest".to_string(), 0).await.unwrap();
+        assert!(details.players.iter().all(|p| p.team == 0));
+    }
+
+    #[tokio::test]
+    async fn test_add_bot_tictactoe_strength_shown_in_details() {
+        use crate::games::tictactoe::BotStrength;
+        use crate::proto::tictactoe::TicTacToeBotLevel;
+
+        let manager = LobbyManager::new();
+        let host = ClientId::new("host".to_string());
+        manager.add_client(&host).await;
+        manager.create_lobby(
+            "Bots".to_string(),
+            2,
+            LobbySettings::TicTacToe(crate::TicTacToeLobbySettings {
+                field_width: 3,
+                field_height: 3,
+                win_count: 3,
+                ..Default::default()
+            }),
+            0,
+            None,
+            host.clone(),
+        ).await.unwrap();
+
+        let medium = BotStrength::preset(TicTacToeBotLevel::TictactoeBotLevelMedium);
+        let (details, _) = manager
+            .add_bot(&host, BotType::TicTacToe(crate::TicTacToeBotType::TictactoeBotTypeMinimax, medium))
+            .await
+            .unwrap();
+
+        let host_info = details.players.iter().find(|p| !p.identity.as_ref().unwrap().is_bot).unwrap();
+        let bot_info = details.players.iter().find(|p| p.identity.as_ref().unwrap().is_bot).unwrap();
+        assert_eq!(host_info.tictactoe_bot_strength, None);
+        assert_eq!(bot_info.tictactoe_bot_strength, Some(medium.to_proto()));
+    }
+
+    #[tokio::test]
+    async fn test_hold_seat_reconnected_player_still_in_lobby_after_game() {
+        let manager = LobbyManager::new();
+        let host = ClientId::new("host".to_string());
+        let guest = ClientId::new("guest".to_string());
+        manager.add_client(&host).await;
+        manager.add_client(&guest).await;
+
+        let details = manager.create_lobby(
+            "Takeover".to_string(),
+            4,
+            default_test_settings(),
+            0,
+            Some(BotTakeover { idle_limit: 0 }),
+            host.clone(),
+        ).await.unwrap();
+        let lobby_id = LobbyId::new(details.lobby_id);
+        manager.join_lobby(lobby_id.clone(), guest.clone(), false).await.unwrap();
+        manager.mark_ready(&guest, true).await.unwrap();
+        manager.start_game(&host).await.unwrap();
+
+        manager.hold_seat(&guest).await;
+        assert!(manager.add_client(&guest).await);
+        assert!(!manager.add_client(&guest).await);
+
+        manager.end_game(&lobby_id).await.unwrap();
+        let details = manager.get_client_lobby(&guest).await.unwrap();
+        assert_eq!(details.lobby_id, lobby_id.to_string());
+        assert!(details.players.iter().any(|p| p.identity.as_ref().unwrap().player_id == "guest"));
+        assert!(manager.release_held_seats(&lobby_id).await.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_release_held_seats_returns_away_players_once() {
+        let manager = LobbyManager::new();
+        let host = ClientId::new("host".to_string());
+        let guest = ClientId::new("guest".to_string());
+        manager.add_client(&host).await;
+        manager.add_client(&guest).await;
+
+        let details = manager.create_lobby(
+            "Takeover".to_string(),
+            4,
+            default_test_settings(),
+            0,
+            Some(BotTakeover { idle_limit: 0 }),
+            host.clone(),
+        ).await.unwrap();
+        let lobby_id = LobbyId::new(details.lobby_id);
+        manager.join_lobby(lobby_id.clone(), guest.clone(), false).await.unwrap();
+        manager.mark_ready(&guest, true).await.unwrap();
+        manager.start_game(&host).await.unwrap();
+
+        manager.hold_seat(&host).await;
+        manager.end_game(&lobby_id).await.unwrap();
+
+        assert_eq!(manager.release_held_seats(&lobby_id).await, vec![host.clone()]);
+        assert!(manager.release_held_seats(&lobby_id).await.is_empty());
+    }
+
     #[tokio::test]
     async fn test_list_lobbies_empty_empty_list_returned() {
         let manager = LobbyManager::new();
@@ -8,4 +8,4 @@ pub use bot_type::BotType;
 pub use identity::PlayerIdentity;
 pub use manager::LobbyManager;
 pub use settings::LobbySettings;
-pub use state::{Lobby, LobbyStateAfterLeave, PlayAgainStatus};
+pub use state::{BotTakeover, Lobby, LobbyStateAfterLeave, PlayAgainStatus, validate_spectator_delay};
@@ -5,7 +5,7 @@ use crate::{
     validate_lobby_settings::ValidateLobbySettings,
 };
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq)]
 pub enum LobbySettings {
     Snake(SnakeLobbySettings),
     TicTacToe(TicTacToeLobbySettings),
@@ -25,9 +25,21 @@ impl LobbySettings {
         }
     }
 
+    /// Whether a game of these settings can be played by `player_count` seats.
+    pub fn validate_player_count(&self, player_count: usize) -> Result<(), String> {
+        use crate::games::LobbySettings as GameSettings;
+        match self {
+            LobbySettings::Snake(s) => GameSettings::validate_player_count(s, player_count),
+            LobbySettings::TicTacToe(t) => GameSettings::validate_player_count(t, player_count),
+            LobbySettings::NumbersMatch(n) => GameSettings::validate_player_count(n, player_count),
+            LobbySettings::StackAttack(s) => GameSettings::validate_player_count(s, player_count),
+            LobbySettings::Puzzle2048(p) => GameSettings::validate_player_count(p, player_count),
+        }
+    }
+
     pub fn to_proto(&self) -> Option<lobby_details::Settings> {
         match self {
-            LobbySettings::Snake(s) => Some(lobby_details::Settings::Snake(*s)),
+            LobbySettings::Snake(s) => Some(lobby_details::Settings::Snake(s.clone())),
             LobbySettings::TicTacToe(t) => Some(lobby_details::Settings::Tictactoe(*t)),
             LobbySettings::NumbersMatch(n) => Some(lobby_details::Settings::NumbersMatch(*n)),
             LobbySettings::StackAttack(s) => Some(lobby_details::Settings::StackAttack(*s)),
@@ -38,7 +50,7 @@ impl LobbySettings {
     pub fn to_info_proto(&self) -> Option<crate::proto::game_service::LobbySettings> {
         Some(crate::proto::game_service::LobbySettings {
             settings: Some(match self {
-                LobbySettings::Snake(s) => lobby_settings::Settings::Snake(*s),
+                LobbySettings::Snake(s) => lobby_settings::Settings::Snake(s.clone()),
                 LobbySettings::TicTacToe(t) => lobby_settings::Settings::Tictactoe(*t),
                 LobbySettings::NumbersMatch(n) => lobby_settings::Settings::NumbersMatch(*n),
                 LobbySettings::StackAttack(s) => lobby_settings::Settings::StackAttack(*s),
@@ -57,4 +69,40 @@ impl LobbySettings {
             None => Err("No settings provided".to_string()),
         }
     }
+
+    /// Like `from_proto`, but drops what only a replay recording may carry.
+    pub fn from_client_proto(settings: Option<lobby_settings::Settings>) -> Result<Self, String> {
+        let mut settings = Self::from_proto(settings)?;
+        if let LobbySettings::Snake(s) = &mut settings {
+            s.map = None;
+        }
+        Ok(settings)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_from_client_proto_drops_embedded_snake_map() {
+        let settings = SnakeLobbySettings {
+            map_name: "box".to_string(),
+            map: Some(crate::proto::snake::SnakeMap::default()),
+            ..Default::default()
+        };
+
+        let proto = Some(lobby_settings::Settings::Snake(settings));
+
+        let LobbySettings::Snake(client) = LobbySettings::from_client_proto(proto.clone()).unwrap() else {
+            panic!("Expected Snake settings");
+        };
+        assert_eq!(client.map, None);
+        assert_eq!(client.map_name, "box");
+
+        let LobbySettings::Snake(replayed) = LobbySettings::from_proto(proto).unwrap() else {
+            panic!("Expected Snake settings");
+        };
+        assert!(replayed.map.is_some());
+    }
 }
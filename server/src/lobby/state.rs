@@ -3,6 +3,36 @@ use crate::{LobbyInfo, LobbyDetails, PlayerInfo, ClientId, LobbyId, PlayerId, Bo
 use crate::id_generator::generate_client_id;
 use super::{LobbySettings, BotType, PlayerIdentity};
 
+pub const MAX_SPECTATOR_DELAY: u32 = 100;
+
+pub fn validate_spectator_delay(spectator_delay: u32) -> Result<(), String> {
+    if spectator_delay > MAX_SPECTATOR_DELAY {
+        return Err(format!("Spectator delay must be at most {}", MAX_SPECTATOR_DELAY));
+    }
+    Ok(())
+}
+
+pub const MAX_BOT_TAKEOVER_IDLE: u32 = 10_000;
+
+/// A server bot plays for humans who disconnect or send no input for `idle_limit` turns. In Snake
+/// these are the ticks on which the player could have turned; ticks stuck going straight don't count.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct BotTakeover {
+    pub idle_limit: u32,
+}
+
+impl BotTakeover {
+    pub fn from_request(enabled: bool, idle_limit: u32) -> Result<Option<Self>, String> {
+        if !enabled {
+            return Ok(None);
+        }
+        if idle_limit > MAX_BOT_TAKEOVER_IDLE {
+            return Err(format!("Bot takeover idle limit must be at most {}", MAX_BOT_TAKEOVER_IDLE));
+        }
+        Ok(Some(Self { idle_limit }))
+    }
+}
+
 #[derive(Debug, Clone)]
 pub struct Lobby {
     pub id: LobbyId,
@@ -17,6 +47,10 @@ pub struct Lobby {
     pub play_again_votes: HashSet<PlayerId>,
     pub original_game_players: HashSet<PlayerId>,
     pub is_replay_lobby: bool,
+    pub spectator_delay: u32,
+    pub bot_takeover: Option<BotTakeover>,
+    /// Team of each player or bot the host put on one; bots are keyed by their player id.
+    pub teams: HashMap<PlayerId, u32>,
 }
 
 #[derive(Debug)]
@@ -49,6 +83,9 @@ impl Lobby {
             play_again_votes: HashSet::new(),
             original_game_players: HashSet::new(),
             is_replay_lobby: false,
+            spectator_delay: 0,
+            bot_takeover: None,
+            teams: HashMap::new(),
         }
     }
 
@@ -61,6 +98,9 @@ impl Lobby {
             observer_count: self.observers.len() as u32,
             settings: self.settings.to_info_proto(),
             is_replay_lobby: self.is_replay_lobby,
+            spectator_delay: self.spectator_delay,
+            bot_takeover: self.bot_takeover.is_some(),
+            bot_takeover_idle: self.bot_takeover.map_or(0, |t| t.idle_limit),
         }
     }
 
@@ -71,6 +111,8 @@ impl Lobby {
             all_players.push(PlayerInfo {
                 identity: Some(PlayerIdentity::Player(player_id.clone()).to_proto()),
                 ready: *ready,
+                team: self.teams.get(player_id).copied().unwrap_or(0),
+                tictactoe_bot_strength: None,
             });
         }
 
@@ -81,6 +123,11 @@ impl Lobby {
                     bot_type: *bot_type
                 }.to_proto()),
                 ready: true,
+                team: self.teams.get(&bot_id.to_player_id()).copied().unwrap_or(0),
+                tictactoe_bot_strength: match bot_type {
+                    BotType::TicTacToe(_, strength) => Some(strength.to_proto()),
+                    _ => None,
+                },
             });
         }
 
@@ -105,6 +152,9 @@ impl Lobby {
             settings: self.settings.to_proto(),
             creator: Some(creator_identity),
             is_replay_lobby: self.is_replay_lobby,
+            spectator_delay: self.spectator_delay,
+            bot_takeover: self.bot_takeover.is_some(),
+            bot_takeover_idle: self.bot_takeover.map_or(0, |t| t.idle_limit),
         }
     }
 
@@ -120,13 +170,30 @@ impl Lobby {
     }
 
     pub fn remove_player(&mut self, player_id: &PlayerId) -> bool {
+        self.teams.remove(player_id);
         self.players.remove(player_id).is_some()
     }
 
     pub fn remove_bot(&mut self, bot_id: &BotId) -> bool {
+        self.teams.remove(&bot_id.to_player_id());
         self.bots.remove(bot_id).is_some()
     }
 
+    /// Puts a player or bot on `team`, or off their team when it is 0.
+    pub fn assign_team(&mut self, player_id: &PlayerId, team: u32) -> bool {
+        let in_lobby = self.players.contains_key(player_id)
+            || self.bots.keys().any(|bot_id| bot_id.to_player_id() == *player_id);
+        if !in_lobby {
+            return false;
+        }
+        if team == 0 {
+            self.teams.remove(player_id);
+        } else {
+            self.teams.insert(player_id.clone(), team);
+        }
+        true
+    }
+
     pub fn set_ready(&mut self, player_id: &PlayerId, ready: bool) -> bool {
         if let Some(player_ready) = self.players.get_mut(player_id) {
             *player_ready = ready;
@@ -174,6 +241,7 @@ impl Lobby {
 
     pub fn player_to_observer(&mut self, player_id: &PlayerId) -> bool {
         if self.players.remove(player_id).is_some() {
+            self.teams.remove(player_id);
             self.observers.insert(player_id.clone());
             true
         } else {
@@ -5,14 +5,18 @@ static LOGGER: OnceLock<Logger> = OnceLock::new();
 
 pub struct Logger {
     prefix: Option<String>,
+    silent: bool,
 }
 
 impl Logger {
     fn new(prefix: Option<String>) -> Self {
-        Self { prefix }
+        Self { prefix, silent: false }
     }
 
     pub fn log(&self, file: &str, line: u32, message: &str) {
+        if self.silent {
+            return;
+        }
         let timestamp = Local::now().format("%Y-%m-%d %H:%M:%S");
         let file_name = file.rsplit(['/', '\\']).next().unwrap_or(file);
         if let Some(ref prefix) = self.prefix {
@@ -27,6 +31,11 @@ pub fn init_logger(prefix: Option<String>) {
     LOGGER.get_or_init(|| Logger::new(prefix));
 }
 
+/// For command-line tools that run game code but print their own output.
+pub fn init_silent_logger() {
+    LOGGER.get_or_init(|| Logger { prefix: None, silent: true });
+}
+
 pub fn log(file: &str, line: u32, message: &str) {
     if let Some(logger) = LOGGER.get() {
         logger.log(file, line, message);
@@ -50,8 +50,8 @@ pub mod proto {
         Puzzle2048GameEndReason, Puzzle2048GameEndInfo,
     };
     pub use replay::{
-        Game as ReplayGame, PlayerAction, PlayerActionContent, PlayerDisconnected,
-        ReplayV1, ReplayV1Metadata, ReplayV1Header,
+        Game as ReplayGame, PlayerAction, PlayerActionContent, PlayerDisconnected, PlayerTimedOut,
+        PlayerControlChanged, ReplayV1, StateHash, ReplayV1Metadata, ReplayV1Header,
     };
     pub use replay::player_action_content;
 }
@@ -66,11 +66,14 @@ pub mod config;
 pub mod version;
 pub mod validate_lobby_settings;
 pub mod lobby;
+pub mod matchmaking;
+pub mod tournament;
 pub mod replay;
 pub mod games;
 
 mod broadcaster;
 mod cleanup_task;
+mod matchmaking_task;
 mod server_config;
 mod game_session_manager;
 mod grpc_service;
@@ -85,6 +88,8 @@ use clap::Parser;
 use game_session_manager::GameSessionManager;
 use grpc_service::GrpcService;
 use lobby::LobbyManager;
+use matchmaking::MatchmakingManager;
+use tournament::TournamentManager;
 use tonic::transport::Server;
 
 #[derive(Parser)]
@@ -95,6 +100,13 @@ struct Args {
 
     #[arg(long, default_value = "./web-client/dist")]
     static_files_path: PathBuf,
+
+    #[arg(long, default_value_t = server_config::MATCHMAKING_BOT_BACKFILL_WAIT.as_secs())]
+    matchmaking_bot_wait_secs: u64,
+
+    /// Directory of extra Snake map files, loaded on top of the bundled maps.
+    #[arg(long)]
+    snake_maps_dir: Option<PathBuf>,
 }
 
 #[tokio::main]
@@ -108,12 +120,28 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
     };
     logger::init_logger(prefix);
 
+    let map_count = games::snake::init_maps(args.snake_maps_dir.as_deref())?;
+    log!("Loaded {} Snake maps", map_count);
+
     let addr = "0.0.0.0:5001".parse()?;
     let lobby_manager = LobbyManager::new();
     let broadcaster = Broadcaster::new();
-    let session_manager = GameSessionManager::new(broadcaster.clone(), lobby_manager.clone());
+    let tournament_manager = TournamentManager::new();
+    let session_manager = GameSessionManager::new(
+        broadcaster.clone(),
+        lobby_manager.clone(),
+        tournament_manager.clone(),
+    );
+    let matchmaking_manager =
+        MatchmakingManager::new(std::time::Duration::from_secs(args.matchmaking_bot_wait_secs));
 
-    let service = GrpcService::new(lobby_manager.clone(), broadcaster.clone(), session_manager.clone());
+    let service = GrpcService::new(
+        lobby_manager.clone(),
+        broadcaster.clone(),
+        session_manager.clone(),
+        matchmaking_manager.clone(),
+        tournament_manager.clone(),
+    );
 
     let cleanup_task = cleanup_task::CleanupTask::new(
         lobby_manager.clone(),
@@ -125,6 +153,17 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
         cleanup_task.run().await;
     });
 
+    let matchmaking_task = matchmaking_task::MatchmakingTask::new(
+        matchmaking_manager.clone(),
+        lobby_manager.clone(),
+        broadcaster.clone(),
+        session_manager.clone(),
+        server_config::MATCHMAKING_CHECK_INTERVAL,
+    );
+    tokio::spawn(async move {
+        matchmaking_task.run().await;
+    });
+
     log!("Mini Games Server - gRPC on {}, Web/WebSocket on 0.0.0.0:5000", addr);
 
     let broadcaster_clone = broadcaster.clone();
@@ -156,6 +195,8 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
         lobby_manager,
         broadcaster,
         session_manager,
+        matchmaking_manager,
+        tournament_manager,
         args.static_files_path,
     );
 
@@ -0,0 +1,351 @@
+use std::collections::HashMap;
+use std::mem::Discriminant;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use tokio::sync::Mutex;
+use crate::{ClientId, QueueStatusNotification, SnakeBotType, TicTacToeBotType};
+use crate::games::tictactoe::BotStrength;
+use crate::lobby::{BotType, LobbySettings};
+
+const WAIT_AVERAGE_WEIGHT: f64 = 0.3;
+
+#[derive(Debug, Clone)]
+struct QueueEntry {
+    client_id: ClientId,
+    settings: LobbySettings,
+    max_players: u32,
+    joined_at: Instant,
+}
+
+impl QueueEntry {
+    fn is_compatible(&self, other: &QueueEntry) -> bool {
+        self.max_players == other.max_players && self.settings == other.settings
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct PendingMatch {
+    pub settings: LobbySettings,
+    pub max_players: u32,
+    pub players: Vec<ClientId>,
+    /// When each of `players` joined the queue, to put them back in their
+    /// place if the match can't start.
+    pub joined_at: Vec<Instant>,
+    pub bots: Vec<BotType>,
+}
+
+#[derive(Debug)]
+struct MatchmakingState {
+    queue: Vec<QueueEntry>,
+    average_wait: HashMap<Discriminant<LobbySettings>, Duration>,
+}
+
+#[derive(Debug, Clone)]
+pub struct MatchmakingManager {
+    state: Arc<Mutex<MatchmakingState>>,
+    bot_backfill_wait: Duration,
+}
+
+impl MatchmakingManager {
+    pub fn new(bot_backfill_wait: Duration) -> Self {
+        Self {
+            state: Arc::new(Mutex::new(MatchmakingState {
+                queue: Vec::new(),
+                average_wait: HashMap::new(),
+            })),
+            bot_backfill_wait,
+        }
+    }
+
+    pub async fn join_queue(&self, client_id: &ClientId, settings: LobbySettings, max_players: u32) -> Result<(), String> {
+        settings.validate(max_players)?;
+        // Matches are cut into chunks of `max_players`, so it must be a seat
+        // count the game can actually be played with.
+        settings.validate_player_count(max_players as usize)?;
+
+        let mut state = self.state.lock().await;
+
+        if state.queue.iter().any(|e| &e.client_id == client_id) {
+            return Err("Already in matchmaking queue".to_string());
+        }
+
+        state.queue.push(QueueEntry {
+            client_id: client_id.clone(),
+            settings,
+            max_players,
+            joined_at: Instant::now(),
+        });
+
+        Ok(())
+    }
+
+    pub async fn leave_queue(&self, client_id: &ClientId) -> bool {
+        let mut state = self.state.lock().await;
+        let before = state.queue.len();
+        state.queue.retain(|e| &e.client_id != client_id);
+        state.queue.len() != before
+    }
+
+    pub async fn is_queued(&self, client_id: &ClientId) -> bool {
+        let state = self.state.lock().await;
+        state.queue.iter().any(|e| &e.client_id == client_id)
+    }
+
+    pub async fn collect_matches(&self, now: Instant) -> Vec<PendingMatch> {
+        let mut state = self.state.lock().await;
+        let mut matches = Vec::new();
+        let mut matched_indices = Vec::new();
+
+        for group in group_compatible(&state.queue) {
+            let first = &state.queue[group[0]];
+            let match_size = first.max_players as usize;
+            let settings = first.settings.clone();
+            let max_players = first.max_players;
+
+            for chunk in group.chunks(match_size) {
+                let is_full = chunk.len() == match_size;
+                let oldest_waited = now.saturating_duration_since(state.queue[chunk[0]].joined_at);
+
+                if !is_full && oldest_waited < self.bot_backfill_wait {
+                    continue;
+                }
+
+                let bots = if is_full {
+                    Vec::new()
+                } else {
+                    backfill_bot_type(&settings)
+                        .map(|bot_type| vec![bot_type; match_size - chunk.len()])
+                        .unwrap_or_default()
+                };
+
+                matches.push(PendingMatch {
+                    settings: settings.clone(),
+                    max_players,
+                    players: chunk.iter().map(|&i| state.queue[i].client_id.clone()).collect(),
+                    joined_at: chunk.iter().map(|&i| state.queue[i].joined_at).collect(),
+                    bots,
+                });
+                matched_indices.extend_from_slice(chunk);
+            }
+        }
+
+        for &index in &matched_indices {
+            let entry = &state.queue[index];
+            let waited = now.saturating_duration_since(entry.joined_at);
+            let key = std::mem::discriminant(&entry.settings);
+            let average = match state.average_wait.get(&key) {
+                Some(previous) => previous.mul_f64(1.0 - WAIT_AVERAGE_WEIGHT) + waited.mul_f64(WAIT_AVERAGE_WEIGHT),
+                None => waited,
+            };
+            state.average_wait.insert(key, average);
+        }
+
+        let mut index = 0;
+        state.queue.retain(|_| {
+            let keep = !matched_indices.contains(&index);
+            index += 1;
+            keep
+        });
+
+        matches
+    }
+
+    /// Puts the players of a match that couldn't start back in the queue with
+    /// their original join times, except `dropped`.
+    pub async fn requeue(&self, pending_match: &PendingMatch, dropped: &[ClientId]) {
+        let mut state = self.state.lock().await;
+
+        for (client_id, &joined_at) in pending_match.players.iter().zip(&pending_match.joined_at) {
+            if dropped.contains(client_id) || state.queue.iter().any(|e| &e.client_id == client_id) {
+                continue;
+            }
+            state.queue.push(QueueEntry {
+                client_id: client_id.clone(),
+                settings: pending_match.settings.clone(),
+                max_players: pending_match.max_players,
+                joined_at,
+            });
+        }
+
+        state.queue.sort_by_key(|e| e.joined_at);
+    }
+
+    pub async fn queue_statuses(&self, now: Instant) -> Vec<(ClientId, QueueStatusNotification)> {
+        let state = self.state.lock().await;
+        let mut statuses = Vec::new();
+
+        for group in group_compatible(&state.queue) {
+            let first = &state.queue[group[0]];
+            let match_size = first.max_players as usize;
+            let average = state.average_wait.get(&std::mem::discriminant(&first.settings)).copied();
+
+            for (position, &index) in group.iter().enumerate() {
+                let entry = &state.queue[index];
+                let waited = now.saturating_duration_since(entry.joined_at);
+                let group_start = position - position % match_size;
+                let chunk_len = (group.len() - group_start).min(match_size);
+
+                let mut estimate = self.bot_backfill_wait.saturating_sub(
+                    now.saturating_duration_since(state.queue[group[group_start]].joined_at),
+                );
+                if let Some(average) = average {
+                    estimate = estimate.min(average.saturating_sub(waited));
+                }
+
+                statuses.push((
+                    entry.client_id.clone(),
+                    QueueStatusNotification {
+                        in_queue: true,
+                        position: (position + 1) as u32,
+                        players_in_queue: group.len() as u32,
+                        players_needed: (match_size - chunk_len) as u32,
+                        waited_seconds: waited.as_secs() as u32,
+                        estimated_wait_seconds: estimate.as_secs() as u32,
+                    },
+                ));
+            }
+        }
+
+        statuses
+    }
+}
+
+fn group_compatible(queue: &[QueueEntry]) -> Vec<Vec<usize>> {
+    let mut groups: Vec<Vec<usize>> = Vec::new();
+
+    for (index, entry) in queue.iter().enumerate() {
+        match groups.iter_mut().find(|g| queue[g[0]].is_compatible(entry)) {
+            Some(group) => group.push(index),
+            None => groups.push(vec![index]),
+        }
+    }
+
+    groups
+}
+
+pub fn backfill_bot_type(settings: &LobbySettings) -> Option<BotType> {
+    match settings {
+        LobbySettings::Snake(_) => Some(BotType::Snake(SnakeBotType::SpaceAware)),
+        LobbySettings::TicTacToe(_) => Some(BotType::TicTacToe(TicTacToeBotType::TictactoeBotTypeMinimax, BotStrength::default())),
+        LobbySettings::NumbersMatch(_) | LobbySettings::StackAttack(_) | LobbySettings::Puzzle2048(_) => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{FirstPlayerMode, TicTacToeLobbySettings};
+
+    fn tictactoe_settings(field_size: u32) -> LobbySettings {
+        LobbySettings::TicTacToe(TicTacToeLobbySettings {
+            field_width: field_size,
+            field_height: field_size,
+            win_count: 3,
+            first_player: FirstPlayerMode::Random.into(),
+            ..Default::default()
+        })
+    }
+
+    #[tokio::test]
+    async fn test_collect_matches_compatible_players_matched() {
+        let manager = MatchmakingManager::new(Duration::from_secs(30));
+        let first = ClientId::new("first".to_string());
+        let second = ClientId::new("second".to_string());
+
+        manager.join_queue(&first, tictactoe_settings(3), 2).await.unwrap();
+        manager.join_queue(&second, tictactoe_settings(3), 2).await.unwrap();
+
+        let matches = manager.collect_matches(Instant::now()).await;
+
+        assert_eq!(matches.len(), 1);
+        assert_eq!(matches[0].players, vec![first.clone(), second]);
+        assert!(matches[0].bots.is_empty());
+        assert!(!manager.is_queued(&first).await);
+    }
+
+    #[tokio::test]
+    async fn test_collect_matches_incompatible_settings_not_matched() {
+        let manager = MatchmakingManager::new(Duration::from_secs(30));
+
+        manager.join_queue(&ClientId::new("first".to_string()), tictactoe_settings(3), 2).await.unwrap();
+        manager.join_queue(&ClientId::new("second".to_string()), tictactoe_settings(5), 2).await.unwrap();
+
+        let matches = manager.collect_matches(Instant::now()).await;
+
+        assert!(matches.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_collect_matches_wait_expired_bots_added() {
+        let manager = MatchmakingManager::new(Duration::from_secs(30));
+        let client_id = ClientId::new("lonely".to_string());
+
+        manager.join_queue(&client_id, tictactoe_settings(3), 2).await.unwrap();
+
+        assert!(manager.collect_matches(Instant::now()).await.is_empty());
+
+        let matches = manager.collect_matches(Instant::now() + Duration::from_secs(31)).await;
+
+        assert_eq!(matches.len(), 1);
+        assert_eq!(matches[0].players, vec![client_id]);
+        assert_eq!(matches[0].bots, vec![BotType::TicTacToe(TicTacToeBotType::TictactoeBotTypeMinimax, BotStrength::default())]);
+    }
+
+    #[tokio::test]
+    async fn test_queue_statuses_waiting_player_position_reported() {
+        let manager = MatchmakingManager::new(Duration::from_secs(30));
+        let client_id = ClientId::new("waiting".to_string());
+
+        manager.join_queue(&client_id, tictactoe_settings(3), 2).await.unwrap();
+
+        let statuses = manager.queue_statuses(Instant::now()).await;
+
+        assert_eq!(statuses.len(), 1);
+        assert_eq!(statuses[0].0, client_id);
+        assert_eq!(statuses[0].1.position, 1);
+        assert_eq!(statuses[0].1.players_needed, 1);
+        assert!(statuses[0].1.estimated_wait_seconds <= 30);
+    }
+
+    #[tokio::test]
+    async fn test_requeue_failed_match_keeps_place_and_drops_at_fault_client() {
+        let manager = MatchmakingManager::new(Duration::from_secs(30));
+        let first = ClientId::new("first".to_string());
+        let busy = ClientId::new("busy".to_string());
+        let later = ClientId::new("later".to_string());
+
+        manager.join_queue(&first, tictactoe_settings(3), 2).await.unwrap();
+        manager.join_queue(&busy, tictactoe_settings(3), 2).await.unwrap();
+        let pending = manager.collect_matches(Instant::now()).await.remove(0);
+        manager.join_queue(&later, tictactoe_settings(3), 2).await.unwrap();
+
+        manager.requeue(&pending, std::slice::from_ref(&busy)).await;
+
+        assert!(!manager.is_queued(&busy).await);
+        let statuses = manager.queue_statuses(Instant::now()).await;
+        let positions: Vec<(ClientId, u32)> = statuses.into_iter().map(|(id, s)| (id, s.position)).collect();
+        assert_eq!(positions, vec![(first.clone(), 1), (later.clone(), 2)]);
+
+        let matches = manager.collect_matches(Instant::now()).await;
+        assert_eq!(matches[0].players, vec![first, later]);
+        assert_eq!(matches[0].joined_at[0], pending.joined_at[0]);
+    }
+
+    #[tokio::test]
+    async fn test_join_queue_unplayable_seat_count_rejected() {
+        let manager = MatchmakingManager::new(Duration::from_secs(30));
+        let client_id = ClientId::new("solo".to_string());
+        let puzzle = LobbySettings::Puzzle2048(crate::Puzzle2048LobbySettings {
+            field_width: 4,
+            field_height: 4,
+            target_value: 2048,
+        });
+
+        assert!(manager.join_queue(&client_id, puzzle.clone(), 0).await.is_err());
+        assert!(manager.join_queue(&client_id, puzzle.clone(), 2).await.is_err());
+        assert!(!manager.is_queued(&client_id).await);
+
+        manager.join_queue(&client_id, puzzle, 1).await.unwrap();
+        assert_eq!(manager.collect_matches(Instant::now()).await.len(), 1);
+    }
+}
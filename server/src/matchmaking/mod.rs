@@ -0,0 +1,3 @@
+mod manager;
+
+pub use manager::{MatchmakingManager, PendingMatch, backfill_bot_type};
@@ -0,0 +1,108 @@
+use std::time::{Duration, Instant};
+
+use crate::{log, server_message, ServerMessage};
+
+use crate::broadcaster::Broadcaster;
+use crate::game_session_manager::GameSessionManager;
+use crate::lobby::LobbyManager;
+use crate::matchmaking::{MatchmakingManager, PendingMatch};
+
+pub struct MatchmakingTask {
+    matchmaking_manager: MatchmakingManager,
+    lobby_manager: LobbyManager,
+    broadcaster: Broadcaster,
+    session_manager: GameSessionManager,
+    check_interval: Duration,
+}
+
+impl MatchmakingTask {
+    pub fn new(
+        matchmaking_manager: MatchmakingManager,
+        lobby_manager: LobbyManager,
+        broadcaster: Broadcaster,
+        session_manager: GameSessionManager,
+        check_interval: Duration,
+    ) -> Self {
+        Self {
+            matchmaking_manager,
+            lobby_manager,
+            broadcaster,
+            session_manager,
+            check_interval,
+        }
+    }
+
+    pub async fn run(&self) {
+        let mut interval = tokio::time::interval(self.check_interval);
+
+        loop {
+            interval.tick().await;
+
+            let now = Instant::now();
+            for pending_match in self.matchmaking_manager.collect_matches(now).await {
+                self.start_match(pending_match).await;
+            }
+
+            self.send_queue_statuses(now).await;
+        }
+    }
+
+    async fn start_match(&self, pending_match: PendingMatch) {
+        let lobby_id = match self
+            .lobby_manager
+            .create_started_lobby(
+                "Quick Play".to_string(),
+                pending_match.settings.clone(),
+                pending_match.max_players,
+                &pending_match.players,
+                &pending_match.bots,
+                0,
+            )
+            .await
+        {
+            Ok(lobby_id) => lobby_id,
+            Err(e) => {
+                log!("Failed to create matchmaking lobby: {}", e);
+                // Players who joined a lobby since they were matched leave the
+                // queue; everyone else keeps their place.
+                let mut busy = Vec::new();
+                for client_id in &pending_match.players {
+                    if self.lobby_manager.get_client_lobby(client_id).await.is_some() {
+                        busy.push(client_id.clone());
+                    }
+                }
+                self.matchmaking_manager.requeue(&pending_match, &busy).await;
+
+                let left_queue_msg = ServerMessage {
+                    message: Some(server_message::Message::QueueStatus(
+                        crate::QueueStatusNotification::default(),
+                    )),
+                };
+                self.broadcaster.broadcast_to_clients(&busy, left_queue_msg).await;
+                return;
+            }
+        };
+
+        log!(
+            "Matchmaking started lobby {} with {} players and {} bots",
+            lobby_id,
+            pending_match.players.len(),
+            pending_match.bots.len()
+        );
+
+        self.session_manager.start_prepared_lobby(&lobby_id).await;
+    }
+
+    async fn send_queue_statuses(&self, now: Instant) {
+        for (client_id, status) in self.matchmaking_manager.queue_statuses(now).await {
+            self.broadcaster
+                .send_to_client(
+                    &client_id,
+                    ServerMessage {
+                        message: Some(server_message::Message::QueueStatus(status)),
+                    },
+                )
+                .await;
+        }
+    }
+}
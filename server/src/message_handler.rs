@@ -8,7 +8,9 @@ use crate::{
 
 use crate::broadcaster::Broadcaster;
 use crate::game_session_manager::GameSessionManager;
-use crate::lobby::{BotType, LobbyManager, LobbyStateAfterLeave, PlayAgainStatus, LobbySettings};
+use crate::lobby::{BotTakeover, BotType, LobbyManager, PlayAgainStatus, LobbySettings};
+use crate::matchmaking::MatchmakingManager;
+use crate::tournament::{self, TournamentManager};
 
 pub type ClientSender = mpsc::Sender<Result<ServerMessage, Status>>;
 
@@ -16,6 +18,8 @@ pub struct MessageHandler {
     lobby_manager: LobbyManager,
     broadcaster: Broadcaster,
     session_manager: GameSessionManager,
+    matchmaking_manager: MatchmakingManager,
+    tournament_manager: TournamentManager,
 }
 
 pub enum HandleResult {
@@ -28,11 +32,15 @@ impl MessageHandler {
         lobby_manager: LobbyManager,
         broadcaster: Broadcaster,
         session_manager: GameSessionManager,
+        matchmaking_manager: MatchmakingManager,
+        tournament_manager: TournamentManager,
     ) -> Self {
         Self {
             lobby_manager,
             broadcaster,
             session_manager,
+            matchmaking_manager,
+            tournament_manager,
         }
     }
 
@@ -97,6 +105,7 @@ impl MessageHandler {
                     })),
                 };
                 self.broadcaster.send_to_client(&client_id, response).await;
+                self.session_manager.handle_player_reconnect(&client_id).await;
             }
             client_message::Message::Disconnect(_) => {
                 if let Some(client_id) = client_id_opt {
@@ -257,9 +266,77 @@ impl MessageHandler {
                     send_not_connected_error(tx, "make player observer").await;
                 }
             }
+            client_message::Message::AssignTeam(req) => {
+                if let Some(client_id) = client_id_opt {
+                    self.handle_assign_team(client_id, req).await;
+                } else {
+                    send_not_connected_error(tx, "assign team").await;
+                }
+            }
+            client_message::Message::JoinQueue(req) => {
+                if let Some(client_id) = client_id_opt {
+                    self.handle_join_queue(client_id, req).await;
+                } else {
+                    send_not_connected_error(tx, "join matchmaking queue").await;
+                }
+            }
+            client_message::Message::LeaveQueue(_) => {
+                if let Some(client_id) = client_id_opt {
+                    self.handle_leave_queue(client_id).await;
+                } else {
+                    send_not_connected_error(tx, "leave matchmaking queue").await;
+                }
+            }
+            client_message::Message::CreateTournament(req) => {
+                if let Some(client_id) = client_id_opt {
+                    self.handle_create_tournament(client_id, req).await;
+                } else {
+                    send_not_connected_error(tx, "create tournament").await;
+                }
+            }
+            client_message::Message::JoinTournament(req) => {
+                if let Some(client_id) = client_id_opt {
+                    self.handle_join_tournament(client_id, req).await;
+                } else {
+                    send_not_connected_error(tx, "join tournament").await;
+                }
+            }
+            client_message::Message::LeaveTournament(req) => {
+                if let Some(client_id) = client_id_opt {
+                    self.handle_leave_tournament(client_id, req).await;
+                } else {
+                    send_not_connected_error(tx, "leave tournament").await;
+                }
+            }
+            client_message::Message::StartTournament(req) => {
+                if let Some(client_id) = client_id_opt {
+                    self.handle_start_tournament(client_id, req).await;
+                } else {
+                    send_not_connected_error(tx, "start tournament").await;
+                }
+            }
+            client_message::Message::ListTournaments(_) => {
+                if let Some(client_id) = client_id_opt {
+                    let response = ServerMessage {
+                        message: Some(server_message::Message::TournamentList(
+                            crate::TournamentListResponse {
+                                tournaments: self.tournament_manager.list_tournaments().await,
+                            },
+                        )),
+                    };
+                    self.broadcaster.send_to_client(client_id, response).await;
+                } else {
+                    send_not_connected_error(tx, "list tournaments").await;
+                }
+            }
             client_message::Message::InReplay(cmd) => {
                 if let Some(client_id) = client_id_opt {
-                    self.session_manager.handle_replay_command(client_id, cmd).await;
+                    match &cmd.command {
+                        Some(crate::in_replay_command::Command::Branch(branch)) => {
+                            self.handle_branch_replay(client_id, branch.position).await;
+                        }
+                        _ => self.session_manager.handle_replay_command(client_id, cmd).await,
+                    }
                 } else {
                     send_not_connected_error(tx, "send replay command").await;
                 }
@@ -300,28 +377,30 @@ impl MessageHandler {
     }
 
     pub async fn handle_client_disconnected(&self, client_id: &ClientId) {
-        if let Ok(leave_state) = self.lobby_manager.leave_lobby(client_id).await {
-            self.broadcast_leave_lobby_result(client_id, leave_state)
-                .await;
+        self.matchmaking_manager.leave_queue(client_id).await;
+
+        for tournament_id in self.tournament_manager.withdraw_everywhere(client_id).await {
+            self.session_manager.start_tournament_games(&tournament_id).await;
+        }
+
+        // A player whose seat a bot took over keeps their lobby place, so
+        // they are still in the lobby if they come back before it ends.
+        if self.session_manager.handle_player_disconnect(client_id).await {
+            self.lobby_manager.hold_seat(client_id).await;
+        } else {
+            if let Ok(leave_state) = self.lobby_manager.leave_lobby(client_id).await {
+                self.session_manager.broadcast_leave_lobby_result(client_id, leave_state)
+                    .await;
+            }
+            self.lobby_manager.remove_client(client_id).await;
         }
 
-        self.lobby_manager.remove_client(client_id).await;
         self.broadcaster.unregister(client_id).await;
-        self.session_manager.handle_player_disconnect(client_id).await;
+        self.session_manager.remove_observer(client_id).await;
     }
 
     async fn notify_lobby_list_update(&self) {
-        let clients_not_in_lobbies = self.lobby_manager.get_clients_not_in_lobbies().await;
-        self.broadcaster
-            .broadcast_to_clients(
-                &clients_not_in_lobbies,
-                ServerMessage {
-                    message: Some(server_message::Message::LobbyListUpdate(
-                        crate::LobbyListUpdateNotification {},
-                    )),
-                },
-            )
-            .await;
+        self.session_manager.notify_lobby_list_update().await;
     }
 
     async fn handle_list_lobbies(&self, client_id: &ClientId) {
@@ -335,7 +414,12 @@ impl MessageHandler {
     }
 
     async fn handle_create_lobby(&self, client_id: &ClientId, request: crate::CreateLobbyRequest) {
-        let settings = match LobbySettings::from_proto(
+        if self.reject_if_in_tournament(client_id).await {
+            return;
+        }
+        self.leave_queue_if_queued(client_id).await;
+
+        let settings = match LobbySettings::from_client_proto(
             request.settings.and_then(|s| s.settings),
         ) {
             Ok(s) => s,
@@ -345,12 +429,22 @@ impl MessageHandler {
             }
         };
 
+        let bot_takeover = match BotTakeover::from_request(request.bot_takeover, request.bot_takeover_idle) {
+            Ok(t) => t,
+            Err(e) => {
+                self.send_error(client_id, e).await;
+                return;
+            }
+        };
+
         match self
             .lobby_manager
             .create_lobby(
                 request.lobby_name,
                 request.max_players,
                 settings,
+                request.spectator_delay,
+                bot_takeover,
                 client_id.clone(),
             )
             .await
@@ -373,11 +467,16 @@ impl MessageHandler {
     }
 
     async fn handle_join_lobby(&self, client_id: &ClientId, request: crate::JoinLobbyRequest) {
+        if self.reject_if_in_tournament(client_id).await {
+            return;
+        }
+        self.leave_queue_if_queued(client_id).await;
+
         let lobby_id = crate::LobbyId::new(request.lobby_id);
 
         match self
             .lobby_manager
-            .join_lobby(lobby_id, client_id.clone(), request.join_as_observer)
+            .join_lobby(lobby_id.clone(), client_id.clone(), request.join_as_observer)
             .await
         {
             Ok(lobby_details) => {
@@ -421,6 +520,10 @@ impl MessageHandler {
                         },
                     )
                     .await;
+
+                if request.join_as_observer {
+                    self.session_manager.add_observer(&lobby_id, client_id).await;
+                }
             }
             Err(e) => {
                 self.send_error(client_id, e).await;
@@ -428,18 +531,32 @@ impl MessageHandler {
         }
     }
 
-    async fn handle_leave_lobby(&self, client_id: &ClientId) {
-        match self.lobby_manager.leave_lobby(client_id).await {
-            Ok(leave_state) => {
-                let response = ServerMessage {
-                    message: Some(server_message::Message::LobbyList(crate::LobbyListResponse {
-                        lobbies: self.lobby_manager.list_lobbies().await,
-                    })),
-                };
-                self.broadcaster.send_to_client(client_id, response).await;
+    async fn handle_join_queue(&self, client_id: &ClientId, request: crate::JoinQueueRequest) {
+        if self.reject_if_in_tournament(client_id).await {
+            return;
+        }
+        if self.lobby_manager.get_client_lobby(client_id).await.is_some() {
+            self.send_error(client_id, "Cannot join queue while in a lobby".to_string()).await;
+            return;
+        }
 
-                self.broadcast_leave_lobby_result(client_id, leave_state)
-                    .await;
+        let settings = match LobbySettings::from_client_proto(
+            request.settings.and_then(|s| s.settings),
+        ) {
+            Ok(s) => s,
+            Err(e) => {
+                self.send_error(client_id, e).await;
+                return;
+            }
+        };
+
+        match self
+            .matchmaking_manager
+            .join_queue(client_id, settings, request.max_players)
+            .await
+        {
+            Ok(()) => {
+                log!("Client {} joined matchmaking queue", client_id);
             }
             Err(e) => {
                 self.send_error(client_id, e).await;
@@ -447,73 +564,160 @@ impl MessageHandler {
         }
     }
 
-    async fn broadcast_leave_lobby_result(
-        &self,
-        client_id: &ClientId,
-        leave_state: LobbyStateAfterLeave,
-    ) {
-        match leave_state {
-            LobbyStateAfterLeave::HostLeft { kicked_players } => {
-                self.broadcaster
-                    .broadcast_to_clients(
-                        &kicked_players,
-                        ServerMessage {
-                            message: Some(server_message::Message::LobbyClosed(
-                                crate::LobbyClosedNotification {
-                                    message: "Lobby closed".to_string(),
-                                },
-                            )),
-                        },
-                    )
-                    .await;
-                self.notify_lobby_list_update().await;
+    async fn handle_leave_queue(&self, client_id: &ClientId) {
+        if !self.leave_queue_if_queued(client_id).await {
+            self.send_error(client_id, "Not in matchmaking queue".to_string()).await;
+        }
+    }
+
+    async fn leave_queue_if_queued(&self, client_id: &ClientId) -> bool {
+        if !self.matchmaking_manager.leave_queue(client_id).await {
+            return false;
+        }
+
+        log!("Client {} left matchmaking queue", client_id);
+        self.broadcaster
+            .send_to_client(
+                client_id,
+                ServerMessage {
+                    message: Some(server_message::Message::QueueStatus(
+                        crate::QueueStatusNotification::default(),
+                    )),
+                },
+            )
+            .await;
+        true
+    }
+
+    async fn reject_if_in_tournament(&self, client_id: &ClientId) -> bool {
+        if !self.tournament_manager.is_active_participant(client_id).await {
+            return false;
+        }
+        self.send_error(client_id, "Cannot do that while playing in a tournament".to_string()).await;
+        true
+    }
+
+    async fn handle_create_tournament(&self, client_id: &ClientId, request: crate::CreateTournamentRequest) {
+        let settings = match LobbySettings::from_client_proto(
+            request.settings.and_then(|s| s.settings),
+        ) {
+            Ok(s) => s,
+            Err(e) => {
+                self.send_error(client_id, e).await;
+                return;
             }
-            LobbyStateAfterLeave::LobbyStillActive { updated_details } => {
-                self.broadcaster
-                    .broadcast_to_lobby(
-                        &updated_details,
-                        ServerMessage {
-                            message: Some(server_message::Message::PlayerLeft(
-                                crate::PlayerLeftNotification {
-                                    player: Some(crate::PlayerIdentity {
-                                        player_id: client_id.to_string(),
-                                        is_bot: false,
-                                    }),
-                                },
-                            )),
-                        },
-                    )
-                    .await;
+        };
+
+        let format = match tournament::format_from_proto(request.format) {
+            Ok(f) => f,
+            Err(e) => {
+                self.send_error(client_id, e).await;
+                return;
+            }
+        };
 
+        match self
+            .tournament_manager
+            .create_tournament(
+                request.name,
+                settings,
+                format,
+                request.best_of,
+                request.spectator_delay,
+                client_id.clone(),
+            )
+            .await
+        {
+            Ok(details) => {
+                log!("Tournament {} created by {}", details.tournament_id, client_id);
                 self.broadcaster
-                    .broadcast_to_lobby(
-                        &updated_details,
+                    .send_to_client(
+                        client_id,
                         ServerMessage {
-                            message: Some(server_message::Message::LobbyUpdate(
-                                crate::LobbyUpdateNotification {
-                                    details: Some(updated_details.clone()),
+                            message: Some(server_message::Message::TournamentUpdate(
+                                crate::TournamentUpdateNotification {
+                                    details: Some(details),
                                 },
                             )),
                         },
                     )
                     .await;
+            }
+            Err(e) => {
+                self.send_error(client_id, e).await;
+            }
+        }
+    }
+
+    async fn handle_join_tournament(&self, client_id: &ClientId, request: crate::JoinTournamentRequest) {
+        let tournament_id = crate::TournamentId::new(request.tournament_id);
+
+        match self.tournament_manager.join_tournament(&tournament_id, client_id).await {
+            Ok(_) => {
+                self.session_manager.broadcast_tournament_update(&tournament_id).await;
+            }
+            Err(e) => {
+                self.send_error(client_id, e).await;
+            }
+        }
+    }
+
+    async fn handle_leave_tournament(&self, client_id: &ClientId, request: crate::LeaveTournamentRequest) {
+        let tournament_id = crate::TournamentId::new(request.tournament_id);
 
+        match self.tournament_manager.leave_tournament(&tournament_id, client_id).await {
+            Ok(details) => {
                 self.broadcaster
-                    .broadcast_to_lobby(
-                        &updated_details,
+                    .send_to_client(
+                        client_id,
                         ServerMessage {
-                            message: Some(server_message::Message::PlayAgainStatus(
-                                crate::PlayAgainStatusNotification {
-                                    ready_players: vec![],
-                                    pending_players: vec![],
-                                    available: false,
+                            message: Some(server_message::Message::TournamentUpdate(
+                                crate::TournamentUpdateNotification {
+                                    details: Some(details),
                                 },
                             )),
                         },
                     )
                     .await;
+                self.session_manager.start_tournament_games(&tournament_id).await;
+            }
+            Err(e) => {
+                self.send_error(client_id, e).await;
+            }
+        }
+    }
 
-                self.notify_lobby_list_update().await;
+    async fn handle_start_tournament(&self, client_id: &ClientId, request: crate::StartTournamentRequest) {
+        let tournament_id = crate::TournamentId::new(request.tournament_id);
+
+        match self.tournament_manager.start_tournament(&tournament_id, client_id).await {
+            Ok(()) => {
+                log!("Tournament {} started by {}", tournament_id, client_id);
+                self.session_manager.start_tournament_games(&tournament_id).await;
+            }
+            Err(e) => {
+                self.send_error(client_id, e).await;
+            }
+        }
+    }
+
+    async fn handle_leave_lobby(&self, client_id: &ClientId) {
+        match self.lobby_manager.leave_lobby(client_id).await {
+            Ok(leave_state) => {
+                self.session_manager.remove_observer(client_id).await;
+
+                let response = ServerMessage {
+                    message: Some(server_message::Message::LobbyList(crate::LobbyListResponse {
+                        lobbies: self.lobby_manager.list_lobbies().await,
+                    })),
+                };
+                self.broadcaster.send_to_client(client_id, response).await;
+
+                self.session_manager.broadcast_leave_lobby_result(client_id, leave_state)
+                    .await;
+            }
+            Err(e) => {
+                self.send_error(client_id, e).await;
             }
         }
     }
@@ -558,7 +762,7 @@ impl MessageHandler {
     }
 
     async fn handle_add_bot(&self, client_id: &ClientId, request: crate::AddBotRequest) {
-        let bot_type = match BotType::from_proto(request.bot_type) {
+        let bot_type = match BotType::from_proto(request.bot_type, request.tictactoe_strength.as_ref()) {
             Ok(bt) => bt,
             Err(e) => {
                 self.send_error(client_id, e).await;
@@ -611,6 +815,7 @@ impl MessageHandler {
             Ok((lobby_details, kicked_identity, is_bot)) => {
                 if !is_bot {
                     let kicked_client_id = ClientId::new(kicked_identity.client_id());
+                    self.session_manager.remove_observer(&kicked_client_id).await;
 
                     let kick_msg = ServerMessage {
                         message: Some(server_message::Message::LobbyClosed(
@@ -782,6 +987,39 @@ impl MessageHandler {
         }
     }
 
+    async fn handle_assign_team(&self, client_id: &ClientId, request: crate::AssignTeamRequest) {
+        match self
+            .lobby_manager
+            .assign_team(client_id, request.player_id, request.team)
+            .await
+        {
+            Ok(lobby_details) => {
+                self.broadcaster
+                    .broadcast_to_lobby(
+                        &lobby_details,
+                        ServerMessage {
+                            message: Some(server_message::Message::LobbyUpdate(
+                                crate::LobbyUpdateNotification {
+                                    details: Some(lobby_details.clone()),
+                                },
+                            )),
+                        },
+                    )
+                    .await;
+            }
+            Err(e) => {
+                self.send_error(client_id, e).await;
+            }
+        }
+    }
+
+    async fn handle_branch_replay(&self, client_id: &ClientId, position: u64) {
+        match self.session_manager.branch_replay(client_id, position).await {
+            Ok(()) => self.notify_lobby_list_update().await,
+            Err(e) => self.send_error(client_id, e).await,
+        }
+    }
+
     async fn handle_create_replay_lobby(
         &self,
         client_id: &ClientId,
@@ -0,0 +1,233 @@
+use std::collections::{HashMap, HashSet};
+
+use crate::games::{BotType, GameSession, GameSessionConfig};
+use crate::replay::ReplayPlayer;
+use crate::replay::timeline::ReplaySimulation;
+use crate::replay::verify::validate_replay;
+use crate::{BotId, ClientId, PlayerId, PlayerIdentity, ReplayGame, ReplayV1};
+
+/// Steps until the simulation reaches `position` or the recording runs out.
+pub(crate) fn advance_to<S: ReplaySimulation>(sim: &mut S, player: &mut ReplayPlayer, position: u64) {
+    while sim.position() < position && !sim.is_finished(player) {
+        sim.step(player);
+    }
+}
+
+/// Picks who continues each recorded seat; `None` seats are left for bots.
+/// Viewers get back their own seat first, then fill the remaining human seats
+/// before the bot seats, in the order given.
+pub fn assign_seats(recorded: &[PlayerIdentity], viewers: &[ClientId]) -> Vec<Option<ClientId>> {
+    let mut seats: Vec<Option<ClientId>> = recorded
+        .iter()
+        .map(|p| viewers.iter().find(|v| !p.is_bot && v.as_str() == p.player_id).cloned())
+        .collect();
+
+    let mut free_viewers = viewers
+        .iter()
+        .filter(|v| !seats.iter().flatten().any(|s| s == *v))
+        .cloned()
+        .collect::<Vec<_>>()
+        .into_iter();
+
+    let open_seats: Vec<usize> = (0..recorded.len())
+        .filter(|&i| seats[i].is_none() && !recorded[i].is_bot)
+        .chain((0..recorded.len()).filter(|&i| seats[i].is_none() && recorded[i].is_bot))
+        .collect();
+
+    for i in open_seats {
+        seats[i] = free_viewers.next();
+    }
+
+    seats
+}
+
+pub fn branch_config(
+    session_id: String,
+    players: &[PlayerIdentity],
+    bot_type: Option<BotType>,
+    observers: HashSet<PlayerId>,
+) -> GameSessionConfig {
+    let human_players: Vec<PlayerId> = players
+        .iter()
+        .filter(|p| !p.is_bot)
+        .map(|p| PlayerId::new(p.player_id.clone()))
+        .collect();
+    let bots: HashMap<BotId, BotType> = players
+        .iter()
+        .filter(|p| p.is_bot)
+        .filter_map(|p| bot_type.map(|t| (BotId::new(p.player_id.clone()), t)))
+        .collect();
+
+    GameSessionConfig::new(session_id, human_players, observers, bots, 0, None)
+}
+
+/// Replays up to `position`, then hands the state to a live session with the
+/// seats taken over by `players`. Branched games don't record replays.
+pub fn branch_session(
+    replay: ReplayV1,
+    position: u64,
+    players: Vec<PlayerIdentity>,
+    config: &GameSessionConfig,
+) -> Result<GameSession, String> {
+    let game = validate_replay(&replay)?;
+    let metadata = replay.metadata.as_ref().ok_or("Replay has no metadata")?;
+    if players.len() != metadata.players.len() {
+        return Err(format!("Replay has {} seats, got {} players", metadata.players.len(), players.len()));
+    }
+    // The recorded ids stay until `position`: games order players and key
+    // their settings by id, so renaming the seats first would change the game.
+    let renames: HashMap<PlayerId, PlayerId> = metadata
+        .players
+        .iter()
+        .zip(&players)
+        .map(|(recorded, p)| (PlayerId::new(recorded.player_id.clone()), PlayerId::new(p.player_id.clone())))
+        .collect();
+
+    let player = ReplayPlayer::new(replay);
+    match game {
+        ReplayGame::Snake => crate::games::snake::replay::branch(player, position, &renames, config),
+        ReplayGame::Tictactoe => crate::games::tictactoe::replay::branch(player, position, &renames, config),
+        ReplayGame::NumbersMatch => crate::games::numbers_match::replay::branch(player, position, config),
+        ReplayGame::StackAttack => crate::games::stack_attack::replay::branch(player, position, &renames, config),
+        ReplayGame::Puzzle2048 => crate::games::puzzle2048::replay::branch(player, position, config),
+        ReplayGame::Unspecified => Err("Unknown game type in replay".to_string()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::games::snake::{Direction, create_turn_command};
+    use crate::games::tictactoe::create_place_command;
+    use crate::proto::tictactoe::{FirstPlayerMode, TimeControlMode};
+    use crate::replay::ReplayRecorder;
+    use crate::lobby_settings;
+
+    fn identity(id: &str, is_bot: bool) -> PlayerIdentity {
+        PlayerIdentity { player_id: id.to_string(), is_bot }
+    }
+
+    fn client(id: &str) -> ClientId {
+        ClientId::new(id.to_string())
+    }
+
+    fn tictactoe_replay() -> ReplayV1 {
+        let settings = crate::TicTacToeLobbySettings {
+            field_width: 3,
+            field_height: 3,
+            win_count: 3,
+            first_player: FirstPlayerMode::Host as i32,
+            time_control: TimeControlMode::Unlimited as i32,
+            ..Default::default()
+        };
+        let mut recorder = ReplayRecorder::new(
+            "1.0.0".to_string(),
+            ReplayGame::Tictactoe,
+            7,
+            Some(lobby_settings::Settings::Tictactoe(settings)),
+            vec![identity("alice", false), identity("bob", false)],
+        );
+        recorder.record_command(0, 0, create_place_command(0, 0));
+        recorder.record_command(1, 1, create_place_command(1, 1));
+        recorder.record_command(2, 0, create_place_command(0, 1));
+        recorder.record_command(3, 1, create_place_command(2, 2));
+        recorder.record_command(4, 0, create_place_command(0, 2));
+        recorder.finalize(None)
+    }
+
+    fn snake_replay() -> ReplayV1 {
+        use crate::proto::snake::{DeadSnakeBehavior, MovementResolution, SnakeTeamMember, WallCollisionMode};
+
+        let team_member = |id: &str, team| SnakeTeamMember { player_id: id.to_string(), team };
+        let settings = crate::SnakeLobbySettings {
+            field_width: 20,
+            field_height: 20,
+            wall_collision_mode: WallCollisionMode::WrapAround as i32,
+            dead_snake_behavior: DeadSnakeBehavior::Disappear as i32,
+            max_food_count: 3,
+            food_spawn_probability: 1.0,
+            tick_interval_ms: 100,
+            movement_resolution: MovementResolution::Simultaneous as i32,
+            team_members: vec![team_member("alice", 1), team_member("bob", 2), team_member("cyd", 1)],
+            ..Default::default()
+        };
+        let mut recorder = ReplayRecorder::new(
+            "1.0.0".to_string(),
+            ReplayGame::Snake,
+            11,
+            Some(lobby_settings::Settings::Snake(settings)),
+            vec![identity("alice", false), identity("bob", false), identity("cyd", false)],
+        );
+        recorder.record_command(2, 0, create_turn_command(Direction::Left));
+        recorder.record_command(4, 1, create_turn_command(Direction::Right));
+        recorder.record_command(6, 2, create_turn_command(Direction::Left));
+        recorder.finalize(None)
+    }
+
+    async fn branched_snake_state(players: Vec<PlayerIdentity>) -> crate::games::snake::SnakeGameState {
+        let config = branch_config("lobby_9".to_string(), &players, Some(BotType::Snake(crate::SnakeBotType::Efficient)), HashSet::new());
+        let GameSession::Snake(state) = branch_session(snake_replay(), 8, players, &config).unwrap() else {
+            panic!("Expected a Snake session");
+        };
+        state.game_state.lock().await.clone()
+    }
+
+    #[test]
+    fn test_assign_seats_own_seat_then_humans_then_bots() {
+        let recorded = [identity("alice", false), identity("bot", true), identity("bob", false)];
+        let viewers = [client("carol"), client("bob")];
+
+        let seats = assign_seats(&recorded, &viewers);
+        assert_eq!(seats, vec![Some(client("carol")), None, Some(client("bob"))]);
+    }
+
+    #[tokio::test]
+    async fn test_branch_session_tictactoe_continues_with_new_players() {
+        let minimax = BotType::TicTacToe(
+            crate::TicTacToeBotType::TictactoeBotTypeMinimax,
+            crate::games::tictactoe::BotStrength::default(),
+        );
+        let players = vec![identity("carol", false), identity("Bot-1", true)];
+        let config = branch_config("lobby_9".to_string(), &players, Some(minimax), HashSet::new());
+
+        let session = branch_session(tictactoe_replay(), 3, players, &config).unwrap();
+        let GameSession::TicTacToe(state) = session else {
+            panic!("Expected a TicTacToe session");
+        };
+
+        let game_state = state.game_state.lock().await;
+        assert_eq!(game_state.player_x, PlayerId::new("carol".to_string()));
+        assert_eq!(game_state.player_o, PlayerId::new("Bot-1".to_string()));
+        assert_eq!(game_state.current_player, PlayerId::new("Bot-1".to_string()));
+        assert_eq!(*state.tick.lock().await, 3);
+        assert_eq!(config.bots.len(), 1);
+    }
+
+    #[test]
+    fn test_branch_session_after_game_over_rejected() {
+        let players = vec![identity("carol", false), identity("dave", false)];
+        let config = branch_config("lobby_9".to_string(), &players, None, HashSet::new());
+
+        let result = branch_session(tictactoe_replay(), 5, players, &config);
+        assert!(result.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_branch_session_snake_renamed_seats_play_the_recorded_game() {
+        let recorded = vec![identity("alice", false), identity("bob", false), identity("cyd", false)];
+        let renamed = vec![identity("zed", false), identity("Bot-1", true), identity("carol", false)];
+
+        let expected = branched_snake_state(recorded.clone()).await;
+        let actual = branched_snake_state(renamed.clone()).await;
+
+        for (recorded, renamed) in recorded.iter().zip(&renamed) {
+            let recorded_id = PlayerId::new(recorded.player_id.clone());
+            let renamed_id = PlayerId::new(renamed.player_id.clone());
+            assert_eq!(actual.snakes[&renamed_id].body, expected.snakes[&recorded_id].body);
+            assert_eq!(actual.teams.get(&renamed_id), expected.teams.get(&recorded_id));
+            assert!(!actual.snakes.contains_key(&recorded_id));
+        }
+        assert_eq!(actual.teams.get(&PlayerId::new("zed".to_string())), Some(&1));
+        assert_eq!(actual.food, expected.food);
+    }
+}
@@ -2,7 +2,7 @@ use std::path::Path;
 use std::io::{Read, Write};
 use prost::Message;
 use crate::{ReplayV1, ReplayV1Header, ReplayV1Metadata, ReplayGame};
-use super::{REPLAY_VERSION, REPLAY_FILE_EXTENSION};
+use super::{MIN_REPLAY_VERSION, REPLAY_VERSION, REPLAY_FILE_EXTENSION};
 
 #[derive(Debug)]
 pub enum ReplayError {
@@ -10,6 +10,7 @@ pub enum ReplayError {
     DecodeError(prost::DecodeError),
     UnsupportedVersion { found: u8, expected: u8 },
     EmptyFile,
+    MissingMetadata,
 }
 
 impl std::fmt::Display for ReplayError {
@@ -21,6 +22,7 @@ impl std::fmt::Display for ReplayError {
                 write!(f, "Unsupported replay version: found {}, expected {}", found, expected)
             }
             ReplayError::EmptyFile => write!(f, "Empty replay file"),
+            ReplayError::MissingMetadata => write!(f, "Missing metadata in replay header"),
         }
     }
 }
@@ -69,13 +71,7 @@ pub fn load_replay_from_bytes(bytes: &[u8]) -> Result<ReplayV1, ReplayError> {
         return Err(ReplayError::EmptyFile);
     }
 
-    let version = bytes[0];
-    if version != REPLAY_VERSION {
-        return Err(ReplayError::UnsupportedVersion {
-            found: version,
-            expected: REPLAY_VERSION,
-        });
-    }
+    check_version(bytes[0])?;
 
     let replay = ReplayV1::decode(&bytes[1..])?;
     Ok(replay)
@@ -94,18 +90,20 @@ pub fn load_replay_metadata_from_bytes(bytes: &[u8]) -> Result<ReplayV1Metadata,
         return Err(ReplayError::EmptyFile);
     }
 
-    let version = bytes[0];
-    if version != REPLAY_VERSION {
+    check_version(bytes[0])?;
+
+    let header = ReplayV1Header::decode(&bytes[1..])?;
+    header.metadata.ok_or(ReplayError::MissingMetadata)
+}
+
+fn check_version(version: u8) -> Result<(), ReplayError> {
+    if !(MIN_REPLAY_VERSION..=REPLAY_VERSION).contains(&version) {
         return Err(ReplayError::UnsupportedVersion {
             found: version,
             expected: REPLAY_VERSION,
         });
     }
-
-    let header = ReplayV1Header::decode(&bytes[1..])?;
-    header.metadata.ok_or_else(|| {
-        ReplayError::DecodeError(prost::DecodeError::new("Missing metadata in replay header"))
-    })
+    Ok(())
 }
 
 pub fn generate_replay_filename(game: ReplayGame, version: &str) -> String {
@@ -144,8 +142,9 @@ mod tests {
                     PlayerIdentity { player_id: "player1".to_string(), is_bot: false },
                     PlayerIdentity { player_id: "bot1".to_string(), is_bot: true },
                 ],
+                ..Default::default()
             }),
-            actions: vec![],
+            ..Default::default()
         };
 
         let bytes = save_replay_to_bytes(&replay);
@@ -172,13 +171,21 @@ mod tests {
                 players: vec![
                     PlayerIdentity { player_id: "alice".to_string(), is_bot: false },
                 ],
+                duration_ms: 61_000,
+                result: Some(crate::GameOverNotification {
+                    winner: Some(PlayerIdentity { player_id: "alice".to_string(), is_bot: false }),
+                    ..Default::default()
+                }),
+                ..Default::default()
             }),
-            actions: vec![],
+            ..Default::default()
         };
 
         let bytes = save_replay_to_bytes(&replay);
         let metadata = load_replay_metadata_from_bytes(&bytes).unwrap();
 
+        assert_eq!(metadata.duration_ms, 61_000);
+        assert_eq!(metadata.result.unwrap().winner.unwrap().player_id, "alice");
         assert_eq!(metadata.engine_version, "2.0.0");
         assert_eq!(metadata.game, ReplayGame::Tictactoe as i32);
         assert_eq!(metadata.seed, 123);
@@ -199,6 +206,25 @@ mod tests {
         assert!(matches!(result, Err(ReplayError::EmptyFile)));
     }
 
+    #[test]
+    fn test_load_v1_bytes_without_state_hashes() {
+        let replay = ReplayV1 {
+            metadata: Some(ReplayV1Metadata {
+                engine_version: "1.0.0".to_string(),
+                game: ReplayGame::Snake.into(),
+                seed: 7,
+                ..Default::default()
+            }),
+            ..Default::default()
+        };
+        let mut bytes = vec![1];
+        bytes.extend(replay.encode_to_vec());
+
+        let loaded = load_replay_from_bytes(&bytes).unwrap();
+        assert_eq!(loaded.metadata.unwrap().rules_revision, 0);
+        assert!(loaded.state_hashes.is_empty());
+    }
+
     #[test]
     fn test_load_unsupported_version_error() {
         let result = load_replay_from_bytes(&[99]);
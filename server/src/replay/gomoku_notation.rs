@@ -0,0 +1,374 @@
+use crate::games::SessionRng;
+use crate::games::tictactoe::{FirstPlayerMode, GameStatus, TicTacToeGameState};
+use crate::proto::tictactoe::{
+    OpeningRule, PlaceMarkCommand, TicTacToeInGameCommand, TicTacToeLobbySettings, TimeControlMode, WinRule,
+    tic_tac_toe_in_game_command,
+};
+use crate::replay::ReplayRecorder;
+use crate::replay::verify::validate_replay;
+use crate::{
+    InGameCommand, PlayerId, PlayerIdentity, ReplayGame, ReplayV1, in_game_command, lobby_settings,
+    player_action_content,
+};
+
+pub const GOMOKU_BOARD_SIZE: u32 = 15;
+pub const GOMOKU_WIN_COUNT: u32 = 5;
+
+const PSQ_HEADER_PREFIX: &str = "Piskvorky";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct GomokuMove {
+    /// Zero-based, with y growing downwards like the TicTacToe board.
+    pub x: u32,
+    pub y: u32,
+    pub elapsed_ms: i64,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct GomokuGame {
+    /// Moves first.
+    pub black: String,
+    pub white: String,
+    pub moves: Vec<GomokuMove>,
+    /// PGN result: "1-0", "0-1", "1/2-1/2" or "*".
+    pub result: String,
+}
+
+pub fn gomoku_game_from_replay(replay: &ReplayV1) -> Result<GomokuGame, String> {
+    validate_replay(replay)?;
+    let metadata = replay.metadata.as_ref().ok_or("Replay has no metadata")?;
+    let settings = match metadata.lobby_settings.as_ref().and_then(|s| s.settings.as_ref()) {
+        Some(lobby_settings::Settings::Tictactoe(s)) => s,
+        _ => return Err("Gomoku notation needs a TicTacToe replay".to_string()),
+    };
+    if settings.field_width != GOMOKU_BOARD_SIZE
+        || settings.field_height != GOMOKU_BOARD_SIZE
+        || settings.win_count != GOMOKU_WIN_COUNT
+    {
+        return Err(format!(
+            "Gomoku notation needs a {}x{} board with {} in a row",
+            GOMOKU_BOARD_SIZE, GOMOKU_BOARD_SIZE, GOMOKU_WIN_COUNT
+        ));
+    }
+    if !matches!(settings.win_rule(), WinRule::Unspecified | WinRule::Freestyle)
+        || !matches!(settings.opening(), OpeningRule::Unspecified | OpeningRule::Standard)
+    {
+        return Err("Gomoku notation only covers freestyle games with a standard opening".to_string());
+    }
+    if metadata.players.len() != 2 {
+        return Err(format!("TicTacToe replay has {} players", metadata.players.len()));
+    }
+
+    let mut moves = Vec::new();
+    let mut first_index = None;
+    let mut timed_out_index = None;
+    for action in &replay.actions {
+        match action.content.as_ref().and_then(|c| c.content.as_ref()) {
+            Some(player_action_content::Content::Command(cmd)) => {
+                if let Some(in_game_command::Command::Tictactoe(ttt_cmd)) = &cmd.command
+                    && let Some(tic_tac_toe_in_game_command::Command::Place(place)) = &ttt_cmd.command
+                {
+                    first_index.get_or_insert(action.player_index);
+                    moves.push(GomokuMove { x: place.x, y: place.y, elapsed_ms: action.elapsed_ms });
+                }
+            }
+            Some(player_action_content::Content::TimedOut(_)) => timed_out_index = Some(action.player_index),
+            _ => {}
+        }
+    }
+
+    let first_index = first_index.unwrap_or(0) as usize;
+    let black = metadata.players[first_index].player_id.clone();
+    let white = metadata.players[1 - first_index].player_id.clone();
+
+    let game_state = play_moves(&black, &white, &moves)?;
+    let result = match timed_out_index {
+        Some(index) if index as usize == first_index => "0-1".to_string(),
+        Some(_) => "1-0".to_string(),
+        None => result_from_status(game_state.status).to_string(),
+    };
+
+    Ok(GomokuGame { black, white, moves, result })
+}
+
+/// Builds a human-vs-human replay; fails on the first illegal move.
+pub fn replay_from_gomoku_game(game: &GomokuGame) -> Result<ReplayV1, String> {
+    let settings = lobby_settings::Settings::Tictactoe(TicTacToeLobbySettings {
+        field_width: GOMOKU_BOARD_SIZE,
+        field_height: GOMOKU_BOARD_SIZE,
+        win_count: GOMOKU_WIN_COUNT,
+        first_player: crate::proto::tictactoe::FirstPlayerMode::Host as i32,
+        time_control: TimeControlMode::Unlimited as i32,
+        ..Default::default()
+    });
+    let mut recorder = ReplayRecorder::new(
+        crate::version::VERSION.to_string(),
+        ReplayGame::Tictactoe,
+        0,
+        Some(settings),
+        vec![
+            PlayerIdentity { player_id: game.black.clone(), is_bot: false },
+            PlayerIdentity { player_id: game.white.clone(), is_bot: false },
+        ],
+    );
+
+    let mut game_state = new_game_state(&game.black, &game.white);
+    for (i, m) in game.moves.iter().enumerate() {
+        place(&mut game_state, i, m)?;
+        let command = InGameCommand {
+            command: Some(in_game_command::Command::Tictactoe(TicTacToeInGameCommand {
+                command: Some(tic_tac_toe_in_game_command::Command::Place(PlaceMarkCommand { x: m.x, y: m.y })),
+            })),
+        };
+        recorder.record_timed_command(i as i64, m.elapsed_ms, (i % 2) as i32, command);
+        recorder.record_state_hash(recorder.actions_count() as u64, game_state.state_hash());
+    }
+
+    Ok(recorder.finalize(None))
+}
+
+/// Piskvork/Gomocup record: one-based "x,y,ms" lines where ms is the time spent on the move.
+pub fn to_psq(game: &GomokuGame) -> String {
+    let mut out = format!("{} {}x{}, 11:11, 0\n", PSQ_HEADER_PREFIX, GOMOKU_BOARD_SIZE, GOMOKU_BOARD_SIZE);
+    let mut previous_ms = 0;
+    for m in &game.moves {
+        out.push_str(&format!("{},{},{}\n", m.x + 1, m.y + 1, (m.elapsed_ms - previous_ms).max(0)));
+        previous_ms = m.elapsed_ms;
+    }
+    out.push_str(&format!("{}\n{}\n-1\n", game.black, game.white));
+    out
+}
+
+pub fn parse_psq(text: &str) -> Result<GomokuGame, String> {
+    let mut lines = text.lines().map(str::trim).filter(|l| !l.is_empty());
+
+    let header = lines.next().ok_or("Empty position file")?;
+    let size = header
+        .strip_prefix(PSQ_HEADER_PREFIX)
+        .and_then(|rest| rest.trim().split(',').next())
+        .ok_or_else(|| format!("Invalid header: {}", header))?;
+    if size != format!("{}x{}", GOMOKU_BOARD_SIZE, GOMOKU_BOARD_SIZE) {
+        return Err(format!("Unsupported board size {}", size));
+    }
+
+    let mut moves = Vec::new();
+    let mut elapsed_ms = 0;
+    let mut names = Vec::new();
+    for line in lines {
+        let fields: Vec<&str> = line.split(',').collect();
+        let is_move = names.is_empty() && (2..=3).contains(&fields.len());
+        if !is_move {
+            names.push(line);
+            continue;
+        }
+
+        let x = parse_psq_coordinate(fields[0], line)?;
+        let y = parse_psq_coordinate(fields[1], line)?;
+        if let Some(ms) = fields.get(2) {
+            elapsed_ms += ms.trim().parse::<i64>().map_err(|_| format!("Invalid move time: {}", line))?;
+        }
+        moves.push(GomokuMove { x, y, elapsed_ms });
+    }
+
+    let black = names.first().map_or("black", |n| n).to_string();
+    let white = names.get(1).filter(|n| **n != "-1").map_or("white", |n| n).to_string();
+    finish_imported_game(black, white, moves)
+}
+
+/// PGN-style record with renju coordinates: columns a-o from the left, rows 1-15 from the bottom.
+pub fn to_pgn(game: &GomokuGame) -> String {
+    let mut out = String::new();
+    out.push_str(&format!("[Black \"{}\"]\n", game.black));
+    out.push_str(&format!("[White \"{}\"]\n", game.white));
+    out.push_str(&format!("[BoardSize \"{}\"]\n", GOMOKU_BOARD_SIZE));
+    out.push_str(&format!("[Result \"{}\"]\n\n", game.result));
+
+    let mut tokens = Vec::new();
+    for (i, m) in game.moves.iter().enumerate() {
+        if i % 2 == 0 {
+            tokens.push(format!("{}.", i / 2 + 1));
+        }
+        tokens.push(format!("{}{}", (b'a' + m.x as u8) as char, GOMOKU_BOARD_SIZE - m.y));
+    }
+    tokens.push(game.result.clone());
+    out.push_str(&tokens.join(" "));
+    out.push('\n');
+    out
+}
+
+pub fn parse_pgn(text: &str) -> Result<GomokuGame, String> {
+    let mut black = "black".to_string();
+    let mut white = "white".to_string();
+    let mut moves = Vec::new();
+
+    for line in text.lines().map(str::trim) {
+        if let Some(tag) = line.strip_prefix('[').and_then(|l| l.strip_suffix(']')) {
+            let (key, value) = tag.split_once(' ').ok_or_else(|| format!("Invalid tag: {}", line))?;
+            let value = value.trim().trim_matches('"').to_string();
+            match key {
+                "Black" => black = value,
+                "White" => white = value,
+                "BoardSize" if value != GOMOKU_BOARD_SIZE.to_string() => {
+                    return Err(format!("Unsupported board size {}", value));
+                }
+                _ => {}
+            }
+            continue;
+        }
+
+        for token in line.split_whitespace() {
+            if token.ends_with('.') || matches!(token, "1-0" | "0-1" | "1/2-1/2" | "*") {
+                continue;
+            }
+            moves.push(parse_pgn_move(token)?);
+        }
+    }
+
+    finish_imported_game(black, white, moves)
+}
+
+fn parse_psq_coordinate(field: &str, line: &str) -> Result<u32, String> {
+    match field.trim().parse::<u32>() {
+        Ok(value) if (1..=GOMOKU_BOARD_SIZE).contains(&value) => Ok(value - 1),
+        _ => Err(format!("Invalid move: {}", line)),
+    }
+}
+
+fn parse_pgn_move(token: &str) -> Result<GomokuMove, String> {
+    let mut chars = token.chars();
+    let column = chars.next().filter(|c| c.is_ascii_lowercase()).ok_or_else(|| format!("Invalid move: {}", token))?;
+    let row: u32 = chars.as_str().parse().map_err(|_| format!("Invalid move: {}", token))?;
+    let x = column as u32 - 'a' as u32;
+    if x >= GOMOKU_BOARD_SIZE || !(1..=GOMOKU_BOARD_SIZE).contains(&row) {
+        return Err(format!("Move {} is off the board", token));
+    }
+    Ok(GomokuMove { x, y: GOMOKU_BOARD_SIZE - row, elapsed_ms: 0 })
+}
+
+fn finish_imported_game(black: String, white: String, moves: Vec<GomokuMove>) -> Result<GomokuGame, String> {
+    if black == white {
+        return Err("Black and white must be different players".to_string());
+    }
+    let game_state = play_moves(&black, &white, &moves)?;
+    let result = result_from_status(game_state.status).to_string();
+    Ok(GomokuGame { black, white, moves, result })
+}
+
+fn new_game_state(black: &str, white: &str) -> TicTacToeGameState {
+    TicTacToeGameState::new(
+        GOMOKU_BOARD_SIZE as usize,
+        GOMOKU_BOARD_SIZE as usize,
+        GOMOKU_WIN_COUNT as usize,
+        vec![PlayerId::new(black.to_string()), PlayerId::new(white.to_string())],
+        FirstPlayerMode::Host,
+        &mut SessionRng::new(0),
+    )
+}
+
+fn play_moves(black: &str, white: &str, moves: &[GomokuMove]) -> Result<TicTacToeGameState, String> {
+    let mut game_state = new_game_state(black, white);
+    for (i, m) in moves.iter().enumerate() {
+        place(&mut game_state, i, m)?;
+    }
+    Ok(game_state)
+}
+
+fn place(game_state: &mut TicTacToeGameState, index: usize, m: &GomokuMove) -> Result<(), String> {
+    let current = game_state.current_player.clone();
+    game_state
+        .place_mark(&current, m.x as usize, m.y as usize)
+        .map_err(|e| format!("Move {} at ({}, {}): {}", index + 1, m.x, m.y, e))
+}
+
+fn result_from_status(status: GameStatus) -> &'static str {
+    match status {
+        GameStatus::XWon => "1-0",
+        GameStatus::OWon => "0-1",
+        GameStatus::Draw => "1/2-1/2",
+        GameStatus::InProgress => "*",
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn five_in_a_row() -> GomokuGame {
+        let moves = [(7, 7), (7, 8), (8, 7), (8, 8), (9, 7), (9, 8), (10, 7), (10, 8), (11, 7)]
+            .into_iter()
+            .enumerate()
+            .map(|(i, (x, y))| GomokuMove { x, y, elapsed_ms: (i as i64 + 1) * 1000 })
+            .collect();
+        GomokuGame { black: "alice".to_string(), white: "bob".to_string(), moves, result: "1-0".to_string() }
+    }
+
+    #[test]
+    fn test_psq_round_trip_through_replay() {
+        let game = five_in_a_row();
+        let replay = replay_from_gomoku_game(&game).unwrap();
+        let exported = gomoku_game_from_replay(&replay).unwrap();
+        assert_eq!(exported, game);
+
+        let psq = to_psq(&exported);
+        assert!(psq.starts_with("Piskvorky 15x15, 11:11, 0\n8,8,1000\n8,9,1000\n"));
+        assert_eq!(parse_psq(&psq).unwrap(), game);
+    }
+
+    #[test]
+    fn test_pgn_round_trip_uses_renju_coordinates() {
+        let mut game = five_in_a_row();
+        for m in &mut game.moves {
+            m.elapsed_ms = 0;
+        }
+
+        let pgn = to_pgn(&game);
+        assert!(pgn.contains("[Result \"1-0\"]"));
+        assert!(pgn.ends_with("1. h8 h7 2. i8 i7 3. j8 j7 4. k8 k7 5. l8 1-0\n"));
+        assert_eq!(parse_pgn(&pgn).unwrap(), game);
+    }
+
+    #[test]
+    fn test_parse_pgn_occupied_cell_error() {
+        let err = parse_pgn("1. h8 h8").unwrap_err();
+        assert!(err.starts_with("Move 2 at (7, 7)"), "{}", err);
+    }
+
+    #[test]
+    fn test_gomoku_game_from_replay_wrong_board_error() {
+        let mut replay = replay_from_gomoku_game(&five_in_a_row()).unwrap();
+        if let Some(lobby_settings::Settings::Tictactoe(s)) = replay
+            .metadata
+            .as_mut()
+            .and_then(|m| m.lobby_settings.as_mut())
+            .and_then(|s| s.settings.as_mut())
+        {
+            s.win_count = 4;
+        }
+
+        assert!(gomoku_game_from_replay(&replay).is_err());
+    }
+
+    #[test]
+    fn test_gomoku_game_from_replay_swap2_error() {
+        let mut replay = replay_from_gomoku_game(&five_in_a_row()).unwrap();
+        if let Some(lobby_settings::Settings::Tictactoe(s)) = replay
+            .metadata
+            .as_mut()
+            .and_then(|m| m.lobby_settings.as_mut())
+            .and_then(|s| s.settings.as_mut())
+        {
+            s.opening = OpeningRule::Swap2 as i32;
+        }
+
+        assert!(gomoku_game_from_replay(&replay).is_err());
+    }
+
+    #[test]
+    fn test_gomoku_game_from_replay_unknown_player_index_error() {
+        let mut replay = replay_from_gomoku_game(&five_in_a_row()).unwrap();
+        replay.actions[0].player_index = 2;
+
+        let err = gomoku_game_from_replay(&replay).unwrap_err();
+        assert!(err.contains("unknown player index 2"), "{}", err);
+    }
+}
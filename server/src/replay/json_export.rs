@@ -0,0 +1,142 @@
+use serde_json::{Value, json};
+
+use crate::proto::numbers_match::numbers_match_in_game_command;
+use crate::proto::puzzle2048::puzzle2048_in_game_command;
+use crate::proto::snake::snake_in_game_command;
+use crate::proto::stack_attack::stack_attack_in_game_command;
+use crate::proto::tictactoe::tic_tac_toe_in_game_command;
+use crate::replay::session::replay_game_type_name;
+use crate::{PlayerAction, ReplayGame, ReplayV1, in_game_command, player_action_content};
+
+/// Readable JSON with every action decoded into its game's command.
+pub fn replay_to_json(replay: &ReplayV1) -> Result<String, String> {
+    let metadata = replay.metadata.as_ref().ok_or("Replay has no metadata")?;
+    let game = ReplayGame::try_from(metadata.game).unwrap_or(ReplayGame::Unspecified);
+
+    let actions: Vec<Value> = replay
+        .actions
+        .iter()
+        .map(|action| {
+            json!({
+                "tick": action.tick,
+                "elapsed_ms": action.elapsed_ms,
+                "player": metadata.players.get(action.player_index as usize).map(|p| &p.player_id),
+                "action": action_to_json(action),
+            })
+        })
+        .collect();
+
+    let value = json!({
+        "engine_version": metadata.engine_version,
+        "rules_revision": metadata.rules_revision,
+        "rng_algorithm": metadata.rng_algorithm,
+        "game": replay_game_type_name(game),
+        "seed": metadata.seed,
+        "game_started_timestamp_ms": metadata.game_started_timestamp_ms,
+        "settings": metadata.lobby_settings.as_ref().and_then(|s| s.settings.as_ref()),
+        "players": metadata.players,
+        "actions": actions,
+        "state_hashes": replay.state_hashes,
+    });
+
+    serde_json::to_string_pretty(&value).map_err(|e| format!("Failed to serialize replay: {}", e))
+}
+
+fn action_to_json(action: &PlayerAction) -> Value {
+    let Some(content) = action.content.as_ref().and_then(|c| c.content.as_ref()) else {
+        return Value::Null;
+    };
+
+    match content {
+        player_action_content::Content::Command(cmd) => match &cmd.command {
+            Some(in_game_command::Command::Snake(c)) => match &c.command {
+                Some(snake_in_game_command::Command::Turn(turn)) => json!({
+                    "type": "turn",
+                    "direction": enum_name(crate::proto::snake::Direction::try_from(turn.direction).map(|d| d.as_str_name())),
+                }),
+                None => Value::Null,
+            },
+            Some(in_game_command::Command::Tictactoe(c)) => match &c.command {
+                Some(tic_tac_toe_in_game_command::Command::Place(place)) => json!({
+                    "type": "place",
+                    "x": place.x,
+                    "y": place.y,
+                }),
+                Some(tic_tac_toe_in_game_command::Command::Swap2Choice(choice)) => json!({
+                    "type": "swap2_choice",
+                    "choice": enum_name(crate::proto::tictactoe::Swap2Choice::try_from(choice.choice).map(|c| c.as_str_name())),
+                }),
+                None => Value::Null,
+            },
+            Some(in_game_command::Command::NumbersMatch(c)) => match &c.command {
+                Some(numbers_match_in_game_command::Command::RemovePair(remove)) => json!({
+                    "type": "remove_pair",
+                    "first_index": remove.first_index,
+                    "second_index": remove.second_index,
+                }),
+                Some(numbers_match_in_game_command::Command::Refill(_)) => json!({ "type": "refill" }),
+                Some(numbers_match_in_game_command::Command::RequestHint(_)) => json!({ "type": "request_hint" }),
+                None => Value::Null,
+            },
+            Some(in_game_command::Command::StackAttack(c)) => match &c.command {
+                Some(stack_attack_in_game_command::Command::Move(m)) => json!({
+                    "type": "move",
+                    "direction": enum_name(crate::proto::stack_attack::HorizontalDirection::try_from(m.direction).map(|d| d.as_str_name())),
+                }),
+                Some(stack_attack_in_game_command::Command::Jump(_)) => json!({ "type": "jump" }),
+                None => Value::Null,
+            },
+            Some(in_game_command::Command::Puzzle2048(c)) => match &c.command {
+                Some(puzzle2048_in_game_command::Command::Move(m)) => json!({
+                    "type": "move",
+                    "direction": enum_name(crate::proto::puzzle2048::Puzzle2048Direction::try_from(m.direction).map(|d| d.as_str_name())),
+                }),
+                None => Value::Null,
+            },
+            None => Value::Null,
+        },
+        player_action_content::Content::Disconnected(_) => json!({ "type": "disconnected" }),
+        player_action_content::Content::TimedOut(_) => json!({ "type": "timed_out" }),
+        player_action_content::Content::ControlChanged(c) => json!({
+            "type": "control_changed",
+            "bot_controlled": c.bot_controlled,
+        }),
+    }
+}
+
+fn enum_name<E>(name: Result<&'static str, E>) -> &'static str {
+    name.unwrap_or("UNKNOWN")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::replay::ReplayRecorder;
+    use crate::{InGameCommand, PlayerIdentity};
+
+    #[test]
+    fn test_replay_to_json_snake_turn_decoded() {
+        let mut recorder = ReplayRecorder::new(
+            "1.0.0".to_string(),
+            ReplayGame::Snake,
+            5,
+            None,
+            vec![PlayerIdentity { player_id: "alice".to_string(), is_bot: false }],
+        );
+        recorder.record_command(3, 0, InGameCommand {
+            command: Some(in_game_command::Command::Snake(crate::SnakeInGameCommand {
+                command: Some(snake_in_game_command::Command::Turn(crate::TurnCommand {
+                    direction: crate::Direction::Left as i32,
+                })),
+            })),
+        });
+        recorder.record_disconnect(4, 0);
+
+        let json: Value = serde_json::from_str(&replay_to_json(&recorder.finalize(None)).unwrap()).unwrap();
+        assert_eq!(json["game"], "Snake");
+        assert_eq!(json["players"][0]["player_id"], "alice");
+        assert_eq!(json["actions"][0]["player"], "alice");
+        assert_eq!(json["actions"][0]["action"], json!({ "type": "turn", "direction": "DIRECTION_LEFT" }));
+        assert_eq!(json["actions"][1]["action"]["type"], "disconnected");
+    }
+}
@@ -0,0 +1,89 @@
+use crate::{GameOverNotification, PlayerIdentity, ReplayV1Metadata};
+
+/// One-line result such as "alice beat bob-bot 3–1 in 4:12", read from metadata alone.
+pub fn describe_result(metadata: &ReplayV1Metadata) -> Option<String> {
+    let result = metadata.result.as_ref()?;
+    let duration = format_duration(metadata.duration_ms);
+    let players: Vec<&PlayerIdentity> = metadata.players.iter().collect();
+
+    let text = match (&result.winner, players.as_slice()) {
+        (_, [player]) => format!("{} scored {}", player.player_id, score_of(result, player)),
+        (Some(winner), _) => {
+            let losers: Vec<&PlayerIdentity> = players.iter().copied().filter(|p| p.player_id != winner.player_id).collect();
+            let names: Vec<&str> = losers.iter().map(|p| p.player_id.as_str()).collect();
+            let mut text = format!("{} beat {}", winner.player_id, names.join(", "));
+            if !result.scores.is_empty() {
+                let scores: Vec<String> = std::iter::once(winner)
+                    .chain(losers)
+                    .map(|p| score_of(result, p).to_string())
+                    .collect();
+                text.push(' ');
+                text.push_str(&scores.join("–"));
+            }
+            text
+        }
+        (None, _) => {
+            let names: Vec<&str> = players.iter().map(|p| p.player_id.as_str()).collect();
+            format!("Draw between {}", names.join(", "))
+        }
+    };
+
+    Some(format!("{} in {}", text, duration))
+}
+
+fn score_of(result: &GameOverNotification, player: &PlayerIdentity) -> u32 {
+    result
+        .scores
+        .iter()
+        .find(|s| s.identity.as_ref().is_some_and(|i| i.player_id == player.player_id))
+        .map_or(0, |s| s.score)
+}
+
+fn format_duration(duration_ms: u64) -> String {
+    let total_secs = duration_ms / 1000;
+    let (hours, minutes, secs) = (total_secs / 3600, total_secs / 60 % 60, total_secs % 60);
+    if hours > 0 {
+        format!("{}:{:02}:{:02}", hours, minutes, secs)
+    } else {
+        format!("{}:{:02}", minutes, secs)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ScoreEntry;
+
+    fn identity(id: &str, is_bot: bool) -> PlayerIdentity {
+        PlayerIdentity { player_id: id.to_string(), is_bot }
+    }
+
+    #[test]
+    fn test_describe_result_winner_with_scores() {
+        let metadata = ReplayV1Metadata {
+            players: vec![identity("bob-bot", true), identity("alice", false)],
+            duration_ms: 252_400,
+            result: Some(GameOverNotification {
+                scores: vec![
+                    ScoreEntry { identity: Some(identity("bob-bot", true)), score: 1 },
+                    ScoreEntry { identity: Some(identity("alice", false)), score: 3 },
+                ],
+                winner: Some(identity("alice", false)),
+                game_info: None,
+            }),
+            ..Default::default()
+        };
+
+        assert_eq!(describe_result(&metadata).unwrap(), "alice beat bob-bot 3–1 in 4:12");
+    }
+
+    #[test]
+    fn test_describe_result_unfinished_game_none() {
+        let metadata = ReplayV1Metadata {
+            players: vec![identity("alice", false)],
+            ..Default::default()
+        };
+
+        assert_eq!(describe_result(&metadata), None);
+    }
+}
@@ -2,13 +2,24 @@ pub mod recorder;
 pub mod file_io;
 pub mod player;
 pub mod session;
+pub mod timeline;
+pub mod json_export;
+pub mod gomoku_notation;
+pub mod listing;
+pub mod verify;
+pub mod branch;
 
 pub use recorder::ReplayRecorder;
 pub use file_io::{save_replay, load_replay, load_replay_metadata, save_replay_to_bytes, generate_replay_filename};
 pub use player::ReplayPlayer;
 
 pub const REPLAY_FILE_EXTENSION: &str = "minigamesreplay";
-pub const REPLAY_VERSION: u8 = 1;
+pub const REPLAY_VERSION: u8 = 2;
+/// Version 1 files load as version 2 replays without state hashes.
+pub const MIN_REPLAY_VERSION: u8 = 1;
+/// Bump whenever a rule change makes the engine play recorded actions out differently.
+pub const RULES_REVISION: u32 = 1;
+pub const STATE_HASH_INTERVAL: u64 = 10;
 
 #[cfg(test)]
 mod integration_tests {
@@ -19,7 +30,7 @@ mod integration_tests {
         lobby_settings, SnakeBotType, TicTacToeBotType,
     };
     use crate::games::snake::{SnakeGameState, FieldSize, WallCollisionMode, DeadSnakeBehavior, Direction, Point, BotController};
-    use crate::games::tictactoe::{TicTacToeGameState, FirstPlayerMode, GameStatus, calculate_move, BotInput};
+    use crate::games::tictactoe::{TicTacToeGameState, FirstPlayerMode, GameStatus, TimeControl, calculate_move, BotInput, BotStrength};
     use crate::games::SessionRng;
     use super::{ReplayRecorder, ReplayPlayer};
 
@@ -92,6 +103,8 @@ mod integration_tests {
             max_food_count: 3,
             food_spawn_probability: 1.0,
             tick_interval_ms: 100,
+            movement_resolution: crate::proto::snake::MovementResolution::Simultaneous as i32,
+            ..Default::default()
         });
 
         let mut recorder = ReplayRecorder::new(
@@ -133,7 +146,7 @@ mod integration_tests {
         let final_alive_p1_original = game_state.snakes.get(&player1).map(|s| s.is_alive()).unwrap_or(false);
         let final_alive_p2_original = game_state.snakes.get(&player2).map(|s| s.is_alive()).unwrap_or(false);
 
-        let replay = recorder.finalize();
+        let replay = recorder.finalize(None);
         let player = ReplayPlayer::new(replay);
 
         let mut replay_game_state = SnakeGameState::new(
@@ -204,6 +217,7 @@ mod integration_tests {
             field_height: 3,
             win_count: 3,
             first_player: crate::proto::tictactoe::FirstPlayerMode::Random as i32,
+            ..Default::default()
         });
 
         let mut recorder = ReplayRecorder::new(
@@ -224,7 +238,7 @@ mod integration_tests {
             let player_index = if current == player1 { 0 } else { 1 };
 
             let bot_input = BotInput::from_game_state(&game_state);
-            if let Some(pos) = calculate_move(TicTacToeBotType::TictactoeBotTypeRandom, bot_input, &mut rng) {
+            if let Some(pos) = calculate_move(TicTacToeBotType::TictactoeBotTypeRandom, &BotStrength::default(), bot_input, &mut rng) {
                 let cmd = create_tictactoe_command(pos.x as u32, pos.y as u32);
                 recorder.record_command(turn, player_index, cmd);
                 game_state.place_mark(&current, pos.x, pos.y).expect("Failed to place mark in test");
@@ -239,7 +253,7 @@ mod integration_tests {
         let original_status = game_state.status;
         let original_winner = game_state.get_winner();
 
-        let replay = recorder.finalize();
+        let replay = recorder.finalize(None);
         let player = ReplayPlayer::new(replay);
 
         let mut replay_rng = SessionRng::new(player.seed());
@@ -267,4 +281,212 @@ mod integration_tests {
         assert_eq!(original_status, replay_game_state.status, "Game status should match after replay");
         assert_eq!(original_winner, replay_game_state.get_winner(), "Winner should match after replay");
     }
+
+    #[test]
+    fn test_tictactoe_state_hashes_detect_tampered_move() {
+        let player1 = PlayerId::new("player1".to_string());
+        let player2 = PlayerId::new("player2".to_string());
+        let players = vec![player1.clone(), player2.clone()];
+        let new_game = || TicTacToeGameState::new(3, 3, 3, players.clone(), FirstPlayerMode::Random, &mut SessionRng::new(3));
+
+        let mut game_state = new_game();
+        let mut recorder = ReplayRecorder::new(
+            "test".to_string(),
+            ReplayGame::Tictactoe,
+            3,
+            None,
+            vec![
+                PlayerIdentity { player_id: player1.to_string(), is_bot: false },
+                PlayerIdentity { player_id: player2.to_string(), is_bot: false },
+            ],
+        );
+        for (turn, (x, y)) in [(0, 0), (1, 1), (2, 2)].into_iter().enumerate() {
+            let current = game_state.current_player.clone();
+            let player_index = if current == player1 { 0 } else { 1 };
+            game_state.place_mark(&current, x, y).unwrap();
+            recorder.record_command(turn as i64, player_index, create_tictactoe_command(x as u32, y as u32));
+            recorder.record_state_hash(recorder.actions_count() as u64, game_state.state_hash());
+        }
+
+        let mut replay = recorder.finalize(None);
+        assert_eq!(replay.state_hashes.len(), 3);
+        assert_eq!(replay.metadata.as_ref().unwrap().rules_revision, super::RULES_REVISION);
+
+        let first_mismatch = |replay: &crate::ReplayV1| {
+            let mut replay_state = new_game();
+            for (i, action) in replay.actions.iter().enumerate() {
+                if let Some(content) = &action.content
+                    && let Some(crate::player_action_content::Content::Command(cmd)) = &content.content
+                    && let Some(in_game_command::Command::Tictactoe(ttt_cmd)) = &cmd.command
+                    && let Some(tic_tac_toe_in_game_command::Command::Place(place)) = &ttt_cmd.command
+                {
+                    let current = replay_state.current_player.clone();
+                    replay_state.place_mark(&current, place.x as usize, place.y as usize).unwrap();
+                }
+                let position = i as u64 + 1;
+                let expected = replay.state_hashes.iter().find(|h| h.position == position).unwrap();
+                if expected.hash != replay_state.state_hash() {
+                    return Some(position);
+                }
+            }
+            None
+        };
+        assert_eq!(first_mismatch(&replay), None);
+
+        replay.actions[1] = crate::PlayerAction {
+            tick: 1,
+            player_index: 1,
+            content: Some(crate::PlayerActionContent {
+                content: Some(crate::player_action_content::Content::Command(create_tictactoe_command(2, 0))),
+            }),
+            ..Default::default()
+        };
+        assert_eq!(first_mismatch(&replay), Some(2));
+    }
+
+    #[test]
+    fn test_tictactoe_replay_clock_timeout_reproduced() {
+        let player1 = PlayerId::new("player1".to_string());
+        let player2 = PlayerId::new("player2".to_string());
+        let control = TimeControl::Fischer { initial_ms: 5_000, increment_ms: 1_000 };
+
+        let new_game = || {
+            let mut rng = SessionRng::new(7);
+            let mut game_state = TicTacToeGameState::new(
+                3, 3, 3,
+                vec![player1.clone(), player2.clone()],
+                FirstPlayerMode::Host,
+                &mut rng,
+            );
+            game_state.set_time_control(control);
+            game_state
+        };
+
+        let mut game_state = new_game();
+        let mut recorder = ReplayRecorder::new(
+            "test".to_string(),
+            ReplayGame::Tictactoe,
+            7,
+            None,
+            vec![
+                PlayerIdentity { player_id: player1.to_string(), is_bot: false },
+                PlayerIdentity { player_id: player2.to_string(), is_bot: false },
+            ],
+        );
+
+        game_state.place_mark_at(&player1, 0, 0, 1_000).unwrap();
+        recorder.record_timed_command(0, 1_000, 0, create_tictactoe_command(0, 0));
+        game_state.place_mark_at(&player2, 1, 1, 3_000).unwrap();
+        recorder.record_timed_command(1, 3_000, 1, create_tictactoe_command(1, 1));
+
+        assert!(!game_state.check_timeout(7_999));
+        assert!(game_state.check_timeout(8_000));
+        recorder.record_timeout(2, 8_000, 0);
+
+        let replay = recorder.finalize(None);
+        let player_map = [player1.clone(), player2.clone()];
+        let mut replay_game_state = new_game();
+
+        for action in &replay.actions {
+            let player_id = &player_map[action.player_index as usize];
+            match action.content.as_ref().and_then(|c| c.content.as_ref()) {
+                Some(crate::player_action_content::Content::Command(cmd)) => {
+                    if let Some(in_game_command::Command::Tictactoe(ttt_cmd)) = &cmd.command
+                        && let Some(tic_tac_toe_in_game_command::Command::Place(place)) = &ttt_cmd.command
+                    {
+                        replay_game_state
+                            .place_mark_at(player_id, place.x as usize, place.y as usize, action.elapsed_ms as u64)
+                            .unwrap();
+                    }
+                }
+                Some(crate::player_action_content::Content::TimedOut(_)) => {
+                    assert!(replay_game_state.check_timeout(action.elapsed_ms as u64));
+                }
+                _ => {}
+            }
+        }
+
+        assert_eq!(replay_game_state.status, GameStatus::OWon);
+        assert!(replay_game_state.timed_out);
+        let original = game_state.to_proto_state(false, false, false, 8_000);
+        let replayed = replay_game_state.to_proto_state(false, false, false, 8_000);
+        assert_eq!(original.player_x_time_ms, Some(0));
+        assert_eq!(original.player_o_time_ms, replayed.player_o_time_ms);
+    }
+
+    #[tokio::test]
+    async fn test_snake_bot_takeover_recorded_as_control_change() {
+        use std::collections::{HashMap, HashSet};
+        use std::sync::Arc;
+        use std::time::Duration;
+        use tokio::sync::Mutex;
+        use crate::ClientId;
+        use crate::games::{BotTakeover, GameSessionConfig};
+        use crate::games::snake::{GameMode, MovementResolution, SpawnPlacement, SnakeSession, SnakeSessionSettings, SnakeSessionState};
+
+        let player = PlayerId::new("player".to_string());
+        let client = ClientId::new("player".to_string());
+        let config = GameSessionConfig::new(
+            "session".to_string(),
+            vec![player.clone()],
+            HashSet::new(),
+            HashMap::new(),
+            0,
+            Some(BotTakeover { idle_limit: 0 }),
+        );
+        let settings = SnakeSessionSettings {
+            field_width: 10,
+            field_height: 10,
+            wall_collision_mode: WallCollisionMode::WrapAround,
+            dead_snake_behavior: DeadSnakeBehavior::Disappear,
+            max_food_count: 1,
+            food_spawn_probability: 1.0,
+            food_weights: Default::default(),
+            map: None,
+            movement_resolution: MovementResolution::Simultaneous,
+            input_buffer_size: 0,
+            game_mode: GameMode::LastStanding,
+            teams: HashMap::new(),
+            teammates_pass_through: false,
+            spawn_placement: SpawnPlacement::Row,
+            tick_interval: Duration::from_millis(100),
+        };
+        let recorder = Arc::new(Mutex::new(ReplayRecorder::new(
+            "test".to_string(),
+            ReplayGame::Snake,
+            1,
+            None,
+            vec![PlayerIdentity { player_id: player.to_string(), is_bot: false }],
+        )));
+        let state = SnakeSessionState::create(&config, &settings, 1, Some(recorder.clone()));
+
+        assert!(SnakeSession::take_over(&state, &client).await);
+        assert!(SnakeSession::take_over(&state, &client).await);
+        assert!(state.takeover.lock().await.is_controlled(&player));
+
+        let turn = SnakeInGameCommand {
+            command: Some(snake_in_game_command::Command::Turn(TurnCommand {
+                direction: ProtoDirection::Left as i32,
+            })),
+        };
+        SnakeSession::handle_command(&state, &client, &turn).await;
+        assert!(!state.takeover.lock().await.is_controlled(&player));
+
+        let replay = recorder.lock().await.finalize(None);
+        let contents: Vec<_> = replay
+            .actions
+            .iter()
+            .map(|a| a.content.as_ref().and_then(|c| c.content))
+            .collect();
+        assert_eq!(contents.len(), 3);
+        assert_eq!(
+            contents[0],
+            Some(crate::player_action_content::Content::ControlChanged(crate::PlayerControlChanged { bot_controlled: true }))
+        );
+        assert_eq!(
+            contents[1],
+            Some(crate::player_action_content::Content::ControlChanged(crate::PlayerControlChanged { bot_controlled: false }))
+        );
+        assert!(matches!(contents[2], Some(crate::player_action_content::Content::Command(_))));
+    }
 }
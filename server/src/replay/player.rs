@@ -1,4 +1,5 @@
 use crate::{ReplayV1, ReplayV1Metadata, PlayerAction, PlayerIdentity, lobby_settings, ReplayGame};
+use crate::games::SessionRng;
 
 pub struct ReplayPlayer {
     replay: ReplayV1,
@@ -29,6 +30,13 @@ impl ReplayPlayer {
         self.metadata().seed
     }
 
+    /// The world stream the replay was recorded with. Replays with an unknown
+    /// algorithm are turned away by `check_rng_algorithm` before playback.
+    pub fn rng(&self) -> SessionRng {
+        SessionRng::with_algorithm(self.seed(), self.metadata().rng_algorithm)
+            .expect("Replay RNG algorithm must be supported")
+    }
+
     pub fn lobby_settings(&self) -> Option<&lobby_settings::Settings> {
         self.metadata().lobby_settings.as_ref().and_then(|s| s.settings.as_ref())
     }
@@ -91,6 +99,10 @@ impl ReplayPlayer {
         self.current_action_index = 0;
     }
 
+    pub fn set_action_index(&mut self, index: usize) {
+        self.current_action_index = index.min(self.replay.actions.len());
+    }
+
     pub fn into_replay(self) -> ReplayV1 {
         self.replay
     }
@@ -117,7 +129,12 @@ mod tests {
                     PlayerIdentity { player_id: "player1".to_string(), is_bot: false },
                     PlayerIdentity { player_id: "player2".to_string(), is_bot: false },
                 ],
+                rules_revision: 1,
+                duration_ms: 0,
+                result: None,
+                rng_algorithm: crate::games::RNG_ALGORITHM,
             }),
+            state_hashes: vec![],
             actions: vec![
                 PlayerAction {
                     tick: 1,
@@ -133,6 +150,7 @@ mod tests {
                             )),
                         })),
                     }),
+                    elapsed_ms: 0,
                 },
                 PlayerAction {
                     tick: 2,
@@ -148,6 +166,7 @@ mod tests {
                             )),
                         })),
                     }),
+                    elapsed_ms: 0,
                 },
                 PlayerAction {
                     tick: 2,
@@ -163,6 +182,7 @@ mod tests {
                             )),
                         })),
                     }),
+                    elapsed_ms: 0,
                 },
             ],
         }
@@ -1,8 +1,10 @@
 use crate::{
     PlayerIdentity, ReplayV1, ReplayV1Metadata, PlayerAction, PlayerActionContent,
-    player_action_content, ReplayGame, InGameCommand, PlayerDisconnected,
-    lobby_settings,
+    player_action_content, ReplayGame, InGameCommand, PlayerDisconnected, PlayerTimedOut, PlayerControlChanged,
+    StateHash, GameOverNotification, lobby_settings,
 };
+use super::RULES_REVISION;
+use crate::games::RNG_ALGORITHM;
 use std::collections::HashMap;
 
 pub struct ReplayRecorder {
@@ -13,6 +15,7 @@ pub struct ReplayRecorder {
     lobby_settings: Option<lobby_settings::Settings>,
     players: Vec<PlayerIdentity>,
     actions: Vec<PlayerAction>,
+    state_hashes: Vec<StateHash>,
     player_index_map: HashMap<String, i32>,
 }
 
@@ -43,38 +46,65 @@ impl ReplayRecorder {
             lobby_settings,
             players,
             actions: Vec::new(),
+            state_hashes: Vec::new(),
             player_index_map: index_map,
         }
     }
 
-    pub fn record_command(&mut self, tick: i64, player_index: i32, command: InGameCommand) {
+    fn push_action(&mut self, tick: i64, elapsed_ms: i64, player_index: i32, content: player_action_content::Content) {
         self.actions.push(PlayerAction {
             tick,
             player_index,
             content: Some(PlayerActionContent {
-                content: Some(player_action_content::Content::Command(command)),
+                content: Some(content),
             }),
+            elapsed_ms,
         });
     }
 
+    pub fn record_command(&mut self, tick: i64, player_index: i32, command: InGameCommand) {
+        self.push_action(tick, 0, player_index, player_action_content::Content::Command(command));
+    }
+
+    pub fn record_timed_command(&mut self, tick: i64, elapsed_ms: i64, player_index: i32, command: InGameCommand) {
+        self.push_action(tick, elapsed_ms, player_index, player_action_content::Content::Command(command));
+    }
+
     pub fn record_disconnect(&mut self, tick: i64, player_index: i32) {
-        self.actions.push(PlayerAction {
+        self.push_action(tick, 0, player_index, player_action_content::Content::Disconnected(PlayerDisconnected {}));
+    }
+
+    pub fn record_timeout(&mut self, tick: i64, elapsed_ms: i64, player_index: i32) {
+        self.push_action(tick, elapsed_ms, player_index, player_action_content::Content::TimedOut(PlayerTimedOut {}));
+    }
+
+    pub fn record_control_change(&mut self, tick: i64, elapsed_ms: i64, player_index: i32, bot_controlled: bool) {
+        self.push_action(
             tick,
+            elapsed_ms,
             player_index,
-            content: Some(PlayerActionContent {
-                content: Some(player_action_content::Content::Disconnected(PlayerDisconnected {})),
-            }),
-        });
+            player_action_content::Content::ControlChanged(PlayerControlChanged { bot_controlled }),
+        );
+    }
+
+    pub fn record_state_hash(&mut self, position: u64, hash: u64) {
+        self.state_hashes.push(StateHash { position, hash });
     }
 
     pub fn find_player_index(&self, player_id: &str) -> Option<i32> {
         self.player_index_map.get(player_id).copied()
     }
 
-    pub fn finalize(&mut self) -> ReplayV1 {
+    pub fn finalize(&mut self, result: Option<GameOverNotification>) -> ReplayV1 {
         let mut actions = std::mem::take(&mut self.actions);
         actions.sort_by_key(|a| a.tick);
 
+        let duration_ms = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .map(|d| d.as_millis() as i64 - self.game_started_timestamp_ms)
+            .unwrap_or(0)
+            .max(0) as u64;
+
         ReplayV1 {
             metadata: Some(ReplayV1Metadata {
                 engine_version: std::mem::take(&mut self.engine_version),
@@ -83,8 +113,13 @@ impl ReplayRecorder {
                 seed: self.seed,
                 lobby_settings: self.lobby_settings.take().map(|s| crate::LobbySettings { settings: Some(s) }),
                 players: std::mem::take(&mut self.players),
+                rules_revision: RULES_REVISION,
+                rng_algorithm: RNG_ALGORITHM,
+                duration_ms,
+                result,
             }),
             actions,
+            state_hashes: std::mem::take(&mut self.state_hashes),
         }
     }
 
@@ -11,6 +11,8 @@ pub struct ReplaySessionHandle {
     pub command_tx: mpsc::UnboundedSender<ReplaySessionCommand>,
     pub host_id: ClientId,
     pub host_only_control: bool,
+    /// Kept so the host can branch a live game off the replay.
+    pub replay: ReplayV1,
 }
 
 pub enum ReplaySessionCommand {
@@ -124,6 +126,7 @@ pub(crate) async fn broadcast_state_and_replay_info(
     speed: f32,
     is_finished: bool,
     host_only_control: bool,
+    desync_position: Option<u64>,
 ) {
     let game_msg = ServerMessage {
         message: Some(server_message::Message::GameState(state_update)),
@@ -138,26 +141,12 @@ pub(crate) async fn broadcast_state_and_replay_info(
             speed,
             is_finished,
             host_only_control,
+            desync_position,
         })),
     };
     broadcaster.broadcast_to_clients(viewers, replay_msg).await;
 }
 
-pub(crate) async fn wait_for_restart_or_stop(
-    command_rx: &mut mpsc::UnboundedReceiver<ReplaySessionCommand>,
-) -> bool {
-    loop {
-        match command_rx.recv().await {
-            Some(ReplaySessionCommand::ReplayCommand(cmd)) => {
-                if let Some(in_replay_command::Command::Restart(_)) = &cmd.command {
-                    return true;
-                }
-            }
-            None => return false,
-        }
-    }
-}
-
 pub(crate) fn handle_replay_command(
     cmd: &InReplayCommand,
     is_paused: &mut bool,
@@ -187,7 +176,16 @@ pub(crate) fn handle_replay_command(
                 ReplayCommandResult::None
             }
         }
+        in_replay_command::Command::StepBackward(_) => {
+            if *is_paused {
+                ReplayCommandResult::StepBackward
+            } else {
+                ReplayCommandResult::None
+            }
+        }
         in_replay_command::Command::Restart(_) => ReplayCommandResult::Restart,
+        in_replay_command::Command::Seek(s) => ReplayCommandResult::Seek(s.position),
+        in_replay_command::Command::Branch(_) => ReplayCommandResult::None,
     }
 }
 
@@ -196,6 +194,8 @@ pub(crate) enum ReplayCommandResult {
     StateChanged,
     SpeedChanged,
     StepForward,
+    StepBackward,
+    Seek(u64),
     Restart,
 }
 
@@ -0,0 +1,195 @@
+use std::collections::HashMap;
+
+use crate::replay::ReplayPlayer;
+
+/// Ticks or moves between in-memory snapshots kept for seeking.
+pub const CHECKPOINT_INTERVAL: u64 = 100;
+
+/// A game's replay state that can be cloned into a checkpoint and advanced one tick or move at a time.
+pub(crate) trait ReplaySimulation: Clone {
+    fn position(&self) -> u64;
+    fn is_finished(&self, player: &ReplayPlayer) -> bool;
+    fn step(&mut self, player: &mut ReplayPlayer);
+    fn state_hash(&self) -> u64;
+}
+
+struct Checkpoint<S> {
+    simulation: S,
+    action_index: usize,
+}
+
+pub(crate) struct ReplayTimeline<S: ReplaySimulation> {
+    checkpoints: Vec<Checkpoint<S>>,
+    expected_hashes: HashMap<u64, u64>,
+    desync_position: Option<u64>,
+}
+
+impl<S: ReplaySimulation> ReplayTimeline<S> {
+    pub fn new(initial: &S, player: &ReplayPlayer) -> Self {
+        Self {
+            checkpoints: vec![Checkpoint {
+                simulation: initial.clone(),
+                action_index: player.current_action_index(),
+            }],
+            expected_hashes: player
+                .replay_ref()
+                .state_hashes
+                .iter()
+                .map(|h| (h.position, h.hash))
+                .collect(),
+            desync_position: None,
+        }
+    }
+
+    /// First position whose simulated state differs from the hash recorded live.
+    pub fn desync_position(&self) -> Option<u64> {
+        self.desync_position
+    }
+
+    pub fn step(&mut self, simulation: &mut S, player: &mut ReplayPlayer) {
+        simulation.step(player);
+
+        let position = simulation.position();
+        if self.expected_hashes.get(&position).is_some_and(|&hash| hash != simulation.state_hash())
+            && self.desync_position.is_none_or(|p| position < p)
+        {
+            self.desync_position = Some(position);
+        }
+
+        let last_position = self.checkpoints.last().map_or(0, |c| c.simulation.position());
+        if position.is_multiple_of(CHECKPOINT_INTERVAL) && position > last_position {
+            self.checkpoints.push(Checkpoint {
+                simulation: simulation.clone(),
+                action_index: player.current_action_index(),
+            });
+        }
+    }
+
+    /// Restores the closest checkpoint at or before `target` and simulates forward from it.
+    pub fn seek(&mut self, simulation: &mut S, player: &mut ReplayPlayer, target: u64) {
+        let checkpoint = self
+            .checkpoints
+            .iter()
+            .rev()
+            .find(|c| c.simulation.position() <= target)
+            .unwrap_or(&self.checkpoints[0]);
+
+        *simulation = checkpoint.simulation.clone();
+        player.set_action_index(checkpoint.action_index);
+
+        while simulation.position() < target && !simulation.is_finished(player) {
+            self.step(simulation, player);
+        }
+    }
+
+    pub fn step_back(&mut self, simulation: &mut S, player: &mut ReplayPlayer) {
+        let target = simulation.position().saturating_sub(1);
+        self.seek(simulation, player, target);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{ReplayV1, StateHash};
+    use crate::games::SessionRng;
+
+    #[derive(Clone)]
+    struct CountingSimulation {
+        rng: SessionRng,
+        value: u64,
+        position: u64,
+    }
+
+    impl ReplaySimulation for CountingSimulation {
+        fn position(&self) -> u64 {
+            self.position
+        }
+
+        fn is_finished(&self, _player: &ReplayPlayer) -> bool {
+            self.position >= 1_000
+        }
+
+        fn step(&mut self, _player: &mut ReplayPlayer) {
+            self.value = self.value.wrapping_mul(31).wrapping_add(self.rng.next_u32() as u64);
+            self.position += 1;
+        }
+
+        fn state_hash(&self) -> u64 {
+            self.value
+        }
+    }
+
+    fn new_simulation() -> CountingSimulation {
+        CountingSimulation { rng: SessionRng::new(7), value: 0, position: 0 }
+    }
+
+    fn play_to(target: u64) -> CountingSimulation {
+        let mut simulation = new_simulation();
+        let mut player = ReplayPlayer::new(ReplayV1::default());
+        while simulation.position < target {
+            simulation.step(&mut player);
+        }
+        simulation
+    }
+
+    #[test]
+    fn test_seek_backward_and_forward_matches_playing_from_start() {
+        let mut player = ReplayPlayer::new(ReplayV1::default());
+        let mut simulation = new_simulation();
+        let mut timeline = ReplayTimeline::new(&simulation, &player);
+
+        timeline.seek(&mut simulation, &mut player, 450);
+        assert_eq!(simulation.position, 450);
+        assert_eq!(timeline.checkpoints.len(), 5);
+
+        timeline.seek(&mut simulation, &mut player, 250);
+        assert_eq!(simulation.value, play_to(250).value);
+
+        timeline.seek(&mut simulation, &mut player, 5_000);
+        assert_eq!(simulation.position, 1_000);
+        assert_eq!(simulation.value, play_to(1_000).value);
+    }
+
+    #[test]
+    fn test_step_back_across_checkpoint_restores_rng() {
+        let mut player = ReplayPlayer::new(ReplayV1::default());
+        let mut simulation = new_simulation();
+        let mut timeline = ReplayTimeline::new(&simulation, &player);
+        timeline.seek(&mut simulation, &mut player, 101);
+
+        timeline.step_back(&mut simulation, &mut player);
+        timeline.step_back(&mut simulation, &mut player);
+        assert_eq!(simulation.position, 99);
+        assert_eq!(simulation.value, play_to(99).value);
+
+        timeline.step(&mut simulation, &mut player);
+        timeline.step(&mut simulation, &mut player);
+        assert_eq!(simulation.value, play_to(101).value);
+
+        let mut start = new_simulation();
+        timeline.step_back(&mut start, &mut player);
+        assert_eq!(start.position, 0);
+    }
+
+    #[test]
+    fn test_desync_position_first_mismatched_hash() {
+        let replay = ReplayV1 {
+            state_hashes: vec![
+                StateHash { position: 10, hash: play_to(10).value },
+                StateHash { position: 20, hash: play_to(20).value ^ 1 },
+                StateHash { position: 30, hash: 0 },
+            ],
+            ..Default::default()
+        };
+        let mut player = ReplayPlayer::new(replay);
+        let mut simulation = new_simulation();
+        let mut timeline = ReplayTimeline::new(&simulation, &player);
+
+        timeline.seek(&mut simulation, &mut player, 15);
+        assert_eq!(timeline.desync_position(), None);
+
+        timeline.seek(&mut simulation, &mut player, 40);
+        assert_eq!(timeline.desync_position(), Some(20));
+    }
+}
@@ -0,0 +1,235 @@
+use std::fmt;
+
+use crate::games::{LEGACY_RNG_ALGORITHM, RNG_ALGORITHM};
+use crate::replay::ReplayPlayer;
+use crate::replay::session::replay_game_type_name;
+use crate::replay::timeline::{ReplaySimulation, ReplayTimeline};
+use crate::{ReplayGame, ReplayV1, in_game_command, player_action_content};
+
+pub struct ReplaySummary {
+    pub game: ReplayGame,
+    /// Ticks for real-time games, applied actions for turn-based ones.
+    pub ticks: u64,
+    pub outcome: String,
+    pub scores: Vec<(String, u32)>,
+    pub desync_position: Option<u64>,
+}
+
+impl fmt::Display for ReplaySummary {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}, {} ticks, {}", replay_game_type_name(self.game), self.ticks, self.outcome)?;
+        if !self.scores.is_empty() {
+            let scores: Vec<String> = self.scores.iter().map(|(id, score)| format!("{}={}", id, score)).collect();
+            write!(f, ", scores: {}", scores.join(" "))?;
+        }
+        if let Some(position) = self.desync_position {
+            write!(f, ", DESYNC at {}", position)?;
+        }
+        Ok(())
+    }
+}
+
+/// Re-simulates a replay to its last recorded action without timers or viewers.
+pub fn verify_replay(replay: ReplayV1) -> Result<ReplaySummary, String> {
+    let game = validate_replay(&replay)?;
+    let player = ReplayPlayer::new(replay);
+
+    match game {
+        ReplayGame::Snake => crate::games::snake::replay::simulate(player),
+        ReplayGame::Tictactoe => crate::games::tictactoe::replay::simulate(player),
+        ReplayGame::NumbersMatch => crate::games::numbers_match::replay::simulate(player),
+        ReplayGame::StackAttack => crate::games::stack_attack::replay::simulate(player),
+        ReplayGame::Puzzle2048 => crate::games::puzzle2048::replay::simulate(player),
+        ReplayGame::Unspecified => Err("Unknown game type in replay".to_string()),
+    }
+}
+
+/// Replays only reproduce with the random number generator they were recorded with.
+pub fn check_rng_algorithm(replay: &ReplayV1) -> Result<(), String> {
+    let algorithm = replay.metadata.as_ref().map_or(0, |m| m.rng_algorithm);
+    if algorithm != RNG_ALGORITHM && algorithm != LEGACY_RNG_ALGORITHM {
+        return Err(format!(
+            "Replay uses unknown random number algorithm {}, this server plays algorithms up to {}",
+            algorithm, RNG_ALGORITHM
+        ));
+    }
+    Ok(())
+}
+
+/// Checks the structure the game simulations take for granted.
+pub fn validate_replay(replay: &ReplayV1) -> Result<ReplayGame, String> {
+    check_rng_algorithm(replay)?;
+    let metadata = replay.metadata.as_ref().ok_or("Replay has no metadata")?;
+    let game = match ReplayGame::try_from(metadata.game) {
+        Ok(ReplayGame::Unspecified) | Err(_) => return Err("Unknown game type in replay".to_string()),
+        Ok(game) => game,
+    };
+
+    let mut last_tick = i64::MIN;
+    for (i, action) in replay.actions.iter().enumerate() {
+        if action.player_index < 0 || action.player_index as usize >= metadata.players.len() {
+            return Err(format!("Action {} references unknown player index {}", i, action.player_index));
+        }
+        if action.tick < last_tick {
+            return Err(format!("Action {} at tick {} comes after tick {}", i, action.tick, last_tick));
+        }
+        last_tick = action.tick;
+
+        let Some(content) = action.content.as_ref().and_then(|c| c.content.as_ref()) else {
+            return Err(format!("Action {} has no content", i));
+        };
+        if let player_action_content::Content::Command(cmd) = content {
+            let command_game = match &cmd.command {
+                Some(in_game_command::Command::Snake(_)) => ReplayGame::Snake,
+                Some(in_game_command::Command::Tictactoe(_)) => ReplayGame::Tictactoe,
+                Some(in_game_command::Command::NumbersMatch(_)) => ReplayGame::NumbersMatch,
+                Some(in_game_command::Command::StackAttack(_)) => ReplayGame::StackAttack,
+                Some(in_game_command::Command::Puzzle2048(_)) => ReplayGame::Puzzle2048,
+                None => return Err(format!("Action {} has an empty command", i)),
+            };
+            if command_game != game {
+                return Err(format!(
+                    "Action {} is a {} command in a {} replay",
+                    i,
+                    replay_game_type_name(command_game),
+                    replay_game_type_name(game),
+                ));
+            }
+        }
+    }
+
+    Ok(game)
+}
+
+/// Steps until the replay ends and returns the first position whose state hash did not match.
+pub(crate) fn play_to_end<S: ReplaySimulation>(simulation: &mut S, player: &mut ReplayPlayer) -> Option<u64> {
+    let mut timeline = ReplayTimeline::new(simulation, player);
+    while !simulation.is_finished(player) {
+        timeline.step(simulation, player);
+    }
+    timeline.desync_position()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::replay::ReplayRecorder;
+    use crate::games::tictactoe::create_place_command;
+    use crate::{InGameCommand, PlayerIdentity, lobby_settings};
+
+    fn tictactoe_recorder() -> ReplayRecorder {
+        let settings = lobby_settings::Settings::Tictactoe(crate::proto::tictactoe::TicTacToeLobbySettings {
+            field_width: 3,
+            field_height: 3,
+            win_count: 3,
+            first_player: crate::proto::tictactoe::FirstPlayerMode::Host as i32,
+            ..Default::default()
+        });
+        ReplayRecorder::new(
+            "test".to_string(),
+            ReplayGame::Tictactoe,
+            1,
+            Some(settings),
+            vec![
+                PlayerIdentity { player_id: "alice".to_string(), is_bot: false },
+                PlayerIdentity { player_id: "bob".to_string(), is_bot: false },
+            ],
+        )
+    }
+
+    #[test]
+    fn test_verify_replay_tictactoe_winner_reported() {
+        let mut recorder = tictactoe_recorder();
+        for (turn, (x, y)) in [(0, 0), (0, 1), (1, 0), (1, 1), (2, 0)].into_iter().enumerate() {
+            recorder.record_command(turn as i64, (turn % 2) as i32, create_place_command(x, y));
+        }
+
+        let summary = verify_replay(recorder.finalize(None)).unwrap();
+        assert_eq!(summary.ticks, 5);
+        assert_eq!(summary.outcome, "alice won");
+        assert_eq!(summary.desync_position, None);
+    }
+
+    #[test]
+    fn test_verify_replay_plays_back_legacy_rng_replay() {
+        use crate::games::SessionRng;
+        use crate::games::puzzle2048::{Direction, Puzzle2048GameState};
+        use crate::proto::puzzle2048::{MoveCommand, Puzzle2048Direction, Puzzle2048InGameCommand, Puzzle2048LobbySettings, puzzle2048_in_game_command};
+
+        let seed = 2024;
+        let settings = Puzzle2048LobbySettings { field_width: 4, field_height: 4, target_value: 2048 };
+        let mut recorder = ReplayRecorder::new(
+            "test".to_string(),
+            ReplayGame::Puzzle2048,
+            seed,
+            Some(lobby_settings::Settings::Puzzle2048(settings)),
+            vec![PlayerIdentity { player_id: "alice".to_string(), is_bot: false }],
+        );
+
+        let mut rng = SessionRng::with_algorithm(seed, LEGACY_RNG_ALGORITHM).unwrap();
+        let mut game_state = Puzzle2048GameState::new(4, 4, 2048, &mut rng);
+        let moves = [
+            (Direction::Left, Puzzle2048Direction::Left),
+            (Direction::Up, Puzzle2048Direction::Up),
+            (Direction::Right, Puzzle2048Direction::Right),
+            (Direction::Down, Puzzle2048Direction::Down),
+        ];
+        for (tick, (direction, proto_direction)) in moves.iter().cycle().take(20).enumerate() {
+            game_state.apply_move(*direction, &mut rng);
+            recorder.record_command(tick as i64, 0, InGameCommand {
+                command: Some(in_game_command::Command::Puzzle2048(Puzzle2048InGameCommand {
+                    command: Some(puzzle2048_in_game_command::Command::Move(MoveCommand { direction: *proto_direction as i32 })),
+                })),
+            });
+            recorder.record_state_hash(recorder.actions_count() as u64, game_state.state_hash());
+        }
+
+        let mut replay = recorder.finalize(None);
+        replay.metadata.as_mut().unwrap().rng_algorithm = LEGACY_RNG_ALGORITHM;
+
+        let summary = verify_replay(replay).unwrap();
+        assert_eq!(summary.desync_position, None);
+        assert_eq!(summary.scores, vec![("alice".to_string(), game_state.score())]);
+    }
+
+    #[test]
+    fn test_validate_replay_unknown_rng_algorithm_error() {
+        let mut replay = tictactoe_recorder().finalize(None);
+        replay.metadata.as_mut().unwrap().rng_algorithm = RNG_ALGORITHM + 1;
+
+        let err = validate_replay(&replay).unwrap_err();
+        assert!(err.contains("unknown random number algorithm"), "{}", err);
+    }
+
+    #[test]
+    fn test_validate_replay_unknown_player_index_error() {
+        let mut recorder = tictactoe_recorder();
+        recorder.record_command(0, 2, create_place_command(0, 0));
+
+        let err = validate_replay(&recorder.finalize(None)).unwrap_err();
+        assert!(err.contains("unknown player index 2"), "{}", err);
+    }
+
+    #[test]
+    fn test_validate_replay_out_of_order_ticks_error() {
+        let mut recorder = tictactoe_recorder();
+        recorder.record_command(0, 0, create_place_command(0, 0));
+        recorder.record_command(1, 1, create_place_command(1, 1));
+        let mut replay = recorder.finalize(None);
+        replay.actions.swap(0, 1);
+
+        let err = validate_replay(&replay).unwrap_err();
+        assert!(err.contains("comes after tick"), "{}", err);
+    }
+
+    #[test]
+    fn test_validate_replay_command_for_wrong_game_error() {
+        let mut recorder = tictactoe_recorder();
+        recorder.record_command(0, 0, InGameCommand {
+            command: Some(in_game_command::Command::Snake(crate::SnakeInGameCommand::default())),
+        });
+
+        let err = validate_replay(&recorder.finalize(None)).unwrap_err();
+        assert_eq!(err, "Action 0 is a Snake command in a TicTacToe replay");
+    }
+}
@@ -2,3 +2,5 @@ use std::time::Duration;
 
 pub const CLEANUP_CHECK_INTERVAL: Duration = Duration::from_secs(300);
 pub const INACTIVITY_TIMEOUT: Duration = Duration::from_secs(3600);
+pub const MATCHMAKING_CHECK_INTERVAL: Duration = Duration::from_secs(1);
+pub const MATCHMAKING_BOT_BACKFILL_WAIT: Duration = Duration::from_secs(30);
@@ -0,0 +1,708 @@
+use std::collections::HashSet;
+use crate::{LobbyId, PlayerId};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TournamentFormat {
+    SingleElimination,
+    DoubleElimination,
+    RoundRobin,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TournamentStatus {
+    Registration,
+    InProgress,
+    Finished,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Bracket {
+    Winners,
+    Losers,
+    GrandFinal,
+    RoundRobin,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MatchStatus {
+    Pending,
+    InProgress,
+    Completed,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Slot {
+    Seed(usize),
+    WinnerOf(usize),
+    LoserOf(usize),
+    Bye,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Occupant {
+    Player(PlayerId),
+    Bye,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SeriesOutcome {
+    Continues,
+    Finished,
+}
+
+#[derive(Debug, Clone)]
+pub struct TournamentMatch {
+    pub id: usize,
+    pub bracket: Bracket,
+    pub round: u32,
+    pub slots: [Slot; 2],
+    pub occupants: [Option<Occupant>; 2],
+    pub wins: [u32; 2],
+    pub draws: u32,
+    pub status: MatchStatus,
+    pub winner: Option<usize>,
+    pub walkover: bool,
+    pub only_if_needed: bool,
+    pub lobby_id: Option<LobbyId>,
+}
+
+impl TournamentMatch {
+    fn new(id: usize, bracket: Bracket, round: u32, slots: [Slot; 2]) -> Self {
+        Self {
+            id,
+            bracket,
+            round,
+            slots,
+            occupants: [None, None],
+            wins: [0, 0],
+            draws: 0,
+            status: MatchStatus::Pending,
+            winner: None,
+            walkover: false,
+            only_if_needed: false,
+            lobby_id: None,
+        }
+    }
+
+    pub fn player(&self, side: usize) -> Option<&PlayerId> {
+        match &self.occupants[side] {
+            Some(Occupant::Player(player_id)) => Some(player_id),
+            _ => None,
+        }
+    }
+
+    pub fn players(&self) -> Option<[PlayerId; 2]> {
+        Some([self.player(0)?.clone(), self.player(1)?.clone()])
+    }
+
+    pub fn side_of(&self, player_id: &PlayerId) -> Option<usize> {
+        (0..2).find(|&side| self.player(side) == Some(player_id))
+    }
+
+    fn winner_occupant(&self) -> Option<Occupant> {
+        match self.status {
+            MatchStatus::Completed => Some(match self.winner {
+                Some(side) => self.occupants[side].clone().unwrap_or(Occupant::Bye),
+                None => Occupant::Bye,
+            }),
+            _ => None,
+        }
+    }
+
+    fn loser_occupant(&self) -> Option<Occupant> {
+        match self.status {
+            MatchStatus::Completed => Some(match self.winner {
+                Some(side) => self.occupants[1 - side].clone().unwrap_or(Occupant::Bye),
+                None => Occupant::Bye,
+            }),
+            _ => None,
+        }
+    }
+
+    fn complete_walkover(&mut self, winner: Option<usize>) {
+        self.status = MatchStatus::Completed;
+        self.winner = winner;
+        self.walkover = true;
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Standing {
+    pub player_id: PlayerId,
+    pub match_wins: u32,
+    pub match_losses: u32,
+    pub match_draws: u32,
+    pub game_wins: u32,
+    pub game_losses: u32,
+    pub eliminated: bool,
+}
+
+impl Standing {
+    fn points(&self) -> u32 {
+        self.match_wins * 2 + self.match_draws
+    }
+
+    fn game_difference(&self) -> i64 {
+        self.game_wins as i64 - self.game_losses as i64
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct Tournament {
+    pub format: TournamentFormat,
+    pub best_of: u32,
+    pub status: TournamentStatus,
+    pub players: Vec<PlayerId>,
+    pub withdrawn: HashSet<PlayerId>,
+    pub matches: Vec<TournamentMatch>,
+}
+
+impl Tournament {
+    pub fn new(format: TournamentFormat, best_of: u32) -> Result<Self, String> {
+        if best_of == 0 || best_of.is_multiple_of(2) || best_of > 9 {
+            return Err("Best-of must be an odd number between 1 and 9".to_string());
+        }
+
+        Ok(Self {
+            format,
+            best_of,
+            status: TournamentStatus::Registration,
+            players: Vec::new(),
+            withdrawn: HashSet::new(),
+            matches: Vec::new(),
+        })
+    }
+
+    pub fn register(&mut self, player_id: PlayerId) -> Result<(), String> {
+        if self.status != TournamentStatus::Registration {
+            return Err("Tournament registration is closed".to_string());
+        }
+        if self.players.contains(&player_id) {
+            return Err("Already registered".to_string());
+        }
+        self.players.push(player_id);
+        Ok(())
+    }
+
+    pub fn unregister(&mut self, player_id: &PlayerId) -> bool {
+        if self.status != TournamentStatus::Registration {
+            return false;
+        }
+        let before = self.players.len();
+        self.players.retain(|p| p != player_id);
+        self.players.len() != before
+    }
+
+    pub fn withdraw(&mut self, player_id: &PlayerId) -> bool {
+        if self.status != TournamentStatus::InProgress || !self.players.contains(player_id) {
+            return false;
+        }
+        if !self.withdrawn.insert(player_id.clone()) {
+            return false;
+        }
+        self.resolve();
+        true
+    }
+
+    pub fn is_active_participant(&self, player_id: &PlayerId) -> bool {
+        self.status == TournamentStatus::InProgress
+            && self.players.contains(player_id)
+            && !self.withdrawn.contains(player_id)
+    }
+
+    pub fn start(&mut self) -> Result<(), String> {
+        if self.status != TournamentStatus::Registration {
+            return Err("Tournament already started".to_string());
+        }
+        if self.players.len() < 2 {
+            return Err("Tournament requires at least 2 players".to_string());
+        }
+
+        self.matches = match self.format {
+            TournamentFormat::SingleElimination => build_elimination(self.players.len(), false),
+            TournamentFormat::DoubleElimination => build_elimination(self.players.len(), true),
+            TournamentFormat::RoundRobin => build_round_robin(self.players.len()),
+        };
+        self.status = TournamentStatus::InProgress;
+        self.resolve();
+
+        Ok(())
+    }
+
+    pub fn ready_matches(&self) -> Vec<usize> {
+        let busy: HashSet<&PlayerId> = self
+            .matches
+            .iter()
+            .filter(|m| m.status == MatchStatus::InProgress)
+            .flat_map(|m| m.occupants.iter())
+            .filter_map(|o| match o {
+                Some(Occupant::Player(player_id)) => Some(player_id),
+                _ => None,
+            })
+            .collect();
+
+        let mut scheduled: HashSet<&PlayerId> = HashSet::new();
+        let mut ready = Vec::new();
+
+        for m in &self.matches {
+            if m.status != MatchStatus::Pending {
+                continue;
+            }
+            let (Some(a), Some(b)) = (m.player(0), m.player(1)) else {
+                continue;
+            };
+            if busy.contains(a) || busy.contains(b) || scheduled.contains(a) || scheduled.contains(b) {
+                continue;
+            }
+            scheduled.insert(a);
+            scheduled.insert(b);
+            ready.push(m.id);
+        }
+
+        ready
+    }
+
+    pub fn begin_match(&mut self, match_id: usize) {
+        if let Some(m) = self.matches.get_mut(match_id) {
+            m.status = MatchStatus::InProgress;
+        }
+    }
+
+    pub fn abort_match(&mut self, match_id: usize) {
+        if let Some(m) = self.matches.get_mut(match_id) {
+            m.status = MatchStatus::Pending;
+            m.lobby_id = None;
+        }
+    }
+
+    /// A series is won by the first side to take a majority of `best_of`
+    /// games. One still tied after `best_of` games is drawn in round robin;
+    /// elsewhere it goes on until a game is won, however many draws that takes.
+    pub fn record_game(&mut self, match_id: usize, winner: Option<&PlayerId>) -> Result<SeriesOutcome, String> {
+        let best_of = self.best_of;
+        let allow_draw = self.format == TournamentFormat::RoundRobin;
+
+        let m = self.matches.get_mut(match_id).ok_or("Match not found")?;
+        if m.status != MatchStatus::InProgress {
+            return Err("Match is not in progress".to_string());
+        }
+
+        match winner.and_then(|w| m.side_of(w)) {
+            Some(side) => m.wins[side] += 1,
+            None => m.draws += 1,
+        }
+        m.lobby_id = None;
+
+        let wins_needed = best_of / 2 + 1;
+        let games_played = m.wins[0] + m.wins[1] + m.draws;
+
+        let decided = if m.wins[0] >= wins_needed {
+            Some(Some(0))
+        } else if m.wins[1] >= wins_needed {
+            Some(Some(1))
+        } else if games_played >= best_of && m.wins[0] != m.wins[1] {
+            Some(Some(if m.wins[0] > m.wins[1] { 0 } else { 1 }))
+        } else if games_played >= best_of && allow_draw {
+            Some(None)
+        } else {
+            None
+        };
+
+        let Some(series_winner) = decided else {
+            return Ok(SeriesOutcome::Continues);
+        };
+
+        m.status = MatchStatus::Completed;
+        m.winner = series_winner;
+        self.resolve();
+
+        Ok(SeriesOutcome::Finished)
+    }
+
+    pub fn champion(&self) -> Option<PlayerId> {
+        if self.status != TournamentStatus::Finished {
+            return None;
+        }
+
+        match self.format {
+            TournamentFormat::RoundRobin => self.standings().first().map(|s| s.player_id.clone()),
+            TournamentFormat::SingleElimination | TournamentFormat::DoubleElimination => {
+                self.bracket_champion()
+            }
+        }
+    }
+
+    fn bracket_champion(&self) -> Option<PlayerId> {
+        if self.status != TournamentStatus::Finished {
+            return None;
+        }
+
+        match self.matches.last()?.winner_occupant()? {
+            Occupant::Player(player_id) => Some(player_id),
+            Occupant::Bye => None,
+        }
+    }
+
+    pub fn standings(&self) -> Vec<Standing> {
+        let mut standings: Vec<Standing> = self
+            .players
+            .iter()
+            .map(|player_id| Standing {
+                player_id: player_id.clone(),
+                match_wins: 0,
+                match_losses: 0,
+                match_draws: 0,
+                game_wins: 0,
+                game_losses: 0,
+                eliminated: self.withdrawn.contains(player_id),
+            })
+            .collect();
+
+        for m in self.matches.iter().filter(|m| m.status == MatchStatus::Completed && !(m.walkover && m.only_if_needed)) {
+            for side in 0..2 {
+                let Some(player_id) = m.player(side) else {
+                    continue;
+                };
+                let Some(standing) = standings.iter_mut().find(|s| &s.player_id == player_id) else {
+                    continue;
+                };
+
+                if m.walkover && m.player(1 - side).is_none() && m.winner == Some(side) {
+                    continue;
+                }
+
+                standing.game_wins += m.wins[side];
+                standing.game_losses += m.wins[1 - side];
+                match m.winner {
+                    Some(winner) if winner == side => standing.match_wins += 1,
+                    Some(_) => standing.match_losses += 1,
+                    None => standing.match_draws += 1,
+                }
+            }
+        }
+
+        let allowed_losses = match self.format {
+            TournamentFormat::SingleElimination => 1,
+            TournamentFormat::DoubleElimination => 2,
+            TournamentFormat::RoundRobin => u32::MAX,
+        };
+        for standing in &mut standings {
+            if standing.match_losses >= allowed_losses {
+                standing.eliminated = true;
+            }
+        }
+
+        let seed_of = |player_id: &PlayerId| self.players.iter().position(|p| p == player_id);
+        standings.sort_by(|a, b| {
+            a.eliminated
+                .cmp(&b.eliminated)
+                .then(b.points().cmp(&a.points()))
+                .then(b.game_difference().cmp(&a.game_difference()))
+                .then(seed_of(&a.player_id).cmp(&seed_of(&b.player_id)))
+        });
+
+        if self.format != TournamentFormat::RoundRobin
+            && let Some(champion) = self.bracket_champion()
+            && let Some(index) = standings.iter().position(|s| s.player_id == champion)
+        {
+            let standing = standings.remove(index);
+            standings.insert(0, standing);
+        }
+
+        standings
+    }
+
+    fn resolve_slot(&self, slot: Slot) -> Option<Occupant> {
+        match slot {
+            Slot::Seed(index) => Some(Occupant::Player(self.players[index].clone())),
+            Slot::WinnerOf(match_id) => self.matches[match_id].winner_occupant(),
+            Slot::LoserOf(match_id) => self.matches[match_id].loser_occupant(),
+            Slot::Bye => Some(Occupant::Bye),
+        }
+    }
+
+    fn resolve(&mut self) {
+        loop {
+            let mut changed = false;
+
+            for index in 0..self.matches.len() {
+                if self.matches[index].status != MatchStatus::Pending {
+                    continue;
+                }
+
+                for side in 0..2 {
+                    if self.matches[index].occupants[side].is_none() {
+                        let occupant = self.resolve_slot(self.matches[index].slots[side]);
+                        if occupant.is_some() {
+                            self.matches[index].occupants[side] = occupant;
+                            changed = true;
+                        }
+                    }
+                }
+
+                let m = &self.matches[index];
+                let (Some(a), Some(b)) = (&m.occupants[0], &m.occupants[1]) else {
+                    continue;
+                };
+
+                let present = |o: &Occupant| matches!(o, Occupant::Player(p) if !self.withdrawn.contains(p));
+                let walkover = match (present(a), present(b)) {
+                    (true, true) => None,
+                    (true, false) => Some(Some(0)),
+                    (false, true) => Some(Some(1)),
+                    (false, false) => Some(None),
+                };
+
+                let upper_bracket_champion_won = m.only_if_needed
+                    && matches!(m.slots[0], Slot::WinnerOf(gf) if self.matches[gf].winner == Some(0));
+
+                if upper_bracket_champion_won {
+                    self.matches[index].complete_walkover(Some(0));
+                    changed = true;
+                } else if let Some(winner) = walkover {
+                    self.matches[index].complete_walkover(winner);
+                    changed = true;
+                }
+            }
+
+            if !changed {
+                break;
+            }
+        }
+
+        if self.status == TournamentStatus::InProgress
+            && self.matches.iter().all(|m| m.status == MatchStatus::Completed)
+        {
+            self.status = TournamentStatus::Finished;
+        }
+    }
+}
+
+fn bracket_order(size: usize) -> Vec<usize> {
+    let mut order = vec![0];
+    while order.len() < size {
+        let next_len = order.len() * 2;
+        order = order.iter().flat_map(|&s| [s, next_len - 1 - s]).collect();
+    }
+    order
+}
+
+fn build_elimination(player_count: usize, double: bool) -> Vec<TournamentMatch> {
+    let size = player_count.next_power_of_two().max(2);
+    let order = bracket_order(size);
+    let mut matches: Vec<TournamentMatch> = Vec::new();
+    let push = |matches: &mut Vec<TournamentMatch>, bracket, round, slots| {
+        let id = matches.len();
+        matches.push(TournamentMatch::new(id, bracket, round, slots));
+        id
+    };
+
+    let seed_slot = |seed: usize| if seed < player_count { Slot::Seed(seed) } else { Slot::Bye };
+
+    let mut winners_rounds: Vec<Vec<usize>> = Vec::new();
+    let first_round = order
+        .chunks(2)
+        .map(|pair| push(&mut matches, Bracket::Winners, 1, [seed_slot(pair[0]), seed_slot(pair[1])]))
+        .collect();
+    winners_rounds.push(first_round);
+
+    while winners_rounds.last().is_some_and(|r| r.len() > 1) {
+        let round = winners_rounds.len() as u32 + 1;
+        let previous = winners_rounds.last().cloned().unwrap_or_default();
+        let next = previous
+            .chunks(2)
+            .map(|pair| push(&mut matches, Bracket::Winners, round, [Slot::WinnerOf(pair[0]), Slot::WinnerOf(pair[1])]))
+            .collect();
+        winners_rounds.push(next);
+    }
+
+    if !double {
+        return matches;
+    }
+
+    let winners_final = winners_rounds[winners_rounds.len() - 1][0];
+
+    let losers_champion = if winners_rounds.len() == 1 {
+        Slot::LoserOf(winners_final)
+    } else {
+        let mut round = 1;
+        let mut current: Vec<usize> = winners_rounds[0]
+            .chunks(2)
+            .map(|pair| push(&mut matches, Bracket::Losers, round, [Slot::LoserOf(pair[0]), Slot::LoserOf(pair[1])]))
+            .collect();
+
+        for dropping in &winners_rounds[1..] {
+            round += 1;
+            current = current
+                .iter()
+                .zip(dropping.iter().rev())
+                .map(|(&survivor, &dropped)| {
+                    push(&mut matches, Bracket::Losers, round, [Slot::WinnerOf(survivor), Slot::LoserOf(dropped)])
+                })
+                .collect();
+
+            if current.len() > 1 {
+                round += 1;
+                current = current
+                    .chunks(2)
+                    .map(|pair| push(&mut matches, Bracket::Losers, round, [Slot::WinnerOf(pair[0]), Slot::WinnerOf(pair[1])]))
+                    .collect();
+            }
+        }
+
+        Slot::WinnerOf(current[0])
+    };
+
+    let grand_final = push(&mut matches, Bracket::GrandFinal, 1, [Slot::WinnerOf(winners_final), losers_champion]);
+    let reset = push(&mut matches, Bracket::GrandFinal, 2, [Slot::WinnerOf(grand_final), Slot::LoserOf(grand_final)]);
+    matches[reset].only_if_needed = true;
+
+    matches
+}
+
+fn build_round_robin(player_count: usize) -> Vec<TournamentMatch> {
+    let mut seats: Vec<Option<usize>> = (0..player_count).map(Some).collect();
+    if seats.len() % 2 == 1 {
+        seats.push(None);
+    }
+
+    let seat_count = seats.len();
+    let mut matches = Vec::new();
+
+    for round in 0..seat_count - 1 {
+        for i in 0..seat_count / 2 {
+            if let (Some(a), Some(b)) = (seats[i], seats[seat_count - 1 - i]) {
+                let id = matches.len();
+                matches.push(TournamentMatch::new(id, Bracket::RoundRobin, round as u32 + 1, [Slot::Seed(a), Slot::Seed(b)]));
+            }
+        }
+        seats[1..].rotate_right(1);
+    }
+
+    matches
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn tournament_with_players(format: TournamentFormat, best_of: u32, count: usize) -> Tournament {
+        let mut tournament = Tournament::new(format, best_of).unwrap();
+        for i in 0..count {
+            tournament.register(PlayerId::new(format!("p{}", i))).unwrap();
+        }
+        tournament.start().unwrap();
+        tournament
+    }
+
+    fn play_all(tournament: &mut Tournament, pick_winner: impl Fn(&TournamentMatch, &[PlayerId; 2]) -> usize) {
+        while tournament.status == TournamentStatus::InProgress {
+            let ready = tournament.ready_matches();
+            assert!(!ready.is_empty(), "tournament stalled");
+            for match_id in ready {
+                tournament.begin_match(match_id);
+                let players = tournament.matches[match_id].players().unwrap();
+                let winner = players[pick_winner(&tournament.matches[match_id], &players)].clone();
+                while tournament.record_game(match_id, Some(&winner)).unwrap() == SeriesOutcome::Continues {}
+            }
+        }
+    }
+
+    fn seed(player_id: &PlayerId) -> usize {
+        player_id.to_string()[1..].parse().unwrap()
+    }
+
+    #[test]
+    fn test_single_elimination_byes_top_seed_wins() {
+        let mut tournament = tournament_with_players(TournamentFormat::SingleElimination, 1, 5);
+
+        assert_eq!(tournament.matches.len(), 7);
+        play_all(&mut tournament, |_, players| if seed(&players[0]) < seed(&players[1]) { 0 } else { 1 });
+
+        assert_eq!(tournament.champion(), Some(PlayerId::new("p0".to_string())));
+        assert_eq!(tournament.standings().iter().filter(|s| !s.eliminated).count(), 1);
+    }
+
+    #[test]
+    fn test_double_elimination_lower_bracket_champion_forces_reset() {
+        let mut tournament = tournament_with_players(TournamentFormat::DoubleElimination, 1, 4);
+
+        play_all(&mut tournament, |m, players| {
+            if m.bracket == Bracket::GrandFinal && m.round == 1 {
+                1
+            } else if seed(&players[0]) < seed(&players[1]) {
+                0
+            } else {
+                1
+            }
+        });
+
+        let reset = tournament.matches.last().unwrap();
+        assert!(!reset.walkover);
+        assert_eq!(tournament.champion(), Some(PlayerId::new("p0".to_string())));
+        assert_eq!(tournament.standings()[1].match_losses, 2);
+    }
+
+    #[test]
+    fn test_double_elimination_upper_bracket_champion_skips_reset() {
+        let mut tournament = tournament_with_players(TournamentFormat::DoubleElimination, 1, 8);
+
+        play_all(&mut tournament, |_, players| if seed(&players[0]) < seed(&players[1]) { 0 } else { 1 });
+
+        assert!(tournament.matches.last().unwrap().walkover);
+        assert_eq!(tournament.champion(), Some(PlayerId::new("p0".to_string())));
+        assert_eq!(tournament.standings().iter().filter(|s| !s.eliminated).count(), 1);
+    }
+
+    #[test]
+    fn test_round_robin_everyone_meets_once() {
+        let mut tournament = tournament_with_players(TournamentFormat::RoundRobin, 3, 5);
+
+        assert_eq!(tournament.matches.len(), 10);
+        play_all(&mut tournament, |_, players| if seed(&players[0]) > seed(&players[1]) { 0 } else { 1 });
+
+        let standings = tournament.standings();
+        assert_eq!(standings[0].player_id, PlayerId::new("p4".to_string()));
+        assert_eq!(standings[0].match_wins, 4);
+        assert_eq!(standings[0].game_wins, 8);
+    }
+
+    #[test]
+    fn test_best_of_series_tied_after_all_games_extends_series() {
+        let mut tournament = tournament_with_players(TournamentFormat::SingleElimination, 3, 2);
+        let winner = PlayerId::new("p1".to_string());
+
+        tournament.begin_match(0);
+        for _ in 0..3 {
+            assert_eq!(tournament.record_game(0, None).unwrap(), SeriesOutcome::Continues);
+        }
+        assert_eq!(tournament.record_game(0, Some(&winner)).unwrap(), SeriesOutcome::Finished);
+
+        assert_eq!(tournament.champion(), Some(winner));
+    }
+
+    #[test]
+    fn test_best_of_series_only_draws_never_awarded_to_higher_seed() {
+        let mut tournament = tournament_with_players(TournamentFormat::SingleElimination, 1, 2);
+        let winner = PlayerId::new("p1".to_string());
+
+        tournament.begin_match(0);
+        for _ in 0..10 {
+            assert_eq!(tournament.record_game(0, None).unwrap(), SeriesOutcome::Continues);
+        }
+        assert_eq!(tournament.status, TournamentStatus::InProgress);
+        assert_eq!(tournament.record_game(0, Some(&winner)).unwrap(), SeriesOutcome::Finished);
+
+        assert_eq!(tournament.champion(), Some(winner));
+    }
+
+    #[test]
+    fn test_withdraw_opponent_advances_by_walkover() {
+        let mut tournament = tournament_with_players(TournamentFormat::SingleElimination, 1, 2);
+
+        assert!(tournament.withdraw(&PlayerId::new("p0".to_string())));
+
+        assert_eq!(tournament.status, TournamentStatus::Finished);
+        assert_eq!(tournament.champion(), Some(PlayerId::new("p1".to_string())));
+    }
+}
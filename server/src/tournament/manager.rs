@@ -0,0 +1,468 @@
+use std::collections::{HashMap, HashSet};
+use std::sync::Arc;
+use tokio::sync::Mutex;
+use crate::{
+    ClientId, LobbyId, PlayerId, TournamentDetails, TournamentId, TournamentMatchInfo,
+    TournamentStanding,
+};
+use crate::lobby::{LobbySettings, validate_spectator_delay};
+use super::bracket::{
+    Bracket, MatchStatus, SeriesOutcome, Tournament, TournamentFormat, TournamentMatch,
+    TournamentStatus,
+};
+
+#[derive(Debug, Clone)]
+pub struct ScheduledGame {
+    pub tournament_id: TournamentId,
+    pub match_id: usize,
+    pub lobby_name: String,
+    pub settings: LobbySettings,
+    pub spectator_delay: u32,
+    pub players: [ClientId; 2],
+}
+
+#[derive(Debug, Clone)]
+struct TournamentEntry {
+    id: TournamentId,
+    name: String,
+    creator_id: ClientId,
+    settings: LobbySettings,
+    spectator_delay: u32,
+    tournament: Tournament,
+}
+
+impl TournamentEntry {
+    fn schedule(&self, match_id: usize) -> Option<ScheduledGame> {
+        let m = &self.tournament.matches[match_id];
+        let [a, b] = m.players()?;
+        let game_number = m.wins[0] + m.wins[1] + m.draws + 1;
+
+        let lobby_name = if self.tournament.best_of > 1 {
+            format!("{} - Match {} Game {}", self.name, match_id + 1, game_number)
+        } else {
+            format!("{} - Match {}", self.name, match_id + 1)
+        };
+
+        Some(ScheduledGame {
+            tournament_id: self.id.clone(),
+            match_id,
+            lobby_name,
+            settings: self.settings.clone(),
+            spectator_delay: self.spectator_delay,
+            players: [ClientId::new(a.to_string()), ClientId::new(b.to_string())],
+        })
+    }
+
+    fn recipients(&self) -> Vec<ClientId> {
+        let mut recipients: Vec<ClientId> = self
+            .tournament
+            .players
+            .iter()
+            .map(|p| ClientId::new(p.to_string()))
+            .collect();
+        if !recipients.contains(&self.creator_id) {
+            recipients.push(self.creator_id.clone());
+        }
+        recipients
+    }
+
+    fn to_details(&self) -> TournamentDetails {
+        let tournament = &self.tournament;
+
+        let matches = tournament
+            .matches
+            .iter()
+            .map(|m| TournamentMatchInfo {
+                match_id: m.id as u32,
+                bracket: bracket_to_proto(m.bracket).into(),
+                round: m.round,
+                player_a: m.player(0).map(player_identity),
+                player_b: m.player(1).map(player_identity),
+                wins_a: m.wins[0],
+                wins_b: m.wins[1],
+                draws: m.draws,
+                status: match_status_to_proto(m).into(),
+                winner: m.winner.and_then(|side| m.player(side)).map(player_identity),
+                walkover: m.walkover,
+                lobby_id: m.lobby_id.as_ref().map(|id| id.to_string()).unwrap_or_default(),
+            })
+            .collect();
+
+        let standings = tournament
+            .standings()
+            .into_iter()
+            .enumerate()
+            .map(|(index, s)| TournamentStanding {
+                rank: index as u32 + 1,
+                player: Some(player_identity(&s.player_id)),
+                match_wins: s.match_wins,
+                match_losses: s.match_losses,
+                match_draws: s.match_draws,
+                game_wins: s.game_wins,
+                game_losses: s.game_losses,
+                eliminated: s.eliminated,
+            })
+            .collect();
+
+        TournamentDetails {
+            tournament_id: self.id.to_string(),
+            name: self.name.clone(),
+            creator: Some(crate::PlayerIdentity {
+                player_id: self.creator_id.to_string(),
+                is_bot: false,
+            }),
+            format: format_to_proto(tournament.format).into(),
+            best_of: tournament.best_of,
+            settings: self.settings.to_info_proto(),
+            status: status_to_proto(tournament.status).into(),
+            players: tournament.players.iter().map(player_identity).collect(),
+            matches,
+            standings,
+            champion: tournament.champion().as_ref().map(player_identity),
+            spectator_delay: self.spectator_delay,
+        }
+    }
+}
+
+#[derive(Debug)]
+struct TournamentManagerState {
+    tournaments: HashMap<TournamentId, TournamentEntry>,
+    lobby_to_match: HashMap<LobbyId, (TournamentId, usize)>,
+    /// Lobbies whose game has been recorded; they stay open until their
+    /// players are needed for another game.
+    finished_lobbies: HashSet<LobbyId>,
+    next_tournament_id: u64,
+}
+
+#[derive(Debug, Clone)]
+pub struct TournamentManager {
+    state: Arc<Mutex<TournamentManagerState>>,
+}
+
+impl Default for TournamentManager {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl TournamentManager {
+    pub fn new() -> Self {
+        Self {
+            state: Arc::new(Mutex::new(TournamentManagerState {
+                tournaments: HashMap::new(),
+                lobby_to_match: HashMap::new(),
+                finished_lobbies: HashSet::new(),
+                next_tournament_id: 1,
+            })),
+        }
+    }
+
+    pub async fn create_tournament(
+        &self,
+        name: String,
+        settings: LobbySettings,
+        format: TournamentFormat,
+        best_of: u32,
+        spectator_delay: u32,
+        creator_id: ClientId,
+    ) -> Result<TournamentDetails, String> {
+        match &settings {
+            LobbySettings::Snake(_) | LobbySettings::TicTacToe(_) => {}
+            _ => return Err("Tournaments are only supported for Snake and TicTacToe".to_string()),
+        }
+        settings.validate(2)?;
+        validate_spectator_delay(spectator_delay)?;
+
+        let tournament = Tournament::new(format, best_of)?;
+
+        let mut state = self.state.lock().await;
+        let id = TournamentId::new(format!("tournament_{}", state.next_tournament_id));
+        state.next_tournament_id += 1;
+
+        let entry = TournamentEntry {
+            id: id.clone(),
+            name,
+            creator_id,
+            settings,
+            spectator_delay,
+            tournament,
+        };
+        let details = entry.to_details();
+        state.tournaments.insert(id, entry);
+
+        Ok(details)
+    }
+
+    pub async fn list_tournaments(&self) -> Vec<TournamentDetails> {
+        let state = self.state.lock().await;
+        state.tournaments.values().map(|t| t.to_details()).collect()
+    }
+
+    pub async fn get_details(&self, tournament_id: &TournamentId) -> Option<TournamentDetails> {
+        let state = self.state.lock().await;
+        state.tournaments.get(tournament_id).map(|t| t.to_details())
+    }
+
+    pub async fn get_recipients(&self, tournament_id: &TournamentId) -> Vec<ClientId> {
+        let state = self.state.lock().await;
+        state
+            .tournaments
+            .get(tournament_id)
+            .map(|t| t.recipients())
+            .unwrap_or_default()
+    }
+
+    pub async fn join_tournament(&self, tournament_id: &TournamentId, client_id: &ClientId) -> Result<TournamentDetails, String> {
+        let mut state = self.state.lock().await;
+        let entry = state.tournaments.get_mut(tournament_id).ok_or("Tournament not found")?;
+        entry.tournament.register(PlayerId::new(client_id.to_string()))?;
+        Ok(entry.to_details())
+    }
+
+    pub async fn leave_tournament(&self, tournament_id: &TournamentId, client_id: &ClientId) -> Result<TournamentDetails, String> {
+        let mut state = self.state.lock().await;
+        let entry = state.tournaments.get_mut(tournament_id).ok_or("Tournament not found")?;
+        let player_id = PlayerId::new(client_id.to_string());
+
+        if !entry.tournament.unregister(&player_id) && !entry.tournament.withdraw(&player_id) {
+            return Err("Not an active participant of this tournament".to_string());
+        }
+
+        Ok(entry.to_details())
+    }
+
+    pub async fn withdraw_everywhere(&self, client_id: &ClientId) -> Vec<TournamentId> {
+        let mut state = self.state.lock().await;
+        let player_id = PlayerId::new(client_id.to_string());
+
+        state
+            .tournaments
+            .values_mut()
+            .filter_map(|entry| {
+                let affected = entry.tournament.unregister(&player_id) || entry.tournament.withdraw(&player_id);
+                affected.then(|| entry.id.clone())
+            })
+            .collect()
+    }
+
+    pub async fn is_active_participant(&self, client_id: &ClientId) -> bool {
+        let state = self.state.lock().await;
+        let player_id = PlayerId::new(client_id.to_string());
+        state
+            .tournaments
+            .values()
+            .any(|entry| entry.tournament.is_active_participant(&player_id))
+    }
+
+    pub async fn start_tournament(&self, tournament_id: &TournamentId, client_id: &ClientId) -> Result<(), String> {
+        let mut state = self.state.lock().await;
+        let entry = state.tournaments.get_mut(tournament_id).ok_or("Tournament not found")?;
+
+        if &entry.creator_id != client_id {
+            return Err("Only the tournament creator can start it".to_string());
+        }
+
+        entry.tournament.start()
+    }
+
+    pub async fn take_ready_games(&self, tournament_id: &TournamentId) -> Vec<ScheduledGame> {
+        let mut state = self.state.lock().await;
+        let Some(entry) = state.tournaments.get_mut(tournament_id) else {
+            return Vec::new();
+        };
+
+        let mut games = Vec::new();
+        for match_id in entry.tournament.ready_matches() {
+            if let Some(game) = entry.schedule(match_id) {
+                entry.tournament.begin_match(match_id);
+                games.push(game);
+            }
+        }
+        games
+    }
+
+    pub async fn attach_lobby(&self, game: &ScheduledGame, lobby_id: LobbyId) {
+        let mut state = self.state.lock().await;
+        if let Some(entry) = state.tournaments.get_mut(&game.tournament_id)
+            && let Some(m) = entry.tournament.matches.get_mut(game.match_id)
+        {
+            m.lobby_id = Some(lobby_id.clone());
+        }
+        state
+            .lobby_to_match
+            .insert(lobby_id, (game.tournament_id.clone(), game.match_id));
+    }
+
+    pub async fn abort_game(&self, game: &ScheduledGame) {
+        let mut state = self.state.lock().await;
+        if let Some(entry) = state.tournaments.get_mut(&game.tournament_id) {
+            entry.tournament.abort_match(game.match_id);
+        }
+    }
+
+    pub async fn is_tournament_lobby(&self, lobby_id: &LobbyId) -> bool {
+        let state = self.state.lock().await;
+        state.lobby_to_match.contains_key(lobby_id)
+    }
+
+    /// Whether `lobby_id` held a finished tournament game, forgetting it.
+    pub async fn release_finished_lobby(&self, lobby_id: &LobbyId) -> bool {
+        let mut state = self.state.lock().await;
+        state.finished_lobbies.remove(lobby_id)
+    }
+
+    pub async fn record_game_result(
+        &self,
+        lobby_id: &LobbyId,
+        winner: Option<&crate::PlayerIdentity>,
+    ) -> Option<(TournamentId, Option<ScheduledGame>)> {
+        let mut state = self.state.lock().await;
+        let (tournament_id, match_id) = state.lobby_to_match.remove(lobby_id)?;
+        state.finished_lobbies.insert(lobby_id.clone());
+        let entry = state.tournaments.get_mut(&tournament_id)?;
+
+        let winner_id = winner
+            .filter(|w| !w.is_bot)
+            .map(|w| PlayerId::new(w.player_id.clone()));
+
+        match entry.tournament.record_game(match_id, winner_id.as_ref()) {
+            Ok(SeriesOutcome::Continues) => Some((tournament_id, entry.schedule(match_id))),
+            Ok(SeriesOutcome::Finished) => Some((tournament_id, None)),
+            Err(e) => {
+                crate::log!("[tournament:{}] Failed to record game result: {}", tournament_id, e);
+                Some((tournament_id, None))
+            }
+        }
+    }
+}
+
+fn player_identity(player_id: &PlayerId) -> crate::PlayerIdentity {
+    crate::PlayerIdentity {
+        player_id: player_id.to_string(),
+        is_bot: false,
+    }
+}
+
+pub fn format_from_proto(format: i32) -> Result<TournamentFormat, String> {
+    match crate::TournamentFormat::try_from(format) {
+        Ok(crate::TournamentFormat::SingleElimination) => Ok(TournamentFormat::SingleElimination),
+        Ok(crate::TournamentFormat::DoubleElimination) => Ok(TournamentFormat::DoubleElimination),
+        Ok(crate::TournamentFormat::RoundRobin) => Ok(TournamentFormat::RoundRobin),
+        _ => Err("Invalid tournament format".to_string()),
+    }
+}
+
+fn format_to_proto(format: TournamentFormat) -> crate::TournamentFormat {
+    match format {
+        TournamentFormat::SingleElimination => crate::TournamentFormat::SingleElimination,
+        TournamentFormat::DoubleElimination => crate::TournamentFormat::DoubleElimination,
+        TournamentFormat::RoundRobin => crate::TournamentFormat::RoundRobin,
+    }
+}
+
+fn status_to_proto(status: TournamentStatus) -> crate::TournamentStatus {
+    match status {
+        TournamentStatus::Registration => crate::TournamentStatus::Registration,
+        TournamentStatus::InProgress => crate::TournamentStatus::InProgress,
+        TournamentStatus::Finished => crate::TournamentStatus::Finished,
+    }
+}
+
+fn bracket_to_proto(bracket: Bracket) -> crate::TournamentBracket {
+    match bracket {
+        Bracket::Winners => crate::TournamentBracket::Winners,
+        Bracket::Losers => crate::TournamentBracket::Losers,
+        Bracket::GrandFinal => crate::TournamentBracket::GrandFinal,
+        Bracket::RoundRobin => crate::TournamentBracket::RoundRobin,
+    }
+}
+
+fn match_status_to_proto(m: &TournamentMatch) -> crate::TournamentMatchStatus {
+    match m.status {
+        MatchStatus::Pending => crate::TournamentMatchStatus::Pending,
+        MatchStatus::InProgress => crate::TournamentMatchStatus::InProgress,
+        MatchStatus::Completed => crate::TournamentMatchStatus::Completed,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{FirstPlayerMode, TicTacToeLobbySettings};
+
+    fn gomoku_settings() -> LobbySettings {
+        LobbySettings::TicTacToe(TicTacToeLobbySettings {
+            field_width: 15,
+            field_height: 15,
+            win_count: 5,
+            first_player: FirstPlayerMode::Random.into(),
+            ..Default::default()
+        })
+    }
+
+    #[tokio::test]
+    async fn test_record_game_result_best_of_three_schedules_next_game() {
+        let manager = TournamentManager::new();
+        let host = ClientId::new("host".to_string());
+        let alice = ClientId::new("alice".to_string());
+        let bob = ClientId::new("bob".to_string());
+
+        let details = manager.create_tournament(
+            "Monthly Gomoku".to_string(),
+            gomoku_settings(),
+            TournamentFormat::SingleElimination,
+            3,
+            0,
+            host.clone(),
+        ).await.unwrap();
+        let tournament_id = TournamentId::new(details.tournament_id);
+
+        manager.join_tournament(&tournament_id, &alice).await.unwrap();
+        manager.join_tournament(&tournament_id, &bob).await.unwrap();
+        manager.start_tournament(&tournament_id, &host).await.unwrap();
+
+        let games = manager.take_ready_games(&tournament_id).await;
+        assert_eq!(games.len(), 1);
+        assert_eq!(games[0].lobby_name, "Monthly Gomoku - Match 1 Game 1");
+
+        let lobby_id = LobbyId::new("lobby_1".to_string());
+        manager.attach_lobby(&games[0], lobby_id.clone()).await;
+        assert!(manager.is_active_participant(&alice).await);
+
+        let winner = crate::PlayerIdentity { player_id: "alice".to_string(), is_bot: false };
+        let (_, next_game) = manager.record_game_result(&lobby_id, Some(&winner)).await.unwrap();
+        let next_game = next_game.unwrap();
+        assert_eq!(next_game.lobby_name, "Monthly Gomoku - Match 1 Game 2");
+
+        let lobby_id = LobbyId::new("lobby_2".to_string());
+        manager.attach_lobby(&next_game, lobby_id.clone()).await;
+        let (_, next_game) = manager.record_game_result(&lobby_id, Some(&winner)).await.unwrap();
+        assert!(next_game.is_none());
+        assert!(manager.release_finished_lobby(&lobby_id).await);
+        assert!(!manager.release_finished_lobby(&lobby_id).await);
+
+        let details = manager.get_details(&tournament_id).await.unwrap();
+        assert_eq!(details.status, crate::TournamentStatus::Finished as i32);
+        assert_eq!(details.champion.unwrap().player_id, "alice");
+    }
+
+    #[tokio::test]
+    async fn test_create_tournament_single_player_game_error_returned() {
+        let manager = TournamentManager::new();
+
+        let result = manager.create_tournament(
+            "Solo".to_string(),
+            LobbySettings::Puzzle2048(crate::Puzzle2048LobbySettings {
+                field_width: 4,
+                field_height: 4,
+                target_value: 2048,
+            }),
+            TournamentFormat::RoundRobin,
+            1,
+            0,
+            ClientId::new("host".to_string()),
+        ).await;
+
+        assert!(result.is_err());
+    }
+}
@@ -0,0 +1,5 @@
+mod bracket;
+mod manager;
+
+pub use bracket::{Bracket, MatchStatus, SeriesOutcome, Standing, Tournament, TournamentFormat, TournamentMatch, TournamentStatus};
+pub use manager::{format_from_proto, ScheduledGame, TournamentManager};
@@ -14,6 +14,8 @@ use crate::log;
 use crate::broadcaster::Broadcaster;
 use crate::game_session_manager::GameSessionManager;
 use crate::lobby::LobbyManager;
+use crate::matchmaking::MatchmakingManager;
+use crate::tournament::TournamentManager;
 use crate::ws_handler::handle_websocket;
 
 #[derive(Clone)]
@@ -21,18 +23,24 @@ pub struct WebServerState {
     pub lobby_manager: LobbyManager,
     pub broadcaster: Broadcaster,
     pub session_manager: GameSessionManager,
+    pub matchmaking_manager: MatchmakingManager,
+    pub tournament_manager: TournamentManager,
 }
 
 pub async fn run_web_server(
     lobby_manager: LobbyManager,
     broadcaster: Broadcaster,
     session_manager: GameSessionManager,
+    matchmaking_manager: MatchmakingManager,
+    tournament_manager: TournamentManager,
     static_files_path: PathBuf,
 ) {
     let state = WebServerState {
         lobby_manager,
         broadcaster,
         session_manager,
+        matchmaking_manager,
+        tournament_manager,
     };
 
     let cors = CorsLayer::new()
@@ -31,6 +31,8 @@ pub async fn handle_websocket(socket: WebSocket, state: WebServerState) {
         state.lobby_manager,
         state.broadcaster,
         state.session_manager,
+        state.matchmaking_manager,
+        state.tournament_manager,
     );
 
     let mut client_id_opt: Option<ClientId> = None;